@@ -1,9 +1,151 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 use std::{env, fs};
 
+// A variant of `Type` or `QueryType`: name, the code as an `isize`
+// pattern and as a `u16` expression, and mnemonic
+struct Variant {
+    name: String,
+    code: String,
+    code_u16: String,
+    mnemonic: String,
+}
+
+// Generates a type code enumeration with an `Unknown(u16)` variant, its
+// conversions from and to codes and mnemonics
+fn type_enum(name: &str, doc: &str, variants: &[Variant], aliases: &str) -> String {
+    let mut code = String::new();
+    code.push_str(doc);
+    code.push_str(&format!(
+        r#"
+        #[derive(Debug, Clone, Copy)]
+        #[non_exhaustive]
+        pub enum {} {{
+    "#,
+        name
+    ));
+    for v in variants {
+        code.push_str(&format!("{},\n", v.name));
+    }
+    code.push_str(&format!(
+        r#"
+            /// A type code without a variant of its own
+            Unknown(u16),
+        }}
+
+        impl {0} {{
+            /// Parse a type code
+            ///
+            /// Never fails, codes this library doesn't know give `Unknown`.
+            pub fn parse(code: u16) -> {0} {{
+                use self::{0}::*;
+                match code as isize {{
+    "#,
+        name
+    ));
+    for v in variants {
+        code.push_str(&format!("{} => {},\n", v.code, v.name));
+    }
+    code.push_str(
+        r#"
+                    _ => Unknown(code),
+                }
+            }
+            /// Returns the type code
+            pub fn code(self) -> u16 {
+                match self {
+    "#,
+    );
+    for v in variants {
+        code.push_str(&format!("{}::{} => {},\n", name, v.name, v.code_u16));
+    }
+    code.push_str(&format!(
+        r#"
+                    {0}::Unknown(code) => code,
+                }}
+            }}
+            /// Returns the mnemonic of the type, e.g. `"AAAA"`
+            ///
+            /// Returns `None` for `Unknown`.
+            pub fn mnemonic(self) -> Option<&'static str> {{
+                match self {{
+    "#,
+        name
+    ));
+    for v in variants {
+        code.push_str(&format!("{}::{} => Some({:?}),\n", name, v.name, v.mnemonic));
+    }
+    code.push_str(&format!(
+        r#"
+                    {0}::Unknown(_) => None,
+                }}
+            }}
+        }}
+
+        impl PartialEq for {0} {{
+            fn eq(&self, other: &{0}) -> bool {{
+                self.code() == other.code()
+            }}
+        }}
+
+        impl Eq for {0} {{}}
+
+        impl ::std::hash::Hash for {0} {{
+            fn hash<H: ::std::hash::Hasher>(&self, state: &mut H) {{
+                self.code().hash(state)
+            }}
+        }}
+
+        impl From<u16> for {0} {{
+            fn from(code: u16) -> {0} {{
+                {0}::parse(code)
+            }}
+        }}
+
+        impl From<{0}> for u16 {{
+            fn from(typ: {0}) -> u16 {{
+                typ.code()
+            }}
+        }}
+
+        /// Writes the mnemonic, or `TYPE` and the code for unknown types
+        impl fmt::Display for {0} {{
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {{
+                match self.mnemonic() {{
+                    Some(mnemonic) => f.pad(mnemonic),
+                    None => f.pad(&format!("TYPE{{}}", self.code())),
+                }}
+            }}
+        }}
+
+        /// Parses a mnemonic ignoring case, or the RFC 3597 `TYPE` syntax
+        impl FromStr for {0} {{
+            type Err = MnemonicError;
+            fn from_str(text: &str) -> Result<{0}, MnemonicError> {{
+                match text.to_ascii_uppercase().as_str() {{
+                    {1}
+    "#,
+        name, aliases
+    ));
+    for v in variants {
+        code.push_str(&format!("{:?} => Ok({}::{}),\n", v.mnemonic, name, v.name));
+    }
+    code.push_str(&format!(
+        r#"
+                    upper => generic_code(upper, "TYPE")
+                        .map({0}::parse)
+                        .ok_or_else(|| MnemonicError::Unknown(text.into())),
+                }}
+            }}
+        }}
+    "#,
+        name
+    ));
+    code
+}
+
 fn main() {
     let root_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
     let rdata_dir = format!("{}/src/rdata", root_dir);
@@ -18,35 +160,44 @@ fn main() {
         set
     };
 
-    let mut imports = String::new();
+    // Types assigned by IANA without a module, their data is kept as is
+    let unparsed = [
+        ("SMIMEA", 53),
+        ("NINFO", 56),
+        ("RKEY", 57),
+        ("TALINK", 58),
+        ("SVCB", 64),
+        ("HTTPS", 65),
+        ("DSYNC", 66),
+        ("HHIT", 67),
+        ("BRID", 68),
+        ("NID", 104),
+        ("L32", 105),
+        ("L64", 106),
+        ("LP", 107),
+        ("EUI48", 108),
+        ("EUI64", 109),
+        ("NXNAME", 128),
+        ("AVC", 258),
+        ("DOA", 259),
+        ("AMTRELAY", 260),
+        ("RESINFO", 261),
+        ("WALLET", 262),
+        ("CLA", 263),
+        ("IPN", 264),
+    ];
 
-    let mut typ = r#"
-        /// The TYPE value according to RFC 1035
-        #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-        pub enum Type {
-    "#.to_string();
-
-    let mut typ_impl = r#"
-        impl Type {
-            /// Parse a type code
-            pub fn parse(code: u16) -> Result<Type, Error> {
-                use self::Type::*;
-                match code as isize {
-    "#.to_string();
-
-    let mut qtyp = r#"
-        /// The QTYPE value according to RFC 1035
-        #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-        pub enum QueryType {
-    "#.to_string();
+    // Mnemonics which are not the upper case file name
+    let mnemonics = {
+        let mut map = HashMap::new();
+        map.insert("NSAPPTR", "NSAP-PTR");
+        map.insert("ALL", "ANY");
+        map
+    };
 
-    let mut qtyp_impl = r#"
-        impl QueryType {
-            /// Parse a query type code
-            pub fn parse(code: u16) -> Result<QueryType, Error> {
-                use self::QueryType::*;
-                match code as isize {
-    "#.to_string();
+    let mut imports = String::new();
+    let mut types = Vec::new();
+    let mut qtypes = Vec::new();
 
     let mut rdata_impl = r#"
         impl<'a> RData<'a> {
@@ -74,50 +225,84 @@ fn main() {
                     lower, camel
                 ));
 
-                let type_variant = format!("{} = {}::Record::TYPE,\n", upper, lower);
-                let code_to_type = format!("{}::Record::TYPE => Ok({}),\n", lower, upper);
+                let mnemonic = mnemonics.get(upper.as_str()).cloned().unwrap_or(&upper);
+                let variant = || Variant {
+                    name: upper.clone(),
+                    code: format!("{}::Record::TYPE", lower),
+                    code_u16: format!("{}::Record::TYPE as u16", lower),
+                    mnemonic: mnemonic.to_string(),
+                };
                 if !qtype_only.contains(upper.as_str()) {
                     let typ_to_rdata =
                         format!("Type::{} => {}::parse(rdata, original),\n", upper, camel);
-                    typ.push_str(&type_variant);
-                    typ_impl.push_str(&code_to_type);
                     rdata_impl.push_str(&typ_to_rdata);
+                    types.push(variant());
                 }
-                qtyp.push_str(&type_variant);
-                qtyp_impl.push_str(&code_to_type);
+                qtypes.push(variant());
             }
         }
     }
 
-    typ.push('}');
-    qtyp.push('}');
+    for &(upper, code) in unparsed.iter() {
+        rdata_impl.push_str(&format!(
+            "Type::{} => Ok(RData::Unknown({}, Cow::Borrowed(rdata))),\n",
+            upper, code
+        ));
+        let variant = || Variant {
+            name: upper.to_string(),
+            code: code.to_string(),
+            code_u16: code.to_string(),
+            mnemonic: upper.to_string(),
+        };
+        types.push(variant());
+        qtypes.push(variant());
+    }
 
-    typ_impl.push_str(&r#"
-                    x => Err(Error::InvalidType(x as u16)),
-                }
-            }
-        }
-    "#.to_string());
-    qtyp_impl.push_str(&r#"
-                    x => Err(Error::InvalidQueryType(x as u16)),
-                }
-            }
-        }
-    "#.to_string());
+    let typ = type_enum(
+        "Type",
+        r#"
+        /// The TYPE value according to RFC 1035
+        ///
+        /// Every type assigned by IANA has a variant, even those without a
+        /// parser, their data is kept as `RData::Unknown`. Other codes are
+        /// kept as `Unknown`, so new variants may be added in minor
+        /// releases. Types are compared by their codes.
+    "#,
+        &types,
+        "",
+    );
+    let qtyp = type_enum(
+        "QueryType",
+        r#"
+        /// The QTYPE value according to RFC 1035
+        ///
+        /// All record types and the types only used in questions, like
+        /// `AXFR` and `ALL`. Other codes are kept as `Unknown`. Query types
+        /// are compared by their codes. `*` is parsed as `ALL`.
+    "#,
+        &qtypes,
+        r#""*" => Ok(QueryType::ALL),"#,
+    );
 
-    rdata_impl.push_str(&r#"
+    rdata_impl.push_str(r#"
+                    Type::Unknown(code) => Ok(RData::Unknown(code, Cow::Borrowed(rdata))),
                 }
             }
         }
-    "#.to_string());
+    "#);
 
-    imports.push_str(&r#"
+    imports.push_str(r#"
         mod config;
 
         pub use self::config::RData;
 
+        use std::borrow::Cow;
+        use std::fmt;
+        use std::str::FromStr;
+
+        use crate::mnemonic::{generic_code, MnemonicError};
         use crate::Error;
-    "#.to_string());
+    "#);
 
     let dest_path = Path::new(&rdata_dir).join("mod.rs");
     let mut f = File::create(&dest_path).unwrap();
@@ -140,18 +325,14 @@ fn main() {
         }}
 
         {typ}
-        {typ_impl}
 
         {qtyp}
-        {qtyp_impl}
 
         {rdata_impl}
     "#,
         imports = imports,
         typ = typ,
-        typ_impl = typ_impl,
         qtyp = qtyp,
-        qtyp_impl = qtyp_impl,
         rdata_impl = rdata_impl
     );
     f.write_all(code.as_bytes()).unwrap();
@@ -0,0 +1,87 @@
+//! Times parsing of packets with large TXT records and many NSEC records
+//!
+//! Run with `cargo run --release --example parse_bench`, add
+//! `--features memchr` to compare.
+use std::time::Instant;
+
+use dns_parser::Packet;
+
+const ITERATIONS: u32 = 20_000;
+
+fn header(answers: u16) -> Vec<u8> {
+    let mut packet = vec![0x12, 0x34, 0x81, 0x80, 0, 1];
+    packet.extend_from_slice(&answers.to_be_bytes());
+    packet.extend_from_slice(&[0, 0, 0, 0]);
+    // question: example.com. TXT IN
+    packet.extend_from_slice(b"\x07example\x03com\x00\x00\x10\x00\x01");
+    packet
+}
+
+fn record(packet: &mut Vec<u8>, typ: u16, rdata: &[u8]) {
+    // owner compressed to the question name, `TTL` of an hour
+    packet.extend_from_slice(&[0xc0, 12]);
+    packet.extend_from_slice(&typ.to_be_bytes());
+    packet.extend_from_slice(&[0, 1, 0, 0, 0x0e, 0x10]);
+    packet.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    packet.extend_from_slice(rdata);
+}
+
+fn txt_packet() -> Vec<u8> {
+    let mut packet = header(16);
+    let mut rdata = Vec::new();
+    for i in 0..15 {
+        let string = format!("key{}={}", i, "v".repeat(240));
+        rdata.push(string.len() as u8);
+        rdata.extend_from_slice(string.as_bytes());
+    }
+    for _ in 0..16 {
+        record(&mut packet, 16, &rdata);
+    }
+    packet
+}
+
+fn nsec_packet() -> Vec<u8> {
+    let mut packet = header(200);
+    for i in 0..200 {
+        let mut rdata = Vec::new();
+        let label = format!("host-{:04}", i);
+        rdata.push(label.len() as u8);
+        rdata.extend_from_slice(label.as_bytes());
+        rdata.extend_from_slice(&[0xc0, 12]);
+        // A NS SOA MX TXT AAAA RRSIG NSEC
+        rdata.extend_from_slice(&[0, 6, 0x62, 0x01, 0x80, 0x08, 0x00, 0x03]);
+        record(&mut packet, 47, &rdata);
+    }
+    packet
+}
+
+fn run(name: &str, packet: &[u8], check: impl Fn(&Packet<'_>) -> usize) {
+    let mut total = 0;
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        let parsed = Packet::parse(packet).expect("valid packet");
+        total += check(&parsed);
+    }
+    let elapsed = start.elapsed();
+    println!(
+        "{:<6} {:>6} bytes {:>10.2?} per packet ({} items)",
+        name,
+        packet.len(),
+        elapsed / ITERATIONS,
+        total / ITERATIONS as usize,
+    );
+}
+
+fn main() {
+    run("txt", &txt_packet(), |packet| {
+        packet
+            .answers()
+            .iter()
+            .map(|record| match record.data {
+                dns_parser::RData::TXT(ref txt) => txt.attributes().count(),
+                _ => 0,
+            })
+            .sum()
+    });
+    run("nsec", &nsec_packet(), |packet| packet.answers().len());
+}
@@ -59,18 +59,15 @@ fn resolve(name: &str) -> Result<(), Box<dyn Error>> {
             }
         }
     };
-    if pkt.header.response_code != ResponseCode::NoError {
-        return Err(pkt.header.response_code.into());
+    if pkt.header().response_code != ResponseCode::NoError {
+        return Err(pkt.header().response_code.into());
     }
-    if pkt.answers.is_empty() {
+    if pkt.answers().is_empty() {
         return Err("No records received".into());
     }
-    for ans in pkt.answers {
-        match ans.data {
-            RData::A(Record(ip)) => {
-                println!("{}", ip);
-            }
-            _ => {} // ignore
+    for ans in pkt.answers() {
+        if let RData::A(Record(ip)) = ans.data {
+            println!("{}", ip);
         }
     }
     Ok(())
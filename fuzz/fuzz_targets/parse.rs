@@ -0,0 +1,46 @@
+//! Feeds the input to every parsing entry point, none of them may panic
+//!
+//! Run with `cargo fuzz run parse` from the root of the repository.
+#![no_main]
+
+use std::fmt::Write;
+
+use dns_parser::dissect::dissect;
+use dns_parser::{parse_with_visitor, MessageVisitor, Packet, ParseArena, RawPacket};
+use libfuzzer_sys::fuzz_target;
+
+struct Visitor;
+
+impl<'a> MessageVisitor<'a> for Visitor {}
+
+fuzz_target!(|data: &[u8]| {
+    let mut out = String::new();
+    if let Ok(packet) = Packet::parse(data) {
+        for name in packet.names() {
+            let _ = write!(out, "{} {:?}", name, name);
+            let _ = name.bytes().count();
+            let _ = name.labels().count();
+            let _ = name.clone().into_owned().to_string();
+        }
+        for record in packet.answers().iter().chain(packet.additional()) {
+            let _ = write!(out, "{:?}", record.data);
+            let mut wire = Vec::new();
+            record.data.write_to(&mut wire);
+            record.data.write_canonical_to(&mut wire);
+        }
+        let _ = packet.to_packet_buf();
+    }
+    if let Ok(packet) = RawPacket::parse(data) {
+        for record in packet.answers().iter().chain(packet.additional()) {
+            let _ = record.parse_data();
+        }
+    }
+    let mut arena = ParseArena::new();
+    if let Ok(packet) = arena.parse(data) {
+        arena.recycle(packet);
+    }
+    let _ = parse_with_visitor(data, &mut Visitor);
+    let split = data.len() / 2;
+    let _ = Packet::parse_segments(&[&data[..split], &data[split..]]);
+    let _ = write!(out, "{}", dissect(data));
+});
@@ -0,0 +1,344 @@
+//! Archival with `rkyv`, enabled by the `rkyv` feature
+//!
+//! [`StoredPacket`] holds a parsed packet with every name and record
+//! data in the uncompressed wire format. Its archived form can be
+//! written to disk and accessed in place, e.g. from a memory-mapped file.
+//! `to_packet` gives back a `Packet` borrowing the archive, names are not
+//! decompressed again and nothing but the section lists is allocated.
+//!
+//! ```
+//! use dns_parser::archive::{ArchivedStoredPacket, StoredPacket};
+//! use dns_parser::{Builder, Packet, QueryClass, QueryType};
+//! use rkyv::rancor::Error;
+//!
+//! let mut builder = Builder::new_query(1, true);
+//! builder.add_question("example.com", false, QueryType::A, QueryClass::IN);
+//! let query = builder.build().unwrap();
+//! let packet = Packet::parse(&query).unwrap();
+//!
+//! let bytes = rkyv::to_bytes::<Error>(&StoredPacket::from(&packet)).unwrap();
+//! let archived = rkyv::access::<ArchivedStoredPacket, Error>(&bytes).unwrap();
+//! assert_eq!(archived.to_packet().unwrap(), packet);
+//! ```
+//!
+//! [`StoredPacket`]: struct.StoredPacket.html
+use std::borrow::Cow;
+
+use rkyv::{Archive, Deserialize, Serialize};
+
+use crate::rdata::opt;
+use crate::structs::{Questions, Records};
+use crate::{Class, Error, Header, Name, Packet, QueryClass, QueryType, Question, RData};
+use crate::{ResourceRecord, Ttl, Type};
+
+/// A packet in the form archived by `rkyv`
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct StoredPacket {
+    /// The ID of the packet
+    pub id: u16,
+    /// The flags, opcode and response code, see `Header::flags_word`
+    pub flags: u16,
+    /// The questions
+    pub questions: Vec<StoredQuestion>,
+    /// The answer section
+    pub answers: Vec<StoredRecord>,
+    /// The authority section
+    pub nameservers: Vec<StoredRecord>,
+    /// The additional section, without the OPT record
+    pub additional: Vec<StoredRecord>,
+    /// The OPT record, if any
+    pub opt: Option<StoredOpt>,
+}
+
+/// A question of a `StoredPacket`
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct StoredQuestion {
+    /// The name in the uncompressed wire format
+    pub name: Vec<u8>,
+    /// The query type code
+    pub qtype: u16,
+    /// The query class code
+    pub qclass: u16,
+    /// Whether a unicast response is preferred (multicast DNS)
+    pub prefer_unicast: bool,
+}
+
+/// A record of a `StoredPacket`
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct StoredRecord {
+    /// The name in the uncompressed wire format
+    pub name: Vec<u8>,
+    /// The type code
+    pub typ: u16,
+    /// The class code
+    pub cls: u16,
+    /// Whether the cache flush bit is set (multicast DNS)
+    pub multicast_unique: bool,
+    /// The time to live in seconds
+    pub ttl: u32,
+    /// The record data in the wire format, names uncompressed
+    pub data: Vec<u8>,
+}
+
+/// The OPT record of a `StoredPacket`
+#[derive(Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize)]
+pub struct StoredOpt {
+    /// The UDP payload size
+    pub udp: u16,
+    /// The upper bits of the extended response code
+    pub extrcode: u8,
+    /// The EDNS version
+    pub version: u8,
+    /// The EDNS flags
+    pub flags: u16,
+    /// The options in the wire format
+    pub data: Vec<u8>,
+}
+
+fn wire_name(name: &Name<'_>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    name.write_to(&mut buf);
+    buf
+}
+
+fn wire_data(data: &RData<'_>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    data.write_to(&mut buf);
+    buf
+}
+
+impl<'a> From<&Packet<'a>> for StoredPacket {
+    fn from(packet: &Packet<'a>) -> StoredPacket {
+        let records =
+            |records: &[ResourceRecord<'_>]| records.iter().map(StoredRecord::from).collect();
+        StoredPacket {
+            id: packet.header.id,
+            flags: packet.header.flags_word(),
+            questions: packet.questions.iter().map(StoredQuestion::from).collect(),
+            answers: records(&packet.answers),
+            nameservers: records(&packet.nameservers),
+            additional: records(&packet.additional),
+            opt: packet.opt.as_ref().map(|opt| StoredOpt {
+                udp: opt.udp,
+                extrcode: opt.extrcode,
+                version: opt.version,
+                flags: opt.flags,
+                data: wire_data(&opt.data),
+            }),
+        }
+    }
+}
+
+impl<'a> From<&Question<'a>> for StoredQuestion {
+    fn from(question: &Question<'a>) -> StoredQuestion {
+        StoredQuestion {
+            name: wire_name(&question.qname),
+            qtype: question.qtype.code(),
+            qclass: question.qclass.code(),
+            prefer_unicast: question.prefer_unicast,
+        }
+    }
+}
+
+impl<'a> From<&ResourceRecord<'a>> for StoredRecord {
+    fn from(record: &ResourceRecord<'a>) -> StoredRecord {
+        StoredRecord {
+            name: wire_name(&record.name),
+            typ: record.data.typ().code(),
+            cls: record.cls.code(),
+            multicast_unique: record.multicast_unique,
+            ttl: record.ttl.as_secs(),
+            data: wire_data(&record.data),
+        }
+    }
+}
+
+// Builds the packet from its parts, the names are checked but they have
+// no compression pointers to follow
+fn packet<'a, Q, R>(
+    id: u16,
+    flags: u16,
+    questions: Q,
+    [answers, nameservers, additional]: [R; 3],
+    opt: Option<opt::Record<'a>>,
+) -> Result<Packet<'a>, Error>
+where
+    Q: Iterator<Item = Result<Question<'a>, Error>>,
+    R: Iterator<Item = Result<ResourceRecord<'a>, Error>>,
+{
+    let questions = questions.collect::<Result<Questions<'_>, Error>>()?;
+    let answers = answers.collect::<Result<Records<'_>, Error>>()?;
+    let nameservers = nameservers.collect::<Result<Records<'_>, Error>>()?;
+    let additional = additional.collect::<Result<Records<'_>, Error>>()?;
+    let header = Header {
+        id,
+        questions: questions.len() as u16,
+        answers: answers.len() as u16,
+        nameservers: nameservers.len() as u16,
+        additional: (additional.len() + opt.is_some() as usize) as u16,
+        ..Header::from_flags_word(flags)
+    };
+    Ok(Packet {
+        header,
+        questions,
+        answers,
+        nameservers,
+        additional,
+        opt,
+    })
+}
+
+fn question(name: &[u8], qtype: u16, qclass: u16, unicast: bool) -> Result<Question<'_>, Error> {
+    Ok(Question {
+        qname: Name::scan(name, name)?,
+        prefer_unicast: unicast,
+        qtype: QueryType::parse(qtype),
+        qclass: QueryClass::parse(qclass),
+    })
+}
+
+fn record<'a>(
+    name: &'a [u8],
+    [typ, cls]: [u16; 2],
+    multicast_unique: bool,
+    ttl: u32,
+    data: &'a [u8],
+) -> Result<ResourceRecord<'a>, Error> {
+    Ok(ResourceRecord {
+        name: Name::scan(name, name)?,
+        multicast_unique,
+        cls: Class::parse(cls),
+        ttl: Ttl::new(ttl),
+        data: RData::parse(Type::parse(typ), data, data)?,
+    })
+}
+
+impl StoredPacket {
+    /// Returns the packet, borrowing the names and data
+    pub fn to_packet(&self) -> Result<Packet<'_>, Error> {
+        fn records(
+            records: &[StoredRecord],
+        ) -> impl Iterator<Item = Result<ResourceRecord<'_>, Error>> {
+            records.iter().map(StoredRecord::to_record)
+        }
+        packet(
+            self.id,
+            self.flags,
+            self.questions.iter().map(StoredQuestion::to_question),
+            [
+                records(&self.answers),
+                records(&self.nameservers),
+                records(&self.additional),
+            ],
+            self.opt.as_ref().map(|opt| opt::Record {
+                udp: opt.udp,
+                extrcode: opt.extrcode,
+                version: opt.version,
+                flags: opt.flags,
+                data: RData::OPT(Cow::Borrowed(&opt.data)),
+            }),
+        )
+    }
+}
+
+impl StoredQuestion {
+    /// Returns the question, borrowing the name
+    pub fn to_question(&self) -> Result<Question<'_>, Error> {
+        question(&self.name, self.qtype, self.qclass, self.prefer_unicast)
+    }
+}
+
+impl StoredRecord {
+    /// Returns the record, borrowing the name and data
+    pub fn to_record(&self) -> Result<ResourceRecord<'_>, Error> {
+        let codes = [self.typ, self.cls];
+        record(
+            &self.name,
+            codes,
+            self.multicast_unique,
+            self.ttl,
+            &self.data,
+        )
+    }
+}
+
+impl ArchivedStoredPacket {
+    /// Returns the packet, borrowing the names and data from the archive
+    pub fn to_packet(&self) -> Result<Packet<'_>, Error> {
+        fn records(
+            records: &[ArchivedStoredRecord],
+        ) -> impl Iterator<Item = Result<ResourceRecord<'_>, Error>> {
+            records.iter().map(ArchivedStoredRecord::to_record)
+        }
+        packet(
+            self.id.to_native(),
+            self.flags.to_native(),
+            self.questions
+                .iter()
+                .map(ArchivedStoredQuestion::to_question),
+            [
+                records(&self.answers),
+                records(&self.nameservers),
+                records(&self.additional),
+            ],
+            self.opt.as_ref().map(|opt| opt::Record {
+                udp: opt.udp.to_native(),
+                extrcode: opt.extrcode,
+                version: opt.version,
+                flags: opt.flags.to_native(),
+                data: RData::OPT(Cow::Borrowed(&opt.data)),
+            }),
+        )
+    }
+}
+
+impl ArchivedStoredQuestion {
+    /// Returns the question, borrowing the name from the archive
+    pub fn to_question(&self) -> Result<Question<'_>, Error> {
+        let (qtype, qclass) = (self.qtype.to_native(), self.qclass.to_native());
+        question(&self.name, qtype, qclass, self.prefer_unicast)
+    }
+}
+
+impl ArchivedStoredRecord {
+    /// Returns the record, borrowing the name and data from the archive
+    pub fn to_record(&self) -> Result<ResourceRecord<'_>, Error> {
+        let codes = [self.typ.to_native(), self.cls.to_native()];
+        let ttl = self.ttl.to_native();
+        record(&self.name, codes, self.multicast_unique, ttl, &self.data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rkyv::rancor::Error;
+
+    use super::{ArchivedStoredPacket, StoredPacket};
+    use crate::Packet;
+
+    #[test]
+    fn round_trip() {
+        // an MX answer pointing into the question and an OPT record
+        let response = b"\x00\x07\x81\x80\x00\x01\x00\x01\x00\x00\x00\x01\
+            \x07example\x03com\x00\x00\x0f\x00\x01\
+            \xc0\x0c\x00\x0f\x00\x01\x00\x00\x0e\x10\x00\x07\x00\x0a\x02mx\xc0\x0c\
+            \x00\x00\x29\x10\x00\x00\x00\x80\x00\x00\x00";
+        let packet = Packet::parse(response).unwrap();
+        let stored = StoredPacket::from(&packet);
+        assert_eq!(
+            stored.answers[0].data,
+            b"\x00\x0a\x02mx\x07example\x03com\x00"
+        );
+        assert_eq!(stored.to_packet().unwrap(), packet);
+
+        let bytes = rkyv::to_bytes::<Error>(&stored).unwrap();
+        let archived = rkyv::access::<ArchivedStoredPacket, Error>(&bytes).unwrap();
+        let restored = archived.to_packet().unwrap();
+        assert_eq!(restored, packet);
+        assert_eq!(restored.header(), packet.header());
+        assert_eq!(
+            rkyv::deserialize::<StoredPacket, Error>(archived).unwrap(),
+            stored
+        );
+    }
+}
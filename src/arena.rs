@@ -0,0 +1,250 @@
+use std::alloc::Layout;
+use std::mem::ManuallyDrop;
+
+use crate::name::NameCache;
+use crate::parser::parse_packet;
+use crate::rdata::opt;
+use crate::small_vec::SmallVec;
+use crate::structs::{Questions, Records};
+use crate::{Error, Packet, ParseOptions, Question, ResourceRecord};
+
+/// Scratch space reused to parse many packets
+///
+/// `Packet::parse` allocates the lists of questions and records of larger
+/// packets and a table of the compression pointers followed. The arena
+/// keeps those buffers once a packet is given back with `recycle`, so a
+/// loop over a capture allocates nothing once it went through the
+/// largest packets.
+///
+/// ```
+/// # use dns_parser::{Error, ParseArena};
+/// # fn analyze(packets: &[Vec<u8>]) -> Result<usize, Error> {
+/// let mut arena = ParseArena::new();
+/// let mut answers = 0;
+/// for data in packets {
+///     let packet = arena.parse(data)?;
+///     answers += packet.answers().len();
+///     arena.recycle(packet);
+/// }
+/// # Ok(answers)
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct ParseArena {
+    names: NameCache,
+    questions: Vec<Vec<Question<'static>>>,
+    records: Vec<Vec<ResourceRecord<'static>>>,
+}
+
+impl ParseArena {
+    /// Creates an empty arena, it allocates nothing until used
+    pub fn new() -> ParseArena {
+        ParseArena::default()
+    }
+    /// Parse a full DNS packet like `Packet::parse` does
+    pub fn parse<'d>(&mut self, data: &'d [u8]) -> Result<Packet<'d>, Error> {
+        parse_packet(data, &ParseOptions::default(), self)
+    }
+    /// Parse a full DNS packet like `Packet::parse_with_options` does
+    pub fn parse_with_options<'d>(
+        &mut self,
+        data: &'d [u8],
+        options: &ParseOptions,
+    ) -> Result<Packet<'d>, Error> {
+        parse_packet(data, options, self)
+    }
+    /// Keeps the buffers of a packet, to be used for the next ones
+    ///
+    /// The packet doesn't have to be one parsed with this arena.
+    pub fn recycle(&mut self, packet: Packet<'_>) {
+        if let Some(questions) = packet.questions.into_vec() {
+            self.questions.push(detach(questions));
+        }
+        for records in [packet.answers, packet.nameservers, packet.additional] {
+            if let Some(records) = records.into_vec() {
+                self.records.push(detach(records));
+            }
+        }
+    }
+    /// Frees all the buffers kept, e.g. after a burst of large packets
+    pub fn reset(&mut self) {
+        *self = ParseArena::default();
+    }
+    pub(crate) fn names(&mut self) -> &mut NameCache {
+        self.names.clear();
+        &mut self.names
+    }
+    pub(crate) fn questions<'d>(&mut self, capacity: usize) -> Questions<'d> {
+        SmallVec::with_spare(capacity, || self.questions.pop())
+    }
+    pub(crate) fn records<'d>(&mut self, capacity: usize) -> Records<'d> {
+        SmallVec::with_spare(capacity, || self.records.pop())
+    }
+}
+
+/// Lists of questions and records owned by the caller
+///
+/// Filled by `Packet::parse_into`, which clears the lists but keeps their
+/// capacity. The lists borrow the buffer of the last packet parsed, use
+/// `recycle` to parse a packet received into the same buffer again.
+///
+/// ```
+/// # use dns_parser::{Error, Packet, PacketStorage};
+/// # fn analyze(packets: &[Vec<u8>]) -> Result<usize, Error> {
+/// let mut storage = PacketStorage::new();
+/// let mut answers = 0;
+/// for data in packets {
+///     let mut current = storage.recycle();
+///     Packet::parse_into(data, &mut current)?;
+///     answers += current.answers().len();
+///     storage = current.recycle();
+/// }
+/// # Ok(answers)
+/// # }
+/// ```
+#[derive(Debug, Default)]
+pub struct PacketStorage<'a> {
+    pub(crate) questions: Vec<Question<'a>>,
+    pub(crate) answers: Vec<ResourceRecord<'a>>,
+    pub(crate) nameservers: Vec<ResourceRecord<'a>>,
+    pub(crate) additional: Vec<ResourceRecord<'a>>,
+    pub(crate) opt: Option<opt::Record<'a>>,
+    pub(crate) names: NameCache,
+}
+
+impl<'a> PacketStorage<'a> {
+    /// Creates empty lists, they allocate nothing until used
+    pub fn new() -> PacketStorage<'a> {
+        PacketStorage::default()
+    }
+    /// Returns the question section
+    pub fn questions(&self) -> &[Question<'a>] {
+        &self.questions
+    }
+    /// Returns the answer section
+    pub fn answers(&self) -> &[ResourceRecord<'a>] {
+        &self.answers
+    }
+    /// Returns the authority section
+    pub fn nameservers(&self) -> &[ResourceRecord<'a>] {
+        &self.nameservers
+    }
+    /// Returns the additional section, without the OPT pseudo-record
+    pub fn additional(&self) -> &[ResourceRecord<'a>] {
+        &self.additional
+    }
+    /// Returns the OPT pseudo-record (RFC 6891)
+    pub fn opt(&self) -> Option<&opt::Record<'a>> {
+        self.opt.as_ref()
+    }
+    /// Empties the lists, keeping their capacity
+    pub fn clear(&mut self) {
+        self.questions.clear();
+        self.answers.clear();
+        self.nameservers.clear();
+        self.additional.clear();
+        self.opt = None;
+        self.names.clear();
+    }
+    /// Empties the lists and releases the buffer they borrow
+    ///
+    /// The capacity of the lists is kept.
+    pub fn recycle<'b>(self) -> PacketStorage<'b> {
+        PacketStorage {
+            questions: detach(self.questions),
+            answers: detach(self.answers),
+            nameservers: detach(self.nameservers),
+            additional: detach(self.additional),
+            opt: None,
+            names: self.names,
+        }
+    }
+}
+
+// Empties a buffer and detaches it from the packet it was borrowing from,
+// keeping its allocation
+//
+// `T` and `U` are the same type with different lifetimes. The layouts are
+// checked anyway, the assertion is decided at compile time.
+fn detach<T, U>(mut items: Vec<T>) -> Vec<U> {
+    assert!(Layout::new::<T>() == Layout::new::<U>());
+    items.clear();
+    let mut items = ManuallyDrop::new(items);
+    // SAFETY: the allocation is handed over to a `Vec` of the same
+    // capacity whose items have the same size and alignment, so it's freed
+    // with the layout it was allocated with. The list is empty, no `T` is
+    // read as a `U`.
+    unsafe { Vec::from_raw_parts(items.as_mut_ptr() as *mut U, 0, items.capacity()) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{detach, PacketStorage, ParseArena};
+    use crate::{Builder, Packet, QueryClass, QueryType, ResourceRecord};
+
+    #[test]
+    fn detach_keeps_capacity() {
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+            \x07example\x00\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x01"
+            .to_vec();
+        let packet = Packet::parse(&response).unwrap();
+        let mut records = Vec::<ResourceRecord<'_>>::with_capacity(16);
+        records.extend(packet.answers().iter().cloned());
+        let pointer = records.as_ptr() as *const u8;
+        let detached: Vec<ResourceRecord<'static>> = detach(records);
+        drop(response);
+        assert!(detached.is_empty());
+        assert_eq!(detached.capacity(), 16);
+        assert_eq!(detached.as_ptr() as *const u8, pointer);
+    }
+
+    #[test]
+    fn reuse_buffers() {
+        let mut builder = Builder::new_query(1, true);
+        for name in &["a.example", "b.example", "c.example"] {
+            builder.add_question(name, false, QueryType::A, QueryClass::IN);
+        }
+        let query = builder.build().unwrap();
+
+        let mut arena = ParseArena::new();
+        let packet = arena.parse(&query).unwrap();
+        assert_eq!(packet.questions().len(), 3);
+        let pointer = packet.questions().as_ptr();
+        arena.recycle(packet);
+        assert_eq!(arena.questions.len(), 1);
+        assert!(arena.questions[0].capacity() >= 3);
+
+        let packet = arena.parse(&query).unwrap();
+        assert_eq!(packet.questions().as_ptr(), pointer);
+        assert!(arena.questions.is_empty());
+        assert_eq!(packet.questions()[2].qname.to_string(), "c.example");
+        arena.recycle(packet);
+        arena.reset();
+        assert!(arena.questions.is_empty());
+    }
+
+    #[test]
+    fn parse_into() {
+        let mut builder = Builder::new_query(1, true);
+        for name in &["a.example", "b.example", "c.example"] {
+            builder.add_question(name, false, QueryType::A, QueryClass::IN);
+        }
+        let query = builder.build().unwrap();
+
+        let mut storage = PacketStorage::new();
+        let header = Packet::parse_into(&query, &mut storage).unwrap();
+        assert_eq!(header.questions, 3);
+        assert_eq!(storage.questions()[1].qname.to_string(), "b.example");
+        let pointer = storage.questions().as_ptr();
+        let mut storage = storage.recycle();
+        assert!(storage.questions().is_empty());
+
+        let mut buf = query.clone();
+        buf[11] = 1; // one additional record, which is missing
+        assert!(Packet::parse_into(&buf, &mut storage).is_err());
+        let mut storage = storage.recycle();
+        Packet::parse_into(&query, &mut storage).unwrap();
+        assert_eq!(storage.questions().len(), 3);
+        assert_eq!(storage.questions().as_ptr(), pointer);
+    }
+}
@@ -0,0 +1,125 @@
+//! Bailiwick checks of responses (RFC 5452 section 6)
+//!
+//! A server is only authoritative for names in its zone, the bailiwick.
+//! Records for other names in its response may be an attempt to poison
+//! the cache of the resolver, so they must not be cached. Resolvers
+//! usually drop them right after parsing with `Packet::sanitize_bailiwick`
+//! before looking at the response any further.
+//!
+//! The zone is the one the query was sent to, e.g. `example.com` when
+//! asking a name server for `example.com` found in a referral. Records
+//! at or below the zone are kept, which includes glue for name servers
+//! inside the zone.
+use std::mem;
+
+use crate::structs::Records;
+use crate::{Name, Packet, ResourceRecord, Section};
+
+/// Returns the records of a packet with owner names outside of `zone`
+///
+/// Records are identified by their section and index in it, the OPT
+/// record is never included.
+pub fn out_of_bailiwick(packet: &Packet<'_>, zone: &Name<'_>) -> Vec<(Section, usize)> {
+    let sections = [
+        (Section::Answer, packet.answers()),
+        (Section::Authority, packet.nameservers()),
+        (Section::Additional, packet.additional()),
+    ];
+    sections
+        .iter()
+        .flat_map(|&(section, records)| {
+            records
+                .iter()
+                .enumerate()
+                .filter(|(_, record)| !record.name.is_subdomain_of(zone))
+                .map(move |(index, _)| (section, index))
+        })
+        .collect()
+}
+
+// Removes the records outside of the zone, appending them to `removed`
+fn retain_in_zone<'a>(
+    records: &mut Records<'a>,
+    section: Section,
+    zone: &Name<'_>,
+    removed: &mut Vec<(Section, ResourceRecord<'a>)>,
+) {
+    let (kept, dropped): (Records<'a>, Records<'a>) = mem::take(records)
+        .into_iter()
+        .partition(|record| record.name.is_subdomain_of(zone));
+    *records = kept;
+    removed.extend(dropped.into_iter().map(|record| (section, record)));
+}
+
+impl<'a> Packet<'a> {
+    /// Removes the records with owner names outside of `zone`
+    ///
+    /// See the `bailiwick` module. The record counts of the header are
+    /// updated. Returns the records removed along with their section, so
+    /// they can be logged.
+    pub fn sanitize_bailiwick(&mut self, zone: &Name<'_>) -> Vec<(Section, ResourceRecord<'a>)> {
+        let mut removed = Vec::new();
+        retain_in_zone(&mut self.answers, Section::Answer, zone, &mut removed);
+        retain_in_zone(
+            &mut self.nameservers,
+            Section::Authority,
+            zone,
+            &mut removed,
+        );
+        retain_in_zone(
+            &mut self.additional,
+            Section::Additional,
+            zone,
+            &mut removed,
+        );
+        self.header.answers = self.answers.len() as u16;
+        self.header.nameservers = self.nameservers.len() as u16;
+        self.header.additional = (self.additional.len() + self.opt.is_some() as usize) as u16;
+        removed
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::out_of_bailiwick;
+    use crate::{Packet, Section};
+
+    #[test]
+    fn sanitize() {
+        // answer for www.example.com from the example.com servers, with
+        // an NS record for com pointing to evil.example.net and an A
+        // record for it in the additional section
+        let response = b"\x00\x01\x81\x80\x00\x01\x00\x01\x00\x02\x00\x02\
+                         \x03www\x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xc0\x00\x02\x01\
+                         \xc0\x10\x00\x02\x00\x01\x00\x00\x0e\x10\x00\x05\x02ns\xc0\x10\
+                         \xc0\x18\x00\x02\x00\x01\x00\x00\x0e\x10\x00\x12\
+                         \x04evil\x07example\x03net\x00\
+                         \xc0\x3d\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xc0\x00\x02\x35\
+                         \xc0\x4e\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xcb\x00\x71\x01";
+        let mut packet = Packet::parse(response).unwrap();
+        let zone = packet.nameservers()[0].name.clone();
+        assert_eq!(
+            out_of_bailiwick(&packet, &zone),
+            [(Section::Authority, 1), (Section::Additional, 1)]
+        );
+
+        let zone = zone.into_owned();
+        let removed = packet
+            .sanitize_bailiwick(&zone)
+            .into_iter()
+            .map(|(section, record)| (section, record.name.to_string()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            removed,
+            [
+                (Section::Authority, "com".to_string()),
+                (Section::Additional, "evil.example.net".to_string())
+            ]
+        );
+        assert_eq!(packet.header().nameservers, 1);
+        assert_eq!(packet.header().additional, 1);
+        assert_eq!(packet.additional()[0].name.to_string(), "ns.example.com");
+        assert!(out_of_bailiwick(&packet, &zone).is_empty());
+    }
+}
@@ -0,0 +1,79 @@
+//! Parsing many packets in parallel
+//!
+//! [`parse`] parses a batch of buffers, e.g. the messages read from a
+//! capture, on the threads of the global rayon pool. The results are in
+//! the order of the buffers. Each thread parses with its own
+//! [`ParseArena`], nothing mutable is shared between them.
+//!
+//! ```
+//! # use dns_parser::batch;
+//! # fn count(messages: &[Vec<u8>]) -> usize {
+//! let answers = batch::parse(messages)
+//!     .iter()
+//!     .filter_map(|result| result.as_ref().ok())
+//!     .map(|packet| packet.answers().len())
+//!     .sum();
+//! # answers
+//! # }
+//! ```
+//!
+//! This module is enabled by the `rayon` feature.
+//!
+//! [`parse`]: fn.parse.html
+//! [`ParseArena`]: ../struct.ParseArena.html
+use rayon::prelude::*;
+
+use crate::{Error, Packet, ParseArena, ParseOptions};
+
+/// Parses every buffer in parallel, like `Packet::parse` does
+///
+/// Returns one result per buffer, in the same order.
+pub fn parse<B>(buffers: &[B]) -> Vec<Result<Packet<'_>, Error>>
+where
+    B: AsRef<[u8]> + Sync,
+{
+    parse_with_options(buffers, &ParseOptions::default())
+}
+
+/// Parses every buffer in parallel, like `Packet::parse_with_options` does
+pub fn parse_with_options<'d, B>(
+    buffers: &'d [B],
+    options: &ParseOptions,
+) -> Vec<Result<Packet<'d>, Error>>
+where
+    B: AsRef<[u8]> + Sync,
+{
+    buffers
+        .par_iter()
+        .map_init(ParseArena::new, |arena, buffer| {
+            arena.parse_with_options(buffer.as_ref(), options)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse;
+    use crate::{Builder, Error, QueryClass, QueryType};
+
+    #[test]
+    fn in_order() {
+        let mut buffers = (0..100u16)
+            .map(|id| {
+                let mut builder = Builder::new_query(id, true);
+                builder.add_question("example.com", false, QueryType::A, QueryClass::IN);
+                builder.build().unwrap()
+            })
+            .collect::<Vec<_>>();
+        buffers[42].truncate(20);
+        let results = parse(&buffers);
+        assert_eq!(results.len(), 100);
+        for (id, result) in results.iter().enumerate() {
+            match result {
+                Ok(packet) => assert_eq!(packet.header().id as usize, id),
+                Err(e) if matches!(e.root(), Error::UnexpectedEOF) => assert_eq!(id, 42),
+                Err(e) => panic!("unexpected error {}", e),
+            }
+        }
+    }
+}
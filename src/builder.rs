@@ -1,6 +1,7 @@
 use std::{convert::TryInto, io::Write};
 
-use crate::{Header, Opcode, QueryClass, QueryType, ResponseCode};
+use crate::rdata::opt;
+use crate::{Header, Name, QueryClass, QueryType, ResourceRecord};
 
 /// Allows to build a DNS packet
 ///
@@ -20,19 +21,27 @@ impl Builder {
         let mut buf = Vec::with_capacity(512);
         let head = Header {
             id,
-            query: true,
-            opcode: Opcode::StandardQuery,
-            authoritative: false,
-            truncated: false,
             recursion_desired: recursion,
-            recursion_available: false,
-            authenticated_data: false,
-            checking_disabled: false,
-            response_code: ResponseCode::NoError,
+            ..Header::default()
+        };
+        buf.extend([0u8; 12].iter());
+        head.write(&mut buf[..12]);
+        Builder { buf }
+    }
+
+    /// Creates a new packet with the ID and flags taken from `header`
+    ///
+    /// Section counts of the header are ignored, all sections are empty
+    /// initially. This is useful for building responses and for
+    /// rewriting parsed packets.
+    pub fn with_header(header: &Header) -> Builder {
+        let mut buf = Vec::with_capacity(512);
+        let head = Header {
             questions: 0,
             answers: 0,
             nameservers: 0,
             additional: 0,
+            ..*header
         };
         buf.extend([0u8; 12].iter());
         head.write(&mut buf[..12]);
@@ -57,18 +66,123 @@ impl Builder {
             panic!("Too late to add a question");
         }
         self.write_name(qname);
-        self.buf.extend((qtype as u16).to_be_bytes());
+        self.write_question_tail(prefer_unicast, qtype, qclass);
+        self
+    }
+
+    /// Adds a question with a name taken from a parsed packet
+    ///
+    /// # Panics
+    ///
+    /// * Answers, nameservers or additional section has already been written
+    /// * There are already 65535 questions in the buffer.
+    pub fn add_question_name(
+        &mut self,
+        qname: &Name<'_>,
+        prefer_unicast: bool,
+        qtype: QueryType,
+        qclass: QueryClass,
+    ) -> &mut Builder {
+        if &self.buf[6..12] != b"\x00\x00\x00\x00\x00\x00" {
+            panic!("Too late to add a question");
+        }
+        qname.write_to(&mut self.buf);
+        self.write_question_tail(prefer_unicast, qtype, qclass);
+        self
+    }
+    fn write_question_tail(&mut self, prefer_unicast: bool, qtype: QueryType, qclass: QueryClass) {
+        self.buf.extend(qtype.code().to_be_bytes());
         let prefer_unicast: u16 = if prefer_unicast { 0x8000 } else { 0x0000 };
         self.buf
-            .write_all(&(qclass as u16 | prefer_unicast).to_be_bytes())
+            .write_all(&(qclass.code() | prefer_unicast).to_be_bytes())
             .unwrap();
-        let oldq = u16::from_be_bytes(self.buf[4..6].try_into().unwrap());
-        if oldq == 65535 {
-            panic!("Too many questions");
+        self.increment_count(4, "Too many questions");
+    }
+
+    /// Adds a record to the answer section
+    ///
+    /// # Panics
+    ///
+    /// * Nameservers or additional section has already been written
+    /// * There are already 65535 answers in the buffer.
+    pub fn add_answer(&mut self, record: &ResourceRecord<'_>) -> &mut Builder {
+        if &self.buf[8..12] != b"\x00\x00\x00\x00" {
+            panic!("Too late to add an answer");
         }
-        self.buf.splice(4..6, (oldq + 1).to_be_bytes());
+        self.write_record(record);
+        self.increment_count(6, "Too many answers");
         self
     }
+
+    /// Adds a record to the authority (nameservers) section
+    ///
+    /// # Panics
+    ///
+    /// * Additional section has already been written
+    /// * There are already 65535 nameservers in the buffer.
+    pub fn add_nameserver(&mut self, record: &ResourceRecord<'_>) -> &mut Builder {
+        if &self.buf[10..12] != b"\x00\x00" {
+            panic!("Too late to add a nameserver");
+        }
+        self.write_record(record);
+        self.increment_count(8, "Too many nameservers");
+        self
+    }
+
+    /// Adds a record to the additional section
+    ///
+    /// # Panics
+    ///
+    /// * There are already 65535 additional records in the buffer.
+    pub fn add_additional(&mut self, record: &ResourceRecord<'_>) -> &mut Builder {
+        self.write_record(record);
+        self.increment_count(10, "Too many additional records");
+        self
+    }
+
+    /// Adds an RFC 6891 OPT pseudo-record to the additional section
+    ///
+    /// # Panics
+    ///
+    /// * There are already 65535 additional records in the buffer.
+    pub fn add_opt(&mut self, opt: &opt::Record<'_>) -> &mut Builder {
+        self.buf.push(0);
+        self.buf.extend(opt.data.typ().code().to_be_bytes());
+        self.buf.extend(opt.udp.to_be_bytes());
+        self.buf.push(opt.extrcode);
+        self.buf.push(opt.version);
+        self.buf.extend(opt.flags.to_be_bytes());
+        self.write_rdata(|buf| opt.data.write_to(buf));
+        self.increment_count(10, "Too many additional records");
+        self
+    }
+    fn write_record(&mut self, record: &ResourceRecord<'_>) {
+        record.name.write_to(&mut self.buf);
+        self.buf.extend(record.data.typ().code().to_be_bytes());
+        let unique: u16 = if record.multicast_unique {
+            0x8000
+        } else {
+            0x0000
+        };
+        self.buf.extend((record.cls.code() | unique).to_be_bytes());
+        self.buf.extend(record.ttl.0.to_be_bytes());
+        self.write_rdata(|buf| record.data.write_to(buf));
+    }
+    fn write_rdata<F: FnOnce(&mut Vec<u8>)>(&mut self, write: F) {
+        let start = self.buf.len();
+        self.buf.extend([0u8; 2].iter());
+        write(&mut self.buf);
+        let rdlen = self.buf.len() - start - 2;
+        assert!(rdlen <= 65535, "Record data is too long");
+        self.buf[start..start + 2].copy_from_slice(&(rdlen as u16).to_be_bytes());
+    }
+    fn increment_count(&mut self, offset: usize, overflow: &str) {
+        let old = u16::from_be_bytes(self.buf[offset..offset + 2].try_into().unwrap());
+        if old == 65535 {
+            panic!("{}", overflow);
+        }
+        self.buf[offset..offset + 2].copy_from_slice(&(old + 1).to_be_bytes());
+    }
     fn write_name(&mut self, name: &str) {
         for part in name.split('.') {
             assert!(part.len() < 63);
@@ -95,6 +209,7 @@ impl Builder {
     // to treat it for EDNS0?
     pub fn build(mut self) -> Result<Vec<u8>, Vec<u8>> {
         // TODO(tailhook) optimize labels
+        trace_event!(debug, len = self.buf.len(), "built packet");
         if self.buf.len() > 512 {
             Header::set_truncated(&mut self.buf[..12]);
             Err(self.buf)
@@ -107,6 +222,7 @@ impl Builder {
 #[cfg(test)]
 mod test {
     use super::Builder;
+    use crate::Packet;
     use crate::QueryClass as QC;
     use crate::QueryType as QT;
 
@@ -136,4 +252,56 @@ mod test {
             \x0c_xmpp-server\x04_tcp\x05gmail\x03com\x00\x00!\x00\x01";
         assert_eq!(&bld.build().unwrap()[..], &result[..]);
     }
+
+    #[test]
+    fn build_response() {
+        let response = b"\x4a\xf0\x81\x80\x00\x01\x00\x01\x00\x01\x00\x01\
+                          \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+                          \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\
+                          \x00\x1c\x07\x6c\x69\x76\x65\x63\x6d\x73\x0e\x74\
+                          \x72\x61\x66\x66\x69\x63\x6d\x61\x6e\x61\x67\x65\
+                          \x72\x03\x6e\x65\x74\x00\
+                          \xc0\x42\x00\x02\x00\x01\x00\x01\xd5\xd3\x00\x11\
+                          \x01\x67\x0c\x67\x74\x6c\x64\x2d\x73\x65\x72\x76\x65\x72\x73\
+                          \xc0\x42\
+                          \x01\x61\xc0\x55\x00\x01\x00\x01\x00\x00\xa3\x1c\
+                          \x00\x04\xc0\x05\x06\x1e";
+        let packet = Packet::parse(response).unwrap();
+        let mut bld = Builder::with_header(packet.header());
+        let q = &packet.questions()[0];
+        bld.add_question_name(&q.qname, q.prefer_unicast, q.qtype, q.qclass);
+        bld.add_answer(&packet.answers()[0]);
+        bld.add_nameserver(&packet.nameservers()[0]);
+        bld.add_additional(&packet.additional()[0]);
+        let data = bld.build().unwrap();
+        // names are not compressed by the builder
+        assert!(data.len() > response.len());
+        assert_eq!(Packet::parse(&data).unwrap(), packet);
+    }
+
+    #[test]
+    fn build_opt() {
+        let query = b"\x95\xce\x01\x00\x00\x01\x00\x00\x00\x00\x00\x01\
+            \x06google\x03com\x00\x00\x01\x00\
+            \x01\x00\x00\x29\x10\x00\x00\x00\x80\x00\x00\x00";
+        let packet = Packet::parse(query).unwrap();
+        let mut bld = Builder::new_query(0x95ce, true);
+        bld.add_question("google.com", false, QT::A, QC::IN);
+        bld.add_opt(packet.opt().unwrap());
+        assert_eq!(&bld.build().unwrap()[..], &query[..]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Too late to add an answer")]
+    fn answer_after_additional() {
+        let packet = Packet::parse(
+            b"\x06%\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+              \x07example\x03com\x00\x00\x01\x00\x01\x00\x00\x04\xf8\
+              \x00\x04]\xb8\xd8\"",
+        )
+        .unwrap();
+        let mut bld = Builder::with_header(packet.header());
+        bld.add_additional(&packet.answers()[0]);
+        bld.add_answer(&packet.answers()[0]);
+    }
 }
@@ -0,0 +1,158 @@
+use crate::{Header, Packet, ResourceRecord};
+
+impl<'a> Packet<'a> {
+    /// Serializes the packet in a normalized form
+    ///
+    /// Two packets carrying the same data give the same bytes, which is
+    /// useful for deduplication and hashing:
+    ///
+    /// * the ID is zero;
+    /// * names are lowercased and not compressed;
+    /// * records of each section are sorted by owner name in the
+    ///   canonical order (RFC 4034 section 6.1), then by type, class and
+    ///   data;
+    /// * the OPT record is at the end of the additional section.
+    ///
+    /// The result is a valid DNS message. TTLs are kept as is.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let header = Header {
+            id: 0,
+            questions: self.questions.len() as u16,
+            answers: self.answers.len() as u16,
+            nameservers: self.nameservers.len() as u16,
+            additional: (self.additional.len() + self.opt.is_some() as usize) as u16,
+            ..self.header
+        };
+        let mut buf = vec![0; Header::size()];
+        header.write(&mut buf);
+        for question in &self.questions {
+            question.qname.write_canonical_to(&mut buf);
+            buf.extend_from_slice(&question.qtype.code().to_be_bytes());
+            let unicast = if question.prefer_unicast { 0x8000 } else { 0 };
+            buf.extend_from_slice(&(question.qclass.code() | unicast).to_be_bytes());
+        }
+        for section in &[&self.answers, &self.nameservers, &self.additional] {
+            let mut records = section
+                .iter()
+                .map(|record| {
+                    let mut rdata = Vec::new();
+                    record.data.write_canonical_to(&mut rdata);
+                    (record, rdata)
+                })
+                .collect::<Vec<_>>();
+            records.sort_by(|(a, a_data), (b, b_data)| {
+                a.name
+                    .canonical_cmp(&b.name)
+                    .then(a.data.typ().code().cmp(&b.data.typ().code()))
+                    .then(a.cls.code().cmp(&b.cls.code()))
+                    .then(a_data.cmp(b_data))
+                    .then(a.ttl.cmp(&b.ttl))
+            });
+            for (record, rdata) in records {
+                let unique = if record.multicast_unique { 0x8000 } else { 0 };
+                let class = record.cls.code() | unique;
+                write_record(&mut buf, record, class, record.ttl.0, &rdata);
+            }
+        }
+        if let Some(ref opt) = self.opt {
+            buf.push(0);
+            buf.extend_from_slice(&opt.data.typ().code().to_be_bytes());
+            buf.extend_from_slice(&opt.udp.to_be_bytes());
+            buf.push(opt.extrcode);
+            buf.push(opt.version);
+            buf.extend_from_slice(&opt.flags.to_be_bytes());
+            let mut rdata = Vec::new();
+            opt.data.write_canonical_to(&mut rdata);
+            buf.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            buf.extend_from_slice(&rdata);
+        }
+        buf
+    }
+}
+
+impl<'a> ResourceRecord<'a> {
+    /// Writes the record in the canonical form (RFC 4034 section 6.2)
+    ///
+    /// The owner name and the names in the record data are lowercased and
+    /// uncompressed, and the TTL is replaced with `original_ttl`, i.e. the
+    /// Original TTL field of the covering RRSIG. This is the form DNSSEC
+    /// signatures are computed over. The mDNS cache flush bit is not
+    /// written.
+    pub fn write_canonical_to(&self, original_ttl: u32, buf: &mut Vec<u8>) {
+        let mut rdata = Vec::new();
+        self.data.write_canonical_to(&mut rdata);
+        write_record(buf, self, self.cls.code(), original_ttl, &rdata);
+    }
+}
+
+fn write_record(
+    buf: &mut Vec<u8>,
+    record: &ResourceRecord<'_>,
+    class: u16,
+    ttl: u32,
+    rdata: &[u8],
+) {
+    record.name.write_canonical_to(buf);
+    buf.extend_from_slice(&record.data.typ().code().to_be_bytes());
+    buf.extend_from_slice(&class.to_be_bytes());
+    buf.extend_from_slice(&ttl.to_be_bytes());
+    buf.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+    buf.extend_from_slice(rdata);
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Packet;
+
+    #[test]
+    fn canonical_response() {
+        // compressed, mixed case, records out of order, nonzero ID
+        let response = b"\x06%\x81\x80\x00\x01\x00\x03\x00\x00\x00\x00\
+                         \x07EXAMPLE\x03com\x00\x00\x0f\x00\x01\
+                         \xc0\x0c\x00\x0f\x00\x01\x00\x00\x00\x3c\x00\x09\x00\x0a\x04MAIL\xc0\x0c\
+                         \xc0\x0c\x00\x0f\x00\x01\x00\x00\x00\x3c\x00\x09\x00\x05\x04mail\xc0\x0c\
+                         \x01a\xc0\x0c\x00\x0f\x00\x01\x00\x00\x00\x3c\x00\x03\x00\x05\x00";
+        let expected = b"\x00\x00\x81\x80\x00\x01\x00\x03\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x0f\x00\x01\
+                         \x07example\x03com\x00\x00\x0f\x00\x01\x00\x00\x00\x3c\x00\x14\
+                         \x00\x05\x04mail\x07example\x03com\x00\
+                         \x07example\x03com\x00\x00\x0f\x00\x01\x00\x00\x00\x3c\x00\x14\
+                         \x00\x0a\x04mail\x07example\x03com\x00\
+                         \x01a\x07example\x03com\x00\x00\x0f\x00\x01\x00\x00\x00\x3c\x00\x03\
+                         \x00\x05\x00";
+        let packet = Packet::parse(response).unwrap();
+        let canonical = packet.canonical_bytes();
+        assert_eq!(&canonical[..], &expected[..]);
+        // canonical form is stable
+        assert_eq!(
+            Packet::parse(&canonical).unwrap().canonical_bytes(),
+            canonical
+        );
+    }
+
+    #[test]
+    fn canonical_opt() {
+        let query = b"\x06%\x01\x00\x00\x01\x00\x00\x00\x00\x00\x01\
+                      \x07example\x03com\x00\x00\x01\x00\x01\
+                      \x00\x00\x29\x10\x00\x00\x00\x80\x00\x00\x00";
+        let canonical = Packet::parse(query).unwrap().canonical_bytes();
+        assert_eq!(&canonical[..2], b"\x00\x00");
+        assert_eq!(&canonical[2..], &query[2..]);
+    }
+
+    #[test]
+    fn canonical_record() {
+        // mDNS response with the cache flush bit and a compressed target
+        let response = b"\x00\x00\x84\x00\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x04Host\x05LOCAL\x00\x00\x05\x80\x01\x00\x00\x00\x78\x00\x08\
+                         \x05Alias\xc0\x11";
+        let packet = Packet::parse(response).unwrap();
+        let mut buf = Vec::new();
+        packet.answers()[0].write_canonical_to(3600, &mut buf);
+        assert_eq!(
+            &buf[..],
+            &b"\x04host\x05local\x00\x00\x05\x00\x01\x00\x00\x0e\x10\x00\x0d\
+               \x05alias\x05local\x00"[..]
+        );
+    }
+}
@@ -0,0 +1,295 @@
+//! Following CNAME and DNAME records within a response
+//!
+//! A response to a query for `www.example.com` may hold a CNAME record
+//! pointing to another name, then the records of the queried type at
+//! that name. `follow` walks such chains, so the caller gets the final
+//! name and its records. DNAME records (RFC 6672) are applied as well
+//! when there is no CNAME record for a name, which happens when the
+//! server leaves out the synthesized CNAME.
+//!
+//! ```
+//! # use dns_parser::Packet;
+//! # fn answer(packet: &Packet) {
+//! let question = &packet.questions()[0];
+//! match packet.follow_chain(&question.qname, question.qtype) {
+//!     Ok(chain) => println!("{} records at {}", chain.records.len(), chain.target),
+//!     Err(err) => println!("broken response: {}", err),
+//! }
+//! # }
+//! ```
+use quick_error::quick_error;
+
+use crate::{Name, Packet, QueryType, RData, ResourceRecord};
+
+quick_error! {
+    /// Error following a CNAME chain
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ChainError {
+        /// The chain leads back to a name already visited
+        Loop {
+            display("CNAME chain loops")
+        }
+        /// A name has several CNAME records with different targets
+        Ambiguous {
+            display("several CNAME records for one name")
+        }
+        /// The name substituted by a DNAME record is longer than 255 bytes
+        NameTooLong {
+            display("DNAME substitution yields a name too long")
+        }
+    }
+}
+
+/// The result of `follow`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Chain<'r, 'a> {
+    /// The CNAME and DNAME records followed, in order
+    pub links: Vec<&'r ResourceRecord<'a>>,
+    /// The name the chain ends at, the query name if there are no links
+    pub target: Name<'static>,
+    /// The records of the queried type at `target`
+    ///
+    /// Empty if the response doesn't have them, e.g. the target is in
+    /// another zone and has to be queried separately.
+    pub records: Vec<&'r ResourceRecord<'a>>,
+}
+
+/// Follows CNAME and DNAME records starting from `name`
+///
+/// Records of other names are ignored, as are records of the chain not
+/// reachable from `name`. Names are compared ignoring case. When `qtype`
+/// is `CNAME` or `ALL`, the records at `name` are returned as is without
+/// following them.
+pub fn follow<'r, 'a>(
+    records: &'r [ResourceRecord<'a>],
+    name: &Name<'_>,
+    qtype: QueryType,
+) -> Result<Chain<'r, 'a>, ChainError> {
+    let mut links = Vec::new();
+    let mut target = name.clone().into_owned();
+    while let Some(record) = link(records, &target, qtype)? {
+        let next_target = match record.data {
+            RData::CNAME(ref cname) => cname.0.clone().into_owned(),
+            RData::DNAME(ref dname) => substitute(&target, &record.name, &dname.0)?,
+            // `link` only returns CNAME and DNAME records
+            _ => break,
+        };
+        let visited = name.eq_ignore_ascii_case(&next_target)
+            || links
+                .iter()
+                .any(|link: &&ResourceRecord<'_>| link.name.eq_ignore_ascii_case(&next_target));
+        links.push(record);
+        if visited {
+            return Err(ChainError::Loop);
+        }
+        target = next_target;
+    }
+    let records = records
+        .iter()
+        .filter(|record| {
+            record.name.eq_ignore_ascii_case(&target)
+                && (qtype == QueryType::ALL || record.data.typ().code() == qtype.code())
+        })
+        .collect();
+    Ok(Chain {
+        links,
+        target,
+        records,
+    })
+}
+
+// Finds the record redirecting the name, if it is to be followed
+fn link<'r, 'a>(
+    records: &'r [ResourceRecord<'a>],
+    name: &Name<'_>,
+    qtype: QueryType,
+) -> Result<Option<&'r ResourceRecord<'a>>, ChainError> {
+    if matches!(qtype, QueryType::CNAME | QueryType::ALL) {
+        return Ok(None);
+    }
+    match cname(records, name)? {
+        Some(record) => Ok(Some(record)),
+        None => Ok(dname(records, name)),
+    }
+}
+
+fn cname<'r, 'a>(
+    records: &'r [ResourceRecord<'a>],
+    name: &Name<'_>,
+) -> Result<Option<&'r ResourceRecord<'a>>, ChainError> {
+    let mut found: Option<&ResourceRecord<'_>> = None;
+    for record in records {
+        if let RData::CNAME(ref cname) = record.data {
+            if !record.name.eq_ignore_ascii_case(name) {
+                continue;
+            }
+            match found {
+                Some(&ResourceRecord {
+                    data: RData::CNAME(ref other),
+                    ..
+                }) if !other.0.eq_ignore_ascii_case(&cname.0) => {
+                    return Err(ChainError::Ambiguous);
+                }
+                Some(_) => {}
+                None => found = Some(record),
+            }
+        }
+    }
+    Ok(found)
+}
+
+// Finds the DNAME record of the closest proper ancestor of the name
+fn dname<'r, 'a>(
+    records: &'r [ResourceRecord<'a>],
+    name: &Name<'_>,
+) -> Option<&'r ResourceRecord<'a>> {
+    let labels = name.labels().collect::<Vec<_>>();
+    records
+        .iter()
+        .filter(|record| matches!(record.data, RData::DNAME(..)))
+        .filter_map(|record| {
+            let owner = record.name.labels().collect::<Vec<_>>();
+            let ancestor = owner.len() < labels.len()
+                && owner
+                    .iter()
+                    .rev()
+                    .zip(labels.iter().rev())
+                    .all(|(a, b)| a.eq_ignore_ascii_case(b));
+            if ancestor {
+                Some((owner.len(), record))
+            } else {
+                None
+            }
+        })
+        .max_by_key(|&(len, _)| len)
+        .map(|(_, record)| record)
+}
+
+// Replaces the `owner` suffix of `name` with `target`
+fn substitute(
+    name: &Name<'_>,
+    owner: &Name<'_>,
+    target: &Name<'_>,
+) -> Result<Name<'static>, ChainError> {
+    let keep = name.labels().count() - owner.labels().count();
+    let mut wire = Vec::new();
+    for label in name.labels().take(keep).chain(target.labels()) {
+        wire.push(label.len() as u8);
+        wire.extend_from_slice(label);
+    }
+    wire.push(0);
+    if wire.len() > 255 {
+        return Err(ChainError::NameTooLong);
+    }
+    Ok(Name::from_uncompressed(wire))
+}
+
+impl<'a> Packet<'a> {
+    /// Follows the CNAME and DNAME records of the answer section
+    ///
+    /// See `chain::follow`.
+    pub fn follow_chain(
+        &self,
+        name: &Name<'_>,
+        qtype: QueryType,
+    ) -> Result<Chain<'_, 'a>, ChainError> {
+        follow(self.answers(), name, qtype)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{follow, ChainError};
+    use crate::{Name, Packet, QueryType, RData};
+
+    fn name(text: &str) -> Name<'static> {
+        let mut wire = Vec::new();
+        for label in text.split('.') {
+            wire.push(label.len() as u8);
+            wire.extend_from_slice(label.as_bytes());
+        }
+        wire.push(0);
+        Name::from_uncompressed(wire)
+    }
+
+    #[test]
+    fn cname_chain() {
+        // www.skype.com CNAME livecms.trafficmanager.net, then an A record
+        let response = b"\x4a\xf0\x81\x80\x00\x01\x00\x02\x00\x00\x00\x00\
+                         \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\
+                         \x00\x1c\x07livecms\x0etrafficmanager\x03net\x00\
+                         \xc0\x2b\x00\x01\x00\x01\x00\x00\x0e\x10\
+                         \x00\x04\xc0\x00\x02\x21";
+        let packet = Packet::parse(response).unwrap();
+        let question = &packet.questions()[0];
+        let chain = packet
+            .follow_chain(&question.qname, question.qtype)
+            .unwrap();
+        assert_eq!(chain.links, [&packet.answers()[0]]);
+        assert_eq!(chain.target.to_string(), "livecms.trafficmanager.net");
+        assert_eq!(chain.records, [&packet.answers()[1]]);
+
+        let chain = packet
+            .follow_chain(&name("WWW.skype.com"), QueryType::AAAA)
+            .unwrap();
+        assert_eq!(chain.links.len(), 1);
+        assert!(chain.records.is_empty());
+
+        let chain = packet
+            .follow_chain(&question.qname, QueryType::CNAME)
+            .unwrap();
+        assert!(chain.links.is_empty());
+        assert_eq!(chain.records, [&packet.answers()[0]]);
+    }
+
+    #[test]
+    fn dname_substitution() {
+        // example.com DNAME example.net, then an A record of
+        // www.example.net, without the synthesized CNAME
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x02\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x27\x00\x01\x00\x00\x0e\x10\x00\x0d\
+                         \x07example\x03net\x00\
+                         \x03www\xc0\x23\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xc0\x00\x02\x01";
+        let packet = Packet::parse(response).unwrap();
+        let chain = packet
+            .follow_chain(&name("www.Example.com"), QueryType::A)
+            .unwrap();
+        assert_eq!(chain.links, [&packet.answers()[0]]);
+        assert_eq!(chain.target.to_string(), "www.example.net");
+        match chain.records[0].data {
+            RData::A(ref a) => assert_eq!(a.0.octets(), [192, 0, 2, 1]),
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        // the DNAME doesn't apply to its owner
+        let chain = packet
+            .follow_chain(&name("example.com"), QueryType::A)
+            .unwrap();
+        assert!(chain.links.is_empty());
+    }
+
+    #[test]
+    fn broken_chains() {
+        // a.example CNAME b.example, b.example CNAME a.example
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x02\x00\x00\x00\x00\
+                         \x01a\x07example\x00\x00\x05\x00\x01\x00\x00\x0e\x10\x00\x04\
+                         \x01b\xc0\x0e\
+                         \x01b\xc0\x0e\x00\x05\x00\x01\x00\x00\x0e\x10\x00\x02\xc0\x0c";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(
+            packet.follow_chain(&name("a.example"), QueryType::A),
+            Err(ChainError::Loop)
+        );
+
+        // a.example CNAME b.example and c.example
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x02\x00\x00\x00\x00\
+                         \x01a\x07example\x00\x00\x05\x00\x01\x00\x00\x0e\x10\x00\x04\
+                         \x01b\xc0\x0e\
+                         \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\x00\x04\x01c\xc0\x0e";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(
+            follow(packet.answers(), &name("a.example"), QueryType::A),
+            Err(ChainError::Ambiguous)
+        );
+    }
+}
@@ -0,0 +1,236 @@
+//! Codecs for framing DNS messages with `tokio-util`
+//!
+//! Use [`DnsCodec`] for stream transports (TCP, TLS) where every message is
+//! prefixed by its length as a two byte integer (RFC 1035 section 4.2.2).
+//! Use [`DnsDatagramCodec`] with `UdpFramed`, where every datagram carries
+//! exactly one message.
+//!
+//! Both decoders yield [`BytesPacket`]s, which keep the received frame.
+//! The frame is split off the read buffer without copying, and the names
+//! and record data of the packet point into it.
+//!
+//! [`DnsCodec`]: struct.DnsCodec.html
+//! [`DnsDatagramCodec`]: struct.DnsDatagramCodec.html
+//! [`BytesPacket`]: ../type.BytesPacket.html
+use std::io;
+
+use bytes::{Buf, BufMut, BytesMut};
+use quick_error::quick_error;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::{BytesPacket, Header};
+
+quick_error! {
+    /// Error reading or writing a framed DNS message
+    #[derive(Debug)]
+    pub enum CodecError {
+        /// I/O error of the underlying transport
+        Io(err: io::Error) {
+            from()
+            display("I/O error: {}", err)
+            source(err)
+        }
+        /// Received message can't be parsed
+        Parse(err: crate::Error) {
+            from()
+            display("error parsing DNS message: {}", err)
+            source(err)
+        }
+        /// Message doesn't fit into a 65535 bytes frame
+        MessageTooLong(len: usize) {
+            display("message of {} bytes is too long", len)
+        }
+        /// Stream ended in the middle of a message
+        TruncatedFrame {
+            display("stream ended in the middle of a message")
+        }
+    }
+}
+
+/// Length-prefixed framing of DNS messages for stream transports
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DnsCodec {
+    _private: (),
+}
+
+impl DnsCodec {
+    /// Creates a new codec
+    pub fn new() -> DnsCodec {
+        DnsCodec::default()
+    }
+}
+
+impl Decoder for DnsCodec {
+    type Item = BytesPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesPacket>, CodecError> {
+        if src.len() < 2 {
+            src.reserve(2 + Header::size());
+            return Ok(None);
+        }
+        let len = u16::from_be_bytes([src[0], src[1]]) as usize;
+        if src.len() < 2 + len {
+            src.reserve(2 + len - src.len());
+            return Ok(None);
+        }
+        src.advance(2);
+        let frame = src.split_to(len).freeze();
+        Ok(Some(BytesPacket::parse(frame)?))
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<BytesPacket>, CodecError> {
+        match self.decode(src)? {
+            Some(packet) => Ok(Some(packet)),
+            None if src.is_empty() => Ok(None),
+            None => Err(CodecError::TruncatedFrame),
+        }
+    }
+}
+
+impl<T: AsRef<[u8]>> Encoder<T> for DnsCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), CodecError> {
+        let data = item.as_ref();
+        if data.len() > u16::MAX as usize {
+            return Err(CodecError::MessageTooLong(data.len()));
+        }
+        dst.reserve(2 + data.len());
+        dst.put_u16(data.len() as u16);
+        dst.put_slice(data);
+        Ok(())
+    }
+}
+
+/// Framing of DNS messages for datagram transports
+///
+/// This is meant to be used with `tokio_util::udp::UdpFramed`, where every
+/// datagram is passed to the decoder separately.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DnsDatagramCodec {
+    _private: (),
+}
+
+impl DnsDatagramCodec {
+    /// Creates a new codec
+    pub fn new() -> DnsDatagramCodec {
+        DnsDatagramCodec::default()
+    }
+}
+
+impl Decoder for DnsDatagramCodec {
+    type Item = BytesPacket;
+    type Error = CodecError;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<BytesPacket>, CodecError> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let datagram = src.split().freeze();
+        Ok(Some(BytesPacket::parse(datagram)?))
+    }
+}
+
+impl<T: AsRef<[u8]>> Encoder<T> for DnsDatagramCodec {
+    type Error = CodecError;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), CodecError> {
+        let data = item.as_ref();
+        if data.len() > u16::MAX as usize {
+            return Err(CodecError::MessageTooLong(data.len()));
+        }
+        dst.extend_from_slice(data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use bytes::BytesMut;
+    use tokio_util::codec::{Decoder, Encoder};
+
+    use super::{CodecError, DnsCodec, DnsDatagramCodec};
+    use crate::{Builder, QueryClass as QC, QueryType as QT};
+
+    fn query() -> Vec<u8> {
+        let mut bld = Builder::new_query(1573, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        bld.build().unwrap()
+    }
+
+    #[test]
+    fn stream_roundtrip() {
+        let mut codec = DnsCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(query(), &mut buf).unwrap();
+        codec.encode(&query()[..], &mut buf).unwrap();
+        assert_eq!(&buf[..2], b"\x00\x1d");
+        assert_eq!(buf.len(), 2 * (2 + 29));
+
+        // feed the data byte by byte to check partial frames
+        let mut input = BytesMut::new();
+        let mut packets = Vec::new();
+        for byte in buf.iter() {
+            input.extend_from_slice(&[*byte]);
+            if let Some(packet) = codec.decode(&mut input).unwrap() {
+                packets.push(packet);
+            }
+        }
+        assert!(codec.decode_eof(&mut input).unwrap().is_none());
+        assert_eq!(packets.len(), 2);
+        for packet in packets {
+            assert_eq!(packet.packet().header().id, 1573);
+            assert_eq!(
+                packet.packet().questions()[0].qname.to_string(),
+                "example.com"
+            );
+        }
+    }
+
+    #[test]
+    fn stream_truncated() {
+        let mut codec = DnsCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(query(), &mut buf).unwrap();
+        buf.truncate(10);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert!(matches!(
+            codec.decode_eof(&mut buf),
+            Err(CodecError::TruncatedFrame)
+        ));
+    }
+
+    #[test]
+    fn stream_too_long() {
+        let mut buf = BytesMut::new();
+        assert!(matches!(
+            DnsCodec::new().encode(vec![0; 65536], &mut buf),
+            Err(CodecError::MessageTooLong(65536))
+        ));
+    }
+
+    #[test]
+    fn datagram_roundtrip() {
+        let mut codec = DnsDatagramCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode(query(), &mut buf).unwrap();
+        assert_eq!(buf.len(), 29);
+        let packet = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(packet.packet().header().id, 1573);
+        let frame = packet.bytes().as_ptr_range();
+        let qname = packet.packet().questions()[0].qname.encoded().as_ptr();
+        assert!(frame.contains(&qname));
+        assert!(buf.is_empty());
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+    }
+
+    #[test]
+    fn datagram_parse_error() {
+        let mut buf = BytesMut::from(&b"\x00\x01"[..]);
+        assert!(matches!(
+            DnsDatagramCodec::new().decode(&mut buf),
+            Err(CodecError::Parse(crate::Error::HeaderTooShort))
+        ));
+    }
+}
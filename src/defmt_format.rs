@@ -0,0 +1,157 @@
+// `defmt::Format` implementations, enabled by the `defmt` feature
+//
+// Codes are written as their mnemonics like `Display` does, names as
+// text. Record data without a text form of its own is written as its
+// type.
+use defmt::{write, Format, Formatter};
+
+use crate::{Class, Header, Name, Opcode, QueryClass, QueryType, Question, RData};
+use crate::{ResourceRecord, ResponseCode, Type};
+
+// Writes the mnemonic, or the prefix and the code like `TYPE65280`
+fn mnemonic(f: Formatter<'_>, mnemonic: Option<&'static str>, prefix: &'static str, code: u16) {
+    match mnemonic {
+        Some(mnemonic) => write!(f, "{=str}", mnemonic),
+        None => write!(f, "{=str}{=u16}", prefix, code),
+    }
+}
+
+impl Format for Type {
+    fn format(&self, f: Formatter<'_>) {
+        mnemonic(f, self.mnemonic(), "TYPE", self.code())
+    }
+}
+
+impl Format for QueryType {
+    fn format(&self, f: Formatter<'_>) {
+        mnemonic(f, self.mnemonic(), "TYPE", self.code())
+    }
+}
+
+impl Format for Class {
+    fn format(&self, f: Formatter<'_>) {
+        mnemonic(f, self.mnemonic(), "CLASS", self.code())
+    }
+}
+
+impl Format for QueryClass {
+    fn format(&self, f: Formatter<'_>) {
+        mnemonic(f, self.mnemonic(), "CLASS", self.code())
+    }
+}
+
+impl Format for Opcode {
+    fn format(&self, f: Formatter<'_>) {
+        mnemonic(f, self.mnemonic(), "OPCODE", u16::from(*self))
+    }
+}
+
+impl Format for ResponseCode {
+    fn format(&self, f: Formatter<'_>) {
+        mnemonic(f, self.mnemonic(), "RCODE", u8::from(*self) as u16)
+    }
+}
+
+/// Writes the labels separated by dots, the root name is empty
+impl<'a> Format for Name<'a> {
+    fn format(&self, f: Formatter<'_>) {
+        for (i, label) in self.labels().enumerate() {
+            if i != 0 {
+                write!(f, ".");
+            }
+            write!(f, "{=[u8]:a}", label);
+        }
+    }
+}
+
+/// Writes the ID, opcode, response code, flags and section counts like
+/// the header line of `dig`
+impl Format for Header {
+    fn format(&self, f: Formatter<'_>) {
+        write!(
+            f,
+            "id {=u16} {} {} flags:",
+            self.id, self.opcode, self.response_code
+        );
+        let flags = [
+            (!self.query, " qr"),
+            (self.authoritative, " aa"),
+            (self.truncated, " tc"),
+            (self.recursion_desired, " rd"),
+            (self.recursion_available, " ra"),
+            (self.authenticated_data, " ad"),
+            (self.checking_disabled, " cd"),
+        ];
+        for &(set, flag) in flags.iter() {
+            if set {
+                write!(f, "{=str}", flag);
+            }
+        }
+        write!(
+            f,
+            "; QUERY {=u16}, ANSWER {=u16}, AUTHORITY {=u16}, ADDITIONAL {=u16}",
+            self.questions, self.answers, self.nameservers, self.additional
+        );
+    }
+}
+
+impl<'a> Format for Question<'a> {
+    fn format(&self, f: Formatter<'_>) {
+        write!(f, "{} {} {}", self.qname, self.qclass, self.qtype);
+    }
+}
+
+impl<'a> Format for ResourceRecord<'a> {
+    fn format(&self, f: Formatter<'_>) {
+        write!(
+            f,
+            "{} {=u32} {} {}",
+            self.name,
+            self.ttl.as_secs(),
+            self.cls,
+            self.data
+        );
+    }
+}
+
+/// Writes the type and the data of address, name, MX, SOA, SRV and TXT
+/// records, only the type for the others
+impl<'a> Format for RData<'a> {
+    fn format(&self, f: Formatter<'_>) {
+        match self {
+            RData::A(a) => write!(f, "A {}", a.0),
+            RData::AAAA(aaaa) => write!(f, "AAAA {}", aaaa.0),
+            RData::CNAME(cname) => write!(f, "CNAME {}", cname.0),
+            RData::NS(ns) => write!(f, "NS {}", ns.0),
+            RData::PTR(ptr) => write!(f, "PTR {}", ptr.0),
+            RData::DNAME(dname) => write!(f, "DNAME {}", dname.0),
+            RData::MX(mx) => write!(f, "MX {=u16} {}", mx.preference, mx.exchange),
+            RData::SOA(soa) => write!(
+                f,
+                "SOA {} {} {=u32} {=u32} {=u32} {=u32} {=u32}",
+                soa.primary_ns,
+                soa.mailbox,
+                soa.serial,
+                soa.refresh,
+                soa.retry,
+                soa.expire,
+                soa.minimum_ttl
+            ),
+            RData::SRV(srv) => write!(
+                f,
+                "SRV {=u16} {=u16} {=u16} {}",
+                srv.priority, srv.weight, srv.port, srv.target
+            ),
+            RData::TXT(txt) => {
+                write!(f, "TXT");
+                for string in txt.iter() {
+                    write!(f, " \"{=[u8]:a}\"", string);
+                }
+            }
+            RData::Unknown(code, data) => {
+                write!(f, "TYPE{=u16} ({=usize} bytes)", code, data.len())
+            }
+            other => write!(f, "{}", other.typ()),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+//! Comparison of DNS packets
+//!
+//! `diff` compares two packets, e.g. responses to the same query from
+//! different resolvers or at different times. Records are matched by
+//! owner name (ignoring case), class and data, so their order in the
+//! section doesn't matter. A record which only differs in TTL is reported
+//! as `TtlChanged`, filter those out to ignore TTL jitter:
+//!
+//! ```
+//! # use dns_parser::Packet;
+//! # use dns_parser::diff::{diff, Difference};
+//! # fn compare(old: &Packet, new: &Packet) {
+//! let changes = diff(old, new)
+//!     .into_iter()
+//!     .filter(|d| !d.is_ttl_change())
+//!     .collect::<Vec<Difference>>();
+//! # }
+//! ```
+use crate::{Header, Packet, Question, ResourceRecord, Section, Ttl};
+
+/// A field of the header, see `Difference::Header`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)] // named after the fields of `Header`
+pub enum HeaderField {
+    Id,
+    Query,
+    Opcode,
+    Authoritative,
+    Truncated,
+    RecursionDesired,
+    RecursionAvailable,
+    AuthenticatedData,
+    CheckingDisabled,
+    ResponseCode,
+}
+
+/// A difference between two packets
+///
+/// References point into the packet where the item is present, for
+/// changes into the new one.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Difference<'p, 'a> {
+    /// The header field has a different value
+    ///
+    /// Section counts are not compared, differences in the sections are
+    /// reported instead.
+    Header(HeaderField),
+    /// A question present only in the new packet
+    QuestionAdded(&'p Question<'a>),
+    /// A question present only in the old packet
+    QuestionRemoved(&'p Question<'a>),
+    /// A record present only in the new packet
+    Added(Section, &'p ResourceRecord<'a>),
+    /// A record present only in the old packet
+    Removed(Section, &'p ResourceRecord<'a>),
+    /// A record present in both packets with a different TTL
+    TtlChanged {
+        /// The section of the record
+        section: Section,
+        /// The record from the new packet
+        record: &'p ResourceRecord<'a>,
+        /// TTL of the record in the old packet
+        old_ttl: Ttl,
+    },
+    /// The OPT record was added, removed or changed
+    Opt,
+}
+
+impl<'p, 'a> Difference<'p, 'a> {
+    /// Returns true for `TtlChanged`
+    pub fn is_ttl_change(&self) -> bool {
+        matches!(*self, Difference::TtlChanged { .. })
+    }
+}
+
+/// Compares two packets
+///
+/// Returns an empty list if the packets are equivalent.
+pub fn diff<'p, 'a>(old: &'p Packet<'a>, new: &'p Packet<'a>) -> Vec<Difference<'p, 'a>> {
+    let mut result = Vec::new();
+    diff_header(old.header(), new.header(), &mut result);
+
+    let mut matched = vec![false; new.questions().len()];
+    for question in old.questions() {
+        let found = new.questions().iter().enumerate().position(|(i, q)| {
+            !matched[i]
+                && q.qtype == question.qtype
+                && q.qclass == question.qclass
+                && q.prefer_unicast == question.prefer_unicast
+                && q.qname.eq_ignore_ascii_case(&question.qname)
+        });
+        match found {
+            Some(i) => matched[i] = true,
+            None => result.push(Difference::QuestionRemoved(question)),
+        }
+    }
+    for (question, _) in new.questions().iter().zip(matched).filter(|&(_, m)| !m) {
+        result.push(Difference::QuestionAdded(question));
+    }
+
+    let sections = [
+        (Section::Answer, old.answers(), new.answers()),
+        (Section::Authority, old.nameservers(), new.nameservers()),
+        (Section::Additional, old.additional(), new.additional()),
+    ];
+    for &(section, old_records, new_records) in sections.iter() {
+        diff_records(section, old_records, new_records, &mut result);
+    }
+    if old.opt() != new.opt() {
+        result.push(Difference::Opt);
+    }
+    result
+}
+
+fn diff_header(old: &Header, new: &Header, result: &mut Vec<Difference<'_, '_>>) {
+    use self::HeaderField::*;
+    let fields = [
+        (Id, old.id == new.id),
+        (Query, old.query == new.query),
+        (Opcode, old.opcode == new.opcode),
+        (Authoritative, old.authoritative == new.authoritative),
+        (Truncated, old.truncated == new.truncated),
+        (
+            RecursionDesired,
+            old.recursion_desired == new.recursion_desired,
+        ),
+        (
+            RecursionAvailable,
+            old.recursion_available == new.recursion_available,
+        ),
+        (
+            AuthenticatedData,
+            old.authenticated_data == new.authenticated_data,
+        ),
+        (
+            CheckingDisabled,
+            old.checking_disabled == new.checking_disabled,
+        ),
+        (ResponseCode, old.response_code == new.response_code),
+    ];
+    for &(field, same) in fields.iter() {
+        if !same {
+            result.push(Difference::Header(field));
+        }
+    }
+}
+
+fn diff_records<'p, 'a>(
+    section: Section,
+    old: &'p [ResourceRecord<'a>],
+    new: &'p [ResourceRecord<'a>],
+    result: &mut Vec<Difference<'p, 'a>>,
+) {
+    let mut matched = vec![false; new.len()];
+    for record in old {
+        let found = new
+            .iter()
+            .enumerate()
+            .position(|(i, r)| !matched[i] && same_record(record, r));
+        match found {
+            Some(i) => {
+                matched[i] = true;
+                if new[i].ttl != record.ttl {
+                    result.push(Difference::TtlChanged {
+                        section,
+                        record: &new[i],
+                        old_ttl: record.ttl,
+                    });
+                }
+            }
+            None => result.push(Difference::Removed(section, record)),
+        }
+    }
+    for (record, _) in new.iter().zip(matched).filter(|&(_, m)| !m) {
+        result.push(Difference::Added(section, record));
+    }
+}
+
+fn same_record(a: &ResourceRecord<'_>, b: &ResourceRecord<'_>) -> bool {
+    a.cls == b.cls
+        && a.multicast_unique == b.multicast_unique
+        && a.data == b.data
+        && a.name.eq_ignore_ascii_case(&b.name)
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use super::{diff, Difference, HeaderField};
+    use crate::rdata::A;
+    use crate::{Builder, Class, Name, Packet, RData, ResourceRecord, Section, Ttl};
+    use crate::{QueryClass as QC, QueryType as QT, ResponseCode};
+
+    fn response(id: u16, answers: &[([u8; 4], u32)]) -> Vec<u8> {
+        let name = b"\x07Example\x03com\x00";
+        let mut bld = Builder::new_query(id, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        for &(addr, ttl) in answers {
+            bld.add_answer(&ResourceRecord {
+                name: Name::scan(name, name).unwrap(),
+                multicast_unique: false,
+                cls: Class::IN,
+                ttl: Ttl(ttl),
+                data: RData::A(A(Ipv4Addr::from(addr))),
+            });
+        }
+        bld.build().unwrap()
+    }
+
+    #[test]
+    fn same_packets() {
+        let data = response(1, &[([1, 1, 1, 1], 60), ([2, 2, 2, 2], 60)]);
+        let packet = Packet::parse(&data).unwrap();
+        assert_eq!(diff(&packet, &packet), []);
+        // order of the records doesn't matter
+        let data = response(1, &[([2, 2, 2, 2], 60), ([1, 1, 1, 1], 60)]);
+        assert_eq!(diff(&packet, &Packet::parse(&data).unwrap()), []);
+    }
+
+    #[test]
+    fn changed_records() {
+        let old = response(1, &[([1, 1, 1, 1], 60), ([2, 2, 2, 2], 60)]);
+        let old = Packet::parse(&old).unwrap();
+        let new = response(2, &[([2, 2, 2, 2], 30), ([3, 3, 3, 3], 60)]);
+        let new = Packet::parse(&new).unwrap();
+        let result = diff(&old, &new);
+        assert_eq!(
+            result,
+            [
+                Difference::Header(HeaderField::Id),
+                Difference::Removed(Section::Answer, &old.answers()[0]),
+                Difference::TtlChanged {
+                    section: Section::Answer,
+                    record: &new.answers()[0],
+                    old_ttl: Ttl(60),
+                },
+                Difference::Added(Section::Answer, &new.answers()[1]),
+            ]
+        );
+        assert_eq!(
+            result.iter().filter(|d| !d.is_ttl_change()).count(),
+            result.len() - 1
+        );
+    }
+
+    #[test]
+    fn changed_header_and_question() {
+        let old = response(1, &[]);
+        let old = Packet::parse(&old).unwrap();
+        let mut new = Builder::new_query(1, true);
+        new.add_question("example.org", false, QT::A, QC::IN);
+        let mut new = new.build().unwrap();
+        new[3] |= u8::from(ResponseCode::NameError);
+        let new = Packet::parse(&new).unwrap();
+        assert_eq!(
+            diff(&old, &new),
+            [
+                Difference::Header(HeaderField::ResponseCode),
+                Difference::QuestionRemoved(&old.questions()[0]),
+                Difference::QuestionAdded(&new.questions()[0]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,346 @@
+//! Annotated hexdump of DNS packets
+//!
+//! `dissect(data)` returns a value which displays the packet as a hexdump
+//! with every field on its own line next to its decoded value:
+//!
+//! ```text
+//! 0000  da 4a                    id: 55882
+//! 0002  81 80                    flags: response, opcode QUERY, RD, RA, rcode NOERROR
+//! ...
+//! 000c  03 77 77 77 05 73 6b 79  qname: www.skype.com
+//! 0014  70 65 03 63 6f 6d 00
+//! ```
+//!
+//! Decoding goes as far as the packet is well-formed, the rest is dumped
+//! as is. This is meant for debugging, the format is not stable.
+use std::convert::TryInto;
+use std::fmt;
+
+use crate::name::{Budget, NameCache};
+use crate::parser::{parse_raw_question, parse_raw_record};
+use crate::{Class, Error, Header, QueryClass, QueryType, RData, Section, Type};
+
+const BYTES_PER_LINE: usize = 8;
+
+/// Creates a displayable dissection of the packet
+pub fn dissect(data: &[u8]) -> Dissection<'_> {
+    Dissection { data }
+}
+
+/// A packet to be displayed as an annotated hexdump, see `dissect`
+#[derive(Debug, Clone, Copy)]
+pub struct Dissection<'a> {
+    data: &'a [u8],
+}
+
+struct Field {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+impl<'a> Dissection<'a> {
+    fn fields(&self) -> Vec<Field> {
+        let mut fields = Vec::new();
+        match self.decode(&mut fields) {
+            Ok(end) if end < self.data.len() => {
+                fields.push(Field::new(end, self.data.len(), "trailing data".into()));
+            }
+            Ok(_) => {}
+            Err((start, err)) => {
+                fields.push(Field::new(
+                    start,
+                    self.data.len(),
+                    format!("undecoded: {}", err),
+                ));
+            }
+        }
+        fields
+    }
+
+    // Returns the offset where decoding stopped, and the error if any
+    fn decode(&self, fields: &mut Vec<Field>) -> Result<usize, (usize, Error)> {
+        let data = self.data;
+        let header = Header::parse(data).map_err(|e| (0, e))?;
+        let u16_at = |pos: usize| u16::from_be_bytes(data[pos..pos + 2].try_into().unwrap());
+        fields.push(Field::new(0, 2, format!("id: {}", header.id)));
+        fields.push(Field::new(2, 4, format!("flags: {}", flags(&header))));
+        let counts = ["questions", "answers", "nameservers", "additional"];
+        for (i, name) in counts.iter().enumerate() {
+            let pos = 4 + i * 2;
+            fields.push(Field::new(
+                pos,
+                pos + 2,
+                format!("{}: {}", name, u16_at(pos)),
+            ));
+        }
+
+        let mut offset = Header::size();
+        let mut names = NameCache::default();
+        let mut budget = Budget::default();
+        for _ in 0..header.questions {
+            let start = offset;
+            let question = parse_raw_question(data, &mut offset, Some(&mut names), &mut budget)
+                .map_err(|e| (start, e))?;
+            let name_end = start + question.qname.byte_len();
+            fields.push(Field::new(
+                start,
+                name_end,
+                format!("qname: {}", question.qname),
+            ));
+            let qtype = QueryType::parse(question.qtype);
+            fields.push(Field::new(
+                name_end,
+                name_end + 2,
+                format!("qtype: {}", qtype),
+            ));
+            let unicast = if question.qclass & 0x8000 != 0 {
+                ", unicast response"
+            } else {
+                ""
+            };
+            let qclass = QueryClass::parse(question.qclass & 0x7FFF);
+            let qclass = format!("{}{}", qclass, unicast);
+            fields.push(Field::new(
+                name_end + 2,
+                offset,
+                format!("qclass: {}", qclass),
+            ));
+        }
+
+        let sections = [
+            (Section::Answer, header.answers),
+            (Section::Authority, header.nameservers),
+            (Section::Additional, header.additional),
+        ];
+        for &(section, count) in sections.iter() {
+            for index in 0..count {
+                let start = offset;
+                let record = parse_raw_record(data, &mut offset, Some(&mut names), &mut budget)
+                    .map_err(|e| (start, e))?;
+                let pos = start + record.name.byte_len();
+                let name = if record.name.is_compressed() {
+                    " (compressed)"
+                } else {
+                    ""
+                };
+                fields.push(Field::new(
+                    start,
+                    pos,
+                    format!("{:?}[{}] name: {}{}", section, index, record.name, name),
+                ));
+                let typ = Type::parse(record.typ);
+                fields.push(Field::new(pos, pos + 2, format!("type: {}", typ)));
+                if typ == Type::OPT {
+                    fields.push(Field::new(
+                        pos + 2,
+                        pos + 4,
+                        format!("udp payload size: {}", record.cls),
+                    ));
+                    let ttl = record.ttl.to_be_bytes();
+                    fields.push(Field::new(
+                        pos + 4,
+                        pos + 8,
+                        format!(
+                            "extended rcode: {}, version: {}, flags: {:#06x}",
+                            ttl[0],
+                            ttl[1],
+                            u16::from_be_bytes([ttl[2], ttl[3]])
+                        ),
+                    ));
+                } else {
+                    let cache_flush = if record.cls & 0x8000 != 0 {
+                        ", cache flush"
+                    } else {
+                        ""
+                    };
+                    let cls = Class::parse(record.cls & 0x7FFF);
+                    let cls = format!("{}{}", cls, cache_flush);
+                    fields.push(Field::new(pos + 2, pos + 4, format!("class: {}", cls)));
+                    fields.push(Field::new(pos + 4, pos + 8, format!("ttl: {}", record.ttl)));
+                }
+                fields.push(Field::new(
+                    pos + 8,
+                    pos + 10,
+                    format!("rdlength: {}", record.data.len()),
+                ));
+                if !record.data.is_empty() {
+                    let rdata = match typ {
+                        typ if has_parser(typ) => match RData::parse(typ, record.data, data) {
+                            Ok(rdata) => format!("rdata: {:?}", rdata),
+                            Err(e) => format!("rdata: undecoded ({})", e),
+                        },
+                        _ => "rdata".into(),
+                    };
+                    fields.push(Field::new(pos + 10, offset, rdata));
+                }
+            }
+        }
+        Ok(offset)
+    }
+}
+
+impl Field {
+    fn new(start: usize, end: usize, text: String) -> Field {
+        Field { start, end, text }
+    }
+}
+
+// Parsers of other types are not implemented yet and would panic
+fn has_parser(typ: Type) -> bool {
+    use crate::Type::*;
+    matches!(
+        typ,
+        A | AAAA
+            | CNAME
+            | MX
+            | NS
+            | PTR
+            | SOA
+            | SRV
+            | TXT
+            | OPT
+            | DNSKEY
+            | NSEC3PARAM
+            | RRSIG
+            | NSEC
+            | NSEC3
+            | CSYNC
+            | DS
+            | ZONEMD
+            | DNAME
+    )
+}
+
+fn flags(header: &Header) -> String {
+    let mut result = String::new();
+    result.push_str(if header.query { "query" } else { "response" });
+    result.push_str(&format!(", opcode {}", header.opcode));
+    let bits = [
+        (header.authoritative, "AA"),
+        (header.truncated, "TC"),
+        (header.recursion_desired, "RD"),
+        (header.recursion_available, "RA"),
+        (header.authenticated_data, "AD"),
+        (header.checking_disabled, "CD"),
+    ];
+    for &(set, name) in bits.iter() {
+        if set {
+            result.push_str(", ");
+            result.push_str(name);
+        }
+    }
+    result.push_str(&format!(", rcode {}", header.response_code));
+    result
+}
+
+impl<'a> fmt::Display for Dissection<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for field in self.fields() {
+            let bytes = &self.data[field.start..field.end];
+            if bytes.is_empty() {
+                writeln!(
+                    f,
+                    "{:04x}  {:width$}  {}",
+                    field.start,
+                    "",
+                    field.text,
+                    width = BYTES_PER_LINE * 3 - 1
+                )?;
+                continue;
+            }
+            for (i, chunk) in bytes.chunks(BYTES_PER_LINE).enumerate() {
+                let hex = chunk
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let offset = field.start + i * BYTES_PER_LINE;
+                if i == 0 {
+                    writeln!(
+                        f,
+                        "{:04x}  {:width$}  {}",
+                        offset,
+                        hex,
+                        field.text,
+                        width = BYTES_PER_LINE * 3 - 1
+                    )?;
+                } else {
+                    writeln!(f, "{:04x}  {}", offset, hex)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::dissect;
+    use crate::{Builder, QueryClass as QC, QueryType as QT};
+
+    #[test]
+    fn dissect_query() {
+        let mut bld = Builder::new_query(1573, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        let query = bld.build().unwrap();
+        assert_eq!(
+            dissect(&query).to_string(),
+            "\
+0000  06 25                    id: 1573
+0002  01 00                    flags: query, opcode QUERY, RD, rcode NOERROR
+0004  00 01                    questions: 1
+0006  00 00                    answers: 0
+0008  00 00                    nameservers: 0
+000a  00 00                    additional: 0
+000c  07 65 78 61 6d 70 6c 65  qname: example.com
+0014  03 63 6f 6d 00
+0019  00 01                    qtype: A
+001b  00 01                    qclass: IN
+"
+        );
+    }
+
+    #[test]
+    fn dissect_response() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x01\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04\x5d\xb8\xd8\x22\
+                         \x00\x00\x29\x10\x00\x00\x00\x80\x00\x00\x00";
+        let text = dissect(response).to_string();
+        let lines = text.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[10..],
+            [
+                "001d  c0 0c                    Answer[0] name: example.com (compressed)",
+                "001f  00 01                    type: A",
+                "0021  00 01                    class: IN",
+                "0023  00 00 04 f8              ttl: 1272",
+                "0027  00 04                    rdlength: 4",
+                "0029  5d b8 d8 22              rdata: A(Record(93.184.216.34))",
+                "002d  00                       Additional[0] name: ",
+                "002e  00 29                    type: OPT",
+                "0030  10 00                    udp payload size: 4096",
+                "0032  00 00 80 00              extended rcode: 0, version: 0, flags: 0x8000",
+                "0036  00 00                    rdlength: 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn dissect_malformed() {
+        let text = dissect(b"\x06%\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\x07exa").to_string();
+        assert_eq!(
+            text.lines().last().unwrap(),
+            "000c  07 65 78 61              undecoded: packet has incomplete data"
+        );
+        let text =
+            dissect(b"\x06%\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x01\x00\x01\x00")
+                .to_string();
+        assert_eq!(
+            text.lines().last().unwrap(),
+            "0011  00                       trailing data"
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! AAAA record synthesis for DNS64 (RFC 6147)
+//!
+//! When the AAAA query for a name yields no records, a DNS64 server sends
+//! an A query instead and converts the response with `synthesize`, which
+//! embeds every IPv4 address into an IPv6 prefix as described in RFC 6052.
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::rdata::Aaaa;
+use crate::{Builder, Error, Packet, QueryType, RData, ResourceRecord, Ttl};
+
+/// TTL used when the AAAA response didn't carry an SOA record
+const DEFAULT_NEGATIVE_TTL: u32 = 600;
+
+/// An IPv6 prefix used to synthesize addresses (a "Pref64::/n")
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Prefix {
+    addr: Ipv6Addr,
+    len: u8,
+}
+
+impl Prefix {
+    /// The Well-Known Prefix `64:ff9b::/96`
+    pub const WELL_KNOWN: Prefix = Prefix {
+        addr: Ipv6Addr::new(0x64, 0xff9b, 0, 0, 0, 0, 0, 0),
+        len: 96,
+    };
+
+    /// Creates a prefix
+    ///
+    /// The length must be one of 32, 40, 48, 56, 64 or 96 as required by
+    /// RFC 6052. Bits of `addr` after the prefix length are ignored.
+    pub fn new(addr: Ipv6Addr, len: u8) -> Result<Prefix, Error> {
+        match len {
+            32 | 40 | 48 | 56 | 64 | 96 => {}
+            _ => return Err(Error::InvalidPrefixLength(len)),
+        }
+        let mut octets = addr.octets();
+        for byte in octets[len as usize / 8..].iter_mut() {
+            *byte = 0;
+        }
+        Ok(Prefix {
+            addr: Ipv6Addr::from(octets),
+            len,
+        })
+    }
+    /// Returns the prefix address
+    pub fn addr(&self) -> Ipv6Addr {
+        self.addr
+    }
+    /// Returns the prefix length in bits
+    pub fn prefix_len(&self) -> u8 {
+        self.len
+    }
+    /// Embeds an IPv4 address into the prefix (RFC 6052 section 2.2)
+    pub fn embed(&self, addr: Ipv4Addr) -> Ipv6Addr {
+        let mut octets = self.addr.octets();
+        let mut pos = self.len as usize / 8;
+        for &byte in addr.octets().iter() {
+            // bits 64 to 71 (the "u" octet) must be zero
+            if pos == 8 {
+                pos += 1;
+            }
+            octets[pos] = byte;
+            pos += 1;
+        }
+        Ipv6Addr::from(octets)
+    }
+}
+
+/// Converts a response to an A query into a response to the AAAA query
+///
+/// The question is rewritten to ask for AAAA records, and every A record
+/// in the answer section is replaced by an AAAA record with the address
+/// embedded into `prefix`. Other answers (e.g. CNAME records leading to
+/// the addresses) and the remaining sections are copied as is. The AD
+/// flag is cleared since the synthesized records can't be validated.
+///
+/// The TTL of the synthesized records is the minimum of the A record's TTL
+/// and `negative_ttl`, which should be the negative caching TTL derived
+/// from the SOA record of the empty AAAA response. When there was no SOA
+/// record, pass `None` and 600 seconds is used instead.
+///
+/// Returns `None` if `response` is not a response to a single A question.
+/// Otherwise returns a builder, so more records may be added before the
+/// packet is built.
+pub fn synthesize(
+    response: &Packet<'_>,
+    prefix: &Prefix,
+    negative_ttl: Option<u32>,
+) -> Option<Builder> {
+    if response.header().query || response.questions().len() != 1 {
+        return None;
+    }
+    let question = &response.questions()[0];
+    if question.qtype != QueryType::A {
+        return None;
+    }
+    let max_ttl = negative_ttl.unwrap_or(DEFAULT_NEGATIVE_TTL);
+
+    let mut header = *response.header();
+    header.authenticated_data = false;
+    let mut builder = Builder::with_header(&header);
+    builder.add_question_name(
+        &question.qname,
+        question.prefer_unicast,
+        QueryType::AAAA,
+        question.qclass,
+    );
+    for answer in response.answers() {
+        match answer.data {
+            RData::A(addr) => {
+                builder.add_answer(&ResourceRecord {
+                    name: answer.name.clone(),
+                    multicast_unique: answer.multicast_unique,
+                    cls: answer.cls,
+                    ttl: answer.ttl.min(Ttl(max_ttl)),
+                    data: RData::AAAA(Aaaa(prefix.embed(addr.0))),
+                });
+            }
+            _ => {
+                builder.add_answer(answer);
+            }
+        }
+    }
+    for record in response.nameservers() {
+        builder.add_nameserver(record);
+    }
+    for record in response.additional() {
+        builder.add_additional(record);
+    }
+    if let Some(opt) = response.opt() {
+        builder.add_opt(opt);
+    }
+    Some(builder)
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    use super::{synthesize, Prefix};
+    use crate::QueryType as QT;
+    use crate::{Error, Packet, RData};
+
+    #[test]
+    fn embed_rfc6052_examples() {
+        let v4 = Ipv4Addr::new(192, 0, 2, 33);
+        let cases = [
+            ("2001:db8::", 32, "2001:db8:c000:221::"),
+            ("2001:db8:100::", 40, "2001:db8:1c0:2:21::"),
+            ("2001:db8:122::", 48, "2001:db8:122:c000:2:2100::"),
+            ("2001:db8:122:300::", 56, "2001:db8:122:3c0:0:221::"),
+            ("2001:db8:122:344::", 64, "2001:db8:122:344:c0:2:2100:0"),
+            ("2001:db8:122:344::", 96, "2001:db8:122:344::192.0.2.33"),
+            ("64:ff9b::", 96, "64:ff9b::192.0.2.33"),
+        ];
+        for &(prefix, len, result) in cases.iter() {
+            let prefix = Prefix::new(prefix.parse().unwrap(), len).unwrap();
+            assert_eq!(prefix.embed(v4), result.parse::<Ipv6Addr>().unwrap());
+        }
+        assert_eq!(
+            Prefix::WELL_KNOWN.embed(v4),
+            "64:ff9b::c000:221".parse::<Ipv6Addr>().unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_prefix() {
+        assert!(matches!(
+            Prefix::new(Ipv6Addr::UNSPECIFIED, 33),
+            Err(Error::InvalidPrefixLength(33))
+        ));
+    }
+
+    #[test]
+    fn synthesize_response() {
+        // www.skype.com CNAME livecms.trafficmanager.net, plus an A record
+        let response = b"\x4a\xf0\x81\xa0\x00\x01\x00\x02\x00\x00\x00\x00\
+                          \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+                          \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\
+                          \x00\x1c\x07livecms\x0etrafficmanager\x03net\x00\
+                          \xc0\x2b\x00\x01\x00\x01\x00\x00\x0e\x10\
+                          \x00\x04\xc0\x00\x02\x21";
+        let response = Packet::parse(response).unwrap();
+        assert!(response.header().authenticated_data);
+        let result = synthesize(&response, &Prefix::WELL_KNOWN, Some(300))
+            .unwrap()
+            .build()
+            .unwrap();
+        let packet = Packet::parse(&result).unwrap();
+        assert_eq!(packet.header().id, 0x4af0);
+        assert!(!packet.header().query);
+        assert!(!packet.header().authenticated_data);
+        assert_eq!(packet.header().answers, 2);
+        assert_eq!(packet.questions()[0].qtype, QT::AAAA);
+        assert_eq!(packet.questions()[0].qname.to_string(), "www.skype.com");
+        assert_eq!(packet.answers()[0], response.answers()[0]);
+        assert_eq!(
+            packet.answers()[1].name.to_string(),
+            "livecms.trafficmanager.net"
+        );
+        assert_eq!(packet.answers()[1].ttl, 300);
+        match packet.answers()[1].data {
+            RData::AAAA(addr) => {
+                assert_eq!(addr.0, "64:ff9b::192.0.2.33".parse::<Ipv6Addr>().unwrap())
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+
+        let result = synthesize(&response, &Prefix::WELL_KNOWN, None)
+            .unwrap()
+            .build()
+            .unwrap();
+        assert_eq!(Packet::parse(&result).unwrap().answers()[1].ttl, 600);
+    }
+
+    #[test]
+    fn not_an_a_response() {
+        let query = b"\x06%\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\
+                      \x07example\x03com\x00\x00\x01\x00\x01";
+        let query = Packet::parse(query).unwrap();
+        assert!(synthesize(&query, &Prefix::WELL_KNOWN, None).is_none());
+        let response = b"\x06%\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x1c\x00\x01";
+        let response = Packet::parse(response).unwrap();
+        assert!(synthesize(&response, &Prefix::WELL_KNOWN, None).is_none());
+    }
+}
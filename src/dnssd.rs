@@ -0,0 +1,194 @@
+//! Correlation of DNS-SD records (RFC 6763)
+//!
+//! A DNS-SD browse response, usually sent over multicast DNS, lists the
+//! instances of a service with PTR records, e.g. `_ipp._tcp.local` to
+//! `Printer._ipp._tcp.local`. The SRV and TXT records of each instance
+//! and the addresses of its host come along in the same packet, most
+//! often in the additional section. `browse` puts them together.
+//!
+//! ```
+//! # use dns_parser::Packet;
+//! # use dns_parser::dnssd::browse;
+//! # fn show(packet: &Packet) {
+//! for instance in browse(packet) {
+//!     if let (Some(host), Some(port)) = (instance.host, instance.port) {
+//!         println!("{} at {}:{}", instance.name, host, port);
+//!     }
+//! }
+//! # }
+//! ```
+use std::net::IpAddr;
+
+use crate::{Name, Packet, RData, ResourceRecord, Ttl};
+
+/// An instance of a service, see `browse`
+///
+/// Fields which are not known from the packet are empty, the instance
+/// has to be resolved with further queries then.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceInstance<'r, 'a> {
+    /// The service type, e.g. `_ipp._tcp.local`
+    pub service: &'r Name<'a>,
+    /// The instance name, e.g. `Printer._ipp._tcp.local`
+    pub name: &'r Name<'a>,
+    /// TTL of the PTR record, zero if the instance is going away
+    pub ttl: Ttl,
+    /// Target host of the SRV record
+    pub host: Option<&'r Name<'a>>,
+    /// Port of the SRV record
+    pub port: Option<u16>,
+    /// `key=value` attributes of the TXT record
+    ///
+    /// See `txt::Record::attributes` for the format.
+    pub attributes: Vec<(&'r [u8], Option<&'r [u8]>)>,
+    /// IPv4 and IPv6 addresses of the host
+    pub addresses: Vec<IpAddr>,
+}
+
+impl<'r, 'a> ServiceInstance<'r, 'a> {
+    /// Returns the user-visible name of the instance, e.g. `Printer`
+    ///
+    /// This is the first label of the instance name, which may contain
+    /// dots and any UTF-8 text.
+    pub fn instance_label(&self) -> &'r [u8] {
+        self.name.labels().next().unwrap_or(b"")
+    }
+    /// Returns the value of an attribute, keys are compared ignoring case
+    ///
+    /// Returns `Some(None)` for a boolean attribute without a value.
+    pub fn attribute(&self, key: &[u8]) -> Option<Option<&'r [u8]>> {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|&(_, value)| value)
+    }
+}
+
+/// Collects the service instances listed in a packet
+///
+/// Every PTR record under a `_tcp` or `_udp` label gives an instance,
+/// except those of the service type enumeration
+/// (`_services._dns-sd._udp`). Records of all sections are searched for
+/// the SRV and TXT records of the instances and the addresses of their
+/// hosts. Names are compared ignoring case. Instances are returned in the
+/// order of their PTR records, each one once.
+pub fn browse<'r, 'a>(packet: &'r Packet<'a>) -> Vec<ServiceInstance<'r, 'a>> {
+    let records = packet
+        .answers()
+        .iter()
+        .chain(packet.nameservers())
+        .chain(packet.additional())
+        .collect::<Vec<_>>();
+    let mut result: Vec<ServiceInstance<'r, 'a>> = Vec::new();
+    for record in &records {
+        let name = match record.data {
+            RData::PTR(ref ptr) if is_service(&record.name) => &ptr.0,
+            _ => continue,
+        };
+        if result.iter().any(|i| i.name.eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        let mut instance = ServiceInstance {
+            service: &record.name,
+            name,
+            ttl: record.ttl,
+            host: None,
+            port: None,
+            attributes: Vec::new(),
+            addresses: Vec::new(),
+        };
+        for other in at(&records, name) {
+            match other.data {
+                RData::SRV(ref srv) if instance.host.is_none() => {
+                    instance.host = Some(&srv.target);
+                    instance.port = Some(srv.port);
+                }
+                RData::TXT(ref txt) if instance.attributes.is_empty() => {
+                    instance.attributes = txt.attributes().collect();
+                }
+                _ => {}
+            }
+        }
+        if let Some(host) = instance.host {
+            for other in at(&records, host) {
+                match other.data {
+                    RData::A(ref a) => instance.addresses.push(a.0.into()),
+                    RData::AAAA(ref aaaa) => instance.addresses.push(aaaa.0.into()),
+                    _ => {}
+                }
+            }
+        }
+        result.push(instance);
+    }
+    result
+}
+
+// Records owned by the name
+fn at<'s, 'r, 'a>(
+    records: &'s [&'r ResourceRecord<'a>],
+    name: &'s Name<'_>,
+) -> impl Iterator<Item = &'r ResourceRecord<'a>> + 's {
+    records
+        .iter()
+        .cloned()
+        .filter(move |record| record.name.eq_ignore_ascii_case(name))
+}
+
+fn is_service(name: &Name<'_>) -> bool {
+    let labels = name.labels().collect::<Vec<_>>();
+    let enumeration = labels.len() >= 3
+        && labels[0].eq_ignore_ascii_case(b"_services")
+        && labels[1].eq_ignore_ascii_case(b"_dns-sd")
+        && labels[2].eq_ignore_ascii_case(b"_udp");
+    !enumeration
+        && labels
+            .iter()
+            .any(|l| l.eq_ignore_ascii_case(b"_tcp") || l.eq_ignore_ascii_case(b"_udp"))
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::IpAddr;
+
+    use super::browse;
+    use crate::Packet;
+
+    #[test]
+    fn browse_response() {
+        // _ipp._tcp.local PTR Printer._ipp._tcp.local and the service
+        // enumeration PTR in the answers, SRV, TXT, A and AAAA records
+        // in the additional section
+        let response = b"\x00\x00\x84\x00\x00\x00\x00\x02\x00\x00\x00\x04\
+            \x04_ipp\x04_tcp\x05local\x00\x00\x0c\x00\x01\x00\x00\x11\x94\x00\x0a\
+            \x07Printer\xc0\x0c\
+            \x09_services\x07_dns-sd\x04_udp\xc0\x16\x00\x0c\x00\x01\x00\x00\x11\x94\x00\x02\
+            \xc0\x0c\
+            \xc0\x27\x00\x21\x80\x01\x00\x00\x00\x78\x00\x0d\x00\x00\x00\x00\x02\x77\
+            \x04host\xc0\x16\
+            \xc0\x27\x00\x10\x80\x01\x00\x00\x11\x94\x00\x14\
+            \x09txtvers=1\x02rp\x06Duplex\
+            \xc0\x68\x00\x01\x80\x01\x00\x00\x00\x78\x00\x04\xc0\xa8\x01\x02\
+            \xc0\x68\x00\x1c\x80\x01\x00\x00\x00\x78\x00\x10\
+            \xfe\x80\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01";
+        let packet = Packet::parse(response).unwrap();
+        let instances = browse(&packet);
+        assert_eq!(instances.len(), 1);
+        let printer = &instances[0];
+        assert_eq!(printer.service.to_string(), "_ipp._tcp.local");
+        assert_eq!(printer.name.to_string(), "Printer._ipp._tcp.local");
+        assert_eq!(printer.instance_label(), b"Printer");
+        assert_eq!(printer.ttl, 4500);
+        assert_eq!(printer.host.unwrap().to_string(), "host.local");
+        assert_eq!(printer.port, Some(631));
+        assert_eq!(printer.attribute(b"TXTVERS"), Some(Some(&b"1"[..])));
+        assert_eq!(printer.attribute(b"duplex"), Some(None));
+        assert_eq!(printer.attribute(b"color"), None);
+        assert_eq!(
+            printer.addresses,
+            [
+                "192.168.1.2".parse::<IpAddr>().unwrap(),
+                "fe80::1".parse::<IpAddr>().unwrap()
+            ]
+        );
+    }
+}
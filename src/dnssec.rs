@@ -0,0 +1,969 @@
+//! Helpers for DNSSEC (RFC 4033-4035, RFC 5155) and zone digests (RFC 8976)
+//!
+//! This module works with the wire format only. No cryptography is done
+//! here except for the SHA-1 hash of NSEC3 owner names, hash functions
+//! are plugged in by implementing the [`Digest`] trait and signature
+//! algorithms by implementing the [`SignatureVerifier`] trait.
+//!
+//! [`Digest`]: trait.Digest.html
+//! [`SignatureVerifier`]: trait.SignatureVerifier.html
+use std::borrow::Cow;
+
+use std::cmp::Ordering;
+
+use quick_error::quick_error;
+
+use crate::rdata::{DnsKey, Ds, Nsec, Nsec3, Rrsig};
+use crate::sha1::Sha1;
+use crate::{Error, Limit, Name, RData, ResourceRecord, SerialNumber, Type};
+
+/// Hash algorithm of NSEC3 records: SHA-1, the only one defined
+pub const NSEC3_SHA1: u8 = 1;
+
+/// Largest NSEC3 iteration count hashed (RFC 9276 section 3.2)
+///
+/// Zones should use no extra iterations at all. Validators treat NSEC3
+/// records with more iterations than their limit as insecure, 150 is the
+/// limit of the common implementations.
+pub const MAX_NSEC3_ITERATIONS: u16 = 150;
+
+/// DS digest type SHA-1 (RFC 4034)
+pub const DIGEST_SHA1: u8 = 1;
+/// DS digest type SHA-256 (RFC 4509)
+pub const DIGEST_SHA256: u8 = 2;
+/// DS digest type SHA-384 (RFC 6605)
+pub const DIGEST_SHA384: u8 = 4;
+
+/// A hash function provided by the user
+///
+/// This crate takes no crypto dependency, implement this trait on top of
+/// the hash functions of a crypto library.
+pub trait Digest {
+    /// Feeds data to the hash function
+    fn update(&mut self, data: &[u8]);
+    /// Returns the hash of all data fed
+    fn finish(self) -> Vec<u8>;
+}
+
+quick_error! {
+    /// Error verifying an RRSIG
+    #[derive(Debug)]
+    pub enum VerifyError {
+        /// Signature algorithm is not supported by the verifier
+        UnsupportedAlgorithm(code: u8) {
+            display("algorithm {} is not supported", code)
+        }
+        /// The DNSKEY is not the one that made the signature
+        KeyMismatch {
+            display("key does not match the signature")
+        }
+        /// The records are not one RRset covered by the signature
+        RRsetMismatch {
+            display("records do not match the signature")
+        }
+        /// The signature inception is in the future
+        NotYetValid {
+            display("signature is not yet valid")
+        }
+        /// The signature expiration is in the past
+        Expired {
+            display("signature has expired")
+        }
+        /// The signature doesn't match the data
+        BadSignature {
+            display("bad signature")
+        }
+    }
+}
+
+/// A signature algorithm provided by the user
+///
+/// This crate takes no crypto dependency, implement this trait on top of
+/// a crypto library and pass it to `verify_rrsig`.
+pub trait SignatureVerifier {
+    /// Checks `signature` over `data` with a public key
+    ///
+    /// `algorithm` is the DNSSEC algorithm number, `public_key` and
+    /// `signature` are in the DNSSEC wire format of that algorithm (e.g.
+    /// RFC 3110 for RSA, RFC 6605 for ECDSA), converting them is up to the
+    /// implementation. Return `UnsupportedAlgorithm` for unknown
+    /// algorithms and `BadSignature` if the signature doesn't match.
+    fn verify(
+        &self,
+        algorithm: u8,
+        public_key: &[u8],
+        data: &[u8],
+        signature: &[u8],
+    ) -> Result<(), VerifyError>;
+}
+
+const BASE32HEX: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+/// Computes the NSEC3 hash of a name (RFC 5155 section 5)
+///
+/// The parameters are the ones of the NSEC3PARAM or NSEC3 record of the
+/// zone. Fails with `UnsupportedAlgorithm` if `algorithm` is not
+/// `NSEC3_SHA1`, and with `LimitExceeded(Limit::Iterations)` if there are
+/// more than `MAX_NSEC3_ITERATIONS` iterations, the records proving
+/// nothing in that case.
+pub fn nsec3_hash(
+    name: &Name<'_>,
+    algorithm: u8,
+    iterations: u16,
+    salt: &[u8],
+) -> Result<[u8; 20], Error> {
+    if algorithm != NSEC3_SHA1 {
+        return Err(Error::UnsupportedAlgorithm(algorithm));
+    }
+    if iterations > MAX_NSEC3_ITERATIONS {
+        return Err(Error::LimitExceeded(Limit::Iterations));
+    }
+    let mut owner = Vec::new();
+    name.write_canonical_to(&mut owner);
+    let mut sha = Sha1::new();
+    sha.update(&owner);
+    sha.update(salt);
+    let mut digest = sha.finish();
+    for _ in 0..iterations {
+        let mut sha = Sha1::new();
+        sha.update(&digest);
+        sha.update(salt);
+        digest = sha.finish();
+    }
+    Ok(digest)
+}
+
+/// Encodes data in lowercase base32hex without padding (RFC 4648)
+///
+/// This is how hashed owner names of NSEC3 records are written.
+pub fn encode_base32hex(data: &[u8]) -> String {
+    let mut result = String::with_capacity(data.len().div_ceil(5) * 8);
+    let mut acc: u64 = 0;
+    let mut bits = 0;
+    for &byte in data {
+        acc = acc << 8 | byte as u64;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            result.push(BASE32HEX[(acc >> bits) as usize & 0x1F] as char);
+        }
+    }
+    if bits > 0 {
+        result.push(BASE32HEX[(acc << (5 - bits)) as usize & 0x1F] as char);
+    }
+    result
+}
+
+// Decodes base32hex in either case, without padding
+fn decode_base32hex(text: &[u8]) -> Option<Vec<u8>> {
+    let mut result = Vec::with_capacity(text.len() * 5 / 8);
+    let mut acc: u64 = 0;
+    let mut bits = 0;
+    for &c in text {
+        let value = match c.to_ascii_lowercase() {
+            c @ b'0'..=b'9' => c - b'0',
+            c @ b'a'..=b'v' => c - b'a' + 10,
+            _ => return None,
+        };
+        acc = acc << 5 | value as u64;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((acc >> bits) as u8);
+        }
+    }
+    Some(result)
+}
+
+/// Builds the data an RRSIG signs (RFC 4034 section 3.1.8.1)
+///
+/// That is the RRSIG record data without the signature followed by the
+/// records of `rrset` in the canonical form, with the Original TTL of the
+/// RRSIG. The records are sorted by their canonical record data and
+/// duplicates are dropped, so they may be passed in any order. All of
+/// them are expected to share the owner name, class and type.
+///
+/// If the owner name has more labels than the `labels` field of the
+/// RRSIG, the records were synthesized from a wildcard and the wildcard
+/// name is used as the owner (RFC 4035 section 5.3.2).
+pub fn signature_input(rrsig: &Rrsig<'_>, rrset: &[ResourceRecord<'_>]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    rrsig.write_header_to(&mut buf, true);
+    let first = match rrset.first() {
+        Some(first) => first,
+        None => return buf,
+    };
+    let mut owner = Vec::new();
+    let labels = first.name.labels().collect::<Vec<_>>();
+    let keep = rrsig.labels as usize;
+    if labels.len() > keep {
+        owner.extend_from_slice(b"\x01*");
+        for label in &labels[labels.len() - keep..] {
+            owner.push(label.len() as u8);
+            owner.extend(label.iter().map(u8::to_ascii_lowercase));
+        }
+        owner.push(0);
+    } else {
+        first.name.write_canonical_to(&mut owner);
+    }
+    let mut rdatas = rrset
+        .iter()
+        .map(|record| {
+            let mut rdata = Vec::new();
+            record.data.write_canonical_to(&mut rdata);
+            rdata
+        })
+        .collect::<Vec<_>>();
+    rdatas.sort();
+    rdatas.dedup();
+    for rdata in rdatas {
+        buf.extend_from_slice(&owner);
+        buf.extend_from_slice(&first.data.typ().code().to_be_bytes());
+        buf.extend_from_slice(&first.cls.code().to_be_bytes());
+        buf.extend_from_slice(&rrsig.original_ttl.to_be_bytes());
+        buf.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+        buf.extend_from_slice(&rdata);
+    }
+    buf
+}
+
+/// Builds the data hashed into a DS record (RFC 4034 section 5.1.4)
+///
+/// That is the canonical owner name of the DNSKEY followed by its record
+/// data.
+pub fn ds_digest_input(owner: &Name<'_>, key: &DnsKey<'_>) -> Vec<u8> {
+    let mut buf = Vec::new();
+    owner.write_canonical_to(&mut buf);
+    key.write_to(&mut buf);
+    buf
+}
+
+/// Builds the DS record of a DNSKEY
+///
+/// `digest_type` is written into the record as is, it must denote the
+/// hash function of `digest`. Compare the result with the DS records
+/// published in the parent zone.
+pub fn ds_from_dnskey<D: Digest>(
+    owner: &Name<'_>,
+    key: &DnsKey<'_>,
+    digest_type: u8,
+    mut digest: D,
+) -> Ds<'static> {
+    digest.update(&ds_digest_input(owner, key));
+    Ds {
+        key_tag: key.key_tag(),
+        algorithm: key.algorithm,
+        digest_type,
+        digest: Cow::Owned(digest.finish()),
+    }
+}
+
+/// Computes the digest of a zone with the SIMPLE scheme (RFC 8976)
+///
+/// The records are hashed in the canonical form, sorted in the canonical
+/// order with duplicates dropped, so they may be passed in any order.
+/// Records outside of the zone at `apex` are ignored. The apex ZONEMD
+/// records and the RRSIGs covering them are excluded, all other records
+/// including glue and occluded data are hashed.
+///
+/// Compare the result with the digest of the apex ZONEMD record with the
+/// `SCHEME_SIMPLE` scheme and the hash algorithm of `digest`. The serial
+/// of that record must match the serial of the SOA record.
+pub fn zone_digest<D: Digest>(
+    apex: &Name<'_>,
+    records: &[ResourceRecord<'_>],
+    mut digest: D,
+) -> Vec<u8> {
+    let apex_labels = apex.labels().count();
+    let mut hashed = records
+        .iter()
+        .filter_map(|record| {
+            let labels = record.name.labels().collect::<Vec<_>>();
+            if common_suffix(&labels, apex) != apex_labels {
+                return None;
+            }
+            let at_apex = labels.len() == apex_labels;
+            let excluded = match record.data {
+                RData::ZONEMD(..) => at_apex,
+                RData::RRSIG(ref rrsig) => at_apex && rrsig.type_covered == Type::ZONEMD.code(),
+                _ => false,
+            };
+            if excluded {
+                return None;
+            }
+            let mut rdata = Vec::new();
+            record.data.write_canonical_to(&mut rdata);
+            Some((record, rdata))
+        })
+        .collect::<Vec<_>>();
+    hashed.sort_by(|(a, a_data), (b, b_data)| {
+        a.name
+            .canonical_cmp(&b.name)
+            .then(a.data.typ().code().cmp(&b.data.typ().code()))
+            .then(a.cls.code().cmp(&b.cls.code()))
+            .then(a_data.cmp(b_data))
+    });
+    hashed.dedup_by(|(a, a_data), (b, b_data)| {
+        a.data.typ() == b.data.typ()
+            && a.cls == b.cls
+            && a_data == b_data
+            && a.name.eq_ignore_ascii_case(&b.name)
+    });
+    let mut buf = Vec::new();
+    for (record, _) in hashed {
+        buf.clear();
+        record.write_canonical_to(record.ttl.0, &mut buf);
+        digest.update(&buf);
+    }
+    digest.finish()
+}
+
+/// Verifies an RRSIG over an RRset (RFC 4035 section 5.3)
+///
+/// `key_owner` and `key` are the owner name and data of the DNSKEY that
+/// made the signature, `now` is the current time in seconds since the
+/// epoch modulo 2^32. The records of `rrset` may come in any order.
+///
+/// Checks that the key matches the signer name, algorithm and key tag of
+/// the RRSIG and is a zone key, that the records form one RRset of the
+/// covered type within the signer's zone, and that `now` is within the
+/// validity period, comparing with serial number arithmetic (RFC 1982).
+/// The signature itself is checked by `verifier`. Whether the key is
+/// trusted is up to the caller.
+pub fn verify_rrsig<V: SignatureVerifier + ?Sized>(
+    rrsig: &Rrsig<'_>,
+    key_owner: &Name<'_>,
+    key: &DnsKey<'_>,
+    rrset: &[ResourceRecord<'_>],
+    now: u32,
+    verifier: &V,
+) -> Result<(), VerifyError> {
+    if !key_owner.eq_ignore_ascii_case(&rrsig.signer_name)
+        || key.protocol != 3
+        || key.flags & DnsKey::ZONE_KEY == 0
+        || key.algorithm != rrsig.algorithm
+        || key.key_tag() != rrsig.key_tag
+    {
+        return Err(VerifyError::KeyMismatch);
+    }
+    let first = rrset.first().ok_or(VerifyError::RRsetMismatch)?;
+    let consistent = rrset.iter().all(|record| {
+        record.data.typ().code() == rrsig.type_covered
+            && record.cls == first.cls
+            && record.name.eq_ignore_ascii_case(&first.name)
+    });
+    let labels = first.name.labels().collect::<Vec<_>>();
+    let in_zone = common_suffix(&labels, &rrsig.signer_name) == rrsig.signer_name.labels().count();
+    let wildcard = labels.first().is_some_and(|label| label == b"*");
+    let owner_labels = labels.len() - wildcard as usize;
+    if !consistent || !in_zone || owner_labels < rrsig.labels as usize {
+        return Err(VerifyError::RRsetMismatch);
+    }
+    let now = SerialNumber(now);
+    if now < SerialNumber(rrsig.inception) {
+        return Err(VerifyError::NotYetValid);
+    }
+    if now > SerialNumber(rrsig.expiration) {
+        return Err(VerifyError::Expired);
+    }
+    let data = signature_input(rrsig, rrset);
+    verifier.verify(rrsig.algorithm, &key.public_key, &data, &rrsig.signature)
+}
+
+/// Returns true if `name` falls strictly between the owner name of an
+/// NSEC record and its next name, i.e. the record proves it doesn't exist
+///
+/// The last NSEC record of a zone points back to the apex, so the
+/// interval wraps around.
+pub fn nsec_covers(owner: &Name<'_>, nsec: &Nsec<'_>, name: &Name<'_>) -> bool {
+    let after_owner = owner.canonical_cmp(name) == Ordering::Less;
+    let before_next = name.canonical_cmp(&nsec.next_name) == Ordering::Less;
+    if owner.canonical_cmp(&nsec.next_name) == Ordering::Less {
+        after_owner && before_next
+    } else {
+        after_owner || before_next
+    }
+}
+
+/// Returns true if an NSEC record proves that `name` has no records of
+/// the type (and no CNAME, which would answer any type)
+pub fn nsec_denies_type(owner: &Name<'_>, nsec: &Nsec<'_>, name: &Name<'_>, typ: u16) -> bool {
+    owner.eq_ignore_ascii_case(name)
+        && !nsec.types.contains(typ)
+        && !nsec.types.contains(Type::CNAME.code())
+}
+
+/// Returns the closest encloser of a name covered by an NSEC record
+///
+/// That is the longest ancestor of `name` shared with either the owner or
+/// the next name of the record (RFC 4035 section 5.4). Its wildcard, see
+/// `wildcard`, must be denied too to prove that `name` doesn't exist.
+pub fn nsec_closest_encloser(owner: &Name<'_>, nsec: &Nsec<'_>, name: &Name<'_>) -> Name<'static> {
+    let labels = name.labels().collect::<Vec<_>>();
+    let common = common_suffix(&labels, owner).max(common_suffix(&labels, &nsec.next_name));
+    name_from_labels(None, &labels[labels.len() - common..])
+}
+
+/// Returns the wildcard name `*.<encloser>`
+pub fn wildcard(encloser: &Name<'_>) -> Name<'static> {
+    name_from_labels(Some(b"*"), &encloser.labels().collect::<Vec<_>>())
+}
+
+/// Returns true if the hashed owner name of an NSEC3 record is the hash
+/// of `name`
+///
+/// `name` must also be in the zone of the record, i.e. at or below the
+/// owner name without its first label.
+pub fn nsec3_matches(owner: &Name<'_>, nsec3: &Nsec3<'_>, name: &Name<'_>) -> Result<bool, Error> {
+    match nsec3_position(owner, nsec3, name)? {
+        Some(hash) => Ok(hash == Ordering::Equal),
+        None => Ok(false),
+    }
+}
+
+/// Returns true if the hash of `name` falls strictly between the hashed
+/// owner name of an NSEC3 record and its next hashed owner name
+///
+/// With the opt-out flag set, a covered name may still exist as an
+/// unsigned delegation.
+pub fn nsec3_covers(owner: &Name<'_>, nsec3: &Nsec3<'_>, name: &Name<'_>) -> Result<bool, Error> {
+    match nsec3_position(owner, nsec3, name)? {
+        Some(Ordering::Greater) => Ok(true),
+        _ => Ok(false),
+    }
+}
+
+// Returns `Equal` if the name matches the record, `Greater` if it's
+// covered and `Less` otherwise, `None` if the name is out of the zone or
+// the owner is not a hash
+fn nsec3_position(
+    owner: &Name<'_>,
+    nsec3: &Nsec3<'_>,
+    name: &Name<'_>,
+) -> Result<Option<Ordering>, Error> {
+    let owner_labels = owner.labels().collect::<Vec<_>>();
+    let zone_len = match owner_labels.len() {
+        0 => return Ok(None),
+        len => len - 1,
+    };
+    let labels = name.labels().collect::<Vec<_>>();
+    let owner_hash = match decode_base32hex(owner_labels[0]) {
+        Some(hash) => hash,
+        None => return Ok(None),
+    };
+    if labels.len() < zone_len || common_suffix(&labels, owner) < zone_len {
+        return Ok(None);
+    }
+    let hash = nsec3_hash(name, nsec3.hash_algorithm, nsec3.iterations, &nsec3.salt)?;
+    let hash = &hash[..];
+    let next = &nsec3.next_hashed_owner[..];
+    let covered = if owner_hash[..] < *next {
+        &owner_hash[..] < hash && hash < next
+    } else {
+        &owner_hash[..] < hash || hash < next
+    };
+    Ok(Some(if hash == &owner_hash[..] {
+        Ordering::Equal
+    } else if covered {
+        Ordering::Greater
+    } else {
+        Ordering::Less
+    }))
+}
+
+/// Closest encloser proof for a name, see `closest_encloser`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClosestEncloser {
+    /// The closest encloser, the longest existing ancestor of the name
+    pub encloser: Name<'static>,
+    /// Index of the NSEC3 record matching the encloser
+    pub matching: usize,
+    /// The next closer name, the encloser with one more label of the name
+    pub next_closer: Name<'static>,
+    /// Index of the NSEC3 record covering the next closer name
+    pub covering: usize,
+}
+
+/// Finds the closest encloser proof of a name (RFC 5155 section 8.3)
+///
+/// Records other than NSEC3 are ignored, indices point into `records`.
+/// Returns `None` if the name itself has a matching NSEC3 record or the
+/// records don't prove anything. To prove that the name doesn't exist,
+/// the wildcard at the encloser must be covered too.
+pub fn closest_encloser(
+    name: &Name<'_>,
+    records: &[ResourceRecord<'_>],
+) -> Result<Option<ClosestEncloser>, Error> {
+    let nsec3s = records
+        .iter()
+        .enumerate()
+        .filter_map(|(index, record)| match record.data {
+            RData::NSEC3(ref nsec3) => Some((index, &record.name, nsec3)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let labels = name.labels().collect::<Vec<_>>();
+    for skip in 0..=labels.len() {
+        let candidate = name_from_labels(None, &labels[skip..]);
+        let mut matching = None;
+        for &(index, owner, nsec3) in &nsec3s {
+            if nsec3_matches(owner, nsec3, &candidate)? {
+                matching = Some(index);
+                break;
+            }
+        }
+        let matching = match matching {
+            Some(index) => index,
+            None => continue,
+        };
+        if skip == 0 {
+            return Ok(None);
+        }
+        let next_closer = name_from_labels(None, &labels[skip - 1..]);
+        for &(index, owner, nsec3) in &nsec3s {
+            if nsec3_covers(owner, nsec3, &next_closer)? {
+                return Ok(Some(ClosestEncloser {
+                    encloser: candidate,
+                    matching,
+                    next_closer,
+                    covering: index,
+                }));
+            }
+        }
+        return Ok(None);
+    }
+    Ok(None)
+}
+
+// Number of rightmost labels shared by the names, ignoring case
+fn common_suffix(labels: &[&[u8]], other: &Name<'_>) -> usize {
+    let other = other.labels().collect::<Vec<_>>();
+    labels
+        .iter()
+        .rev()
+        .zip(other.iter().rev())
+        .take_while(|(a, b)| a.eq_ignore_ascii_case(b))
+        .count()
+}
+
+fn name_from_labels(first: Option<&[u8]>, labels: &[&[u8]]) -> Name<'static> {
+    let mut wire = Vec::new();
+    for label in first.into_iter().chain(labels.iter().cloned()) {
+        wire.push(label.len() as u8);
+        wire.extend_from_slice(label);
+    }
+    wire.push(0);
+    Name::from_uncompressed(wire)
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::{closest_encloser, nsec3_covers, nsec3_matches, wildcard};
+    use super::{ds_from_dnskey, encode_base32hex, nsec3_hash, signature_input};
+    use super::{nsec_closest_encloser, nsec_covers, nsec_denies_type};
+    use super::{verify_rrsig, zone_digest, SignatureVerifier, VerifyError};
+    use super::{Digest, DIGEST_SHA1, MAX_NSEC3_ITERATIONS};
+    use crate::rdata::{DnsKey, Ds, Nsec, Nsec3, Rrsig};
+    use crate::sha1::Sha1;
+    use crate::{Class, Error, Limit, Name, Packet, RData, ResourceRecord, Ttl, Type, TypeBitmap};
+
+    impl Digest for Sha1 {
+        fn update(&mut self, data: &[u8]) {
+            Sha1::update(self, data)
+        }
+        fn finish(self) -> Vec<u8> {
+            Sha1::finish(self).to_vec()
+        }
+    }
+
+    fn name(wire: &[u8]) -> Name<'_> {
+        Name::scan(wire, wire).unwrap()
+    }
+
+    fn parse_name(text: &str) -> Name<'static> {
+        let mut wire = Vec::new();
+        for label in text.split('.').filter(|label| !label.is_empty()) {
+            wire.push(label.len() as u8);
+            wire.extend_from_slice(label.as_bytes());
+        }
+        wire.push(0);
+        Name::scan(&wire, &wire).unwrap().into_owned()
+    }
+
+    #[test]
+    fn rfc5155_hashes() {
+        // appendix A, iterations 12, salt aabbccdd
+        let salt = b"\xaa\xbb\xcc\xdd";
+        let cases: &[(&[u8], &str)] = &[
+            (b"\x07example\x00", "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom"),
+            (b"\x01a\x07EXAMPLE\x00", "35mthgpgcu1qg68fab165klnsnk3dpvl"),
+            (
+                b"\x03ns1\x07example\x00",
+                "2t7b4g4vsa5smi47k61mv5bv1a22bojr",
+            ),
+        ];
+        for &(wire, hash) in cases {
+            let digest = nsec3_hash(&name(wire), 1, 12, salt).unwrap();
+            assert_eq!(encode_base32hex(&digest), hash);
+        }
+        assert!(matches!(
+            nsec3_hash(&name(b"\x00"), 2, 0, b""),
+            Err(Error::UnsupportedAlgorithm(2))
+        ));
+        assert!(nsec3_hash(&name(b"\x00"), 1, MAX_NSEC3_ITERATIONS, b"").is_ok());
+        assert!(matches!(
+            nsec3_hash(&name(b"\x00"), 1, MAX_NSEC3_ITERATIONS + 1, b""),
+            Err(Error::LimitExceeded(Limit::Iterations))
+        ));
+    }
+
+    fn rrsig(labels: u8) -> Rrsig<'static> {
+        Rrsig {
+            type_covered: 1,
+            algorithm: 8,
+            labels,
+            original_ttl: 3600,
+            expiration: 0x6500_0000,
+            inception: 0x6400_0000,
+            key_tag: 2642,
+            signer_name: Name::scan(b"\x07EXAMPLE\x00", b"\x07EXAMPLE\x00")
+                .unwrap()
+                .into_owned(),
+            signature: Cow::Borrowed(b"\xde\xad"),
+        }
+    }
+
+    // Three A records of a.Example, the first one repeated last
+    const RESPONSE: &[u8] = b"\x00\x01\x81\x80\x00\x00\x00\x03\x00\x00\x00\x00\
+        \x01a\x07Example\x00\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x02\
+        \xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x01\
+        \xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x02";
+
+    const PREFIX: &[u8] = b"\x00\x01\x08\x02\x00\x00\x0e\x10\x65\x00\x00\x00\x64\x00\x00\x00\
+        \x0a\x52\x07example\x00";
+
+    #[test]
+    fn rrsig_input() {
+        let packet = Packet::parse(RESPONSE).unwrap();
+        let mut expected = PREFIX.to_vec();
+        for last in &[1, 2] {
+            expected.extend_from_slice(b"\x01a\x07example\x00\x00\x01\x00\x01\x00\x00\x0e\x10");
+            expected.extend_from_slice(&[0, 4, 192, 0, 2, *last]);
+        }
+        assert_eq!(signature_input(&rrsig(2), packet.answers()), expected);
+    }
+
+    #[test]
+    fn wildcard_input() {
+        let packet = Packet::parse(RESPONSE).unwrap();
+        let input = signature_input(&rrsig(1), &packet.answers()[..1]);
+        let mut expected = PREFIX.to_vec();
+        expected[3] = 1;
+        expected.extend_from_slice(b"\x01*\x07example\x00\x00\x01\x00\x01\x00\x00\x0e\x10");
+        expected.extend_from_slice(b"\x00\x04\xc0\x00\x02\x02");
+        assert_eq!(input, expected);
+    }
+
+    // Toy algorithm 8: the signature is the SHA-1 hash of key and data
+    struct TestVerifier;
+
+    impl TestVerifier {
+        fn sign(key: &[u8], data: &[u8]) -> Vec<u8> {
+            let mut sha = Sha1::new();
+            sha.update(key);
+            sha.update(data);
+            sha.finish().to_vec()
+        }
+    }
+
+    impl SignatureVerifier for TestVerifier {
+        fn verify(
+            &self,
+            algorithm: u8,
+            public_key: &[u8],
+            data: &[u8],
+            signature: &[u8],
+        ) -> Result<(), VerifyError> {
+            if algorithm != 8 {
+                return Err(VerifyError::UnsupportedAlgorithm(algorithm));
+            }
+            if TestVerifier::sign(public_key, data) != signature {
+                return Err(VerifyError::BadSignature);
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn verify() {
+        let packet = Packet::parse(RESPONSE).unwrap();
+        let rrset = packet.answers();
+        let key = DnsKey {
+            flags: DnsKey::ZONE_KEY,
+            protocol: 3,
+            algorithm: 8,
+            public_key: Cow::Borrowed(b"\x03\x01\x00\x01\xab\xcd"),
+        };
+        let owner = parse_name("example");
+        let mut sig = rrsig(2);
+        sig.key_tag = key.key_tag();
+        sig.signature = TestVerifier::sign(&key.public_key, &signature_input(&sig, rrset)).into();
+        let now = 0x6480_0000;
+        let check = |sig: &Rrsig<'_>, key: &DnsKey<'_>, rrset: &[ResourceRecord<'_>], now| {
+            verify_rrsig(sig, &owner, key, rrset, now, &TestVerifier)
+        };
+        check(&sig, &key, rrset, now).unwrap();
+        // records in another order
+        let reversed = rrset.iter().rev().cloned().collect::<Vec<_>>();
+        check(&sig, &key, &reversed, now).unwrap();
+
+        assert!(matches!(
+            check(&sig, &key, &rrset[..1], now),
+            Err(VerifyError::BadSignature)
+        ));
+        assert!(matches!(
+            check(&sig, &key, &[], now),
+            Err(VerifyError::RRsetMismatch)
+        ));
+        assert!(matches!(
+            check(&sig, &key, rrset, 0x6380_0000),
+            Err(VerifyError::NotYetValid)
+        ));
+        assert!(matches!(
+            check(&sig, &key, rrset, 0x6580_0000),
+            Err(VerifyError::Expired)
+        ));
+        let mut other = sig.clone();
+        other.labels = 3;
+        assert!(matches!(
+            check(&other, &key, rrset, now),
+            Err(VerifyError::RRsetMismatch)
+        ));
+        other = sig.clone();
+        other.type_covered = 28;
+        assert!(matches!(
+            check(&other, &key, rrset, now),
+            Err(VerifyError::RRsetMismatch)
+        ));
+        let mut other_key = key.clone();
+        other_key.flags = 0;
+        assert!(matches!(
+            check(&sig, &other_key, rrset, now),
+            Err(VerifyError::KeyMismatch)
+        ));
+        other_key = key.clone();
+        other_key.public_key = Cow::Borrowed(b"\x03\x01\x00\x01\xab\xce");
+        assert!(matches!(
+            check(&sig, &other_key, rrset, now),
+            Err(VerifyError::KeyMismatch)
+        ));
+    }
+
+    #[test]
+    fn rfc4034_ds() {
+        // example of RFC 4034 section 5.4
+        let key = DnsKey {
+            flags: 256,
+            protocol: 3,
+            algorithm: 5,
+            public_key: Cow::Borrowed(
+                b"\x01\x03\x9e\x8a\x24\x74\x18\xe3\x18\x90\x3b\x21\x5a\x84\x8a\xcf\
+                  \xd5\xf3\x7f\x02\x6b\xd4\x06\x2d\xb2\x6c\x77\x4c\x69\x09\x68\xd5\
+                  \xd5\x6d\xf8\xbf\xda\x91\xe6\xf3\x6d\x9a\x27\x98\x88\xf4\x13\x33\
+                  \x35\x7c\x5e\x60\x29\x99\x0d\x10\xfd\xf5\x66\x30\x62\xa5\x12\x76\
+                  \x33\x26\x98\x0a\x61\x5d\xdb\xf1\x7a\x05\xdd\xfc\xce\x7e\x5f\xb3\
+                  \xab\xcc\xa0\x5a\x31\xb0\x95\x74\x52\xd4\x52\x1e\x83\x87\x07\x89\
+                  \x06\x31\x15\xbf\x97\xf6\xc3\x08\xcc\xf5\x7c\xdc\x9c\xe7\xfe\x10\
+                  \xf6\xed\x1b\xd0\xcc\x06\x60\x03\x8c\x50\xdc\xdb\x0f\xeb\x96\x3c\
+                  \x2f\x17",
+            ),
+        };
+        let owner = b"\x05DSKEY\x07example\x03com\x00";
+        let owner = Name::scan(owner, owner).unwrap();
+        assert_eq!(
+            ds_from_dnskey(&owner, &key, DIGEST_SHA1, Sha1::new()),
+            Ds {
+                key_tag: 60485,
+                algorithm: 5,
+                digest_type: DIGEST_SHA1,
+                digest: Cow::Borrowed(
+                    b"\x2b\xb1\x83\xaf\x5f\x22\x58\x81\x79\xa5\
+                      \x3b\x0a\x98\x63\x1f\xad\x1a\x29\x21\x18"
+                ),
+            }
+        );
+    }
+
+    // The zone of RFC 8976 appendix A.1 shuffled, with a duplicate A
+    // record, an RRSIG covering the ZONEMD record and an out of zone record
+    const ZONE: &[u8] = b"\x00\x01\x81\x80\x00\x00\x00\x09\x00\x00\x00\x00\
+        \x07example\x00\x00\x3f\x00\x01\x00\x01\x51\x80\x00\x12\x78\x48\xb9\x1c\
+        \x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+        \x03NS2\x07example\x00\x00\x1c\x00\x01\x00\x00\x0e\x10\x00\x10\x20\x01\
+        \x0d\xb8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x63\
+        \x07example\x00\x00\x06\x00\x01\x00\x01\x51\x80\x00\x30\x03ns1\
+        \x07example\x00\x05admin\x07example\x00\x78\x48\xb9\x1c\x00\x00\x07\x08\
+        \x00\x00\x03\x84\x00\x09\x3a\x80\x00\x01\x51\x80\
+        \x07example\x00\x00\x02\x00\x01\x00\x01\x51\x80\x00\x0d\x03ns2\x07example\x00\
+        \x03ns1\x07example\x00\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xcb\x00\x71\x3f\
+        \x07example\x00\x00\x02\x00\x01\x00\x01\x51\x80\x00\x0d\x03ns1\x07example\x00\
+        \x03ns1\x07example\x00\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xcb\x00\x71\x3f\
+        \x07example\x00\x00\x2e\x00\x01\x00\x01\x51\x80\x00\x1d\x00\x3f\x08\x01\
+        \x00\x01\x51\x80\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\x07example\x00\
+        \xde\xad\
+        \x05other\x00\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xc0\x00\x02\x01";
+
+    #[test]
+    fn simple_zone_digest() {
+        let packet = Packet::parse(ZONE).unwrap();
+        let apex = parse_name("example");
+        // with SHA-384 this gives the digest of the RFC, c68090d9...
+        assert_eq!(
+            zone_digest(&apex, packet.answers(), Sha1::new()),
+            b"\x3d\x9a\x46\x8c\x6e\xe5\xaf\x89\x96\xc6\
+              \xe9\x33\x8a\xe3\x61\xb5\xe1\xdb\x73\xde"
+        );
+        match packet.answers()[0].data {
+            RData::ZONEMD(ref zonemd) => assert_eq!(zonemd.serial, 2018031900),
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn nsec_denial() {
+        let nsec = Nsec {
+            next_name: parse_name("host.example.com"),
+            types: TypeBitmap::from_types(vec![Type::A, Type::RRSIG, Type::NSEC]),
+        };
+        let owner = parse_name("alfa.example.com");
+        assert!(nsec_covers(&owner, &nsec, &parse_name("beta.example.com")));
+        assert!(nsec_covers(
+            &owner,
+            &nsec,
+            &parse_name("x.ALFA.example.com")
+        ));
+        assert!(!nsec_covers(&owner, &nsec, &parse_name("alfa.example.com")));
+        assert!(!nsec_covers(&owner, &nsec, &parse_name("zulu.example.com")));
+        assert!(nsec_denies_type(
+            &owner,
+            &nsec,
+            &parse_name("Alfa.example.com"),
+            15
+        ));
+        assert!(!nsec_denies_type(&owner, &nsec, &owner, 1));
+
+        let encloser = nsec_closest_encloser(&owner, &nsec, &parse_name("x.b.example.com"));
+        assert_eq!(encloser.to_string(), "example.com");
+        assert_eq!(wildcard(&encloser).to_string(), "*.example.com");
+        let encloser = nsec_closest_encloser(&owner, &nsec, &parse_name("x.alfa.example.com"));
+        assert_eq!(encloser.to_string(), "alfa.example.com");
+
+        // the last record of the zone wraps around to the apex
+        let last = Nsec {
+            next_name: parse_name("example.com"),
+            types: TypeBitmap::from_codes(vec![]),
+        };
+        let owner = parse_name("zulu.example.com");
+        assert!(nsec_covers(&owner, &last, &parse_name("zz.example.com")));
+        assert!(!nsec_covers(&owner, &last, &parse_name("beta.example.com")));
+    }
+
+    // NSEC3 chain of a zone with names example, a.example, w.example and
+    // x.w.example
+    fn nsec3_chain() -> Vec<ResourceRecord<'static>> {
+        let salt = b"\xaa\xbb";
+        let mut hashes = ["example", "a.example", "w.example", "x.w.example"]
+            .iter()
+            .map(|name| nsec3_hash(&parse_name(name), 1, 2, salt).unwrap())
+            .collect::<Vec<_>>();
+        hashes.sort();
+        (0..hashes.len())
+            .map(|i| ResourceRecord {
+                name: parse_name(&format!("{}.example", encode_base32hex(&hashes[i]))),
+                multicast_unique: false,
+                cls: Class::IN,
+                ttl: Ttl(3600),
+                data: RData::NSEC3(Nsec3 {
+                    hash_algorithm: 1,
+                    flags: 0,
+                    iterations: 2,
+                    salt: Cow::Borrowed(salt),
+                    next_hashed_owner: Cow::Owned(hashes[(i + 1) % hashes.len()].to_vec()),
+                    types: TypeBitmap::from_types(vec![Type::A]),
+                }),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nsec3_denial() {
+        let records = nsec3_chain();
+        let matches = |name: &str| {
+            records
+                .iter()
+                .filter(|record| match record.data {
+                    RData::NSEC3(ref nsec3) => {
+                        nsec3_matches(&record.name, nsec3, &parse_name(name)).unwrap()
+                    }
+                    _ => false,
+                })
+                .count()
+        };
+        let covers = |name: &str| {
+            records
+                .iter()
+                .filter(|record| match record.data {
+                    RData::NSEC3(ref nsec3) => {
+                        nsec3_covers(&record.name, nsec3, &parse_name(name)).unwrap()
+                    }
+                    _ => false,
+                })
+                .count()
+        };
+        assert_eq!(matches("A.example"), 1);
+        assert_eq!(covers("a.example"), 0);
+        assert_eq!(matches("b.example"), 0);
+        assert_eq!(covers("b.example"), 1);
+        // out of the zone
+        assert_eq!(matches("example.com"), 0);
+        assert_eq!(covers("example.com"), 0);
+
+        let proof = closest_encloser(&parse_name("b.c.x.w.example"), &records)
+            .unwrap()
+            .unwrap();
+        assert_eq!(proof.encloser.to_string(), "x.w.example");
+        assert_eq!(proof.next_closer.to_string(), "c.x.w.example");
+        assert!(
+            matches!(records[proof.matching].data, RData::NSEC3(ref nsec3)
+            if nsec3_matches(&records[proof.matching].name, nsec3, &proof.encloser).unwrap())
+        );
+        assert_eq!(covers("*.x.w.example"), 1);
+        assert_eq!(
+            closest_encloser(&parse_name("w.example"), &records).unwrap(),
+            None
+        );
+
+        // too many iterations to be hashed
+        let mut records = records;
+        for record in &mut records {
+            if let RData::NSEC3(ref mut nsec3) = record.data {
+                nsec3.iterations = u16::MAX;
+            }
+        }
+        assert!(matches!(
+            closest_encloser(&parse_name("b.c.x.w.example"), &records),
+            Err(Error::LimitExceeded(Limit::Iterations))
+        ));
+    }
+
+    #[test]
+    fn base32hex() {
+        // RFC 4648 test vectors
+        assert_eq!(encode_base32hex(b""), "");
+        assert_eq!(encode_base32hex(b"f"), "co");
+        assert_eq!(encode_base32hex(b"fo"), "cpng");
+        assert_eq!(encode_base32hex(b"foobar"), "cpnmuoj1e8");
+    }
+}
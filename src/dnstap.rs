@@ -0,0 +1,345 @@
+//! Logging of DNS messages in the dnstap format
+//!
+//! A [`Message`] holds a DNS message along with the transport metadata.
+//! It's encoded as a `Dnstap` protobuf message by `Message::encode`, and
+//! written into a Frame Streams file or socket by [`FrameWriter`], which
+//! is what dnstap collectors expect.
+//!
+//! This module is enabled by the `dnstap` feature.
+//!
+//! [`Message`]: struct.Message.html
+//! [`FrameWriter`]: struct.FrameWriter.html
+use std::io::{self, Write};
+use std::net::{IpAddr, SocketAddr};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{Error, Header};
+
+/// Content type of the frame stream carrying dnstap messages
+pub const CONTENT_TYPE: &str = "protobuf:dnstap.Dnstap";
+
+/// The kind of the logged message, `Message.Type` in `dnstap.proto`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)] // named after the values of the proto file
+pub enum MessageType {
+    AuthQuery = 1,
+    AuthResponse = 2,
+    ResolverQuery = 3,
+    ResolverResponse = 4,
+    ClientQuery = 5,
+    ClientResponse = 6,
+    ForwarderQuery = 7,
+    ForwarderResponse = 8,
+    StubQuery = 9,
+    StubResponse = 10,
+    ToolQuery = 11,
+    ToolResponse = 12,
+    UpdateQuery = 13,
+    UpdateResponse = 14,
+}
+
+/// The role of the logging software, see `Message::from_wire`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Role {
+    Auth,
+    Resolver,
+    Client,
+    Forwarder,
+    Stub,
+    Tool,
+    Update,
+}
+
+impl Role {
+    fn message_type(self, query: bool) -> MessageType {
+        use self::MessageType::*;
+        let (q, r) = match self {
+            Role::Auth => (AuthQuery, AuthResponse),
+            Role::Resolver => (ResolverQuery, ResolverResponse),
+            Role::Client => (ClientQuery, ClientResponse),
+            Role::Forwarder => (ForwarderQuery, ForwarderResponse),
+            Role::Stub => (StubQuery, StubResponse),
+            Role::Tool => (ToolQuery, ToolResponse),
+            Role::Update => (UpdateQuery, UpdateResponse),
+        };
+        if query {
+            q
+        } else {
+            r
+        }
+    }
+}
+
+/// Transport protocol, `SocketProtocol` in `dnstap.proto`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum Protocol {
+    Udp = 1,
+    Tcp = 2,
+    Dot = 3,
+    Doh = 4,
+    DnsCryptUdp = 5,
+    DnsCryptTcp = 6,
+    Doq = 7,
+}
+
+/// A DNS message with its metadata
+///
+/// Fields left as `None` are not encoded. The socket family is derived
+/// from the addresses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Message<'a> {
+    /// Name of the server, e.g. the host name
+    pub identity: Option<&'a [u8]>,
+    /// Version of the server software
+    pub version: Option<&'a [u8]>,
+    /// The kind of the message
+    pub typ: MessageType,
+    /// Transport protocol
+    pub protocol: Option<Protocol>,
+    /// Address and port of the side which sent the query
+    pub query_address: Option<SocketAddr>,
+    /// Address and port of the side which sent the response
+    pub response_address: Option<SocketAddr>,
+    /// Time the query was sent or received
+    pub query_time: Option<SystemTime>,
+    /// The query in the wire format
+    pub query_message: Option<&'a [u8]>,
+    /// Time the response was sent or received
+    pub response_time: Option<SystemTime>,
+    /// The response in the wire format
+    pub response_message: Option<&'a [u8]>,
+}
+
+impl<'a> Message<'a> {
+    /// Creates a message of the type with all other fields empty
+    pub fn new(typ: MessageType) -> Message<'a> {
+        Message {
+            identity: None,
+            version: None,
+            typ,
+            protocol: None,
+            query_address: None,
+            response_address: None,
+            query_time: None,
+            query_message: None,
+            response_time: None,
+            response_message: None,
+        }
+    }
+    /// Creates a message from a query or a response in the wire format
+    ///
+    /// The header is parsed to find out whether `data` is a query or
+    /// a response, which determines the message type and where `data`
+    /// and `time` are put.
+    pub fn from_wire(role: Role, data: &'a [u8], time: SystemTime) -> Result<Message<'a>, Error> {
+        let query = Header::parse(data)?.query;
+        let mut message = Message::new(role.message_type(query));
+        if query {
+            message.query_message = Some(data);
+            message.query_time = Some(time);
+        } else {
+            message.response_message = Some(data);
+            message.response_time = Some(time);
+        }
+        Ok(message)
+    }
+    /// Encodes the message as a `Dnstap` protobuf message
+    pub fn encode(&self) -> Vec<u8> {
+        let mut msg = Vec::new();
+        put_varint_field(&mut msg, 1, self.typ as u64);
+        let family = self
+            .query_address
+            .or(self.response_address)
+            .map(|a| match a {
+                SocketAddr::V4(_) => 1,
+                SocketAddr::V6(_) => 2,
+            });
+        if let Some(family) = family {
+            put_varint_field(&mut msg, 2, family);
+        }
+        if let Some(protocol) = self.protocol {
+            put_varint_field(&mut msg, 3, protocol as u64);
+        }
+        if let Some(addr) = self.query_address {
+            put_address(&mut msg, 4, addr.ip());
+        }
+        if let Some(addr) = self.response_address {
+            put_address(&mut msg, 5, addr.ip());
+        }
+        if let Some(addr) = self.query_address {
+            put_varint_field(&mut msg, 6, addr.port() as u64);
+        }
+        if let Some(addr) = self.response_address {
+            put_varint_field(&mut msg, 7, addr.port() as u64);
+        }
+        if let Some(time) = self.query_time {
+            put_time(&mut msg, 8, time);
+        }
+        if let Some(data) = self.query_message {
+            put_bytes_field(&mut msg, 10, data);
+        }
+        if let Some(time) = self.response_time {
+            put_time(&mut msg, 12, time);
+        }
+        if let Some(data) = self.response_message {
+            put_bytes_field(&mut msg, 14, data);
+        }
+
+        let mut buf = Vec::with_capacity(msg.len() + 32);
+        if let Some(identity) = self.identity {
+            put_bytes_field(&mut buf, 1, identity);
+        }
+        if let Some(version) = self.version {
+            put_bytes_field(&mut buf, 2, version);
+        }
+        put_bytes_field(&mut buf, 14, &msg);
+        // Dnstap.Type MESSAGE
+        put_varint_field(&mut buf, 15, 1);
+        buf
+    }
+}
+
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_varint_field(buf: &mut Vec<u8>, field: u32, value: u64) {
+    put_varint(buf, (field as u64) << 3);
+    put_varint(buf, value);
+}
+
+fn put_bytes_field(buf: &mut Vec<u8>, field: u32, data: &[u8]) {
+    put_varint(buf, (field as u64) << 3 | 2);
+    put_varint(buf, data.len() as u64);
+    buf.extend_from_slice(data);
+}
+
+fn put_address(buf: &mut Vec<u8>, field: u32, addr: IpAddr) {
+    match addr {
+        IpAddr::V4(addr) => put_bytes_field(buf, field, &addr.octets()),
+        IpAddr::V6(addr) => put_bytes_field(buf, field, &addr.octets()),
+    }
+}
+
+// Writes the seconds into `field` and nanoseconds into the next one
+fn put_time(buf: &mut Vec<u8>, field: u32, time: SystemTime) {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    put_varint_field(buf, field, since_epoch.as_secs());
+    put_varint(buf, ((field as u64 + 1) << 3) | 5);
+    buf.extend_from_slice(&since_epoch.subsec_nanos().to_le_bytes());
+}
+
+/// Writes dnstap messages as a unidirectional Frame Stream
+///
+/// The START control frame is written on creation and the STOP frame by
+/// `finish`. Dropping the writer without calling `finish` leaves the
+/// stream incomplete.
+#[derive(Debug)]
+pub struct FrameWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> FrameWriter<W> {
+    /// Starts a stream
+    pub fn new(mut writer: W) -> io::Result<FrameWriter<W>> {
+        let content_type = CONTENT_TYPE.as_bytes();
+        let mut control = Vec::with_capacity(12 + content_type.len());
+        // START frame with a CONTENT_TYPE field
+        control.extend_from_slice(&2u32.to_be_bytes());
+        control.extend_from_slice(&1u32.to_be_bytes());
+        control.extend_from_slice(&(content_type.len() as u32).to_be_bytes());
+        control.extend_from_slice(content_type);
+        write_control(&mut writer, &control)?;
+        Ok(FrameWriter { writer })
+    }
+    /// Writes a message as a data frame
+    pub fn write(&mut self, message: &Message<'_>) -> io::Result<()> {
+        let data = message.encode();
+        self.writer.write_all(&(data.len() as u32).to_be_bytes())?;
+        self.writer.write_all(&data)
+    }
+    /// Ends the stream and returns the underlying writer
+    pub fn finish(mut self) -> io::Result<W> {
+        write_control(&mut self.writer, &3u32.to_be_bytes())?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn write_control<W: Write>(writer: &mut W, control: &[u8]) -> io::Result<()> {
+    // the escape sequence is a zero data frame length
+    writer.write_all(&0u32.to_be_bytes())?;
+    writer.write_all(&(control.len() as u32).to_be_bytes())?;
+    writer.write_all(control)
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{FrameWriter, Message, MessageType, Protocol, Role};
+    use crate::{Builder, QueryClass as QC, QueryType as QT};
+
+    #[test]
+    fn encode_message() {
+        let mut message = Message::new(MessageType::ClientQuery);
+        message.protocol = Some(Protocol::Udp);
+        message.query_address = Some("192.0.2.1:53000".parse::<SocketAddr>().unwrap());
+        message.query_time = Some(UNIX_EPOCH + Duration::new(1_600_000_000, 5));
+        message.query_message = Some(b"\x06%");
+        message.identity = Some(b"ns1");
+        assert_eq!(
+            message.encode(),
+            &b"\x0a\x03ns1\
+               \x72\x1f\
+               \x08\x05\x10\x01\x18\x01\
+               \x22\x04\xc0\x00\x02\x01\
+               \x30\x88\x9e\x03\
+               \x40\x80\xa0\xf8\xfa\x05\x4d\x05\x00\x00\x00\
+               \x52\x02\x06%\
+               \x78\x01"[..]
+        );
+    }
+
+    #[test]
+    fn from_wire() {
+        let mut bld = Builder::new_query(1573, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        let mut data = bld.build().unwrap();
+        let time = UNIX_EPOCH + Duration::from_secs(1);
+        let message = Message::from_wire(Role::Resolver, &data, time).unwrap();
+        assert_eq!(message.typ, MessageType::ResolverQuery);
+        assert_eq!(message.query_message, Some(&data[..]));
+        assert_eq!(message.query_time, Some(time));
+        assert_eq!(message.response_message, None);
+
+        data[2] |= 0x80;
+        let message = Message::from_wire(Role::Resolver, &data, time).unwrap();
+        assert_eq!(message.typ, MessageType::ResolverResponse);
+        assert_eq!(message.response_message, Some(&data[..]));
+        assert!(Message::from_wire(Role::Resolver, &data[..4], time).is_err());
+    }
+
+    #[test]
+    fn frame_stream() {
+        let mut writer = FrameWriter::new(Vec::new()).unwrap();
+        writer.write(&Message::new(MessageType::AuthQuery)).unwrap();
+        let stream = writer.finish().unwrap();
+        assert_eq!(
+            stream,
+            &b"\x00\x00\x00\x00\x00\x00\x00\x22\
+               \x00\x00\x00\x02\x00\x00\x00\x01\x00\x00\x00\x16\
+               protobuf:dnstap.Dnstap\
+               \x00\x00\x00\x06\x72\x02\x08\x01\x78\x01\
+               \x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x03"[..]
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Helpers for DNS over HTTPS (RFC 8484)
+//!
+//! With the GET method a message is sent in the `dns` query parameter,
+//! encoded with unpadded base64url. With the POST method the message is
+//! sent as the request body, so only its length needs checking.
+use crate::{Error, Header};
+
+/// Media type of the DNS wire format messages
+pub const CONTENT_TYPE: &str = "application/dns-message";
+
+/// Name of the query parameter carrying the message for GET requests
+pub const GET_PARAM: &str = "dns";
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes a message for the `dns` GET parameter
+///
+/// The result uses the base64url alphabet without padding, so it doesn't
+/// need any further escaping in the URL. RFC 8484 recommends using zero as
+/// the message ID to make the responses more cache-friendly.
+pub fn encode_get_param(message: &[u8]) -> String {
+    let mut result = String::with_capacity((message.len() * 4).div_ceil(3));
+    for chunk in message.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let chars = [
+            b[0] >> 2,
+            (b[0] & 0b11) << 4 | b[1] >> 4,
+            (b[1] & 0b1111) << 2 | b[2] >> 6,
+            b[2] & 0b11_1111,
+        ];
+        for &c in &chars[..chunk.len() + 1] {
+            result.push(ALPHABET[c as usize] as char);
+        }
+    }
+    result
+}
+
+/// Decodes the value of the `dns` GET parameter into a message
+///
+/// Padding is tolerated, although clients must not send it. The length
+/// of the decoded message is checked with `check_message_len`.
+pub fn decode_get_param(param: &str) -> Result<Vec<u8>, Error> {
+    let data = param.trim_end_matches('=').as_bytes();
+    if data.len() % 4 == 1 {
+        return Err(Error::InvalidBase64Url);
+    }
+    let mut result = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.chunks(4) {
+        let mut acc = 0u32;
+        for &c in chunk {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'-' => 62,
+                b'_' => 63,
+                _ => return Err(Error::InvalidBase64Url),
+            };
+            acc = acc << 6 | value as u32;
+        }
+        acc <<= 6 * (4 - chunk.len()) as u32;
+        let bytes = acc.to_be_bytes();
+        result.extend_from_slice(&bytes[1..chunk.len()]);
+    }
+    check_message_len(result.len())?;
+    Ok(result)
+}
+
+/// Checks that the length of a message (e.g. the `Content-Length` of
+/// a POST request) is sane
+///
+/// The message must hold at least a header and must fit into the
+/// 65535 bytes allowed for DNS messages.
+pub fn check_message_len(len: usize) -> Result<(), Error> {
+    if len < Header::size() || len > u16::MAX as usize {
+        return Err(Error::WrongMessageLength(len));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_message_len, decode_get_param, encode_get_param};
+    use crate::{Builder, Error, QueryClass as QC, QueryType as QT};
+
+    #[test]
+    fn rfc8484_example() {
+        let mut bld = Builder::new_query(0, true);
+        bld.add_question("www.example.com", false, QT::A, QC::IN);
+        let query = bld.build().unwrap();
+        let param = "AAABAAABAAAAAAAAA3d3dwdleGFtcGxlA2NvbQAAAQAB";
+        assert_eq!(encode_get_param(&query), param);
+        assert_eq!(decode_get_param(param).unwrap(), query);
+    }
+
+    #[test]
+    fn all_tail_lengths() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        for len in 12..16 {
+            let param = encode_get_param(&data[..len]);
+            assert!(!param.contains('='));
+            assert_eq!(decode_get_param(&param).unwrap(), &data[..len]);
+        }
+        assert_eq!(decode_get_param(&encode_get_param(&data)).unwrap(), data);
+    }
+
+    #[test]
+    fn padding_tolerated() {
+        let param = "AAABAAABAAAAAAAAA3d3dwdleGFtcGxlA2NvbQAAAQAB";
+        assert_eq!(
+            decode_get_param(&format!("{}AA==", param)).unwrap().len(),
+            34
+        );
+    }
+
+    #[test]
+    fn invalid_param() {
+        assert!(matches!(
+            decode_get_param("AAAB+AAB/AAAAAAAA"),
+            Err(Error::InvalidBase64Url)
+        ));
+        assert!(matches!(
+            decode_get_param("AAABAAABAAAAAAAAA"),
+            Err(Error::InvalidBase64Url)
+        ));
+        assert!(matches!(
+            decode_get_param("AAABAAAB"),
+            Err(Error::WrongMessageLength(6))
+        ));
+    }
+
+    #[test]
+    fn message_len() {
+        assert!(check_message_len(12).is_ok());
+        assert!(check_message_len(65535).is_ok());
+        assert!(matches!(
+            check_message_len(11),
+            Err(Error::WrongMessageLength(11))
+        ));
+        assert!(matches!(
+            check_message_len(65536),
+            Err(Error::WrongMessageLength(65536))
+        ));
+    }
+}
@@ -1,34 +1,55 @@
-use crate::Error;
+use std::convert::TryFrom;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+
+use crate::mnemonic::{generic_code, MnemonicError};
+use crate::name::RdataNames;
+use crate::{Error, QueryType, Type};
 use quick_error::quick_error;
 
 /// The CLASS value according to RFC 1035
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+///
+/// Codes without a variant are kept as `Unknown`, see
+/// `ParseOptions::strict_classes` to reject them. Classes are compared by
+/// their codes.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum Class {
     /// the Internet
-    IN = 1,
+    IN,
     /// the CSNET class (Obsolete - used only for examples in some obsolete
     /// RFCs)
-    CS = 2,
+    CS,
     /// the CHAOS class
-    CH = 3,
+    CH,
     /// Hesiod [Dyer 87]
-    HS = 4,
+    HS,
+    /// A class code without a variant of its own
+    Unknown(u16),
 }
 
 /// The QCLASS value according to RFC 1035
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+///
+/// Codes without a variant are kept as `Unknown`, see
+/// `ParseOptions::strict_classes` to reject them. Query classes are
+/// compared by their codes.
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum QueryClass {
     /// the Internet
-    IN = 1,
+    IN,
     /// the CSNET class (Obsolete - used only for examples in some obsolete
     /// RFCs)
-    CS = 2,
+    CS,
     /// the CHAOS class
-    CH = 3,
+    CH,
     /// Hesiod [Dyer 87]
-    HS = 4,
+    HS,
     /// Any class
-    Any = 255,
+    Any,
+    /// A class code without a variant of its own
+    Unknown(u16),
 }
 
 /// The OPCODE value according to RFC 1035
@@ -46,19 +67,61 @@ pub enum Opcode {
 
 quick_error! {
     /// The RCODE value according to RFC 1035
-    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    #[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
     #[allow(missing_docs)] // names are from spec
     pub enum ResponseCode {
-        NoError
-        FormatError
-        ServerFailure
-        NameError
-        NotImplemented
-        Refused
-        Reserved(code: u8)
+        NoError {
+            display("NOERROR")
+        }
+        FormatError {
+            display("FORMERR")
+        }
+        ServerFailure {
+            display("SERVFAIL")
+        }
+        NameError {
+            display("NXDOMAIN")
+        }
+        NotImplemented {
+            display("NOTIMP")
+        }
+        Refused {
+            display("REFUSED")
+        }
+        Reserved(code: u8) {
+            display("{}", RCODE_MNEMONICS.get(*code as usize).map_or_else(
+                || format!("RCODE{}", code), |m| m.to_string()))
+        }
     }
 }
 
+// Mnemonics of the response codes by value (RFC 6895 section 2.3)
+const RCODE_MNEMONICS: [&str; 12] = [
+    "NOERROR",
+    "FORMERR",
+    "SERVFAIL",
+    "NXDOMAIN",
+    "NOTIMP",
+    "REFUSED",
+    "YXDOMAIN",
+    "YXRRSET",
+    "NXRRSET",
+    "NOTAUTH",
+    "NOTZONE",
+    "DSOTYPENI",
+];
+
+// Mnemonics of the opcodes by value, `None` for unassigned ones
+const OPCODE_MNEMONICS: [Option<&str>; 7] = [
+    Some("QUERY"),
+    Some("IQUERY"),
+    Some("STATUS"),
+    None,
+    Some("NOTIFY"),
+    Some("UPDATE"),
+    Some("DSO"),
+];
+
 impl From<u16> for Opcode {
     fn from(code: u16) -> Opcode {
         use self::Opcode::*;
@@ -82,6 +145,69 @@ impl From<Opcode> for u16 {
     }
 }
 
+impl Opcode {
+    /// Returns the mnemonic of the opcode, e.g. `"QUERY"` or `"NOTIFY"`
+    ///
+    /// Returns `None` for unassigned opcodes.
+    pub fn mnemonic(self) -> Option<&'static str> {
+        OPCODE_MNEMONICS
+            .get(u16::from(self) as usize)
+            .cloned()
+            .flatten()
+    }
+}
+
+/// Writes the mnemonic, or `OPCODE` and the value for unassigned opcodes
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mnemonic() {
+            Some(mnemonic) => f.pad(mnemonic),
+            None => f.pad(&format!("OPCODE{}", u16::from(*self))),
+        }
+    }
+}
+
+/// Parses a mnemonic ignoring case, or `OPCODE` and the value
+impl FromStr for Opcode {
+    type Err = MnemonicError;
+    fn from_str(text: &str) -> Result<Opcode, MnemonicError> {
+        let upper = text.to_ascii_uppercase();
+        OPCODE_MNEMONICS
+            .iter()
+            .position(|&m| m == Some(upper.as_str()))
+            .map(|code| code as u16)
+            .or_else(|| generic_code(&upper, "OPCODE"))
+            .filter(|&code| code < 16)
+            .map(Opcode::from)
+            .ok_or_else(|| MnemonicError::Unknown(text.into()))
+    }
+}
+
+impl ResponseCode {
+    /// Returns the mnemonic of the response code, e.g. `"NXDOMAIN"`
+    ///
+    /// Returns `None` for unassigned response codes.
+    pub fn mnemonic(self) -> Option<&'static str> {
+        RCODE_MNEMONICS.get(u8::from(self) as usize).cloned()
+    }
+}
+
+/// Parses a mnemonic ignoring case, or `RCODE` and the value
+impl FromStr for ResponseCode {
+    type Err = MnemonicError;
+    fn from_str(text: &str) -> Result<ResponseCode, MnemonicError> {
+        let upper = text.to_ascii_uppercase();
+        RCODE_MNEMONICS
+            .iter()
+            .position(|&m| m == upper)
+            .map(|code| code as u16)
+            .or_else(|| generic_code(&upper, "RCODE"))
+            .filter(|&code| code < 16)
+            .map(|code| ResponseCode::from(code as u8))
+            .ok_or_else(|| MnemonicError::Unknown(text.into()))
+    }
+}
+
 impl From<u8> for ResponseCode {
     fn from(code: u8) -> ResponseCode {
         use ResponseCode::*;
@@ -92,8 +218,8 @@ impl From<u8> for ResponseCode {
             3 => NameError,
             4 => NotImplemented,
             5 => Refused,
-            6..=15 => Reserved(code),
-            x => panic!("Invalid response code {}", x),
+            // codes above 15 don't fit the header, they are kept as well
+            _ => Reserved(code),
         }
     }
 }
@@ -114,29 +240,294 @@ impl From<ResponseCode> for u8 {
 
 impl QueryClass {
     /// Parse a query class code
-    pub fn parse(code: u16) -> Result<QueryClass, Error> {
+    ///
+    /// Never fails, codes this library doesn't know give `Unknown`.
+    pub fn parse(code: u16) -> QueryClass {
         use QueryClass::*;
         match code {
-            1 => Ok(IN),
-            2 => Ok(CS),
-            3 => Ok(CH),
-            4 => Ok(HS),
-            255 => Ok(Any),
-            x => Err(Error::InvalidQueryClass(x)),
+            1 => IN,
+            2 => CS,
+            3 => CH,
+            4 => HS,
+            255 => Any,
+            x => Unknown(x),
+        }
+    }
+    /// Returns the query class code
+    pub fn code(self) -> u16 {
+        use QueryClass::*;
+        match self {
+            IN => 1,
+            CS => 2,
+            CH => 3,
+            HS => 4,
+            Any => 255,
+            Unknown(code) => code,
+        }
+    }
+    /// Returns the mnemonic of the query class, e.g. `"IN"`
+    ///
+    /// Returns `None` for `Unknown`.
+    pub fn mnemonic(self) -> Option<&'static str> {
+        use QueryClass::*;
+        match self {
+            IN => Some("IN"),
+            CS => Some("CS"),
+            CH => Some("CH"),
+            HS => Some("HS"),
+            Any => Some("ANY"),
+            Unknown(_) => None,
         }
     }
 }
 
 impl Class {
     /// Parse a class code
-    pub fn parse(code: u16) -> Result<Class, Error> {
+    ///
+    /// Never fails, codes this library doesn't know give `Unknown`.
+    pub fn parse(code: u16) -> Class {
         use Class::*;
         match code {
-            1 => Ok(IN),
-            2 => Ok(CS),
-            3 => Ok(CH),
-            4 => Ok(HS),
-            x => Err(Error::InvalidClass(x)),
+            1 => IN,
+            2 => CS,
+            3 => CH,
+            4 => HS,
+            x => Unknown(x),
+        }
+    }
+    /// Returns the class code
+    pub fn code(self) -> u16 {
+        use Class::*;
+        match self {
+            IN => 1,
+            CS => 2,
+            CH => 3,
+            HS => 4,
+            Unknown(code) => code,
+        }
+    }
+    /// Returns the mnemonic of the class, e.g. `"IN"`
+    ///
+    /// Returns `None` for `Unknown`.
+    pub fn mnemonic(self) -> Option<&'static str> {
+        use Class::*;
+        match self {
+            IN => Some("IN"),
+            CS => Some("CS"),
+            CH => Some("CH"),
+            HS => Some("HS"),
+            Unknown(_) => None,
+        }
+    }
+}
+
+impl PartialEq for QueryClass {
+    fn eq(&self, other: &QueryClass) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl Eq for QueryClass {}
+
+impl Hash for QueryClass {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code().hash(state)
+    }
+}
+
+impl From<u16> for QueryClass {
+    fn from(code: u16) -> QueryClass {
+        QueryClass::parse(code)
+    }
+}
+
+impl From<QueryClass> for u16 {
+    fn from(qclass: QueryClass) -> u16 {
+        qclass.code()
+    }
+}
+
+/// Writes the mnemonic, or `CLASS` and the code for unknown classes
+impl fmt::Display for QueryClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mnemonic() {
+            Some(mnemonic) => f.pad(mnemonic),
+            None => f.pad(&format!("CLASS{}", self.code())),
+        }
+    }
+}
+
+/// Parses a mnemonic ignoring case, or the RFC 3597 `CLASS` syntax
+///
+/// `*` is accepted for `Any` as well.
+impl FromStr for QueryClass {
+    type Err = MnemonicError;
+    fn from_str(text: &str) -> Result<QueryClass, MnemonicError> {
+        use QueryClass::*;
+        match text.to_ascii_uppercase().as_str() {
+            "IN" => Ok(IN),
+            "CS" => Ok(CS),
+            "CH" => Ok(CH),
+            "HS" => Ok(HS),
+            "ANY" | "*" => Ok(Any),
+            upper => generic_code(upper, "CLASS")
+                .map(QueryClass::parse)
+                .ok_or_else(|| MnemonicError::Unknown(text.into())),
+        }
+    }
+}
+
+impl PartialEq for Class {
+    fn eq(&self, other: &Class) -> bool {
+        self.code() == other.code()
+    }
+}
+
+impl Eq for Class {}
+
+impl Hash for Class {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.code().hash(state)
+    }
+}
+
+impl From<u16> for Class {
+    fn from(code: u16) -> Class {
+        Class::parse(code)
+    }
+}
+
+impl From<Class> for u16 {
+    fn from(cls: Class) -> u16 {
+        cls.code()
+    }
+}
+
+/// Writes the mnemonic, or `CLASS` and the code for unknown classes
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mnemonic() {
+            Some(mnemonic) => f.pad(mnemonic),
+            None => f.pad(&format!("CLASS{}", self.code())),
+        }
+    }
+}
+
+/// Parses a mnemonic ignoring case, or the RFC 3597 `CLASS` syntax
+impl FromStr for Class {
+    type Err = MnemonicError;
+    fn from_str(text: &str) -> Result<Class, MnemonicError> {
+        use Class::*;
+        match text.to_ascii_uppercase().as_str() {
+            "IN" => Ok(IN),
+            "CS" => Ok(CS),
+            "CH" => Ok(CH),
+            "HS" => Ok(HS),
+            upper => generic_code(upper, "CLASS")
+                .map(Class::parse)
+                .ok_or_else(|| MnemonicError::Unknown(text.into())),
+        }
+    }
+}
+
+impl QueryType {
+    /// Returns true for types which never occur as data in a zone
+    ///
+    /// These are the types only used in questions, like `AXFR` and `ALL`,
+    /// and the meta-types like `OPT` and `TSIG`, which carry data for a
+    /// single message (RFC 6895 section 3.1).
+    pub fn is_meta(self) -> bool {
+        matches!(self.code(), 41 | 128..=255)
+    }
+}
+
+impl Type {
+    /// Returns true for the meta-types, like `OPT` and `TSIG`
+    ///
+    /// They carry data for a single message and never occur in a zone
+    /// (RFC 6895 section 3.1).
+    pub fn is_meta(self) -> bool {
+        QueryType::from(self).is_meta()
+    }
+    /// Returns true for types which are obsolete, historic or deprecated
+    ///
+    /// These are the mail types of RFC 1035 (`MD`, `MF`, `MB`, `MG`, `MR`
+    /// and `MINFO`), `NULL` and `WKS` (RFC 1123), `NSAP-PTR` (RFC 1706),
+    /// `NXT` (RFC 3755), `A6` (RFC 6563) and `SPF` (RFC 7208).
+    pub fn is_obsolete(self) -> bool {
+        use crate::Type::*;
+        matches!(
+            self,
+            MD | MF | MB | MG | MR | MINFO | NULL | WKS | NSAPPTR | NXT | A6 | SPF
+        )
+    }
+    /// Returns true for the types defined by DNSSEC
+    ///
+    /// These are the types of RFC 4034 and RFC 5155, `CDS` and `CDNSKEY`
+    /// (RFC 7344), and `DLV` (RFC 4431). The old `SIG` and `KEY` are not
+    /// included, they are still used by SIG(0) outside DNSSEC.
+    pub fn is_dnssec(self) -> bool {
+        use crate::Type::*;
+        matches!(
+            self,
+            DS | RRSIG | NSEC | DNSKEY | NSEC3 | NSEC3PARAM | CDS | CDNSKEY | DLV
+        )
+    }
+    /// Returns true if names in the record data may be compressed
+    ///
+    /// Only the types of RFC 1035 may use compression, a sender must not
+    /// compress names in the data of other types (RFC 3597 section 4).
+    pub fn allows_compression_in_rdata(self) -> bool {
+        use crate::Type::*;
+        matches!(
+            self,
+            NS | MD | MF | CNAME | SOA | MB | MG | MR | PTR | MINFO | MX
+        )
+    }
+    // Where the names in the record data of this type are, one table for
+    // every part of the library looking for them without parsing the data
+    //
+    // Covers the types of RFC 3597 section 4 and the later ones holding
+    // names, whether this library has a parser for them or not.
+    pub(crate) fn rdata_names(self) -> Option<RdataNames> {
+        use crate::Type::*;
+        let (offset, strings, count, parsed) = match self {
+            CNAME | NS | PTR | DNAME | NSEC => (0, 0, 1, true),
+            MB | MD | MF | MG | MR | NXT => (0, 0, 1, false),
+            SOA => (0, 0, 2, true),
+            MINFO | RP => (0, 0, 2, false),
+            MX => (2, 0, 1, true),
+            AFSDB | RT | KX => (2, 0, 1, false),
+            PX => (2, 0, 2, false),
+            SRV => (6, 0, 1, true),
+            RRSIG => (18, 0, 1, true),
+            SIG => (18, 0, 1, false),
+            NAPTR => (4, 3, 1, false),
+            _ => return None,
+        };
+        Some(RdataNames {
+            offset,
+            strings,
+            count,
+            parsed,
+        })
+    }
+}
+
+impl From<Type> for QueryType {
+    fn from(typ: Type) -> QueryType {
+        QueryType::parse(typ.code())
+    }
+}
+
+/// Fails for the types only used in questions, like `AXFR` and `ALL`
+impl TryFrom<QueryType> for Type {
+    type Error = Error;
+    fn try_from(qtype: QueryType) -> Result<Type, Error> {
+        use crate::QueryType::*;
+        match qtype {
+            AXFR | MAILB | MAILA | ALL => Err(Error::InvalidType(qtype.code())),
+            _ => Ok(Type::parse(qtype.code())),
         }
     }
 }
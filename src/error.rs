@@ -1,72 +1,266 @@
-use quick_error::quick_error;
+use std::fmt;
 use std::str::Utf8Error;
 
-quick_error! {
-    /// Error parsing DNS packet
-    #[derive(Debug)]
-    pub enum Error {
-        /// Invalid compression pointer not pointing backwards
-        /// when parsing label
-        BadPointer {
-            description("invalid compression pointer not pointing backwards \
-                         when parsing label")
-        }
-        /// Packet is smaller than header size
-        HeaderTooShort {
-            description("packet is smaller than header size")
-        }
-        /// Packet ihas incomplete data
-        UnexpectedEOF {
-            description("packet is has incomplete data")
-        }
-        /// Wrong (too short or too long) size of RDATA
-        WrongRdataLength {
-            description("wrong (too short or too long) size of RDATA")
-        }
-        /// Packet has non-zero reserved bits
-        ReservedBitsAreNonZero {
-            description("packet has non-zero reserved bits")
-        }
-        /// Label in domain name has unknown label format
-        UnknownLabelFormat {
-            description("label in domain name has unknown label format")
-        }
-        /// Query type code is invalid
-        InvalidQueryType(code: u16) {
-            description("query type code is invalid")
-            display("query type {} is invalid", code)
-        }
-        /// Query class code is invalid
-        InvalidQueryClass(code: u16) {
-            description("query class code is invalid")
-            display("query class {} is invalid", code)
+use crate::visitor::Section;
+use crate::Type;
+
+/// Error parsing DNS packet
+///
+/// Errors found in a question or record of a packet are wrapped in
+/// `Question` or `Record`, which tell where the problem is and return
+/// the error itself from `source()`. Use `root` to look at the error
+/// without its context and `kind` to sort errors into categories.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Invalid compression pointer not pointing backwards
+    /// when parsing label
+    BadPointer {
+        /// Offset the pointer points to
+        target: usize,
+    },
+    /// Packet is smaller than header size
+    HeaderTooShort,
+    /// Packet has incomplete data
+    UnexpectedEOF,
+    /// Record data is shorter or longer than its type allows
+    InvalidRdataLength {
+        /// Type of the record
+        typ: Type,
+        /// The exact length of data of fixed size, otherwise the least
+        /// length the data read so far requires
+        expected: usize,
+        /// Length of the record data
+        got: usize,
+    },
+    /// Packet has non-zero reserved bits
+    ReservedBitsAreNonZero,
+    /// Label in domain name has unknown label format
+    UnknownLabelFormat {
+        /// First byte of the label, the one holding the format bits
+        byte: u8,
+    },
+    /// Query type code is invalid
+    InvalidQueryType(u16),
+    /// Query class code is invalid
+    InvalidQueryClass(u16),
+    /// Type code is invalid
+    InvalidType(u16),
+    /// Class code is invalid
+    InvalidClass(u16),
+    /// Invalid characters encountered while reading label
+    LabelIsNotUtf8,
+    /// Invalid characters encountered while reading TXT
+    TxtDataIsNotUTF8(Utf8Error),
+    /// Parser is in the wrong state
+    WrongState,
+    /// Additional OPT record found
+    AdditionalOPT,
+    /// Invalid characters or length of base64url encoded message
+    InvalidBase64Url,
+    /// Message is too short or too long
+    WrongMessageLength(usize),
+    /// Length of an IPv6 prefix is not allowed
+    InvalidPrefixLength(u8),
+    /// Name is not a valid first-level encoded NetBIOS name
+    InvalidNetbiosName,
+    /// Type bitmap has windows out of order or of wrong length
+    InvalidTypeBitmap,
+    /// Name has an empty label, a label longer than 63 bytes or
+    /// containing a dot, or is longer than 255 bytes
+    InvalidName,
+    /// Parsing took more work than allowed by `ParseOptions`, or an
+    /// NSEC3 record asks for more hash iterations than are computed
+    LimitExceeded(Limit),
+    /// DNSSEC algorithm is not supported
+    UnsupportedAlgorithm(u8),
+    /// Data can't be converted to the type of another library
+    ConversionFailed(String),
+    /// Question number `index` (from zero) starting at byte `offset`
+    /// of the packet is invalid, as `(index, offset, error)`
+    Question(usize, usize, Box<Error>),
+    /// Record number `index` (from zero) of the section starting at
+    /// byte `offset` of the packet is invalid, as
+    /// `(section, index, offset, error)`
+    Record(Section, usize, usize, Box<Error>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::Error::*;
+        match self {
+            BadPointer { target } => write!(
+                f,
+                "invalid compression pointer to {} not pointing backwards when parsing label",
+                target
+            ),
+            HeaderTooShort => write!(f, "packet is smaller than header size"),
+            UnexpectedEOF => write!(f, "packet has incomplete data"),
+            InvalidRdataLength { typ, expected, got } => write!(
+                f,
+                "{} record data of {} bytes, expected {}",
+                typ, got, expected
+            ),
+            ReservedBitsAreNonZero => write!(f, "packet has non-zero reserved bits"),
+            UnknownLabelFormat { byte } => write!(
+                f,
+                "label in domain name has unknown label format {:#04x}",
+                byte
+            ),
+            InvalidQueryType(code) => write!(f, "query type {} is invalid", code),
+            InvalidQueryClass(code) => write!(f, "query class {} is invalid", code),
+            InvalidType(code) => write!(f, "type {} is invalid", code),
+            InvalidClass(code) => write!(f, "class {} is invalid", code),
+            LabelIsNotUtf8 => write!(f, "invalid characters encountered while reading label"),
+            TxtDataIsNotUTF8(error) => write!(f, "TXT data is not UTF-8: {}", error),
+            WrongState => write!(f, "parser is in the wrong state"),
+            AdditionalOPT => write!(f, "additional OPT record found"),
+            InvalidBase64Url => write!(f, "invalid base64url encoded message"),
+            WrongMessageLength(len) => write!(f, "message length {} is out of range", len),
+            InvalidPrefixLength(len) => write!(f, "prefix length {} is not allowed", len),
+            InvalidNetbiosName => write!(f, "invalid encoded NetBIOS name"),
+            InvalidTypeBitmap => write!(f, "invalid type bitmap"),
+            InvalidName => write!(f, "invalid domain name"),
+            LimitExceeded(limit) => write!(f, "limit on {} exceeded", limit),
+            UnsupportedAlgorithm(code) => write!(f, "algorithm {} is not supported", code),
+            ConversionFailed(reason) => write!(f, "conversion failed: {}", reason),
+            Question(index, offset, error) => {
+                write!(f, "question {} at offset {}: {}", index, offset, error)
+            }
+            Record(section, index, offset, error) => write!(
+                f,
+                "{} record {} at offset {}: {}",
+                section, index, offset, error
+            ),
         }
-        /// Type code is invalid
-        InvalidType(code: u16) {
-            description("type code is invalid")
-            display("type {} is invalid", code)
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::TxtDataIsNotUTF8(error) => Some(error),
+            Error::Question(_, _, error) | Error::Record(_, _, _, error) => Some(&**error),
+            _ => None,
         }
-        /// Class code is invalid
-        InvalidClass(code: u16) {
-            description("class code is invalid")
-            display("class {} is invalid", code)
+    }
+}
+
+/// Category of an `Error`, e.g. to label metrics
+///
+/// Only `Truncated` errors may go away when the same message is received
+/// again in full, e.g. over TCP.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// The message ends before the data it announces
+    Truncated,
+    /// The message breaks the protocol, e.g. has a pointer loop, a
+    /// record data of wrong length or an invalid code
+    Malformed,
+    /// The data is valid but this library can't handle it
+    Unsupported,
+    /// Parsing took more work than allowed by `ParseOptions`
+    LimitExceeded,
+    /// A function was called with arguments it doesn't accept, e.g. text
+    /// which is not a domain name
+    InvalidInput,
+}
+
+/// Work limit a packet or a record needed more of, see
+/// `Error::LimitExceeded`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Limit {
+    /// Labels decoded, see `ParseOptions::max_labels`
+    Labels,
+    /// Compression pointers followed, see `ParseOptions::max_pointers`
+    Pointers,
+    /// Bytes of names and record data read, see `ParseOptions::max_bytes`
+    Bytes,
+    /// NSEC3 hash iterations, see `dnssec::MAX_NSEC3_ITERATIONS`
+    Iterations,
+}
+
+impl Limit {
+    /// Returns a short lower case name, e.g. `"labels"`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Limit::Labels => "labels",
+            Limit::Pointers => "pointers",
+            Limit::Bytes => "bytes",
+            Limit::Iterations => "iterations",
         }
-        /// Invalid characters encountered while reading label
-        LabelIsNotUtf8 {
-            description("invalid characters encountered while reading label")
+    }
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl ErrorKind {
+    /// Returns a short lower case name, e.g. `"truncated"`
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::Truncated => "truncated",
+            ErrorKind::Malformed => "malformed",
+            ErrorKind::Unsupported => "unsupported",
+            ErrorKind::LimitExceeded => "limit_exceeded",
+            ErrorKind::InvalidInput => "invalid_input",
         }
-        /// Invalid characters encountered while reading TXT
-        TxtDataIsNotUTF8(error: Utf8Error) {
-            description("invalid characters encountered while reading TXT")
-            display("{:?}", error)
+    }
+}
+
+impl Error {
+    pub(crate) fn in_question(index: usize, offset: usize, error: Error) -> Error {
+        trace_event!(debug, index, offset, %error, "invalid question");
+        Error::Question(index, offset, Box::new(error))
+    }
+    pub(crate) fn in_record(section: Section, index: usize, offset: usize, error: Error) -> Error {
+        trace_event!(debug, %section, index, offset, %error, "invalid record");
+        Error::Record(section, index, offset, Box::new(error))
+    }
+    /// Returns the error without the `Question` or `Record` context
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Question(_, _, error) | Error::Record(_, _, _, error) => error.root(),
+            error => error,
         }
-        /// Parser is in the wrong state
-        WrongState {
-            description("parser is in the wrong state")
+    }
+    /// Returns the offset of the question or record the error was found
+    /// in, if known
+    pub fn offset(&self) -> Option<usize> {
+        match *self {
+            Error::Question(_, offset, _) | Error::Record(_, _, offset, _) => Some(offset),
+            _ => None,
         }
-        /// Additional OPT record found
-        AdditionalOPT {
-            description("additional OPT record found")
+    }
+    /// Returns the category of the error
+    pub fn kind(&self) -> ErrorKind {
+        use self::Error::*;
+        match self {
+            HeaderTooShort | UnexpectedEOF => ErrorKind::Truncated,
+            BadPointer { .. }
+            | InvalidRdataLength { .. }
+            | ReservedBitsAreNonZero
+            | UnknownLabelFormat { .. }
+            | InvalidQueryType(_)
+            | InvalidQueryClass(_)
+            | InvalidType(_)
+            | InvalidClass(_)
+            | LabelIsNotUtf8
+            | TxtDataIsNotUTF8(_)
+            | AdditionalOPT
+            | InvalidBase64Url
+            | WrongMessageLength(_)
+            | InvalidNetbiosName
+            | InvalidTypeBitmap => ErrorKind::Malformed,
+            UnsupportedAlgorithm(_) | ConversionFailed(_) => ErrorKind::Unsupported,
+            LimitExceeded(_) => ErrorKind::LimitExceeded,
+            WrongState | InvalidPrefixLength(_) | InvalidName => ErrorKind::InvalidInput,
+            Question(_, _, error) | Record(_, _, _, error) => error.kind(),
         }
     }
 }
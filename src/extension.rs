@@ -0,0 +1,266 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::{Error, RData};
+
+/// Record data of a type this library has no parser for
+///
+/// Data of such types is kept as `RData::Unknown`, implement this trait
+/// to parse it with `RData::parse_as` and to create it with
+/// `RData::custom`. Compressed names are not allowed in the data of
+/// types defined after RFC 1035 (RFC 3597 section 4), so the parser only
+/// gets the record data.
+///
+/// ```
+/// # use dns_parser::{Error, RData, RDataParse, Type};
+/// /// A private type holding a priority and an opaque token
+/// #[derive(Debug, PartialEq)]
+/// struct Token<'a> {
+///     priority: u8,
+///     token: &'a [u8],
+/// }
+///
+/// impl<'a> RDataParse<'a> for Token<'a> {
+///     const TYPE: u16 = 65400;
+///     const MNEMONIC: &'static str = "TOKEN";
+///     fn parse(rdata: &'a [u8]) -> Result<Token<'a>, Error> {
+///         match rdata.split_first() {
+///             Some((&priority, token)) => Ok(Token { priority, token }),
+///             None => Err(Error::InvalidRdataLength {
+///                 typ: Type::parse(Self::TYPE),
+///                 expected: 1,
+///                 got: 0,
+///             }),
+///         }
+///     }
+///     fn write_to(&self, buf: &mut Vec<u8>) {
+///         buf.push(self.priority);
+///         buf.extend_from_slice(self.token);
+///     }
+/// }
+///
+/// let data = RData::custom(&Token { priority: 1, token: b"abc" });
+/// assert_eq!(data.typ().code(), 65400);
+/// let token = data.parse_as::<Token<'_>>().unwrap().unwrap();
+/// assert_eq!(token.token, b"abc");
+/// ```
+pub trait RDataParse<'a>: Sized {
+    /// The type code
+    const TYPE: u16;
+    /// The mnemonic of the type, e.g. `"AAAA"`
+    const MNEMONIC: &'static str;
+    /// Parses the record data
+    fn parse(rdata: &'a [u8]) -> Result<Self, Error>;
+    /// Writes the record data in the wire format
+    fn write_to(&self, buf: &mut Vec<u8>);
+}
+
+impl<'a> RData<'a> {
+    /// Parses data kept as `Unknown` with a parser defined outside this
+    /// library
+    ///
+    /// Returns `None` if this is not `Unknown` data of type `T::TYPE`.
+    pub fn parse_as<'s, T: RDataParse<'s>>(&'s self) -> Option<Result<T, Error>> {
+        match *self {
+            RData::Unknown(code, ref data) if code == T::TYPE => Some(T::parse(data)),
+            _ => None,
+        }
+    }
+}
+
+impl RData<'static> {
+    /// Creates the record data of a type defined outside this library
+    ///
+    /// The data is stored as `Unknown`, so it may be added to a `Builder`
+    /// or compared with parsed data.
+    pub fn custom<'d, T: RDataParse<'d>>(data: &T) -> RData<'static> {
+        let mut buf = Vec::new();
+        data.write_to(&mut buf);
+        RData::Unknown(T::TYPE, Cow::Owned(buf))
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Entry {
+    mnemonic: &'static str,
+    check: fn(&[u8]) -> Result<(), Error>,
+    format: fn(&[u8], &mut fmt::Formatter<'_>) -> fmt::Result,
+}
+
+/// Parsers of record types defined outside this library, by type code
+///
+/// Lets code handling records of any type, e.g. a logger or a zone
+/// checker, validate and print the types registered by the application.
+/// Only types not borrowing the data can be registered, i.e. those
+/// implementing `RDataParse` for every lifetime.
+#[derive(Clone, Default)]
+pub struct RDataRegistry {
+    entries: HashMap<u16, Entry>,
+}
+
+impl RDataRegistry {
+    /// Creates an empty registry
+    pub fn new() -> RDataRegistry {
+        RDataRegistry::default()
+    }
+    /// Registers the type `T`, replacing a type with the same code
+    pub fn register<T>(&mut self) -> &mut RDataRegistry
+    where
+        T: for<'a> RDataParse<'a> + fmt::Display,
+    {
+        fn check<T: for<'a> RDataParse<'a>>(data: &[u8]) -> Result<(), Error> {
+            T::parse(data).map(|_| ())
+        }
+        fn format<T>(data: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result
+        where
+            T: for<'a> RDataParse<'a> + fmt::Display,
+        {
+            match T::parse(data) {
+                Ok(value) => fmt::Display::fmt(&value, f),
+                Err(_) => write!(f, "\\# {}", data.len()),
+            }
+        }
+        self.entries.insert(
+            T::TYPE,
+            Entry {
+                mnemonic: T::MNEMONIC,
+                check: check::<T>,
+                format: format::<T>,
+            },
+        );
+        self
+    }
+    /// Returns whether a type with the code is registered
+    pub fn contains(&self, code: u16) -> bool {
+        self.entries.contains_key(&code)
+    }
+    /// Returns the mnemonic of a registered type
+    pub fn mnemonic(&self, code: u16) -> Option<&'static str> {
+        self.entries.get(&code).map(|entry| entry.mnemonic)
+    }
+    /// Returns the code of a registered type by its mnemonic, ignoring
+    /// case
+    pub fn code(&self, mnemonic: &str) -> Option<u16> {
+        self.entries
+            .iter()
+            .find(|(_, entry)| entry.mnemonic.eq_ignore_ascii_case(mnemonic))
+            .map(|(&code, _)| code)
+    }
+    /// Checks the data of a registered type with its parser
+    ///
+    /// Data of other types, including all those known to this library,
+    /// is not checked.
+    pub fn check(&self, data: &RData<'_>) -> Result<(), Error> {
+        match *data {
+            RData::Unknown(code, ref data) => match self.entries.get(&code) {
+                Some(entry) => (entry.check)(data),
+                None => Ok(()),
+            },
+            _ => Ok(()),
+        }
+    }
+    /// Formats the data of a registered type with its `Display`
+    /// implementation
+    ///
+    /// Returns `None` for data of other types. Data that fails to parse is
+    /// written in the generic syntax of RFC 3597, without the bytes.
+    pub fn display<'s>(&'s self, data: &'s RData<'_>) -> Option<impl fmt::Display + 's> {
+        match *data {
+            RData::Unknown(code, ref data) => self.entries.get(&code).map(|entry| Display {
+                format: entry.format,
+                data,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Debug for RDataRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut codes = self.entries.keys().collect::<Vec<_>>();
+        codes.sort();
+        f.debug_struct("RDataRegistry")
+            .field("codes", &codes)
+            .finish()
+    }
+}
+
+struct Display<'s> {
+    format: fn(&[u8], &mut fmt::Formatter<'_>) -> fmt::Result,
+    data: &'s [u8],
+}
+
+impl<'s> fmt::Display for Display<'s> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.format)(self.data, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::fmt;
+
+    use super::{RDataParse, RDataRegistry};
+    use crate::{Error, RData, Type};
+
+    #[derive(Debug, PartialEq)]
+    struct Level(u16);
+
+    impl<'a> RDataParse<'a> for Level {
+        const TYPE: u16 = 65280;
+        const MNEMONIC: &'static str = "LEVEL";
+        fn parse(rdata: &'a [u8]) -> Result<Level, Error> {
+            match *rdata {
+                [high, low] => Ok(Level(u16::from_be_bytes([high, low]))),
+                _ => Err(Error::InvalidRdataLength {
+                    typ: Type::parse(Self::TYPE),
+                    expected: 2,
+                    got: rdata.len(),
+                }),
+            }
+        }
+        fn write_to(&self, buf: &mut Vec<u8>) {
+            buf.extend_from_slice(&self.0.to_be_bytes());
+        }
+    }
+
+    impl fmt::Display for Level {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "level {}", self.0)
+        }
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = RDataRegistry::new();
+        registry.register::<Level>();
+        assert!(registry.contains(65280));
+        assert_eq!(registry.mnemonic(65280), Some("LEVEL"));
+        assert_eq!(registry.code("level"), Some(65280));
+        assert_eq!(registry.code("TXT"), None);
+
+        let good = RData::custom(&Level(7));
+        assert_eq!(good, RData::Unknown(65280, Cow::Borrowed(&[0, 7])));
+        assert_eq!(good.parse_as::<Level>().unwrap().unwrap(), Level(7));
+        assert!(registry.check(&good).is_ok());
+        assert_eq!(registry.display(&good).unwrap().to_string(), "level 7");
+
+        let bad = RData::Unknown(65280, Cow::Borrowed(&[1]));
+        assert!(matches!(
+            registry.check(&bad),
+            Err(Error::InvalidRdataLength {
+                expected: 2,
+                got: 1,
+                ..
+            })
+        ));
+        assert_eq!(registry.display(&bad).unwrap().to_string(), "\\# 1");
+
+        let other = RData::Unknown(65281, Cow::Borrowed(&[1]));
+        assert!(other.parse_as::<Level>().is_none());
+        assert!(registry.check(&other).is_ok());
+        assert!(registry.display(&other).is_none());
+    }
+}
@@ -0,0 +1,483 @@
+//! C interface, enabled by the `ffi` feature
+//!
+//! The functions are meant to be called from C through a header
+//! generated with cbindgen, with the crate built as a `staticlib` or
+//! `cdylib`, e.g. `cargo rustc --release --features ffi --crate-type
+//! staticlib`.
+//!
+//! A packet is parsed into an opaque `DnsPacket` handle which must be
+//! released with `dns_packet_free`. Questions and records are looked up
+//! by section and index, from zero to `dns_packet_count`. Names and
+//! record data are copied into buffers owned by the caller:
+//!
+//! ```c
+//! DnsStatus status;
+//! DnsPacket *packet = dns_packet_parse(buf, len, &status);
+//! if (packet == NULL) {
+//!     return status;
+//! }
+//! for (size_t i = 0; i < dns_packet_count(packet, DnsSectionAnswer); i++) {
+//!     char name[256];
+//!     DnsRecord record;
+//!     dns_packet_name(packet, DnsSectionAnswer, i, name, sizeof(name));
+//!     dns_packet_record(packet, DnsSectionAnswer, i, &record);
+//!     printf("%s %u\n", name, record.rtype);
+//! }
+//! dns_packet_free(packet);
+//! ```
+//!
+//! Functions writing into a buffer return the length of the whole
+//! output, like `snprintf` does, the output was cut if it's not smaller
+//! than the size of the buffer. They return -1 if the question or record
+//! doesn't exist.
+//!
+//! No panic unwinds into C: should this library panic, the function
+//! returns `DnsInternalError`, null, -1 or zero.
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use crate::{Builder, ErrorKind, Name, Packet, PacketBuf, QueryClass, QueryType, ResourceRecord};
+
+/// Outcome of a call
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsStatus {
+    /// Success
+    DnsOk = 0,
+    /// The packet ends before the data it announces
+    DnsTruncated = 1,
+    /// The packet breaks the protocol
+    DnsMalformed = 2,
+    /// The packet is valid but can't be handled
+    DnsUnsupported = 3,
+    /// Parsing took more work than allowed
+    DnsLimitExceeded = 4,
+    /// An argument is a null pointer, an invalid name or an index out of
+    /// range
+    DnsInvalidArgument = 5,
+    /// This library panicked, which is a bug
+    DnsInternalError = 6,
+}
+
+impl From<ErrorKind> for DnsStatus {
+    fn from(kind: ErrorKind) -> DnsStatus {
+        match kind {
+            ErrorKind::Truncated => DnsStatus::DnsTruncated,
+            ErrorKind::Malformed => DnsStatus::DnsMalformed,
+            ErrorKind::Unsupported => DnsStatus::DnsUnsupported,
+            ErrorKind::LimitExceeded => DnsStatus::DnsLimitExceeded,
+            ErrorKind::InvalidInput => DnsStatus::DnsInvalidArgument,
+        }
+    }
+}
+
+/// A section of a packet
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DnsSection {
+    /// The questions
+    DnsSectionQuestion = 0,
+    /// The answer section
+    DnsSectionAnswer = 1,
+    /// The authority section
+    DnsSectionAuthority = 2,
+    /// The additional section, without the OPT pseudo-record
+    DnsSectionAdditional = 3,
+}
+
+/// The fields of a question besides its name
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DnsQuestion {
+    /// The query type code
+    pub qtype: u16,
+    /// The query class code, without the unicast response bit
+    pub qclass: u16,
+    /// Whether a unicast response is preferred (multicast DNS)
+    pub prefer_unicast: bool,
+}
+
+/// The fields of a record besides its name and data
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DnsRecord {
+    /// The type code
+    pub rtype: u16,
+    /// The class code, without the cache flush bit
+    pub rclass: u16,
+    /// The time to live in seconds
+    pub ttl: u32,
+    /// Whether the cache flush bit is set (multicast DNS)
+    pub cache_flush: bool,
+}
+
+/// A parsed packet, see `dns_packet_parse`
+#[derive(Debug)]
+pub struct DnsPacket {
+    packet: PacketBuf,
+}
+
+impl DnsPacket {
+    fn name(&self, section: DnsSection, index: usize) -> Option<&Name<'static>> {
+        match section {
+            DnsSection::DnsSectionQuestion => self.packet.questions().get(index).map(|q| &q.qname),
+            _ => self.record(section, index).map(|r| &r.name),
+        }
+    }
+    fn record(&self, section: DnsSection, index: usize) -> Option<&ResourceRecord<'static>> {
+        let records = match section {
+            DnsSection::DnsSectionQuestion => return None,
+            DnsSection::DnsSectionAnswer => self.packet.answers(),
+            DnsSection::DnsSectionAuthority => self.packet.nameservers(),
+            DnsSection::DnsSectionAdditional => self.packet.additional(),
+        };
+        records.get(index)
+    }
+}
+
+// Runs the body of an entry point, returns `fallback` if it panics
+fn guard<T>(fallback: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+// Copies `data` into the buffer, cut to its size and followed by a zero
+// byte if `terminate` is set, returns the length of `data`
+unsafe fn write_out(data: &[u8], buf: *mut u8, len: usize, terminate: bool) -> isize {
+    if !buf.is_null() && len > 0 {
+        let room = if terminate { len - 1 } else { len };
+        let count = data.len().min(room);
+        ptr::copy_nonoverlapping(data.as_ptr(), buf, count);
+        if terminate {
+            *buf.add(count) = 0;
+        }
+    }
+    data.len() as isize
+}
+
+/// Parses a packet, the data is copied
+///
+/// Returns null if the packet is invalid, the reason is stored in
+/// `status` unless it's null.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, `status` must be null or
+/// writable.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_parse(
+    data: *const u8,
+    len: usize,
+    status: *mut DnsStatus,
+) -> *mut DnsPacket {
+    let result = guard(Err(DnsStatus::DnsInternalError), || {
+        if data.is_null() {
+            return Err(DnsStatus::DnsInvalidArgument);
+        }
+        Packet::parse(slice::from_raw_parts(data, len))
+            .map(|packet| DnsPacket {
+                packet: packet.into_owned(),
+            })
+            .map_err(|error| error.kind().into())
+    });
+    let (packet, outcome) = match result {
+        Ok(packet) => (Box::into_raw(Box::new(packet)), DnsStatus::DnsOk),
+        Err(outcome) => (ptr::null_mut(), outcome),
+    };
+    if !status.is_null() {
+        *status = outcome;
+    }
+    packet
+}
+
+/// Releases a packet returned by `dns_packet_parse`, null is ignored
+///
+/// # Safety
+///
+/// `packet` must be null or a packet not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_free(packet: *mut DnsPacket) {
+    if !packet.is_null() {
+        guard((), || drop(Box::from_raw(packet)));
+    }
+}
+
+/// Returns the ID of the packet
+///
+/// # Safety
+///
+/// `packet` must be a packet returned by `dns_packet_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_id(packet: *const DnsPacket) -> u16 {
+    guard(0, || (*packet).packet.header().id)
+}
+
+/// Returns the word with the flags, opcode and response code following
+/// the ID in the wire format
+///
+/// # Safety
+///
+/// `packet` must be a packet returned by `dns_packet_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_flags(packet: *const DnsPacket) -> u16 {
+    guard(0, || (*packet).packet.header().flags_word())
+}
+
+/// Returns the number of questions or records in a section
+///
+/// # Safety
+///
+/// `packet` must be a packet returned by `dns_packet_parse`.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_count(packet: *const DnsPacket, section: DnsSection) -> usize {
+    let packet = &(*packet).packet;
+    guard(0, || match section {
+        DnsSection::DnsSectionQuestion => packet.questions().len(),
+        DnsSection::DnsSectionAnswer => packet.answers().len(),
+        DnsSection::DnsSectionAuthority => packet.nameservers().len(),
+        DnsSection::DnsSectionAdditional => packet.additional().len(),
+    })
+}
+
+/// Fills `question` with the fields of a question
+///
+/// # Safety
+///
+/// `packet` must be a packet returned by `dns_packet_parse`, `question`
+/// must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_question(
+    packet: *const DnsPacket,
+    index: usize,
+    question: *mut DnsQuestion,
+) -> DnsStatus {
+    guard(DnsStatus::DnsInternalError, || {
+        match (*packet).packet.questions().get(index) {
+            Some(q) if !question.is_null() => {
+                *question = DnsQuestion {
+                    qtype: q.qtype.code(),
+                    qclass: q.qclass.code(),
+                    prefer_unicast: q.prefer_unicast,
+                };
+                DnsStatus::DnsOk
+            }
+            _ => DnsStatus::DnsInvalidArgument,
+        }
+    })
+}
+
+/// Fills `record` with the fields of a record
+///
+/// # Safety
+///
+/// `packet` must be a packet returned by `dns_packet_parse`, `record`
+/// must be writable.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_record(
+    packet: *const DnsPacket,
+    section: DnsSection,
+    index: usize,
+    record: *mut DnsRecord,
+) -> DnsStatus {
+    guard(DnsStatus::DnsInternalError, || {
+        match (*packet).record(section, index) {
+            Some(r) if !record.is_null() => {
+                *record = DnsRecord {
+                    rtype: r.data.typ().code(),
+                    rclass: r.cls.code(),
+                    ttl: r.ttl.as_secs(),
+                    cache_flush: r.multicast_unique,
+                };
+                DnsStatus::DnsOk
+            }
+            _ => DnsStatus::DnsInvalidArgument,
+        }
+    })
+}
+
+/// Writes the name of a question or record as text, followed by a zero
+/// byte
+///
+/// Names are written without the trailing dot, the root name is empty.
+///
+/// # Safety
+///
+/// `packet` must be a packet returned by `dns_packet_parse`, `buf` must
+/// be null or point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_name(
+    packet: *const DnsPacket,
+    section: DnsSection,
+    index: usize,
+    buf: *mut c_char,
+    len: usize,
+) -> isize {
+    guard(-1, || match (*packet).name(section, index) {
+        Some(name) => write_out(name.to_string().as_bytes(), buf.cast(), len, true),
+        None => -1,
+    })
+}
+
+/// Writes the data of a record in the wire format, names uncompressed
+///
+/// # Safety
+///
+/// `packet` must be a packet returned by `dns_packet_parse`, `buf` must
+/// be null or point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn dns_packet_record_data(
+    packet: *const DnsPacket,
+    section: DnsSection,
+    index: usize,
+    buf: *mut u8,
+    len: usize,
+) -> isize {
+    guard(-1, || match (*packet).record(section, index) {
+        Some(record) => {
+            let mut data = Vec::new();
+            record.data.write_to(&mut data);
+            write_out(&data, buf, len, false)
+        }
+        None => -1,
+    })
+}
+
+/// Writes a query for `name` with the type code `qtype` in the class IN
+///
+/// `name` is the text form, with or without the trailing dot. Returns
+/// the length of the query, the query was cut and must not be sent if
+/// it's not smaller than `len`. Returns -1 if `name` is not a valid name
+/// or the query would be longer than 512 bytes and have the truncation
+/// bit set.
+///
+/// # Safety
+///
+/// `name` must be a zero terminated string, `buf` must be null or point
+/// to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn dns_build_query(
+    id: u16,
+    name: *const c_char,
+    qtype: u16,
+    recursion: bool,
+    buf: *mut u8,
+    len: usize,
+) -> isize {
+    guard(-1, || {
+        if name.is_null() {
+            return -1;
+        }
+        let name = match CStr::from_ptr(name).to_str().map(str::parse::<Name<'_>>) {
+            Ok(Ok(name)) => name,
+            _ => return -1,
+        };
+        let mut builder = Builder::new_query(id, recursion);
+        builder.add_question_name(&name, false, QueryType::parse(qtype), QueryClass::IN);
+        match builder.build() {
+            Ok(query) => write_out(&query, buf, len, false),
+            Err(_) => -1,
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::ptr;
+
+    use super::*;
+
+    #[test]
+    fn parse_and_build() {
+        let mut query = [0u8; 64];
+        let len = unsafe {
+            dns_build_query(
+                0x1234,
+                b"example.com.\0".as_ptr().cast(),
+                28,
+                true,
+                query.as_mut_ptr(),
+                query.len(),
+            )
+        };
+        assert_eq!(len, 29);
+        let bad = b"a..b\0".as_ptr().cast();
+        assert_eq!(
+            unsafe { dns_build_query(1, bad, 1, true, ptr::null_mut(), 0) },
+            -1
+        );
+
+        let mut status = DnsStatus::DnsOk;
+        let packet = unsafe { dns_packet_parse(query.as_ptr(), 20, &mut status) };
+        assert!(packet.is_null());
+        assert_eq!(status, DnsStatus::DnsTruncated);
+
+        let packet = unsafe { dns_packet_parse(query.as_ptr(), len as usize, &mut status) };
+        assert!(!packet.is_null());
+        assert_eq!(status, DnsStatus::DnsOk);
+        unsafe {
+            assert_eq!(dns_packet_id(packet), 0x1234);
+            assert_eq!(dns_packet_flags(packet), 0x0100);
+            assert_eq!(dns_packet_count(packet, DnsSection::DnsSectionQuestion), 1);
+            assert_eq!(dns_packet_count(packet, DnsSection::DnsSectionAnswer), 0);
+            let mut question = DnsQuestion::default();
+            let status = dns_packet_question(packet, 0, &mut question);
+            assert_eq!(status, DnsStatus::DnsOk);
+            assert_eq!((question.qtype, question.qclass), (28, 1));
+            let status = dns_packet_question(packet, 1, &mut question);
+            assert_eq!(status, DnsStatus::DnsInvalidArgument);
+
+            let mut name = [0x55u8; 8];
+            let section = DnsSection::DnsSectionQuestion;
+            let len = dns_packet_name(packet, section, 0, name.as_mut_ptr().cast(), 8);
+            assert_eq!(len, 11);
+            assert_eq!(&name, b"example\0");
+            let mut record = DnsRecord::default();
+            let section = DnsSection::DnsSectionAnswer;
+            let status = dns_packet_record(packet, section, 0, &mut record);
+            assert_eq!(status, DnsStatus::DnsInvalidArgument);
+            assert_eq!(
+                dns_packet_record_data(packet, section, 0, ptr::null_mut(), 0),
+                -1
+            );
+            dns_packet_free(packet);
+        }
+    }
+
+    #[test]
+    fn panics_caught() {
+        assert_eq!(guard(-1, || 7), 7);
+        assert_eq!(guard(-1, || panic!("bug")), -1);
+        let status = guard(DnsStatus::DnsInternalError, || -> DnsStatus {
+            panic!("bug")
+        });
+        assert_eq!(status, DnsStatus::DnsInternalError);
+    }
+
+    #[test]
+    fn records() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04]\xb8\xd8\"";
+        unsafe {
+            let packet = dns_packet_parse(response.as_ptr(), response.len(), ptr::null_mut());
+            let section = DnsSection::DnsSectionAnswer;
+            let mut record = DnsRecord::default();
+            assert_eq!(
+                dns_packet_record(packet, section, 0, &mut record),
+                DnsStatus::DnsOk
+            );
+            assert_eq!((record.rtype, record.rclass, record.ttl), (1, 1, 1272));
+            let mut data = [0u8; 4];
+            let len = dns_packet_record_data(packet, section, 0, data.as_mut_ptr(), 4);
+            assert_eq!(len, 4);
+            assert_eq!(data, [93, 184, 216, 34]);
+            let mut name = [0u8; 16];
+            let len = dns_packet_name(packet, section, 0, name.as_mut_ptr().cast(), 16);
+            assert_eq!(&name[..len as usize + 1], b"example.com\0");
+            dns_packet_free(packet);
+        }
+    }
+}
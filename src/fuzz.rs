@@ -0,0 +1,134 @@
+// Feeds random and mutated packets to every parsing entry point
+//
+// None of them may panic, whatever the input. The generator is seeded, so
+// a failure is reproduced by running the test again. This is a quick
+// smoke test, the `parse` target in `fuzz/` does the real fuzzing with
+// `cargo fuzz run parse`.
+use std::fmt::Write;
+
+use crate::dissect::dissect;
+use crate::{parse_with_visitor, MessageVisitor, Packet, ParseArena, RawPacket};
+
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        // xorshift64
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+    fn byte(&mut self) -> u8 {
+        self.next() as u8
+    }
+}
+
+struct Visitor;
+
+impl<'a> MessageVisitor<'a> for Visitor {}
+
+fn name(rng: &mut Rng, packet: &mut Vec<u8>) {
+    for _ in 0..rng.below(4) {
+        let len = rng.below(8);
+        packet.push(len as u8);
+        packet.extend((0..len).map(|_| b'a' + rng.below(26) as u8));
+    }
+    if packet.len() > 12 && rng.below(2) == 0 {
+        let target = rng.below(packet.len()) as u16;
+        packet.extend_from_slice(&(0xc000 | target).to_be_bytes());
+    } else {
+        packet.push(0);
+    }
+}
+
+// A packet with a question and records of random types, data made of
+// names and random bytes
+fn generate(rng: &mut Rng) -> Vec<u8> {
+    let records = rng.below(6) as u16;
+    let mut packet = vec![rng.byte(), rng.byte(), rng.byte(), rng.byte(), 0, 1];
+    for count in &[records, 0, rng.below(2) as u16] {
+        packet.extend_from_slice(&count.to_be_bytes());
+    }
+    name(rng, &mut packet);
+    packet.extend_from_slice(&[0, 1, 0, 1]);
+    for _ in 0..records + 1 {
+        name(rng, &mut packet);
+        let typ = match rng.below(4) {
+            0 => rng.next() as u16,
+            _ => rng.below(300) as u16,
+        };
+        packet.extend_from_slice(&typ.to_be_bytes());
+        packet.extend_from_slice(&[0, 1, 0, 0, 0x0e, 0x10, 0, 0]);
+        let start = packet.len();
+        if rng.below(2) == 0 {
+            name(rng, &mut packet);
+        }
+        let len = rng.below(40);
+        packet.extend((0..len).map(|_| rng.byte()));
+        let rdlen = (packet.len() - start) as u16;
+        packet[start - 2..start].copy_from_slice(&rdlen.to_be_bytes());
+    }
+    packet
+}
+
+fn mutate(rng: &mut Rng, packet: &mut Vec<u8>) {
+    for _ in 0..rng.below(4) {
+        if packet.is_empty() {
+            return;
+        }
+        let pos = rng.below(packet.len());
+        match rng.below(4) {
+            0 => packet[pos] = rng.byte(),
+            1 => packet[pos] ^= 1 << rng.below(8),
+            2 => packet.truncate(pos),
+            _ => packet.insert(pos, rng.byte()),
+        }
+    }
+}
+
+fn exercise(data: &[u8], arena: &mut ParseArena) {
+    let mut out = String::new();
+    if let Ok(packet) = Packet::parse(data) {
+        for name in packet.names() {
+            write!(out, "{} {:?}", name, name).unwrap();
+            let _ = name.bytes().count();
+            let _ = name.labels().count();
+            let _ = name.clone().into_owned().to_string();
+        }
+        for record in packet.answers().iter().chain(packet.additional()) {
+            write!(out, "{:?}", record.data).unwrap();
+            let mut wire = Vec::new();
+            record.data.write_to(&mut wire);
+            record.data.write_canonical_to(&mut wire);
+        }
+        let _ = packet.to_packet_buf();
+    }
+    if let Ok(packet) = RawPacket::parse(data) {
+        for record in packet.answers().iter().chain(packet.additional()) {
+            let _ = record.parse_data();
+        }
+    }
+    if let Ok(packet) = arena.parse(data) {
+        arena.recycle(packet);
+    }
+    let _ = parse_with_visitor(data, &mut Visitor);
+    let split = data.len() / 2;
+    let _ = Packet::parse_segments(&[&data[..split], &data[split..]]);
+    write!(out, "{}", dissect(data)).unwrap();
+}
+
+#[test]
+fn no_panics() {
+    let mut rng = Rng(0x5eed_d15c_0bad_cafe);
+    let mut arena = ParseArena::new();
+    for _ in 0..500 {
+        let mut packet = generate(&mut rng);
+        exercise(&packet, &mut arena);
+        mutate(&mut rng, &mut packet);
+        exercise(&packet, &mut arena);
+    }
+}
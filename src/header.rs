@@ -15,8 +15,12 @@ mod flag {
 }
 
 /// Represents parsed header of the packet
+///
+/// The struct is non-exhaustive, start from `Header::default()` to
+/// construct one.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 #[allow(missing_docs)] // fields are from the spec I think
+#[non_exhaustive]
 pub struct Header {
     pub id: u16,
     pub query: bool,
@@ -27,6 +31,8 @@ pub struct Header {
     pub recursion_available: bool,
     pub authenticated_data: bool,
     pub checking_disabled: bool,
+    /// The reserved Z bit, always zero in headers from `parse`
+    pub reserved: bool,
     pub response_code: ResponseCode,
     pub questions: u16,
     pub answers: u16,
@@ -34,6 +40,29 @@ pub struct Header {
     pub additional: u16,
 }
 
+impl Default for Header {
+    /// A standard query with ID zero, no flags set and empty sections
+    fn default() -> Header {
+        Header {
+            id: 0,
+            query: true,
+            opcode: Opcode::StandardQuery,
+            authoritative: false,
+            truncated: false,
+            recursion_desired: false,
+            recursion_available: false,
+            authenticated_data: false,
+            checking_disabled: false,
+            reserved: false,
+            response_code: ResponseCode::NoError,
+            questions: 0,
+            answers: 0,
+            nameservers: 0,
+            additional: 0,
+        }
+    }
+}
+
 impl Header {
     /// Parse the header into a header structure
     pub fn parse(data: &[u8]) -> Result<Header, Error> {
@@ -46,6 +75,21 @@ impl Header {
         }
         let header = Header {
             id: u16::from_be_bytes(data[..2].try_into().unwrap()),
+            questions: u16::from_be_bytes(data[4..6].try_into().unwrap()),
+            answers: u16::from_be_bytes(data[6..8].try_into().unwrap()),
+            nameservers: u16::from_be_bytes(data[8..10].try_into().unwrap()),
+            additional: u16::from_be_bytes(data[10..12].try_into().unwrap()),
+            ..Header::from_flags_word(flags)
+        };
+        Ok(header)
+    }
+    /// Creates a header from the 16-bit word with the flags, opcode and
+    /// response code
+    ///
+    /// All bits are kept, including the reserved one, so `flags_word`
+    /// returns the same word. The ID and section counts are zero.
+    pub fn from_flags_word(flags: u16) -> Header {
+        Header {
             query: flags & flag::QUERY == 0,
             opcode: ((flags & flag::OPCODE_MASK) >> flag::OPCODE_MASK.trailing_zeros()).into(),
             authoritative: flags & flag::AUTHORITATIVE != 0,
@@ -54,26 +98,20 @@ impl Header {
             recursion_available: flags & flag::RECURSION_AVAILABLE != 0,
             authenticated_data: flags & flag::AUTHENTICATED_DATA != 0,
             checking_disabled: flags & flag::CHECKING_DISABLED != 0,
+            reserved: flags & flag::RESERVED_MASK != 0,
             response_code: From::from((flags & flag::RESPONSE_CODE_MASK) as u8),
-            questions: u16::from_be_bytes(data[4..6].try_into().unwrap()),
-            answers: u16::from_be_bytes(data[6..8].try_into().unwrap()),
-            nameservers: u16::from_be_bytes(data[8..10].try_into().unwrap()),
-            additional: u16::from_be_bytes(data[10..12].try_into().unwrap()),
-        };
-        Ok(header)
+            ..Header::default()
+        }
     }
-    /// Write a header to a buffer slice
-    ///
-    /// # Panics
+    /// Returns the 16-bit word with the flags, opcode and response code
     ///
-    /// When buffer size is not exactly 12 bytes
-    pub fn write(&self, data: &mut [u8]) {
-        if data.len() != 12 {
-            panic!("Header size is exactly 12 bytes");
-        }
+    /// This is the word following the ID in the wire format.
+    pub fn flags_word(&self) -> u16 {
         let mut flags = 0u16;
-        flags |= Into::<u16>::into(self.opcode) << flag::OPCODE_MASK.trailing_zeros();
-        flags |= Into::<u8>::into(self.response_code) as u16;
+        // codes too large for their fields are truncated
+        flags |= (Into::<u16>::into(self.opcode) << flag::OPCODE_MASK.trailing_zeros())
+            & flag::OPCODE_MASK;
+        flags |= Into::<u8>::into(self.response_code) as u16 & flag::RESPONSE_CODE_MASK;
         if !self.query {
             flags |= flag::QUERY;
         }
@@ -89,18 +127,71 @@ impl Header {
         if self.truncated {
             flags |= flag::TRUNCATED;
         }
-        data[..2].copy_from_slice(&(self.id as u16).to_be_bytes());
-        data[2..4].copy_from_slice(&(flags as u16).to_be_bytes());
-        data[4..6].copy_from_slice(&(self.questions as u16).to_be_bytes());
-        data[6..8].copy_from_slice(&(self.answers as u16).to_be_bytes());
-        data[8..10].copy_from_slice(&(self.nameservers as u16).to_be_bytes());
-        data[10..12].copy_from_slice(&(self.additional as u16).to_be_bytes());
+        if self.authenticated_data {
+            flags |= flag::AUTHENTICATED_DATA;
+        }
+        if self.checking_disabled {
+            flags |= flag::CHECKING_DISABLED;
+        }
+        if self.reserved {
+            flags |= flag::RESERVED_MASK;
+        }
+        flags
+    }
+    /// Write a header to a buffer slice
+    ///
+    /// # Panics
+    ///
+    /// When buffer size is not exactly 12 bytes
+    pub fn write(&self, data: &mut [u8]) {
+        if data.len() != 12 {
+            panic!("Header size is exactly 12 bytes");
+        }
+        let flags = self.flags_word();
+        data[..2].copy_from_slice(&self.id.to_be_bytes());
+        data[2..4].copy_from_slice(&flags.to_be_bytes());
+        data[4..6].copy_from_slice(&self.questions.to_be_bytes());
+        data[6..8].copy_from_slice(&self.answers.to_be_bytes());
+        data[8..10].copy_from_slice(&self.nameservers.to_be_bytes());
+        data[10..12].copy_from_slice(&self.additional.to_be_bytes());
+    }
+    /// Returns the conflict (C) bit of an LLMNR message (RFC 4795)
+    ///
+    /// LLMNR sends it in place of the authoritative answer bit.
+    pub fn llmnr_conflict(&self) -> bool {
+        self.authoritative
+    }
+    /// Sets the conflict (C) bit of an LLMNR message
+    pub fn set_llmnr_conflict(&mut self, value: bool) {
+        self.authoritative = value;
+    }
+    /// Returns the tentative (T) bit of an LLMNR message (RFC 4795)
+    ///
+    /// LLMNR sends it in place of the recursion desired bit.
+    pub fn llmnr_tentative(&self) -> bool {
+        self.recursion_desired
+    }
+    /// Sets the tentative (T) bit of an LLMNR message
+    pub fn set_llmnr_tentative(&mut self, value: bool) {
+        self.recursion_desired = value;
+    }
+    /// Returns the broadcast (B) bit of a NetBIOS Name Service message
+    ///
+    /// NBNS sends it in place of the checking disabled bit.
+    #[cfg(feature = "nbns")]
+    pub fn nbns_broadcast(&self) -> bool {
+        self.checking_disabled
+    }
+    /// Sets the broadcast (B) bit of a NetBIOS Name Service message
+    #[cfg(feature = "nbns")]
+    pub fn set_nbns_broadcast(&mut self, value: bool) {
+        self.checking_disabled = value;
     }
     /// Set "truncated flag" in the raw data
     // shouldn't this method be non-public?
     pub fn set_truncated(data: &mut [u8]) {
         let oldflags = u16::from_be_bytes(data[2..4].try_into().unwrap());
-        data[2..4].copy_from_slice(&(oldflags & flag::TRUNCATED as u16).to_be_bytes());
+        data[2..4].copy_from_slice(&(oldflags | flag::TRUNCATED).to_be_bytes());
     }
     /// Returns a size of the header (always 12 bytes)
     pub fn size() -> usize {
@@ -132,6 +223,7 @@ mod test {
                 recursion_available: false,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 0,
@@ -160,6 +252,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 1,
@@ -186,6 +279,7 @@ mod test {
                 recursion_available: false,
                 authenticated_data: true,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 0,
@@ -212,6 +306,7 @@ mod test {
                 recursion_available: false,
                 authenticated_data: false,
                 checking_disabled: true,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 0,
@@ -220,4 +315,66 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn write_roundtrip() {
+        let response = b"\x06%\x85\xb3\x00\x01\x00\x02\x00\x03\x00\x04";
+        let header = Header::parse(response).unwrap();
+        assert!(header.authoritative);
+        assert!(header.authenticated_data);
+        assert!(header.checking_disabled);
+        let mut buf = [0u8; 12];
+        header.write(&mut buf);
+        assert_eq!(&buf, response);
+    }
+
+    #[test]
+    fn set_truncated() {
+        let mut data = *b"\x06%\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00";
+        Header::set_truncated(&mut data);
+        let header = Header::parse(&data).unwrap();
+        assert!(header.truncated);
+        assert!(header.recursion_desired);
+        assert!(header.recursion_available);
+        assert!(!header.query);
+    }
+
+    #[test]
+    fn llmnr_bits() {
+        // LLMNR response with the C and T bits set
+        let response = b"\x12\x34\x85\x00\x00\x01\x00\x00\x00\x00\x00\x00";
+        let header = Header::parse(response).unwrap();
+        assert!(!header.query);
+        assert!(header.llmnr_conflict());
+        assert!(!header.truncated);
+        assert!(header.llmnr_tentative());
+
+        let mut header = Header::default();
+        header.set_llmnr_tentative(true);
+        let mut buf = [0u8; 12];
+        header.write(&mut buf);
+        assert_eq!(buf[2], 0x01);
+        header.set_llmnr_tentative(false);
+        header.set_llmnr_conflict(true);
+        header.write(&mut buf);
+        assert_eq!(buf[2], 0x04);
+    }
+
+    #[test]
+    fn flags_word() {
+        let header = Header::from_flags_word(0x85b3);
+        assert!(!header.query);
+        assert!(header.authoritative);
+        assert!(header.recursion_available);
+        assert!(!header.reserved);
+        assert_eq!(header.flags_word(), 0x85b3);
+        assert_eq!(header.id, 0);
+
+        // query with the reserved bit set and opcode 15
+        let header = Header::from_flags_word(0x7840);
+        assert!(header.reserved);
+        assert_eq!(header.opcode, Reserved(15));
+        assert_eq!(header.flags_word(), 0x7840);
+        assert_eq!(Header::default().flags_word(), 0);
+    }
 }
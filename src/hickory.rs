@@ -0,0 +1,220 @@
+// Conversions from and to the types of hickory-proto, enabled by the
+// `hickory` feature
+//
+// Codes convert both ways without loss. Record data is converted through
+// its wire format, so every type either library has a parser for keeps
+// its meaning, others are carried as unknown data.
+use std::convert::TryFrom;
+
+use hickory_proto::op::Query;
+use hickory_proto::rr::{self as hickory, DNSClass, RecordType};
+use hickory_proto::serialize::binary::{BinDecoder, BinEncodable, BinEncoder, Restrict};
+
+use crate::{
+    Class, Error, Name, QueryClass, QueryType, Question, RData, ResourceRecord, Ttl, Type,
+};
+
+impl From<Type> for RecordType {
+    fn from(typ: Type) -> RecordType {
+        RecordType::from(typ.code())
+    }
+}
+
+impl From<RecordType> for Type {
+    fn from(typ: RecordType) -> Type {
+        Type::parse(u16::from(typ))
+    }
+}
+
+impl From<QueryType> for RecordType {
+    fn from(qtype: QueryType) -> RecordType {
+        RecordType::from(qtype.code())
+    }
+}
+
+impl From<RecordType> for QueryType {
+    fn from(qtype: RecordType) -> QueryType {
+        QueryType::parse(u16::from(qtype))
+    }
+}
+
+impl From<Class> for DNSClass {
+    fn from(cls: Class) -> DNSClass {
+        DNSClass::from(cls.code())
+    }
+}
+
+/// `OPT` gives the payload size as the code, like hickory-proto does
+impl From<DNSClass> for Class {
+    fn from(cls: DNSClass) -> Class {
+        Class::parse(u16::from(cls))
+    }
+}
+
+impl From<QueryClass> for DNSClass {
+    fn from(qclass: QueryClass) -> DNSClass {
+        DNSClass::from(qclass.code())
+    }
+}
+
+impl From<DNSClass> for QueryClass {
+    fn from(qclass: DNSClass) -> QueryClass {
+        QueryClass::parse(u16::from(qclass))
+    }
+}
+
+impl<'a> TryFrom<&Name<'a>> for hickory::Name {
+    type Error = Error;
+    fn try_from(name: &Name<'a>) -> Result<hickory::Name, Error> {
+        hickory::Name::from_labels(name.labels())
+            .map_err(|error| Error::ConversionFailed(error.to_string()))
+    }
+}
+
+/// Fails if a label is not UTF-8 or contains a dot
+impl TryFrom<&hickory::Name> for Name<'static> {
+    type Error = Error;
+    fn try_from(name: &hickory::Name) -> Result<Name<'static>, Error> {
+        Name::from_labels(name.iter())
+    }
+}
+
+impl<'a> TryFrom<&RData<'a>> for hickory::RData {
+    type Error = Error;
+    fn try_from(data: &RData<'a>) -> Result<hickory::RData, Error> {
+        let mut wire = Vec::new();
+        data.write_to(&mut wire);
+        let len = u16::try_from(wire.len())
+            .map_err(|_| Error::ConversionFailed(format!("{} bytes of record data", wire.len())))?;
+        let mut decoder = BinDecoder::new(&wire);
+        hickory::RData::read(&mut decoder, data.typ().into(), Restrict::new(len))
+            .map_err(|error| Error::ConversionFailed(error.to_string()))
+    }
+}
+
+impl TryFrom<&hickory::RData> for RData<'static> {
+    type Error = Error;
+    fn try_from(data: &hickory::RData) -> Result<RData<'static>, Error> {
+        let mut wire = Vec::new();
+        data.emit(&mut BinEncoder::new(&mut wire))
+            .map_err(|error| Error::ConversionFailed(error.to_string()))?;
+        // names may be compressed, pointing into `wire` itself
+        let data = RData::parse(data.record_type().into(), &wire, &wire)?;
+        Ok(data.into_owned())
+    }
+}
+
+impl<'a> TryFrom<&ResourceRecord<'a>> for hickory::Record {
+    type Error = Error;
+    fn try_from(record: &ResourceRecord<'a>) -> Result<hickory::Record, Error> {
+        let name = hickory::Name::try_from(&record.name)?;
+        let data = hickory::RData::try_from(&record.data)?;
+        let mut result = hickory::Record::from_rdata(name, record.ttl.as_secs(), data);
+        result.dns_class = record.cls.into();
+        result.mdns_cache_flush = record.multicast_unique;
+        Ok(result)
+    }
+}
+
+impl TryFrom<&hickory::Record> for ResourceRecord<'static> {
+    type Error = Error;
+    fn try_from(record: &hickory::Record) -> Result<ResourceRecord<'static>, Error> {
+        Ok(ResourceRecord {
+            name: Name::try_from(&record.name)?,
+            multicast_unique: record.mdns_cache_flush,
+            cls: record.dns_class.into(),
+            ttl: Ttl::new(record.ttl),
+            data: RData::try_from(&record.data)?,
+        })
+    }
+}
+
+impl<'a> TryFrom<&Question<'a>> for Query {
+    type Error = Error;
+    fn try_from(question: &Question<'a>) -> Result<Query, Error> {
+        let name = hickory::Name::try_from(&question.qname)?;
+        let mut query = Query::query(name, question.qtype.into());
+        query.set_query_class(question.qclass.into());
+        query.set_mdns_unicast_response(question.prefer_unicast);
+        Ok(query)
+    }
+}
+
+impl TryFrom<&Query> for Question<'static> {
+    type Error = Error;
+    fn try_from(query: &Query) -> Result<Question<'static>, Error> {
+        Ok(Question {
+            qname: Name::try_from(query.name())?,
+            prefer_unicast: query.mdns_unicast_response(),
+            qtype: query.query_type().into(),
+            qclass: query.query_class().into(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::convert::TryFrom;
+
+    use hickory_proto::op::Query;
+    use hickory_proto::rr::{self as hickory, rdata, DNSClass, RecordType};
+
+    use crate::{Class, Name, Packet, QueryType, Question, RData, ResourceRecord, Type};
+
+    #[test]
+    fn codes() {
+        assert_eq!(RecordType::from(Type::AAAA), RecordType::AAAA);
+        assert_eq!(Type::from(RecordType::from(65280)), Type::Unknown(65280));
+        assert_eq!(QueryType::from(RecordType::AXFR), QueryType::AXFR);
+        assert_eq!(DNSClass::from(Class::CH), DNSClass::CH);
+        assert_eq!(Class::from(DNSClass::Unknown(32)), Class::Unknown(32));
+    }
+
+    #[test]
+    fn records() {
+        let response = b"\x00\x00\x81\x80\x00\x01\x00\x02\x00\x00\x00\x00\
+            \x07example\x03com\x00\x00\x0f\x00\x01\
+            \xc0\x0c\x00\x0f\x00\x01\x00\x00\x0e\x10\x00\x07\x00\x0a\x02mx\xc0\x0c\
+            \xc0\x0c\x00\x10\x00\x01\x00\x00\x0e\x10\x00\x06\x02hi\x02yo";
+        let packet = Packet::parse(response).unwrap();
+        let question = Query::try_from(&packet.questions()[0]).unwrap();
+        assert_eq!(question.name().to_string(), "example.com.");
+        assert_eq!(question.query_type(), RecordType::MX);
+        assert_eq!(
+            Question::try_from(&question).unwrap(),
+            packet.questions()[0]
+        );
+
+        let mx = hickory::Record::try_from(&packet.answers()[0]).unwrap();
+        assert_eq!(
+            mx.data,
+            hickory::RData::MX(rdata::MX::new(10, "mx.example.com.".parse().unwrap()))
+        );
+        assert_eq!(mx.ttl, 3600);
+        assert_eq!(ResourceRecord::try_from(&mx).unwrap(), packet.answers()[0]);
+        let txt = hickory::Record::try_from(&packet.answers()[1]).unwrap();
+        let strings = vec!["hi".to_string(), "yo".to_string()];
+        assert_eq!(txt.data, hickory::RData::TXT(rdata::TXT::new(strings)));
+        assert_eq!(ResourceRecord::try_from(&txt).unwrap(), packet.answers()[1]);
+
+        // compressed names from hickory-proto are resolved
+        let soa = rdata::SOA::new(
+            "ns.example.com.".parse().unwrap(),
+            "admin.ns.example.com.".parse().unwrap(),
+            1,
+            2,
+            3,
+            4,
+            5,
+        );
+        match RData::try_from(&hickory::RData::SOA(soa)).unwrap() {
+            RData::SOA(soa) => assert_eq!(soa.mailbox.to_string(), "admin.ns.example.com"),
+            data => panic!("wrong data {:?}", data),
+        }
+        let name = Name::try_from(&hickory::Name::root()).unwrap();
+        assert_eq!(
+            hickory::Name::try_from(&name).unwrap(),
+            hickory::Name::root()
+        );
+    }
+}
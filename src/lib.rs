@@ -9,8 +9,62 @@
 //!
 //! Use [`Packet::parse`] to parse a packet into a data structure.
 //!
+//! Use [`RawPacket::parse`] to split a packet into records without parsing
+//! the record data.
+//!
+//! Use [`parse_with_visitor`] to scan a packet with callbacks, without
+//! allocating anything.
+//!
+//! Use [`Packet::parse_segments`] to parse a packet split across several
+//! buffers without joining them first.
+//!
+//! Use [`ParseArena`] to parse many packets in a row reusing the buffers
+//! allocated for the previous ones.
+//!
+//! Use [`SharedPacket`] to keep a packet along with the buffer it was
+//! parsed from, e.g. an `Arc<[u8]>`.
+//!
+//! With the `bytes` feature enabled, [`BytesPacket`] parses a `bytes::Bytes`
+//! buffer without copying the names and record data out of it, and
+//! `Packet::parse_buf` parses the chunks of a `bytes::Buf`.
+//!
+//! With the `memchr` feature enabled, the attributes of TXT records are
+//! split with the `memchr` crate.
+//!
+//! With the `defmt` feature enabled, headers, questions, records, names
+//! and codes implement `defmt::Format` for logging on embedded targets.
+//!
+//! With the `tracing` feature enabled, parsing emits `tracing` spans and
+//! events: `debug` spans per packet and section with their counts,
+//! `debug` events for invalid questions and records with their offsets,
+//! and `trace` events for every record and compression pointer. The
+//! length of packets from `Builder::build` is logged as well.
+//!
+//! With the `ffi` feature enabled, the `ffi` module exposes a C interface
+//! to parse packets and build queries.
+//!
+//! With the `hickory` feature enabled, names, codes, questions and records
+//! convert from and to the types of `hickory-proto` with `From` and
+//! `TryFrom`.
+//!
+//! With the `rkyv` feature enabled, the `archive` module stores parsed
+//! packets in a form which is read in place after archival with `rkyv`.
+//!
+//! Parsing never panics, malformed input gives an [`Error`]. The same
+//! holds for formatting and iterating over the parsed data. Only misuse
+//! by the caller panics, as documented, e.g. adding a question to a
+//! `Builder` after an answer or adding too much to a `SerialNumber`. The
+//! `fuzz` directory has a `cargo fuzz` target checking the parsing.
+//!
 //! [`Builder`]: struct.Builder.html
+//! [`Error`]: enum.Error.html
 //! [`Packet::parse`]: struct.Packet.html#method.parse
+//! [`RawPacket::parse`]: struct.RawPacket.html#method.parse
+//! [`parse_with_visitor`]: fn.parse_with_visitor.html
+//! [`Packet::parse_segments`]: struct.Packet.html#method.parse_segments
+//! [`ParseArena`]: struct.ParseArena.html
+//! [`BytesPacket`]: type.BytesPacket.html
+//! [`SharedPacket`]: struct.SharedPacket.html
 //!
 #![warn(missing_docs)]
 #![warn(missing_debug_implementations)]
@@ -19,22 +73,86 @@
 #[macro_use]
 extern crate matches;
 
+// the macros must be defined before the modules using them
+#[macro_use]
+mod trace;
+
+mod arena;
+#[cfg(feature = "rkyv")]
+pub mod archive;
+pub mod bailiwick;
+#[cfg(feature = "rayon")]
+pub mod batch;
 mod builder;
+mod canonical;
+pub mod chain;
+#[cfg(feature = "codec")]
+pub mod codec;
+pub mod diff;
+#[cfg(feature = "defmt")]
+mod defmt_format;
+pub mod dissect;
+pub mod dnssd;
+pub mod dnssec;
+pub mod dns64;
+#[cfg(feature = "dnstap")]
+pub mod dnstap;
+pub mod doh;
 mod enums;
 mod error;
+mod extension;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(test)]
+mod fuzz;
 mod header;
+#[cfg(feature = "hickory")]
+mod hickory;
+pub mod lint;
+pub mod llmnr;
+pub mod mdns;
+mod mnemonic;
 mod name;
+#[cfg(feature = "nbns")]
+pub mod nbns;
+mod options;
 mod parser;
+#[cfg(feature = "pcap")]
+pub mod pcap;
+pub mod qmin;
+pub mod rrset;
+mod segments;
+mod serial;
+mod sha1;
+mod shared;
+mod small_vec;
+pub mod stats;
 mod structs;
+mod ttl;
+mod type_bitmap;
+mod visitor;
 
 /// Data types and methods for handling the RData field
 #[allow(missing_docs)] // resource records are pretty self-descriptive
 pub mod rdata;
 
+pub use crate::arena::{PacketStorage, ParseArena};
 pub use crate::builder::Builder;
 pub use crate::enums::{Class, Opcode, QueryClass, ResponseCode};
-pub use crate::error::Error;
+pub use crate::error::{Error, ErrorKind, Limit};
+pub use crate::extension::{RDataParse, RDataRegistry};
 pub use crate::header::Header;
+pub use crate::mnemonic::MnemonicError;
 pub use crate::name::Name;
+pub use crate::options::ParseOptions;
 pub use crate::rdata::{QueryType, RData, Type};
-pub use crate::structs::{Packet, Question, ResourceRecord};
+pub use crate::serial::SerialNumber;
+#[cfg(feature = "bytes")]
+pub use crate::shared::BytesPacket;
+pub use crate::shared::SharedPacket;
+pub use crate::structs::{Packet, PacketBuf, Question, QuestionKey, ResourceRecord};
+pub use crate::structs::{RawPacket, RawQuestion, RawRecord};
+pub use crate::ttl::Ttl;
+pub use crate::type_bitmap::{Codes, TypeBitmap};
+pub use crate::visitor::{parse_with_visitor, parse_with_visitor_with_options};
+pub use crate::visitor::{MessageVisitor, Section};
@@ -0,0 +1,480 @@
+//! Checks of packets for RFC conformance
+//!
+//! The parser only rejects packets which can't be interpreted at all. The
+//! functions here look for things that parse fine but violate the RFCs,
+//! and report them as a list of warnings, which is what monitoring tools
+//! usually want.
+//!
+//! `lint` works with parsed packets. Some problems, like several OPT
+//! records, out of range TTLs or bytes after the last record, are either
+//! rejected, fixed up or ignored by `Packet::parse`, use `lint_raw` on a
+//! `RawPacket` to see those. Packets with more records in the header
+//! counts than they hold fail to parse at all. Whether the TC bit is
+//! allowed depends on the transport, which `lint_transport` checks.
+use std::fmt;
+
+use crate::name::Budget;
+use crate::parser::parse_raw_record;
+use crate::{Header, Name, Opcode, Packet, RData, RawPacket, Section, Type};
+
+/// A conformance problem found in a packet
+///
+/// Records are identified by their section and index in it. For `Packet`
+/// the index in the additional section doesn't count the OPT record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Warning {
+    /// A standard query with more than one question (RFC 9619)
+    MultipleQuestions(usize),
+    /// The question at the index repeats an earlier one
+    DuplicateQuestion(usize),
+    /// The TC bit is set in a query, it's only meaningful in responses
+    TruncatedQuery,
+    /// The TC bit is set in a response received over a transport other
+    /// than UDP, where messages are never truncated (RFC 7766 section 8)
+    TruncatedNotUdp,
+    /// Section count in the header doesn't match the records found
+    ///
+    /// Records following the last counted one are attributed to the
+    /// additional section.
+    CountMismatch {
+        /// The section with the wrong count
+        section: Section,
+        /// Count in the header
+        header: u16,
+        /// Number of records in the section
+        actual: usize,
+    },
+    /// An OPT record outside of the additional section, or one with an
+    /// owner name other than root (RFC 6891 section 6.1.1)
+    MisplacedOpt {
+        /// Section of the record
+        section: Section,
+        /// Index of the record
+        index: usize,
+    },
+    /// Bytes after the last record which don't form whole records
+    TrailingBytes(usize),
+    /// More than one OPT record in the additional section
+    MultipleOpt,
+    /// A compression pointer in the data of a record type that must not
+    /// be compressed (RFC 3597 section 4)
+    CompressedRdata {
+        /// Section of the record
+        section: Section,
+        /// Index of the record
+        index: usize,
+    },
+    /// A TTL with the most significant bit set (RFC 2181 section 8)
+    TtlTooLong {
+        /// Section of the record
+        section: Section,
+        /// Index of the record
+        index: usize,
+        /// The TTL as received
+        ttl: u32,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use self::Warning::*;
+        match *self {
+            MultipleQuestions(n) => write!(f, "standard query with {} questions", n),
+            DuplicateQuestion(i) => write!(f, "question {} is a duplicate", i),
+            TruncatedQuery => write!(f, "truncation flag set in a query"),
+            TruncatedNotUdp => write!(f, "truncation flag set in a response not over UDP"),
+            CountMismatch {
+                section,
+                header,
+                actual,
+            } => write!(
+                f,
+                "{:?} count is {} in the header, but there are {} records",
+                section, header, actual
+            ),
+            MisplacedOpt { section, index } => {
+                write!(f, "misplaced OPT record {:?}[{}]", section, index)
+            }
+            TrailingBytes(n) => write!(f, "{} bytes after the last record", n),
+            MultipleOpt => write!(f, "more than one OPT record"),
+            CompressedRdata { section, index } => write!(
+                f,
+                "compressed name in the data of record {:?}[{}]",
+                section, index
+            ),
+            TtlTooLong {
+                section,
+                index,
+                ttl,
+            } => write!(
+                f,
+                "TTL {} of record {:?}[{}] is too long",
+                ttl, section, index
+            ),
+        }
+    }
+}
+
+/// How a packet was received, see `lint_transport`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Transport {
+    /// UDP, where a response too long for the datagram is truncated
+    Udp,
+    /// A stream transport like TCP or TLS, or HTTPS
+    Stream,
+}
+
+/// Checks a parsed packet
+pub fn lint(packet: &Packet<'_>) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let questions = packet
+        .questions()
+        .iter()
+        .map(|q| (&q.qname, q.qtype.code(), q.qclass.code()));
+    check_header(packet.header(), questions, &mut warnings);
+
+    let sections = [
+        (Section::Answer, packet.answers()),
+        (Section::Authority, packet.nameservers()),
+        (Section::Additional, packet.additional()),
+    ];
+    for &(section, records) in sections.iter() {
+        for (index, record) in records.iter().enumerate() {
+            let compressed = match record.data {
+                // a valid OPT record is never in the list of records
+                RData::OPT(..) => {
+                    warnings.push(Warning::MisplacedOpt { section, index });
+                    continue;
+                }
+                RData::Unknown(code, ref data) => compressed_rdata(Type::parse(code), data),
+                ref data => {
+                    !data.typ().allows_compression_in_rdata()
+                        && data.names().any(Name::is_compressed)
+                }
+            };
+            if compressed {
+                warnings.push(Warning::CompressedRdata { section, index });
+            }
+        }
+    }
+    warnings
+}
+
+/// Checks a packet with unparsed record data
+pub fn lint_raw(packet: &RawPacket<'_>) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    let questions = packet
+        .questions()
+        .iter()
+        .map(|q| (&q.qname, q.qtype, q.qclass & 0x7FFF));
+    check_header(packet.header(), questions, &mut warnings);
+
+    let sections = [
+        (Section::Answer, packet.answers()),
+        (Section::Authority, packet.nameservers()),
+        (Section::Additional, packet.additional()),
+    ];
+    let mut opts = 0;
+    for &(section, records) in sections.iter() {
+        for (index, record) in records.iter().enumerate() {
+            if record.typ == Type::OPT.code() {
+                if section != Section::Additional || record.name.labels().next().is_some() {
+                    warnings.push(Warning::MisplacedOpt { section, index });
+                }
+                opts += 1;
+                // the TTL field holds flags
+                continue;
+            }
+            if compressed_rdata(Type::parse(record.typ), record.data) {
+                warnings.push(Warning::CompressedRdata { section, index });
+            }
+            if record.ttl > i32::MAX as u32 {
+                warnings.push(Warning::TtlTooLong {
+                    section,
+                    index,
+                    ttl: record.ttl,
+                });
+            }
+        }
+    }
+    if opts > 1 {
+        warnings.push(Warning::MultipleOpt);
+    }
+    check_trailing(packet, &mut warnings);
+    warnings
+}
+
+/// Checks the header of a packet received over `transport`
+///
+/// These are the problems `lint` and `lint_raw` can't tell without
+/// knowing the transport.
+pub fn lint_transport(header: &Header, transport: Transport) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+    if !header.query && header.truncated && transport != Transport::Udp {
+        warnings.push(Warning::TruncatedNotUdp);
+    }
+    warnings
+}
+
+// Reports bytes after the last record, as records missing from the
+// additional count if they are whole records
+fn check_trailing(packet: &RawPacket<'_>, warnings: &mut Vec<Warning>) {
+    let trailing = packet.trailing();
+    if trailing.is_empty() {
+        return;
+    }
+    let mut offset = packet.end;
+    let mut extra = 0;
+    while offset < packet.original.len() {
+        let mut next = offset;
+        if parse_raw_record(packet.original, &mut next, None, &mut Budget::default()).is_err() {
+            break;
+        }
+        offset = next;
+        extra += 1;
+    }
+    if offset == packet.original.len() {
+        warnings.push(Warning::CountMismatch {
+            section: Section::Additional,
+            header: packet.header().additional,
+            actual: packet.additional().len() + extra,
+        });
+    } else {
+        warnings.push(Warning::TrailingBytes(trailing.len()));
+    }
+}
+
+// Returns true if a name in record data of a type that doesn't allow it
+// is compressed
+fn compressed_rdata(typ: Type, data: &[u8]) -> bool {
+    let names = match typ.rdata_names() {
+        Some(names) if !typ.allows_compression_in_rdata() => names,
+        _ => return false,
+    };
+    let mut pos = match names.start(data, 0, data.len()) {
+        Some(pos) => pos,
+        None => return false,
+    };
+    for _ in 0..names.count {
+        loop {
+            match data.get(pos) {
+                Some(0) => break,
+                Some(&byte) if byte & 0b1100_0000 == 0b1100_0000 => return true,
+                Some(&byte) => pos += byte as usize + 1,
+                None => return false,
+            }
+        }
+        pos += 1;
+    }
+    false
+}
+
+fn check_header<'n, 'a: 'n, I>(header: &Header, questions: I, warnings: &mut Vec<Warning>)
+where
+    I: ExactSizeIterator<Item = (&'n Name<'a>, u16, u16)> + Clone,
+{
+    if header.query && header.truncated {
+        warnings.push(Warning::TruncatedQuery);
+    }
+    if header.opcode == Opcode::StandardQuery && questions.len() > 1 {
+        warnings.push(Warning::MultipleQuestions(questions.len()));
+    }
+    for (index, (name, qtype, qclass)) in questions.clone().enumerate() {
+        let duplicate = questions
+            .clone()
+            .take(index)
+            .any(|(n, t, c)| t == qtype && c == qclass && n.eq_ignore_ascii_case(name));
+        if duplicate {
+            warnings.push(Warning::DuplicateQuestion(index));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lint, lint_raw, lint_transport, Transport, Warning};
+    use crate::rdata::Srv;
+    use crate::{Builder, Class, Name, Packet, RData, RawPacket, ResourceRecord, Section, Ttl};
+    use crate::{QueryClass as QC, QueryType as QT};
+
+    #[test]
+    fn clean_packet() {
+        let mut bld = Builder::new_query(1, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        let query = bld.build().unwrap();
+        assert_eq!(lint(&Packet::parse(&query).unwrap()), []);
+        assert_eq!(lint_raw(&RawPacket::parse(&query).unwrap()), []);
+    }
+
+    #[test]
+    fn questions() {
+        let mut bld = Builder::new_query(1, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        bld.add_question("example.com", false, QT::AAAA, QC::IN);
+        bld.add_question("Example.COM", false, QT::A, QC::IN);
+        let mut query = bld.build().unwrap();
+        query[2] |= 0b10; // TC
+        let expected = [
+            Warning::TruncatedQuery,
+            Warning::MultipleQuestions(3),
+            Warning::DuplicateQuestion(2),
+        ];
+        assert_eq!(lint(&Packet::parse(&query).unwrap()), expected);
+        assert_eq!(lint_raw(&RawPacket::parse(&query).unwrap()), expected);
+    }
+
+    #[test]
+    fn misplaced_opt() {
+        let mut bld = Builder::new_query(1, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        let name = b"\x07example\x03com\x00";
+        bld.add_answer(&ResourceRecord {
+            name: Name::scan(name, name).unwrap(),
+            multicast_unique: false,
+            cls: Class::IN,
+            ttl: Ttl(0),
+            data: RData::OPT(Default::default()),
+        });
+        let mut query = bld.build().unwrap();
+        // two OPT records in the additional section
+        query[11] = 2;
+        query.extend_from_slice(b"\x00\x00\x29\x10\x00\x00\x00\x00\x00\x00\x00");
+        query.extend_from_slice(b"\x00\x00\x29\x10\x00\x00\x00\x00\x00\x00\x00");
+        assert_eq!(
+            lint_raw(&RawPacket::parse(&query).unwrap()),
+            [
+                Warning::MisplacedOpt {
+                    section: Section::Answer,
+                    index: 0
+                },
+                Warning::MultipleOpt,
+            ]
+        );
+        query[11] = 1;
+        query.truncate(query.len() - 11);
+        assert_eq!(
+            lint(&Packet::parse(&query).unwrap()),
+            [Warning::MisplacedOpt {
+                section: Section::Answer,
+                index: 0
+            }]
+        );
+    }
+
+    #[test]
+    fn compressed_srv_target() {
+        let mut bld = Builder::new_query(1, true);
+        bld.add_question("_sip._tcp.example.com", false, QT::SRV, QC::IN);
+        let mut response = bld.build().unwrap();
+        response[2] |= 0x80;
+        response[7] = 1;
+        // target is "sip" followed by a pointer to "example.com"
+        response.extend_from_slice(b"\xc0\x0c\x00\x21\x00\x01\x00\x00\x00\x10\x00\x0c");
+        response.extend_from_slice(b"\x00\x00\x00\x00\x13\xc4\x03sip\xc0\x16");
+        let packet = Packet::parse(&response).unwrap();
+        match packet.answers()[0].data {
+            RData::SRV(Srv { ref target, .. }) => {
+                assert_eq!(target.to_string(), "sip.example.com")
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        let expected = [Warning::CompressedRdata {
+            section: Section::Answer,
+            index: 0,
+        }];
+        assert_eq!(lint(&packet), expected);
+        assert_eq!(lint_raw(&RawPacket::parse(&response).unwrap()), expected);
+    }
+
+    #[test]
+    fn compressed_rdata() {
+        let mut bld = Builder::new_query(1, true);
+        bld.add_question("example.com", false, QT::MX, QC::IN);
+        let mut response = bld.build().unwrap();
+        response[2] |= 0x80;
+        response[7] = 2;
+        // MX may be compressed, RP, which has no parser, may not
+        response.extend_from_slice(b"\xc0\x0c\x00\x0f\x00\x01\x00\x00\x00\x10\x00\x07");
+        response.extend_from_slice(b"\x00\x0a\x02mx\xc0\x0c");
+        response.extend_from_slice(b"\xc0\x0c\x00\x11\x00\x01\x00\x00\x00\x10\x00\x06");
+        response.extend_from_slice(b"\x02hm\xc0\x0c\x00");
+        let expected = [Warning::CompressedRdata {
+            section: Section::Answer,
+            index: 1,
+        }];
+        assert_eq!(lint(&Packet::parse(&response).unwrap()), expected);
+        assert_eq!(lint_raw(&RawPacket::parse(&response).unwrap()), expected);
+    }
+
+    #[test]
+    fn counts() {
+        let mut bld = Builder::new_query(1, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        let mut response = bld.build().unwrap();
+        response[2] |= 0x80;
+        // an answer not in the header counts
+        response.extend_from_slice(b"\xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x10\x00\x04");
+        response.extend_from_slice(b"\x7f\x00\x00\x01");
+        assert_eq!(lint(&Packet::parse(&response).unwrap()), []);
+        assert_eq!(
+            lint_raw(&RawPacket::parse(&response).unwrap()),
+            [Warning::CountMismatch {
+                section: Section::Additional,
+                header: 0,
+                actual: 1,
+            }]
+        );
+        response.extend_from_slice(b"\x00\x00");
+        assert_eq!(
+            lint_raw(&RawPacket::parse(&response).unwrap()),
+            [Warning::TrailingBytes(18)]
+        );
+    }
+
+    #[test]
+    fn truncated_over_stream() {
+        let mut bld = Builder::new_query(1, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        let mut response = bld.build().unwrap();
+        response[2] |= 0x82; // QR and TC
+        let header = *Packet::parse(&response).unwrap().header();
+        assert_eq!(lint_transport(&header, Transport::Udp), []);
+        assert_eq!(
+            lint_transport(&header, Transport::Stream),
+            [Warning::TruncatedNotUdp]
+        );
+        response[2] &= !0x80;
+        let query = *Packet::parse(&response).unwrap().header();
+        assert_eq!(lint_transport(&query, Transport::Stream), []);
+    }
+
+    #[test]
+    fn long_ttl() {
+        let mut bld = Builder::new_query(1, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        let mut response = bld.build().unwrap();
+        response[2] |= 0x80;
+        response[7] = 1;
+        response.extend_from_slice(b"\xc0\x0c\x00\x01\x00\x01\x80\x00\x00\x00\x00\x04");
+        response.extend_from_slice(b"\x7f\x00\x00\x01");
+        assert_eq!(
+            lint_raw(&RawPacket::parse(&response).unwrap()),
+            [Warning::TtlTooLong {
+                section: Section::Answer,
+                index: 0,
+                ttl: 0x8000_0000,
+            }]
+        );
+        assert_eq!(
+            Warning::TtlTooLong {
+                section: Section::Answer,
+                index: 0,
+                ttl: 0x8000_0000,
+            }
+            .to_string(),
+            "TTL 2147483648 of record Answer[0] is too long"
+        );
+    }
+}
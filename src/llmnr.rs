@@ -0,0 +1,17 @@
+//! Constants for Link-Local Multicast Name Resolution (RFC 4795)
+//!
+//! LLMNR messages use the DNS format, so they are parsed and built as
+//! usual. The header bits differ though: the conflict (C) bit is sent in
+//! place of AA and the tentative (T) bit in place of RD, see
+//! `Header::llmnr_conflict` and `Header::llmnr_tentative`. The bits in
+//! place of RA, AD and CD are reserved and must be zero.
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// UDP and TCP port of LLMNR
+pub const PORT: u16 = 5355;
+
+/// IPv4 multicast group queries are sent to
+pub const IPV4_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 252);
+
+/// IPv6 multicast group queries are sent to
+pub const IPV6_GROUP: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 1, 3);
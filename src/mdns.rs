@@ -0,0 +1,80 @@
+//! Helpers for multicast DNS responders (RFC 6762)
+//!
+//! mDNS messages use the DNS format, so they are parsed and built as
+//! usual. The top bit of the class is the unicast-response bit in
+//! questions (`Question::prefer_unicast`) and the cache-flush bit in
+//! records (`ResourceRecord::multicast_unique`).
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use crate::{Packet, ResourceRecord};
+
+/// UDP port of mDNS
+pub const PORT: u16 = 5353;
+
+/// IPv4 multicast group queries are sent to
+pub const IPV4_GROUP: Ipv4Addr = Ipv4Addr::new(224, 0, 0, 251);
+
+/// IPv6 multicast group queries are sent to
+pub const IPV6_GROUP: Ipv6Addr = Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb);
+
+/// Leaves out the answers the querier already knows (RFC 6762 section 7.1)
+///
+/// A querier lists the records it has cached in the answer section of
+/// its query. A responder must not send an answer if such a known answer
+/// has the same name, type, class and data, and a TTL of at least half
+/// the TTL of the answer. Returns the answers to send, in order.
+pub fn suppress_known_answers<'r, 'a>(
+    answers: &'r [ResourceRecord<'a>],
+    query: &Packet<'_>,
+) -> Vec<&'r ResourceRecord<'a>> {
+    answers
+        .iter()
+        .filter(|answer| !query.answers().iter().any(|known| is_known(known, answer)))
+        .collect()
+}
+
+fn is_known(known: &ResourceRecord<'_>, answer: &ResourceRecord<'_>) -> bool {
+    if known.data.typ() != answer.data.typ()
+        || known.cls != answer.cls
+        || u64::from(known.ttl.0) * 2 < u64::from(answer.ttl.0)
+        || !known.name.eq_ignore_ascii_case(&answer.name)
+    {
+        return false;
+    }
+    let mut known_data = Vec::new();
+    known.data.write_canonical_to(&mut known_data);
+    let mut answer_data = Vec::new();
+    answer.data.write_canonical_to(&mut answer_data);
+    known_data == answer_data
+}
+
+#[cfg(test)]
+mod test {
+    use super::suppress_known_answers;
+    use crate::{Packet, RData};
+
+    #[test]
+    fn known_answers() {
+        // PTR query for _ipp._tcp.local with two known answers: A.local
+        // with TTL 2250 and B.local with TTL 2249
+        let query = b"\x00\x00\x00\x00\x00\x01\x00\x02\x00\x00\x00\x00\
+            \x04_ipp\x04_tcp\x05local\x00\x00\x0c\x00\x01\
+            \xc0\x0c\x00\x0c\x00\x01\x00\x00\x08\xca\x00\x04\x01A\xc0\x0c\
+            \xc0\x0c\x00\x0c\x00\x01\x00\x00\x08\xc9\x00\x04\x01B\xc0\x0c";
+        let query = Packet::parse(query).unwrap();
+        // answers A, B and C with TTL 4500, A in another case
+        let response = b"\x00\x00\x84\x00\x00\x00\x00\x03\x00\x00\x00\x00\
+            \x04_ipp\x04_tcp\x05local\x00\x00\x0c\x00\x01\x00\x00\x11\x94\x00\x04\x01a\xc0\x0c\
+            \xc0\x0c\x00\x0c\x00\x01\x00\x00\x11\x94\x00\x04\x01B\xc0\x0c\
+            \xc0\x0c\x00\x0c\x00\x01\x00\x00\x11\x94\x00\x04\x01C\xc0\x0c";
+        let response = Packet::parse(response).unwrap();
+        let send = suppress_known_answers(response.answers(), &query)
+            .iter()
+            .map(|answer| match answer.data {
+                RData::PTR(ref ptr) => ptr.0.to_string(),
+                ref x => panic!("Wrong rdata {:?}", x),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(send, ["B._ipp._tcp.local", "C._ipp._tcp.local"]);
+    }
+}
@@ -0,0 +1,157 @@
+use quick_error::quick_error;
+
+quick_error! {
+    /// Error converting text to a type, class, opcode or response code
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum MnemonicError {
+        /// The text is neither a known mnemonic nor in the generic syntax
+        Unknown(text: String) {
+            display("unknown mnemonic {:?}", text)
+        }
+    }
+}
+
+// Parses the generic syntax of RFC 3597, e.g. `TYPE65280` or `CLASS32`
+//
+// The prefix is matched ignoring case, the number must be decimal.
+pub(crate) fn generic_code(text: &str, prefix: &str) -> Option<u16> {
+    if text.len() <= prefix.len() || !text.is_char_boundary(prefix.len()) {
+        return None;
+    }
+    let (head, digits) = text.split_at(prefix.len());
+    if !head.eq_ignore_ascii_case(prefix) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+    use std::convert::TryFrom;
+
+    use super::generic_code;
+    use crate::{Class, Opcode, QueryClass, QueryType, RData, ResponseCode, Type};
+
+    #[test]
+    fn generic() {
+        assert_eq!(generic_code("TYPE65280", "TYPE"), Some(65280));
+        assert_eq!(generic_code("type1", "TYPE"), Some(1));
+        assert_eq!(generic_code("TYPE65536", "TYPE"), None);
+        assert_eq!(generic_code("TYPE+1", "TYPE"), None);
+        assert_eq!(generic_code("TYPE", "TYPE"), None);
+        assert_eq!(generic_code("CLASS1", "TYPE"), None);
+    }
+
+    #[test]
+    fn types() {
+        assert_eq!(Type::A.to_string(), "A");
+        assert_eq!(Type::NSAPPTR.to_string(), "NSAP-PTR");
+        assert_eq!(Type::Unknown(65280).to_string(), "TYPE65280");
+        assert_eq!(Type::parse(64), Type::SVCB);
+        assert_eq!(Type::HTTPS.to_string(), "HTTPS");
+        let https = RData::Unknown(65, Cow::Borrowed(b"\x00\x01\x00"));
+        assert_eq!(https.typ().to_string(), "HTTPS");
+        assert_eq!("aaaa".parse::<Type>(), Ok(Type::AAAA));
+        assert_eq!("NSAP-PTR".parse::<Type>(), Ok(Type::NSAPPTR));
+        assert_eq!("TYPE1".parse::<Type>(), Ok(Type::A));
+        assert_eq!("TYPE65280".parse::<Type>(), Ok(Type::Unknown(65280)));
+        assert!("ANY".parse::<Type>().is_err());
+        assert_eq!(
+            "BOGUS".parse::<Type>().unwrap_err().to_string(),
+            "unknown mnemonic \"BOGUS\""
+        );
+    }
+
+    #[test]
+    fn query_types() {
+        assert_eq!(QueryType::ALL.to_string(), "ANY");
+        assert_eq!(QueryType::AXFR.to_string(), "AXFR");
+        assert_eq!("any".parse::<QueryType>(), Ok(QueryType::ALL));
+        assert_eq!("*".parse::<QueryType>(), Ok(QueryType::ALL));
+        assert_eq!("TYPE252".parse::<QueryType>(), Ok(QueryType::AXFR));
+        assert_eq!(
+            "TYPE65280".parse::<QueryType>(),
+            Ok(QueryType::Unknown(65280))
+        );
+    }
+
+    #[test]
+    fn type_conversions() {
+        assert_eq!(QueryType::from(Type::AAAA), QueryType::AAAA);
+        assert_eq!(QueryType::from(Type::HTTPS), QueryType::HTTPS);
+        assert_eq!(
+            QueryType::from(Type::Unknown(65280)),
+            QueryType::Unknown(65280)
+        );
+        assert_eq!(Type::try_from(QueryType::MX).unwrap(), Type::MX);
+        assert_eq!(Type::try_from(QueryType::IXFR).unwrap(), Type::IXFR);
+        assert!(Type::try_from(QueryType::ALL).is_err());
+        assert!(Type::try_from(QueryType::AXFR).is_err());
+
+        assert!(QueryType::AXFR.is_meta());
+        assert!(QueryType::OPT.is_meta());
+        assert!(QueryType::TSIG.is_meta());
+        assert!(!QueryType::A.is_meta());
+        assert!(!QueryType::Unknown(65280).is_meta());
+    }
+
+    #[test]
+    fn type_predicates() {
+        assert!(Type::OPT.is_meta());
+        assert!(Type::TKEY.is_meta());
+        assert!(!Type::SOA.is_meta());
+        assert!(Type::MD.is_obsolete());
+        assert!(Type::SPF.is_obsolete());
+        assert!(!Type::TXT.is_obsolete());
+        assert!(Type::RRSIG.is_dnssec());
+        assert!(Type::CDNSKEY.is_dnssec());
+        assert!(!Type::SIG.is_dnssec());
+        assert!(Type::MX.allows_compression_in_rdata());
+        assert!(!Type::SRV.allows_compression_in_rdata());
+        assert!(!Type::Unknown(65280).allows_compression_in_rdata());
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(Class::CH.to_string(), "CH");
+        assert_eq!(format!("{:<4}|", Class::IN), "IN  |");
+        assert_eq!("in".parse::<Class>(), Ok(Class::IN));
+        assert_eq!("CLASS4".parse::<Class>(), Ok(Class::HS));
+        assert!("ANY".parse::<Class>().is_err());
+        assert_eq!("CLASS0".parse::<Class>(), Ok(Class::Unknown(0)));
+        assert_eq!(Class::Unknown(32).to_string(), "CLASS32");
+        assert_eq!(Class::parse(1), Class::IN);
+
+        assert_eq!(QueryClass::Any.to_string(), "ANY");
+        assert_eq!("*".parse::<QueryClass>(), Ok(QueryClass::Any));
+        assert_eq!("CLASS255".parse::<QueryClass>(), Ok(QueryClass::Any));
+        assert_eq!("hs".parse::<QueryClass>(), Ok(QueryClass::HS));
+    }
+
+    #[test]
+    fn opcodes() {
+        assert_eq!(Opcode::StandardQuery.to_string(), "QUERY");
+        assert_eq!(Opcode::Reserved(4).to_string(), "NOTIFY");
+        assert_eq!(Opcode::Reserved(3).to_string(), "OPCODE3");
+        assert_eq!("query".parse::<Opcode>(), Ok(Opcode::StandardQuery));
+        assert_eq!("UPDATE".parse::<Opcode>(), Ok(Opcode::Reserved(5)));
+        assert_eq!("OPCODE2".parse::<Opcode>(), Ok(Opcode::ServerStatusRequest));
+        assert!("OPCODE16".parse::<Opcode>().is_err());
+    }
+
+    #[test]
+    fn response_codes() {
+        assert_eq!(ResponseCode::NoError.to_string(), "NOERROR");
+        assert_eq!(ResponseCode::NameError.to_string(), "NXDOMAIN");
+        assert_eq!(ResponseCode::Reserved(9).to_string(), "NOTAUTH");
+        assert_eq!(ResponseCode::Reserved(12).to_string(), "RCODE12");
+        assert_eq!(ResponseCode::Reserved(12).mnemonic(), None);
+        assert_eq!("servfail".parse(), Ok(ResponseCode::ServerFailure));
+        assert_eq!("YXDOMAIN".parse(), Ok(ResponseCode::Reserved(6)));
+        assert_eq!("RCODE3".parse(), Ok(ResponseCode::NameError));
+        assert!("RCODE16".parse::<ResponseCode>().is_err());
+        assert!("BADVERS".parse::<ResponseCode>().is_err());
+        assert_eq!(ResponseCode::from(200), ResponseCode::Reserved(200));
+    }
+}
@@ -1,23 +1,311 @@
-use std::convert::TryInto;
+use std::borrow::Cow;
+use std::cmp::Ordering;
 use std::fmt;
 use std::fmt::Write;
-use std::iter::Peekable;
 use std::slice::Iter;
-use std::str::from_utf8;
+use std::str::{from_utf8, FromStr};
 
 // Deprecated since rustc 1.23
 #[allow(unused_imports, deprecated)]
 use std::ascii::AsciiExt;
 
-use crate::Error;
+use crate::{Error, Limit, ParseOptions, Type};
+
+// Offsets in a packet known to start a valid name
+//
+// Only offsets reached through a compression pointer are added: the first
+// pointer of a name may point forward, so its own labels are not valid
+// pointer targets for every later name. Offsets are added while a name is
+// scanned, so the cache must be cleared once scanning a name failed, like
+// the packet being parsed is dropped.
+#[derive(Debug, Default)]
+pub(crate) struct NameCache {
+    bits: Vec<u64>,
+}
+
+impl NameCache {
+    pub(crate) fn clear(&mut self) {
+        // keeps the allocation, `insert` sizes the table again
+        self.bits.clear();
+    }
+    fn contains(&self, offset: usize) -> bool {
+        self.bits
+            .get(offset / 64)
+            .is_some_and(|word| word & (1 << (offset % 64)) != 0)
+    }
+    fn insert(&mut self, offset: usize, packet_len: usize) {
+        if self.bits.is_empty() {
+            // pointers have 14 bits, later offsets are never looked up
+            self.bits.resize(packet_len.min(0x4000).div_ceil(64), 0);
+        }
+        if let Some(word) = self.bits.get_mut(offset / 64) {
+            *word |= 1 << (offset % 64);
+        }
+    }
+}
+
+// Work left for parsing a packet, see `ParseOptions::max_labels`
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Budget {
+    labels: usize,
+    pointers: usize,
+    bytes: usize,
+}
+
+impl Default for Budget {
+    fn default() -> Budget {
+        Budget {
+            labels: usize::MAX,
+            pointers: usize::MAX,
+            bytes: usize::MAX,
+        }
+    }
+}
+
+fn spend(left: &mut usize, amount: usize, limit: Limit) -> Result<(), Error> {
+    *left = left
+        .checked_sub(amount)
+        .ok_or(Error::LimitExceeded(limit))?;
+    Ok(())
+}
+
+impl Budget {
+    pub(crate) fn new(options: &ParseOptions) -> Budget {
+        Budget {
+            labels: options.label_limit().unwrap_or(usize::MAX),
+            pointers: options.pointer_limit().unwrap_or(usize::MAX),
+            bytes: options.byte_limit().unwrap_or(usize::MAX),
+        }
+    }
+    fn label(&mut self, len: usize) -> Result<(), Error> {
+        spend(&mut self.labels, 1, Limit::Labels)?;
+        self.bytes(len + 1)
+    }
+    fn pointer(&mut self) -> Result<(), Error> {
+        spend(&mut self.pointers, 1, Limit::Pointers)?;
+        self.bytes(2)
+    }
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<(), Error> {
+        spend(&mut self.bytes, len, Limit::Bytes)
+    }
+}
+
+// Position of the names in record data, see `Type::rdata_names`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RdataNames {
+    // bytes before the character-strings, or the first name
+    pub(crate) offset: usize,
+    // character-strings preceding the first name, as in NAPTR records
+    pub(crate) strings: usize,
+    // names following each other
+    pub(crate) count: usize,
+    // whether `RData::parse` reads the names, data of the other types is
+    // kept as is in `RData::Unknown`
+    pub(crate) parsed: bool,
+}
+
+impl RdataNames {
+    // Returns the position of the first name in the record data from
+    // `start` to `end` of `message`, `None` if the data ends before it
+    pub(crate) fn start<M: Message + ?Sized>(
+        &self,
+        message: &M,
+        start: usize,
+        end: usize,
+    ) -> Option<usize> {
+        let mut pos = start + self.offset;
+        for _ in 0..self.strings {
+            if pos >= end {
+                return None;
+            }
+            pos += message.byte(pos) as usize + 1;
+        }
+        Some(pos).filter(|&pos| pos < end)
+    }
+}
+
+// Random access to the bytes of a message, which may not be contiguous
+pub(crate) trait Message {
+    fn len(&self) -> usize;
+    // Returns the byte at `pos`, which is below `len`
+    fn byte(&self, pos: usize) -> u8;
+}
+
+impl Message for [u8] {
+    fn len(&self) -> usize {
+        <[u8]>::len(self)
+    }
+    fn byte(&self, pos: usize) -> u8 {
+        self[pos]
+    }
+}
+
+// Scans the name at `start` of `data`, whose labels must end before
+// `end`, following compression pointers into `original`, and returns its
+// length in `data`
+//
+// This is the only name scanner, `Name::scan` and the parser of split
+// messages use it. `run` gets the runs of labels between pointers in
+// order, the terminating zero byte excluded, e.g. to check or copy them.
+// With a cache the runs stop at a pointer to a name scanned before.
+pub(crate) fn scan_name<M, F>(
+    data: &M,
+    start: usize,
+    end: usize,
+    original: &M,
+    mut cache: Option<&mut NameCache>,
+    budget: &mut Budget,
+    mut run: F,
+) -> Result<usize, Error>
+where
+    M: Message + ?Sized,
+    F: FnMut(&M, usize, usize) -> Result<(), Error>,
+{
+    let mut src = data;
+    let mut pos = start;
+    let mut limit = end;
+    // whether `src` is `original`, reached through a pointer
+    let mut followed = false;
+    let mut return_pos = None;
+    // start of the labels of `src` not passed to `run` yet
+    let mut unchecked = pos;
+    if limit <= pos {
+        return Err(Error::UnexpectedEOF);
+    }
+    // By setting the largest_pos to be the original len, a side effect
+    // is that the pos variable can move forwards in the buffer once.
+    let mut largest_pos = original.len();
+    let mut byte = src.byte(pos);
+    while byte != 0 {
+        if followed {
+            if let Some(cache) = cache.as_deref_mut() {
+                cache.insert(pos, original.len());
+            }
+        }
+        if byte & 0b1100_0000 == 0b1100_0000 {
+            run(src, unchecked, pos)?;
+            unchecked = pos;
+            if limit < pos + 2 {
+                return Err(Error::UnexpectedEOF);
+            }
+            let off =
+                (u16::from_be_bytes([byte, src.byte(pos + 1)]) & !0b1100_0000_0000_0000) as usize;
+            budget.pointer()?;
+            if off >= original.len() {
+                return Err(Error::UnexpectedEOF);
+            }
+            // Set value for return_pos which is the pos in `data` that
+            // should be used to return after validating the offsetted
+            // labels.
+            if return_pos.is_none() {
+                return_pos = Some(pos);
+            }
+
+            // Check then set largest_pos to ensure we never go backwards
+            // in the buffer.
+            if off >= largest_pos {
+                return Err(Error::BadPointer { target: off });
+            }
+            largest_pos = off;
+            let known = cache.as_ref().is_some_and(|cache| cache.contains(off));
+            trace_event!(trace, target = off, known, "followed compression pointer");
+            if known {
+                break;
+            }
+            followed = true;
+            src = original;
+            pos = off;
+            limit = original.len();
+            unchecked = off;
+        } else if byte & 0b1100_0000 == 0 {
+            budget.label(byte as usize)?;
+            let label_end = pos + byte as usize + 1;
+            if limit < label_end {
+                run(src, unchecked, pos)?;
+                return Err(Error::UnexpectedEOF);
+            }
+            pos = label_end;
+            if limit <= pos {
+                run(src, unchecked, pos)?;
+                return Err(Error::UnexpectedEOF);
+            }
+        } else {
+            run(src, unchecked, pos)?;
+            return Err(Error::UnknownLabelFormat { byte });
+        }
+        byte = src.byte(pos);
+    }
+    run(src, unchecked, pos)?;
+
+    // a pointer takes two bytes, otherwise the name ends with a zero byte
+    Ok(return_pos.map_or(pos + 1, |pos| pos + 2) - start)
+}
+
+// Charges the names of record data of type `typ` which `RData::parse`
+// reads, while walking them, so that the limits stop a name before it is
+// decoded. Malformed names are left for `RData::parse` to report.
+pub(crate) fn scan_rdata_names(
+    typ: Type,
+    rdata: &[u8],
+    original: &[u8],
+    budget: &mut Budget,
+) -> Result<(), Error> {
+    let names = match typ.rdata_names() {
+        Some(names) if names.parsed => names,
+        _ => return Ok(()),
+    };
+    let mut pos = match names.start(rdata, 0, rdata.len()) {
+        Some(pos) => pos,
+        None => return Ok(()),
+    };
+    for _ in 0..names.count {
+        let len = scan_name(
+            rdata,
+            pos,
+            rdata.len(),
+            original,
+            None,
+            budget,
+            |_, _, _| Ok(()),
+        );
+        match len {
+            Ok(len) => pos += len,
+            Err(error @ Error::LimitExceeded(_)) => return Err(error),
+            Err(_) => break,
+        }
+    }
+    Ok(())
+}
+
+// Checks that complete labels are UTF-8, in a single pass when they are
+// ASCII, which is the case for almost all names
+//
+// Length octets are below 64, so they don't make the labels non-ASCII.
+pub(crate) fn check_labels(labels: &[u8]) -> Result<(), Error> {
+    if labels.is_ascii() {
+        return Ok(());
+    }
+    let mut pos = 0;
+    while pos < labels.len() {
+        let end = pos + labels[pos] as usize + 1;
+        if from_utf8(&labels[pos + 1..end]).is_err() {
+            return Err(Error::LabelIsNotUtf8);
+        }
+        pos = end;
+    }
+    Ok(())
+}
 
 /// The DNS name as stored in the original packet
 ///
 /// This contains just a reference to a slice that contains the data.
 /// You may turn this into a string using `.to_string()`
-#[derive(Clone, Copy, PartialEq)]
+///
+/// A name detached from the packet with `into_owned` keeps its labels
+/// uncompressed in a buffer of its own.
+#[derive(Clone)]
 pub struct Name<'a> {
-    labels: &'a [u8],
+    labels: Cow<'a, [u8]>,
     /// This is the original buffer size. The compressed names in original
     /// are calculated in this buffer
     original: &'a [u8],
@@ -30,116 +318,220 @@ impl<'a> Name<'a> {
     /// The `original` is the data starting a the start of a packet, so
     /// that offsets in compressed name starts from the `original`.
     pub fn scan(data: &'a [u8], original: &'a [u8]) -> Result<Name<'a>, Error> {
-        let mut parse_data = data;
-        let mut return_pos = None;
-        let mut pos = 0;
-        if parse_data.len() <= pos {
-            return Err(Error::UnexpectedEOF);
+        Name::scan_cached(data, original, None, &mut Budget::default())
+    }
+    // `scan` remembering the names reached through compression pointers,
+    // so that names pointing to them later are not walked again, and
+    // charging the work to `budget`
+    pub(crate) fn scan_cached(
+        data: &'a [u8],
+        original: &'a [u8],
+        cache: Option<&mut NameCache>,
+        budget: &mut Budget,
+    ) -> Result<Name<'a>, Error> {
+        let len = scan_name(
+            data,
+            0,
+            data.len(),
+            original,
+            cache,
+            budget,
+            |src, from, to| check_labels(&src[from..to]),
+        )?;
+        Ok(Name {
+            labels: Cow::Borrowed(&data[..len]),
+            original,
+        })
+    }
+    /// Converts the name into one that does not borrow the packet
+    ///
+    /// Compression pointers are resolved, so the result holds the
+    /// uncompressed wire form of the name.
+    pub fn into_owned(self) -> Name<'static> {
+        let labels = match self.labels {
+            Cow::Owned(labels) => labels,
+            Cow::Borrowed(_) => {
+                let mut buf = Vec::with_capacity(self.labels.len());
+                self.write_to(&mut buf);
+                buf
+            }
+        };
+        Name {
+            labels: Cow::Owned(labels),
+            original: &[],
         }
-        // By setting the largest_pos to be the original len, a side effect
-        // is that the pos variable can move forwards in the buffer once.
-        let mut largest_pos = original.len();
-        let mut byte = parse_data[pos];
-        while byte != 0 {
-            if parse_data.len() <= pos {
-                return Err(Error::UnexpectedEOF);
+    }
+    /// Creates a name from its labels, e.g. `["www", "example", "com"]`
+    ///
+    /// The root name has no labels. Fails if a label is empty, is longer
+    /// than 63 bytes, contains a dot, which `Display` couldn't tell from a
+    /// label separator, or is not UTF-8, or if the name is longer than 255
+    /// bytes. Such names may be put into the record data of the `RData`
+    /// variants to build records from scratch.
+    pub fn from_labels<I, L>(labels: I) -> Result<Name<'static>, Error>
+    where
+        I: IntoIterator<Item = L>,
+        L: AsRef<[u8]>,
+    {
+        let mut wire = Vec::new();
+        for label in labels {
+            let label = label.as_ref();
+            if label.is_empty() || label.len() > 63 || label.contains(&b'.') {
+                return Err(Error::InvalidName);
             }
-            if byte & 0b1100_0000 == 0b1100_0000 {
-                if parse_data.len() < pos + 2 {
-                    return Err(Error::UnexpectedEOF);
-                }
-                let off = (u16::from_be_bytes(parse_data[pos..pos + 2].try_into().unwrap())
-                    & !0b1100_0000_0000_0000) as usize;
-                if off >= original.len() {
-                    return Err(Error::UnexpectedEOF);
-                }
-                // Set value for return_pos which is the pos in the original
-                // data buffer that should be used to return after validating
-                // the offsetted labels.
-                if return_pos.is_none() {
-                    return_pos = Some(pos);
-                }
-
-                // Check then set largest_pos to ensure we never go backwards
-                // in the buffer.
-                if off >= largest_pos {
-                    return Err(Error::BadPointer);
-                }
-                largest_pos = off;
-                pos = 0;
-                parse_data = &original[off..];
-            } else if byte & 0b1100_0000 == 0 {
-                let end = pos + byte as usize + 1;
-                if parse_data.len() < end {
-                    return Err(Error::UnexpectedEOF);
-                }
-                if from_utf8(&parse_data[pos + 1..end]).is_err() {
-                    return Err(Error::LabelIsNotUtf8);
-                }
-                pos = end;
-                if parse_data.len() <= pos {
-                    return Err(Error::UnexpectedEOF);
-                }
-            } else {
-                return Err(Error::UnknownLabelFormat);
+            if from_utf8(label).is_err() {
+                return Err(Error::LabelIsNotUtf8);
             }
-            byte = parse_data[pos];
+            wire.push(label.len() as u8);
+            wire.extend_from_slice(label);
         }
-
-        let return_pos = return_pos.unwrap_or(pos - 1);
-        Ok(Name {
-            labels: &data[..return_pos + 2],
+        wire.push(0);
+        if wire.len() > 255 {
+            return Err(Error::InvalidName);
+        }
+        Ok(Name::from_uncompressed(wire))
+    }
+    // A name scanned before, whose encoding is `labels` of `original`
+    pub(crate) fn from_scanned(labels: &'a [u8], original: &'a [u8]) -> Name<'a> {
+        Name {
+            labels: Cow::Borrowed(labels),
             original,
-        })
+        }
+    }
+    // The encoding of the name, as found in the packet for parsed names
+    pub(crate) fn encoded(&self) -> &[u8] {
+        &self.labels
+    }
+    // Wraps labels already validated by the parser
+    pub(crate) fn from_uncompressed(labels: Vec<u8>) -> Name<'static> {
+        Name {
+            labels: Cow::Owned(labels),
+            original: &[],
+        }
+    }
+    /// Writes the name in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        for label in self.labels() {
+            buf.push(label.len() as u8);
+            buf.extend_from_slice(label);
+        }
+        buf.push(0);
+    }
+    /// Writes the name in the canonical form (RFC 4034 section 6.2)
+    ///
+    /// This is the uncompressed wire format with ASCII letters lowercased.
+    pub fn write_canonical_to(&self, buf: &mut Vec<u8>) {
+        for label in self.labels() {
+            buf.push(label.len() as u8);
+            buf.extend(label.iter().map(u8::to_ascii_lowercase));
+        }
+        buf.push(0);
+    }
+    /// Compares names in the canonical order (RFC 4034 section 6.1)
+    ///
+    /// Names are sorted by their rightmost labels first, labels are
+    /// compared as lowercased byte strings.
+    pub fn canonical_cmp(&self, other: &Name<'_>) -> Ordering {
+        let left = self.labels().collect::<Vec<_>>();
+        let right = other.labels().collect::<Vec<_>>();
+        for (a, b) in left.iter().rev().zip(right.iter().rev()) {
+            let a = a.iter().map(u8::to_ascii_lowercase);
+            let b = b.iter().map(u8::to_ascii_lowercase);
+            match a.cmp(b) {
+                Ordering::Equal => continue,
+                other => return other,
+            }
+        }
+        left.len().cmp(&right.len())
+    }
+    /// Returns true if the name ends with a compression pointer
+    ///
+    /// Names detached from the packet are never compressed.
+    pub fn is_compressed(&self) -> bool {
+        let len = self.labels.len();
+        len >= 2 && self.labels[len - 2] & 0b1100_0000 == 0b1100_0000
     }
     /// Number of bytes serialized name occupies
     pub fn byte_len(&self) -> usize {
         self.labels.len()
     }
     /// Returns an iterator over the bytes that make up this domain name
-    pub fn bytes(&self) -> NameBytes<'a> {
-        // Top 2 bits of a length octet indicate that it and the next byte are a pointer to a label
-        if self.labels.len() >= 2 && self.labels[0] >> 6 == 0b11 {
-            let pointer = (u16::from_be_bytes([self.labels[0], self.labels[1]])
-                & !0b1100_0000_0000_0000) as usize;
-            let len = *self
-                .original
-                .get(pointer)
-                .unwrap_or_else(|| panic!("{:?}", self.original)) as usize;
-            NameBytes {
-                original: self.original,
-                remaining_labels: self
-                    .original
-                    .get(pointer + 1 + len..)
-                    .unwrap_or_else(|| panic!("{:?}", self.original)),
-                current_label: self
-                    .original
-                    .get(pointer + 1..pointer + 1 + len)
-                    .unwrap_or_else(|| panic!("{:?}", self.original))
-                    .iter()
-                    .peekable(),
-            }
-        } else if !self.labels.is_empty() {
-            let len = self.labels[0] as usize;
-            NameBytes {
-                original: self.original,
-                remaining_labels: self
-                    .labels
-                    .get(1 + len..)
-                    .unwrap_or_else(|| panic!("{:?}", self.original)),
-                current_label: self
-                    .labels
-                    .get(1..1 + len)
-                    .unwrap_or_else(|| panic!("{:?}", self.original))
-                    .iter()
-                    .peekable(),
+    pub fn bytes(&self) -> NameBytes<'_> {
+        NameBytes {
+            labels: self.labels(),
+            current_label: [].iter(),
+            started: false,
+        }
+    }
+    /// Returns an iterator over the labels of this domain name
+    ///
+    /// Compression pointers are followed, so labels are yielded in order
+    /// from the leftmost one. The terminating root label is not included.
+    pub fn labels(&self) -> Labels<'_> {
+        Labels {
+            data: &self.labels,
+            original: self.original,
+        }
+    }
+    /// Compares two names ignoring ASCII case, as required by RFC 4343
+    pub fn eq_ignore_ascii_case(&self, other: &Name<'_>) -> bool {
+        let mut left = self.labels();
+        let mut right = other.labels();
+        loop {
+            match (left.next(), right.next()) {
+                (Some(a), Some(b)) if a.eq_ignore_ascii_case(b) => continue,
+                (None, None) => return true,
+                _ => return false,
             }
-        } else {
-            // self.labels is empty
-            NameBytes {
-                original: self.original,
-                remaining_labels: self.labels,
-                current_label: self.labels.iter().peekable(),
+        }
+    }
+    /// Returns true if the name equals `zone` or is below it
+    ///
+    /// Labels are compared ignoring ASCII case. Every name is below the
+    /// root.
+    pub fn is_subdomain_of(&self, zone: &Name<'_>) -> bool {
+        let labels = self.labels().collect::<Vec<_>>();
+        let zone = zone.labels().collect::<Vec<_>>();
+        zone.len() <= labels.len()
+            && labels
+                .iter()
+                .rev()
+                .zip(zone.iter().rev())
+                .all(|(a, b)| a.eq_ignore_ascii_case(b))
+    }
+    /// Compares two names label by label, preserving case
+    ///
+    /// This is what `==` does, spelled out for symmetry with
+    /// `eq_ignore_ascii_case`.
+    pub fn eq_case_sensitive(&self, other: &Name<'_>) -> bool {
+        self.labels().eq(other.labels())
+    }
+}
+
+/// Iterator over the labels of a `Name`, see `Name::labels`
+#[derive(Clone, Debug)]
+pub struct Labels<'a> {
+    data: &'a [u8],
+    original: &'a [u8],
+}
+
+impl<'a> Iterator for Labels<'a> {
+    type Item = &'a [u8];
+
+    fn next(&mut self) -> Option<&'a [u8]> {
+        loop {
+            let byte = *self.data.first()?;
+            if byte & 0b1100_0000 == 0b1100_0000 {
+                let off = (u16::from_be_bytes([byte, *self.data.get(1)?]) & !0b1100_0000_0000_0000)
+                    as usize;
+                self.data = self.original.get(off..)?;
+            } else if byte == 0 {
+                return None;
+            } else {
+                let end = byte as usize + 1;
+                let label = self.data.get(1..end)?;
+                self.data = &self.data[end..];
+                return Some(label);
             }
         }
     }
@@ -147,9 +539,9 @@ impl<'a> Name<'a> {
 
 #[derive(Clone, Debug)]
 pub struct NameBytes<'a> {
-    original: &'a [u8],
-    remaining_labels: &'a [u8],
-    current_label: Peekable<Iter<'a, u8>>,
+    labels: Labels<'a>,
+    current_label: Iter<'a, u8>,
+    started: bool,
 }
 
 impl<'a> Iterator for NameBytes<'a> {
@@ -160,56 +552,54 @@ impl<'a> Iterator for NameBytes<'a> {
         if let Some(x) = self.current_label.next() {
             return Some(x);
         }
-        // If we're done with the current label and the next octet is 0 or no more bytes are left,
-        // we are done.
-        if self.remaining_labels.get(0) == Some(&0) {
-            return None;
-        }
-        // Else replace self with a new iterator
-        let name = Name {
-            labels: self.remaining_labels,
-            original: self.original,
-        };
-        *self = name.bytes();
-        if self.current_label.peek().is_none() {
-            None
-        } else {
+        // Else continue with the next label, labels are never empty
+        let label = self.labels.next()?;
+        self.current_label = label.iter();
+        if self.started {
             Some(&b'.')
+        } else {
+            self.started = true;
+            self.current_label.next()
         }
     }
 }
 
+/// Parses a name with labels separated by dots, e.g. `www.example.com`
+///
+/// A trailing dot is allowed, `""` and `"."` are the root name. Escapes
+/// are not supported, labels can't contain dots.
+impl FromStr for Name<'static> {
+    type Err = Error;
+    fn from_str(text: &str) -> Result<Name<'static>, Error> {
+        let text = text.strip_suffix('.').unwrap_or(text);
+        if text.is_empty() {
+            return Ok(Name::from_uncompressed(vec![0]));
+        }
+        Name::from_labels(text.split('.'))
+    }
+}
+
 impl<'a> fmt::Display for Name<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let data = self.labels;
-        let original = self.original;
-        let mut pos = 0;
-        loop {
-            let byte = data[pos];
-            if byte == 0 {
-                return Ok(());
-            } else if byte & 0b1100_0000 == 0b1100_0000 {
-                let off = (u16::from_be_bytes(data[pos..pos + 2].try_into().unwrap())
-                    & !0b1100_0000_0000_0000) as usize;
-                if pos != 0 {
-                    fmt.write_char('.')?;
-                }
-                return fmt::Display::fmt(&Name::scan(&original[off..], original).unwrap(), fmt);
-            } else if byte & 0b1100_0000 == 0 {
-                if pos != 0 {
-                    fmt.write_char('.')?;
-                }
-                let end = pos + byte as usize + 1;
-                fmt.write_str(from_utf8(&data[pos + 1..end]).unwrap())?;
-                pos = end;
-                continue;
-            } else {
-                unreachable!();
+        for (i, label) in self.labels().enumerate() {
+            if i != 0 {
+                fmt.write_char('.')?;
             }
+            // labels were checked when parsing, this never allocates
+            fmt.write_str(&String::from_utf8_lossy(label))?;
         }
+        Ok(())
     }
 }
 
+impl<'a, 'b> PartialEq<Name<'b>> for Name<'a> {
+    fn eq(&self, other: &Name<'b>) -> bool {
+        self.eq_case_sensitive(other)
+    }
+}
+
+impl<'a> Eq for Name<'a> {}
+
 impl<'a> fmt::Debug for Name<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt.debug_tuple("Name").field(&format!("{}", self)).finish()
@@ -218,8 +608,10 @@ impl<'a> fmt::Debug for Name<'a> {
 
 #[cfg(test)]
 mod test {
+    use super::{Budget, NameCache};
+    use crate::rdata::Mx;
     use crate::Error;
-    use crate::Name;
+    use crate::{Name, RData, Type};
 
     #[test]
     fn parse_badpointer_same_offset() {
@@ -228,7 +620,7 @@ mod test {
         let same_offset = vec![192, 2, 192, 2];
         let is_match = matches!(
             Name::scan(&same_offset, &same_offset),
-            Err(Error::BadPointer)
+            Err(Error::BadPointer { target: 2 })
         );
 
         assert!(is_match);
@@ -241,7 +633,7 @@ mod test {
         let forwards_offset = vec![192, 2, 192, 4, 192, 2];
         let is_match = matches!(
             Name::scan(&forwards_offset, &forwards_offset),
-            Err(Error::BadPointer)
+            Err(Error::BadPointer { target: 4 })
         );
 
         assert!(is_match);
@@ -253,16 +645,151 @@ mod test {
         let buf = b"\x02xx\x00\x02yy\xc0\x00\x02zz\xc0\x04";
 
         assert_eq!(Name::scan(&buf[..], buf).unwrap().to_string(), "xx");
-        assert_eq!(Name::scan(&buf[..], buf).unwrap().labels, b"\x02xx\x00");
+        assert_eq!(
+            &Name::scan(&buf[..], buf).unwrap().labels[..],
+            b"\x02xx\x00"
+        );
         assert_eq!(Name::scan(&buf[4..], buf).unwrap().to_string(), "yy.xx");
         assert_eq!(
-            Name::scan(&buf[4..], buf).unwrap().labels,
+            &Name::scan(&buf[4..], buf).unwrap().labels[..],
             b"\x02yy\xc0\x00"
         );
         assert_eq!(Name::scan(&buf[9..], buf).unwrap().to_string(), "zz.yy.xx");
         assert_eq!(
-            Name::scan(&buf[9..], buf).unwrap().labels,
+            &Name::scan(&buf[9..], buf).unwrap().labels[..],
             b"\x02zz\xc0\x04"
         );
     }
+
+    #[test]
+    fn from_text() {
+        let name = "Mail.Example.com.".parse::<Name<'_>>().unwrap();
+        assert_eq!(name.to_string(), "Mail.Example.com");
+        assert_eq!(name, Name::from_labels(["Mail", "Example", "com"]).unwrap());
+        assert_eq!(".".parse::<Name<'_>>().unwrap().byte_len(), 1);
+        assert!(matches!(
+            "a..b".parse::<Name<'_>>(),
+            Err(Error::InvalidName)
+        ));
+        let long = "x".repeat(64);
+        assert!(matches!(long.parse::<Name<'_>>(), Err(Error::InvalidName)));
+        let long = vec!["x".repeat(63); 4];
+        assert!(matches!(Name::from_labels(&long), Err(Error::InvalidName)));
+        assert!(Name::from_labels(&long[..3]).is_ok());
+        assert!(matches!(
+            Name::from_labels(["a.b", "example"]),
+            Err(Error::InvalidName)
+        ));
+        assert!(matches!(
+            Name::from_labels([&b"\xff"[..]]),
+            Err(Error::LabelIsNotUtf8)
+        ));
+
+        // owned names make records built from scratch
+        let mx = RData::MX(Mx {
+            preference: 10,
+            exchange: name,
+        });
+        let mut wire = Vec::new();
+        mx.write_to(&mut wire);
+        assert_eq!(RData::parse(Type::MX, &wire, &wire).unwrap(), mx);
+    }
+
+    #[test]
+    fn label_not_utf8() {
+        let buf = b"\x02xx\x02\xff\xfe\x00";
+        assert!(matches!(Name::scan(buf, buf), Err(Error::LabelIsNotUtf8)));
+        // the labels before a truncated one are checked first
+        assert!(matches!(
+            Name::scan(&buf[..6], buf),
+            Err(Error::LabelIsNotUtf8)
+        ));
+        let buf = b"\x02\xc3\xa9\x02xx\x00";
+        assert_eq!(Name::scan(buf, buf).unwrap().to_string(), "\u{e9}.xx");
+    }
+
+    #[test]
+    fn cached_targets() {
+        let buf = b"\x02xx\x00\x02yy\xc0\x00\x02zz\xc0\x04\x02ww\xc0\x04";
+        let mut cache = NameCache::default();
+        for &(start, name) in &[(9, "zz.yy.xx"), (14, "ww.yy.xx"), (4, "yy.xx")] {
+            let scanned =
+                Name::scan_cached(&buf[start..], buf, Some(&mut cache), &mut Budget::default())
+                    .unwrap();
+            assert_eq!(scanned.to_string(), name);
+            assert_eq!(scanned.byte_len(), 5);
+        }
+        assert!(cache.contains(0));
+        assert!(cache.contains(4));
+        assert!(!cache.contains(9));
+
+        // labels before the first pointer may be followed by one pointing
+        // forward, they are not valid targets for every name
+        let buf = b"\x02xx\xc0\x05\x00\xc0\x00";
+        let mut cache = NameCache::default();
+        let scanned =
+            Name::scan_cached(buf, buf, Some(&mut cache), &mut Budget::default()).unwrap();
+        assert_eq!(scanned.labels().collect::<Vec<_>>(), [b"xx"]);
+        assert!(!cache.contains(0));
+        assert!(matches!(
+            Name::scan_cached(&buf[6..], buf, Some(&mut cache), &mut Budget::default()),
+            Err(Error::BadPointer { .. })
+        ));
+    }
+
+    #[test]
+    fn rdata_names_table() {
+        // the table agrees with the names the parsers find
+        for &typ in &[
+            Type::CNAME,
+            Type::NS,
+            Type::PTR,
+            Type::DNAME,
+            Type::NSEC,
+            Type::MB,
+            Type::MD,
+            Type::MF,
+            Type::MG,
+            Type::MR,
+            Type::NXT,
+            Type::SOA,
+            Type::MINFO,
+            Type::RP,
+            Type::MX,
+            Type::AFSDB,
+            Type::RT,
+            Type::KX,
+            Type::PX,
+            Type::SRV,
+            Type::RRSIG,
+            Type::SIG,
+        ] {
+            let names = typ.rdata_names().unwrap();
+            let mut data = vec![0; names.offset];
+            data.extend_from_slice(&b"\x01a\x00\x01b\x00"[..names.count * 3]);
+            if typ != Type::NSEC {
+                // the fixed fields following the names of SOA
+                data.extend_from_slice(&[0; 20]);
+            }
+            let rdata = RData::parse(typ, &data, &data).unwrap();
+            let found = rdata
+                .names()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>();
+            if names.parsed {
+                assert_eq!(found, ["a", "b"][..names.count], "{}", typ);
+            } else {
+                assert_eq!(
+                    rdata,
+                    RData::Unknown(typ.code(), data[..].into()),
+                    "{}",
+                    typ
+                );
+            }
+        }
+        let naptr = Type::NAPTR.rdata_names().unwrap();
+        let data = b"\x00\x01\x00\x01\x01S\x00\x00\x01a\x00";
+        assert_eq!(naptr.start(&data[..], 0, data.len()), Some(8));
+        assert_eq!(naptr.start(&data[..], 0, 8), None);
+    }
 }
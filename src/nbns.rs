@@ -0,0 +1,295 @@
+//! NetBIOS Name Service support (RFC 1002)
+//!
+//! NBNS messages share the DNS message format, parse them with
+//! `RawPacket::parse` and decode the parts which differ with this module:
+//!
+//! * names are first-level encoded into 32 letters, see `NetbiosName`;
+//! * record types `NB` and `NBSTAT` reuse the codes of NIMLOC and SRV,
+//!   see `parse_rdata`;
+//! * the broadcast (B) bit takes the place of the checking disabled bit,
+//!   see `Header::nbns_broadcast`.
+//!
+//! This module is enabled by the `nbns` feature.
+use std::convert::TryInto;
+use std::fmt;
+use std::net::Ipv4Addr;
+
+use crate::{Error, Name, Opcode, RawRecord, Type};
+
+/// UDP port of the name service
+pub const PORT: u16 = 137;
+
+/// Type code of the NetBIOS general name service record
+pub const NB: u16 = 0x0020;
+
+/// Type code of the NetBIOS node status record
+pub const NBSTAT: u16 = 0x0021;
+
+/// Opcode of name registration requests
+pub const REGISTRATION: Opcode = Opcode::Reserved(5);
+/// Opcode of name release requests
+pub const RELEASE: Opcode = Opcode::Reserved(6);
+/// Opcode of wait for acknowledgement responses
+pub const WACK: Opcode = Opcode::Reserved(7);
+/// Opcode of name refresh requests
+pub const REFRESH: Opcode = Opcode::Reserved(8);
+
+/// A decoded NetBIOS name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetbiosName {
+    /// The name without the trailing padding, up to 15 bytes
+    pub name: String,
+    /// The last byte of the name, which denotes the service
+    pub suffix: u8,
+    /// The NetBIOS scope, i.e. the rest of the domain name, may be empty
+    pub scope: String,
+}
+
+impl NetbiosName {
+    /// Decodes a first-level encoded name
+    pub fn decode(name: &Name<'_>) -> Result<NetbiosName, Error> {
+        let mut labels = name.labels();
+        let encoded = labels.next().ok_or(Error::InvalidNetbiosName)?;
+        if encoded.len() != 32 {
+            return Err(Error::InvalidNetbiosName);
+        }
+        let mut bytes = [0u8; 16];
+        for (byte, pair) in bytes.iter_mut().zip(encoded.chunks(2)) {
+            let high = decode_half(pair[0])?;
+            let low = decode_half(pair[1])?;
+            *byte = high << 4 | low;
+        }
+        let scope = labels
+            .map(|label| String::from_utf8_lossy(label))
+            .collect::<Vec<_>>()
+            .join(".");
+        Ok(NetbiosName {
+            name: String::from_utf8_lossy(&bytes[..15])
+                .trim_end_matches(' ')
+                .to_string(),
+            suffix: bytes[15],
+            scope,
+        })
+    }
+    /// Encodes the name into a domain name
+    ///
+    /// The result may be passed to `Builder::add_question`.
+    ///
+    /// # Panics
+    ///
+    /// When the name is longer than 15 bytes
+    pub fn encode(&self) -> String {
+        assert!(self.name.len() <= 15, "NetBIOS name is too long");
+        let mut bytes = [b' '; 16];
+        bytes[..self.name.len()].copy_from_slice(self.name.as_bytes());
+        bytes[15] = self.suffix;
+        let mut result = String::with_capacity(33 + self.scope.len());
+        for byte in bytes.iter() {
+            result.push((b'A' + (byte >> 4)) as char);
+            result.push((b'A' + (byte & 0xF)) as char);
+        }
+        if !self.scope.is_empty() {
+            result.push('.');
+            result.push_str(&self.scope);
+        }
+        result
+    }
+}
+
+fn decode_half(c: u8) -> Result<u8, Error> {
+    match c {
+        b'A'..=b'P' => Ok(c - b'A'),
+        b'a'..=b'p' => Ok(c - b'a'),
+        _ => Err(Error::InvalidNetbiosName),
+    }
+}
+
+impl fmt::Display for NetbiosName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}<{:02x}>", self.name, self.suffix)?;
+        if !self.scope.is_empty() {
+            write!(f, ".{}", self.scope)?;
+        }
+        Ok(())
+    }
+}
+
+/// An address entry of an `NB` record
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NbEntry {
+    /// The name is a group name
+    pub group: bool,
+    /// Owner node type: 0 is B-node, 1 is P-node, 2 is M-node
+    pub node_type: u8,
+    /// The address of the node
+    pub addr: Ipv4Addr,
+}
+
+/// A name entry of an `NBSTAT` record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeName {
+    /// The name without the trailing padding
+    pub name: String,
+    /// The last byte of the name
+    pub suffix: u8,
+    /// Name flags (group, node type, state bits)
+    pub flags: u16,
+}
+
+/// Data of an `NBSTAT` record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeStatus {
+    /// Names registered by the node
+    pub names: Vec<NodeName>,
+    /// Unique unit ID, usually the MAC address
+    pub unit_id: [u8; 6],
+}
+
+/// Record data of a NetBIOS name service record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RData<'a> {
+    /// General name service record
+    NB(Vec<NbEntry>),
+    /// Node status record
+    NBSTAT(NodeStatus),
+    /// Other types (A, NS, NULL), left unparsed
+    Other(&'a [u8]),
+}
+
+/// Parses the data of a record of a NetBIOS name service packet
+pub fn parse_rdata<'a>(record: &RawRecord<'a>) -> Result<RData<'a>, Error> {
+    let data = record.data;
+    let length_error = |expected| Error::InvalidRdataLength {
+        typ: Type::parse(record.typ),
+        expected,
+        got: data.len(),
+    };
+    match record.typ {
+        NB => {
+            if !data.len().is_multiple_of(6) {
+                return Err(length_error(data.len().div_ceil(6) * 6));
+            }
+            let entries = data
+                .chunks(6)
+                .map(|entry| {
+                    let flags = u16::from_be_bytes([entry[0], entry[1]]);
+                    NbEntry {
+                        group: flags & 0x8000 != 0,
+                        node_type: ((flags >> 13) & 0b11) as u8,
+                        addr: Ipv4Addr::new(entry[2], entry[3], entry[4], entry[5]),
+                    }
+                })
+                .collect();
+            Ok(RData::NB(entries))
+        }
+        NBSTAT => {
+            let count = *data.first().ok_or_else(|| length_error(1))? as usize;
+            let names_end = 1 + count * 18;
+            // the statistics start with the unit ID, the rest is ignored
+            if data.len() < names_end + 6 {
+                return Err(length_error(names_end + 6));
+            }
+            let names = data[1..names_end]
+                .chunks(18)
+                .map(|entry| NodeName {
+                    name: String::from_utf8_lossy(&entry[..15])
+                        .trim_end_matches(' ')
+                        .to_string(),
+                    suffix: entry[15],
+                    flags: u16::from_be_bytes([entry[16], entry[17]]),
+                })
+                .collect();
+            Ok(RData::NBSTAT(NodeStatus {
+                names,
+                unit_id: data[names_end..names_end + 6].try_into().unwrap(),
+            }))
+        }
+        _ => Ok(RData::Other(data)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::Ipv4Addr;
+
+    use super::{parse_rdata, NbEntry, NetbiosName, RData, NB, NBSTAT};
+    use crate::{Builder, Error, Name, QueryClass as QC, QueryType as QT, RawPacket};
+
+    #[test]
+    fn encode_rfc1001_example() {
+        let name = NetbiosName {
+            name: "FRED".into(),
+            suffix: b' ',
+            scope: "NETBIOS.COM".into(),
+        };
+        let encoded = name.encode();
+        assert_eq!(encoded, "EGFCEFEECACACACACACACACACACACACA.NETBIOS.COM");
+        let mut wire = Vec::new();
+        for part in encoded.split('.') {
+            wire.push(part.len() as u8);
+            wire.extend_from_slice(part.as_bytes());
+        }
+        wire.push(0);
+        let decoded = NetbiosName::decode(&Name::scan(&wire, &wire).unwrap()).unwrap();
+        assert_eq!(decoded, name);
+        assert_eq!(decoded.to_string(), "FRED<20>.NETBIOS.COM");
+    }
+
+    #[test]
+    fn invalid_name() {
+        let wire = b"\x07example\x03com\x00";
+        assert!(matches!(
+            NetbiosName::decode(&Name::scan(wire, wire).unwrap()),
+            Err(Error::InvalidNetbiosName)
+        ));
+    }
+
+    #[test]
+    fn parse_responses() {
+        let name = NetbiosName {
+            name: "WORKSTATION".into(),
+            suffix: 0,
+            scope: String::new(),
+        };
+        let mut bld = Builder::new_query(0x1234, false);
+        // NB is the same code as NIMLOC
+        bld.add_question(&name.encode(), false, QT::NIMLOC, QC::IN);
+        let mut response = bld.build().unwrap();
+        response[2] = 0x85; // response, AA, RD
+        response[7] = 2; // two answers
+        response.extend_from_slice(b"\xc0\x0c\x00\x20\x00\x01\x00\x04\x93\xe0\x00\x06");
+        response.extend_from_slice(b"\x60\x00\xc0\xa8\x01\x0a");
+        response.extend_from_slice(b"\xc0\x0c\x00\x21\x00\x01\x00\x00\x00\x00\x00\x24\x01");
+        response.extend_from_slice(b"WORKSTATION    \x00\x04\x00");
+        response.extend_from_slice(b"\x00\x11\x22\x33\x44\x55extra stats");
+
+        let packet = RawPacket::parse(&response).unwrap();
+        let decoded = NetbiosName::decode(&packet.questions()[0].qname).unwrap();
+        assert_eq!(decoded, name);
+        assert_eq!(packet.answers()[0].typ, NB);
+        assert_eq!(
+            parse_rdata(&packet.answers()[0]).unwrap(),
+            RData::NB(vec![NbEntry {
+                group: false,
+                node_type: 3,
+                addr: Ipv4Addr::new(192, 168, 1, 10),
+            }])
+        );
+        assert_eq!(packet.answers()[1].typ, NBSTAT);
+        match parse_rdata(&packet.answers()[1]).unwrap() {
+            RData::NBSTAT(status) => {
+                assert_eq!(status.names.len(), 1);
+                assert_eq!(status.names[0].name, "WORKSTATION");
+                assert_eq!(status.names[0].flags, 0x0400);
+                assert_eq!(status.unit_id, [0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+            }
+            x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn broadcast_bit() {
+        let query = b"\x12\x34\x01\x10\x00\x00\x00\x00\x00\x00\x00\x00";
+        assert!(RawPacket::parse(query).unwrap().header().nbns_broadcast());
+    }
+}
@@ -0,0 +1,68 @@
+/// Options for `Packet::parse_with_options` and the other `*_with_options`
+/// parsers
+///
+/// The defaults are those of `Packet::parse`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    strict_classes: bool,
+    max_labels: Option<usize>,
+    max_pointers: Option<usize>,
+    max_bytes: Option<usize>,
+}
+
+impl ParseOptions {
+    /// Creates the default options
+    pub fn new() -> ParseOptions {
+        ParseOptions::default()
+    }
+    /// Fail on class codes without a variant
+    ///
+    /// By default they are kept as `Class::Unknown` and
+    /// `QueryClass::Unknown`. When set, parsing fails with
+    /// `Error::InvalidClass` or `Error::InvalidQueryClass` instead.
+    pub fn strict_classes(mut self, strict: bool) -> ParseOptions {
+        self.strict_classes = strict;
+        self
+    }
+    /// Returns whether unknown class codes are rejected
+    pub fn is_strict_classes(&self) -> bool {
+        self.strict_classes
+    }
+    /// Fail once more than `limit` labels were decoded
+    ///
+    /// Labels are counted every time they are read, so a label reached
+    /// through pointers from several names counts several times. Parsing
+    /// fails with `Error::LimitExceeded`. Unlimited by default.
+    pub fn max_labels(mut self, limit: usize) -> ParseOptions {
+        self.max_labels = Some(limit);
+        self
+    }
+    /// Fail once more than `limit` compression pointers were followed
+    ///
+    /// Unlimited by default.
+    pub fn max_pointers(mut self, limit: usize) -> ParseOptions {
+        self.max_pointers = Some(limit);
+        self
+    }
+    /// Fail once more than `limit` bytes of names and record data were
+    /// read
+    ///
+    /// Bytes read again when following pointers count again. Unlimited
+    /// by default.
+    pub fn max_bytes(mut self, limit: usize) -> ParseOptions {
+        self.max_bytes = Some(limit);
+        self
+    }
+    /// Returns the limit set with `max_labels`
+    pub fn label_limit(&self) -> Option<usize> {
+        self.max_labels
+    }
+    /// Returns the limit set with `max_pointers`
+    pub fn pointer_limit(&self) -> Option<usize> {
+        self.max_pointers
+    }
+    /// Returns the limit set with `max_bytes`
+    pub fn byte_limit(&self) -> Option<usize> {
+        self.max_bytes
+    }
+}
@@ -1,121 +1,385 @@
 use std::convert::TryInto;
 
+use crate::arena::{PacketStorage, ParseArena};
+use crate::name::{scan_rdata_names, Budget, NameCache};
 use crate::rdata::opt::Record as Opt;
-use crate::{Class, RData, ResourceRecord, Type};
-use crate::{Error, Header, Name, Packet, QueryClass, QueryType, Question};
+use crate::{Class, RData, RawPacket, RawQuestion, RawRecord, ResourceRecord, Type};
+use crate::{
+    Error, Header, Name, Packet, ParseOptions, QueryClass, QueryType, Question, Section, Ttl,
+};
 
 const OPT_RR_START: [u8; 3] = [0, 0, 41];
 
+// Smallest encodings, a root name followed by the fixed fields
+pub(crate) const MIN_QUESTION_SIZE: usize = 1 + 4;
+pub(crate) const MIN_RECORD_SIZE: usize = 1 + 10;
+
+// Number of items to allocate room for up front. The counts of the header
+// can't be trusted, so no more is reserved than a packet of `len` bytes
+// can hold.
+pub(crate) fn capacity(count: u16, len: usize, min_size: usize) -> usize {
+    (count as usize).min(len.saturating_sub(Header::size()) / min_size)
+}
+
 impl<'a> Packet<'a> {
     /// Parse a full DNS Packet and return a structure that has all the
     /// data borrowed from the passed buffer.
     pub fn parse(data: &[u8]) -> Result<Packet<'_>, Error> {
+        Packet::parse_with_options(data, &ParseOptions::default())
+    }
+    /// Parse a full DNS Packet like `parse` does, with custom options
+    pub fn parse_with_options<'d>(
+        data: &'d [u8],
+        options: &ParseOptions,
+    ) -> Result<Packet<'d>, Error> {
+        parse_packet(data, options, &mut ParseArena::new())
+    }
+    /// Parse a full DNS Packet into lists owned by the caller
+    ///
+    /// The lists of `storage` are cleared first, their capacity is
+    /// kept. Returns the header, the questions and records are read from
+    /// `storage`. Parsing the packets of a capture into the same storage
+    /// doesn't allocate once it has grown large enough, see
+    /// `PacketStorage::recycle`.
+    pub fn parse_into<'d>(
+        data: &'d [u8],
+        storage: &mut PacketStorage<'d>,
+    ) -> Result<Header, Error> {
+        parse_into_storage(data, &ParseOptions::default(), storage)
+    }
+    /// Parse a packet like `parse_into` does, with custom options
+    pub fn parse_into_with_options<'d>(
+        data: &'d [u8],
+        options: &ParseOptions,
+        storage: &mut PacketStorage<'d>,
+    ) -> Result<Header, Error> {
+        parse_into_storage(data, options, storage)
+    }
+}
+
+pub(crate) fn parse_packet<'d>(
+    data: &'d [u8],
+    options: &ParseOptions,
+    arena: &mut ParseArena,
+) -> Result<Packet<'d>, Error> {
+    let header = Header::parse(data)?;
+    let len = data.len();
+    let mut questions = arena.questions(capacity(header.questions, len, MIN_QUESTION_SIZE));
+    let mut answers = arena.records(capacity(header.answers, len, MIN_RECORD_SIZE));
+    let mut nameservers = arena.records(capacity(header.nameservers, len, MIN_RECORD_SIZE));
+    let mut additional = arena.records(capacity(header.additional, len, MIN_RECORD_SIZE));
+    let opt = parse_sections(
+        data,
+        &header,
+        options,
+        arena.names(),
+        &mut questions,
+        [&mut answers, &mut nameservers, &mut additional],
+    )?;
+    Ok(Packet {
+        header,
+        questions,
+        answers,
+        nameservers,
+        additional,
+        opt,
+    })
+}
+
+pub(crate) fn parse_into_storage<'d>(
+    data: &'d [u8],
+    options: &ParseOptions,
+    storage: &mut PacketStorage<'d>,
+) -> Result<Header, Error> {
+    storage.clear();
+    let header = Header::parse(data)?;
+    let len = data.len();
+    storage
+        .questions
+        .reserve(capacity(header.questions, len, MIN_QUESTION_SIZE));
+    storage
+        .answers
+        .reserve(capacity(header.answers, len, MIN_RECORD_SIZE));
+    storage
+        .nameservers
+        .reserve(capacity(header.nameservers, len, MIN_RECORD_SIZE));
+    storage
+        .additional
+        .reserve(capacity(header.additional, len, MIN_RECORD_SIZE));
+    storage.opt = parse_sections(
+        data,
+        &header,
+        options,
+        &mut storage.names,
+        &mut storage.questions,
+        [
+            &mut storage.answers,
+            &mut storage.nameservers,
+            &mut storage.additional,
+        ],
+    )?;
+    Ok(header)
+}
+
+// Parses everything after the header into any kind of list, returns the
+// OPT record which is kept aside
+fn parse_sections<'d, Q, R>(
+    data: &'d [u8],
+    header: &Header,
+    options: &ParseOptions,
+    names: &mut NameCache,
+    questions: &mut Q,
+    [answers, nameservers, additional]: [&mut R; 3],
+) -> Result<Option<Opt<'d>>, Error>
+where
+    Q: Extend<Question<'d>>,
+    R: Extend<ResourceRecord<'d>>,
+{
+    trace_span!(debug_span, "parse", len = data.len(), id = header.id);
+    trace_event!(
+        debug,
+        questions = header.questions,
+        answers = header.answers,
+        nameservers = header.nameservers,
+        additional = header.additional,
+        "parsed header"
+    );
+    let mut budget = Budget::new(options);
+    let mut offset = Header::size();
+    for index in 0..header.questions as usize {
+        let start = offset;
+        let question = parse_question(data, &mut offset, names, &mut budget, options)
+            .map_err(|error| Error::in_question(index, start, error))?;
+        questions.extend(Some(question));
+    }
+    let sections = [
+        (Section::Answer, header.answers, answers),
+        (Section::Authority, header.nameservers, nameservers),
+    ];
+    for (section, count, records) in sections {
+        trace_span!(debug_span, "section", %section, count);
+        for index in 0..count as usize {
+            let start = offset;
+            let record = parse_record(data, &mut offset, names, &mut budget, options)
+                .map_err(|error| Error::in_record(section, index, start, error))?;
+            trace_event!(
+                trace,
+                index,
+                offset = start,
+                typ = %record.data.typ(),
+                "parsed record"
+            );
+            records.extend(Some(record));
+        }
+    }
+    let mut opt = None;
+    trace_span!(
+        debug_span,
+        "section",
+        section = %Section::Additional,
+        count = header.additional
+    );
+    for index in 0..header.additional as usize {
+        let start = offset;
+        let in_record = |error| Error::in_record(Section::Additional, index, start, error);
+        if offset + 3 <= data.len() && data[offset..offset + 3] == OPT_RR_START {
+            if opt.is_none() {
+                opt = Some(parse_opt_record(data, &mut offset).map_err(in_record)?);
+            } else {
+                return Err(in_record(Error::AdditionalOPT));
+            }
+        } else {
+            let record =
+                parse_record(data, &mut offset, names, &mut budget, options).map_err(in_record)?;
+            trace_event!(
+                trace,
+                index,
+                offset = start,
+                typ = %record.data.typ(),
+                "parsed record"
+            );
+            additional.extend(Some(record));
+        }
+    }
+    Ok(opt)
+}
+
+impl<'a> RawPacket<'a> {
+    /// Parse a DNS packet leaving record data unparsed
+    ///
+    /// This only checks the structure of the packet, so it doesn't fail on
+    /// unknown types and classes or on malformed record data. It's useful
+    /// for forwarders and caches which don't look into the records.
+    pub fn parse(data: &[u8]) -> Result<RawPacket<'_>, Error> {
+        RawPacket::parse_with_options(data, &ParseOptions::default())
+    }
+    /// Parse a DNS packet like `parse` does, with custom options
+    ///
+    /// Only the limits of `options` apply, classes are not interpreted.
+    pub fn parse_with_options<'d>(
+        data: &'d [u8],
+        options: &ParseOptions,
+    ) -> Result<RawPacket<'d>, Error> {
         let header = Header::parse(data)?;
         let mut offset = Header::size();
-        let mut questions = Vec::with_capacity(header.questions as usize);
+        let mut names = NameCache::default();
+        let mut budget = Budget::new(options);
+        let len = data.len();
+        let mut questions = Vec::with_capacity(capacity(header.questions, len, MIN_QUESTION_SIZE));
         for _ in 0..header.questions {
-            let qname = Name::scan(&data[offset..], data)?;
-            offset += qname.byte_len();
-            if offset + 4 > data.len() {
-                return Err(Error::UnexpectedEOF);
-            }
-            let buf: [u8; 2] = data[offset..offset + 2].try_into().unwrap();
-            let qtype = QueryType::parse(u16::from_be_bytes(buf))?;
-            offset += 2;
-
-            let buf: [u8; 2] = data[offset..offset + 2].try_into().unwrap();
-            let (prefer_unicast, qclass) = parse_qclass_code(u16::from_be_bytes(buf))?;
-            offset += 2;
-
-            questions.push(Question {
-                qname,
-                qtype,
-                prefer_unicast,
-                qclass,
-            });
+            let question = parse_raw_question(data, &mut offset, Some(&mut names), &mut budget)?;
+            questions.push(question);
         }
-        let mut answers = Vec::with_capacity(header.answers as usize);
-        for _ in 0..header.answers {
-            answers.push(parse_record(data, &mut offset)?);
-        }
-        let mut nameservers = Vec::with_capacity(header.nameservers as usize);
-        for _ in 0..header.nameservers {
-            nameservers.push(parse_record(data, &mut offset)?);
-        }
-        let mut additional = Vec::with_capacity(header.additional as usize);
-        let mut opt = None;
-        for _ in 0..header.additional {
-            if offset + 3 <= data.len() && data[offset..offset + 3] == OPT_RR_START {
-                if opt.is_none() {
-                    opt = Some(parse_opt_record(data, &mut offset)?);
-                } else {
-                    return Err(Error::AdditionalOPT);
-                }
-            } else {
-                additional.push(parse_record(data, &mut offset)?);
+        let mut records = |count| {
+            let mut records = Vec::with_capacity(capacity(count, len, MIN_RECORD_SIZE));
+            for _ in 0..count {
+                let record = parse_raw_record(data, &mut offset, Some(&mut names), &mut budget)?;
+                records.push(record);
             }
-        }
-        Ok(Packet {
+            Ok::<_, Error>(records)
+        };
+        let answers = records(header.answers)?;
+        let nameservers = records(header.nameservers)?;
+        let additional = records(header.additional)?;
+        Ok(RawPacket {
             header,
             questions,
             answers,
             nameservers,
             additional,
-            opt,
+            original: data,
+            end: offset,
         })
     }
 }
 
-fn parse_qclass_code(value: u16) -> Result<(bool, QueryClass), Error> {
+pub(crate) fn parse_qclass_code(
+    value: u16,
+    options: &ParseOptions,
+) -> Result<(bool, QueryClass), Error> {
     let prefer_unicast = value & 0x8000 == 0x8000;
     let qclass_code = value & 0x7FFF;
 
-    let qclass = QueryClass::parse(qclass_code)?;
+    let qclass = QueryClass::parse(qclass_code);
+    if let QueryClass::Unknown(code) = qclass {
+        if options.is_strict_classes() {
+            return Err(Error::InvalidQueryClass(code));
+        }
+    }
     Ok((prefer_unicast, qclass))
 }
 
-fn parse_class_code(value: u16) -> Result<(bool, Class), Error> {
+pub(crate) fn parse_class_code(value: u16, options: &ParseOptions) -> Result<(bool, Class), Error> {
     let is_unique = value & 0x8000 == 0x8000;
     let class_code = value & 0x7FFF;
 
-    let cls = Class::parse(class_code)?;
+    let cls = Class::parse(class_code);
+    if let Class::Unknown(code) = cls {
+        if options.is_strict_classes() {
+            return Err(Error::InvalidClass(code));
+        }
+    }
     Ok((is_unique, cls))
 }
 
+fn parse_question<'a>(
+    data: &'a [u8],
+    offset: &mut usize,
+    names: &mut NameCache,
+    budget: &mut Budget,
+    options: &ParseOptions,
+) -> Result<Question<'a>, Error> {
+    let raw = parse_raw_question(data, offset, Some(names), budget)?;
+    let (prefer_unicast, qclass) = parse_qclass_code(raw.qclass, options)?;
+    Ok(Question {
+        qname: raw.qname,
+        qtype: QueryType::parse(raw.qtype),
+        prefer_unicast,
+        qclass,
+    })
+}
+
 // Generic function to parse answer, nameservers, and additional records.
-fn parse_record<'a>(data: &'a [u8], offset: &mut usize) -> Result<ResourceRecord<'a>, Error> {
-    let name = Name::scan(&data[*offset..], data)?;
+fn parse_record<'a>(
+    data: &'a [u8],
+    offset: &mut usize,
+    names: &mut NameCache,
+    budget: &mut Budget,
+    options: &ParseOptions,
+) -> Result<ResourceRecord<'a>, Error> {
+    let raw = parse_raw_record(data, offset, Some(names), budget)?;
+    let typ = Type::parse(raw.typ);
+    let (multicast_unique, cls) = parse_class_code(raw.cls, options)?;
+    let ttl = Ttl::new(raw.ttl);
+    scan_rdata_names(typ, raw.data, data, budget)?;
+    let data = RData::parse(typ, raw.data, data)?;
+    Ok(ResourceRecord {
+        name: raw.name,
+        multicast_unique,
+        cls,
+        ttl,
+        data,
+    })
+}
+
+// Splits a question into its fields without interpreting them.
+//
+// Names are scanned without memoizing pointer targets if there's no
+// cache, which doesn't allocate.
+pub(crate) fn parse_raw_question<'a>(
+    data: &'a [u8],
+    offset: &mut usize,
+    names: Option<&mut NameCache>,
+    budget: &mut Budget,
+) -> Result<RawQuestion<'a>, Error> {
+    let qname = Name::scan_cached(&data[*offset..], data, names, budget)?;
+    *offset += qname.byte_len();
+    if *offset + 4 > data.len() {
+        return Err(Error::UnexpectedEOF);
+    }
+    let qtype = u16::from_be_bytes(data[*offset..*offset + 2].try_into().unwrap());
+    let qclass = u16::from_be_bytes(data[*offset + 2..*offset + 4].try_into().unwrap());
+    *offset += 4;
+    Ok(RawQuestion {
+        qname,
+        qtype,
+        qclass,
+    })
+}
+
+// Splits a record into its fields without interpreting them.
+pub(crate) fn parse_raw_record<'a>(
+    data: &'a [u8],
+    offset: &mut usize,
+    names: Option<&mut NameCache>,
+    budget: &mut Budget,
+) -> Result<RawRecord<'a>, Error> {
+    let name = Name::scan_cached(&data[*offset..], data, names, budget)?;
     *offset += name.byte_len();
     if *offset + 10 > data.len() {
         return Err(Error::UnexpectedEOF);
     }
-    let typ = Type::parse(u16::from_be_bytes(
-        data[*offset..*offset + 2].try_into().unwrap(),
-    ))?;
+    let typ = u16::from_be_bytes(data[*offset..*offset + 2].try_into().unwrap());
     *offset += 2;
-
-    let class_code = u16::from_be_bytes(data[*offset..*offset + 2].try_into().unwrap());
-    let (multicast_unique, cls) = parse_class_code(class_code)?;
+    let cls = u16::from_be_bytes(data[*offset..*offset + 2].try_into().unwrap());
     *offset += 2;
-
-    let mut ttl = u32::from_be_bytes(data[*offset..*offset + 4].try_into().unwrap());
-    if ttl > i32::MAX as u32 {
-        ttl = 0;
-    }
+    let ttl = u32::from_be_bytes(data[*offset..*offset + 4].try_into().unwrap());
     *offset += 4;
     let rdlen = u16::from_be_bytes(data[*offset..*offset + 2].try_into().unwrap()) as usize;
     *offset += 2;
     if *offset + rdlen > data.len() {
         return Err(Error::UnexpectedEOF);
     }
-    let data = RData::parse(typ, &data[*offset..*offset + rdlen], data)?;
+    budget.bytes(rdlen)?;
+    let rdata = &data[*offset..*offset + rdlen];
     *offset += rdlen;
-    Ok(ResourceRecord {
+    Ok(RawRecord {
         name,
-        multicast_unique,
+        typ,
         cls,
         ttl,
-        data,
+        data: rdata,
+        original: data,
     })
 }
 
@@ -127,9 +391,9 @@ fn parse_opt_record<'a>(data: &'a [u8], offset: &mut usize) -> Result<Opt<'a>, E
     *offset += 1;
     let typ = Type::parse(u16::from_be_bytes(
         data[*offset..*offset + 2].try_into().unwrap(),
-    ))?;
+    ));
     if typ != Type::OPT {
-        return Err(Error::InvalidType(typ as u16));
+        return Err(Error::InvalidType(typ.code()));
     }
     *offset += 2;
     let udp = u16::from_be_bytes(data[*offset..*offset + 2].try_into().unwrap());
@@ -166,7 +430,8 @@ mod test {
     use crate::QueryType as QT;
     use crate::RData;
     use crate::ResponseCode::NoError;
-    use crate::{Header, Packet};
+    use crate::{Header, Packet, ParseOptions, RawPacket, Type};
+    use std::borrow::Cow;
     use std::net::Ipv4Addr;
 
     #[test]
@@ -175,7 +440,7 @@ mod test {
                       \x07example\x03com\x00\x00\x01\x00\x01";
         let packet = Packet::parse(query).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 1573,
                 query: true,
@@ -186,6 +451,7 @@ mod test {
                 recursion_available: false,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 0,
@@ -193,11 +459,11 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "example.com");
-        assert_eq!(packet.answers.len(), 0);
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(&packet.questions()[0].qname.to_string()[..], "example.com");
+        assert_eq!(packet.answers().len(), 0);
     }
 
     #[test]
@@ -208,7 +474,7 @@ mod test {
                          \x00\x04]\xb8\xd8\"";
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 1573,
                 query: false,
@@ -219,6 +485,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 1,
@@ -226,16 +493,16 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "example.com");
-        assert_eq!(packet.answers.len(), 1);
-        assert_eq!(&packet.answers[0].name.to_string()[..], "example.com");
-        assert_eq!(packet.answers[0].multicast_unique, false);
-        assert_eq!(packet.answers[0].cls, C::IN);
-        assert_eq!(packet.answers[0].ttl, 1272);
-        match packet.answers[0].data {
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(&packet.questions()[0].qname.to_string()[..], "example.com");
+        assert_eq!(packet.answers().len(), 1);
+        assert_eq!(&packet.answers()[0].name.to_string()[..], "example.com");
+        assert!(!packet.answers()[0].multicast_unique);
+        assert_eq!(packet.answers()[0].cls, C::IN);
+        assert_eq!(packet.answers()[0].ttl, 1272);
+        match packet.answers()[0].data {
             RData::A(addr) => {
                 assert_eq!(addr.0, Ipv4Addr::new(93, 184, 216, 34));
             }
@@ -251,9 +518,9 @@ mod test {
                          \x00\x04]\xb8\xd8\"";
         let packet = Packet::parse(response).unwrap();
 
-        assert_eq!(packet.answers.len(), 1);
-        assert_eq!(packet.answers[0].multicast_unique, true);
-        assert_eq!(packet.answers[0].cls, C::IN);
+        assert_eq!(packet.answers().len(), 1);
+        assert!(packet.answers()[0].multicast_unique);
+        assert_eq!(packet.answers()[0].cls, C::IN);
     }
 
     #[test]
@@ -271,7 +538,7 @@ mod test {
                           \x00\x04\xc0\x05\x06\x1e";
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 19184,
                 query: false,
@@ -282,6 +549,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 1,
@@ -289,38 +557,41 @@ mod test {
                 additional: 1,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "www.skype.com");
-        assert_eq!(packet.answers.len(), 1);
-        assert_eq!(&packet.answers[0].name.to_string()[..], "www.skype.com");
-        assert_eq!(packet.answers[0].cls, C::IN);
-        assert_eq!(packet.answers[0].ttl, 3600);
-        match packet.answers[0].data {
-            RData::CNAME(cname) => {
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(
+            &packet.questions()[0].qname.to_string()[..],
+            "www.skype.com"
+        );
+        assert_eq!(packet.answers().len(), 1);
+        assert_eq!(&packet.answers()[0].name.to_string()[..], "www.skype.com");
+        assert_eq!(packet.answers()[0].cls, C::IN);
+        assert_eq!(packet.answers()[0].ttl, 3600);
+        match packet.answers()[0].data {
+            RData::CNAME(ref cname) => {
                 assert_eq!(&cname.0.to_string()[..], "livecms.trafficmanager.net");
             }
             ref x => panic!("Wrong rdata {:?}", x),
         }
-        assert_eq!(packet.nameservers.len(), 1);
-        assert_eq!(&packet.nameservers[0].name.to_string()[..], "net");
-        assert_eq!(packet.nameservers[0].cls, C::IN);
-        assert_eq!(packet.nameservers[0].ttl, 120275);
-        match packet.nameservers[0].data {
-            RData::NS(ns) => {
+        assert_eq!(packet.nameservers().len(), 1);
+        assert_eq!(&packet.nameservers()[0].name.to_string()[..], "net");
+        assert_eq!(packet.nameservers()[0].cls, C::IN);
+        assert_eq!(packet.nameservers()[0].ttl, 120275);
+        match packet.nameservers()[0].data {
+            RData::NS(ref ns) => {
                 assert_eq!(&ns.0.to_string()[..], "g.gtld-servers.net");
             }
             ref x => panic!("Wrong rdata {:?}", x),
         }
-        assert_eq!(packet.additional.len(), 1);
+        assert_eq!(packet.additional().len(), 1);
         assert_eq!(
-            &packet.additional[0].name.to_string()[..],
+            &packet.additional()[0].name.to_string()[..],
             "a.gtld-servers.net"
         );
-        assert_eq!(packet.additional[0].cls, C::IN);
-        assert_eq!(packet.additional[0].ttl, 41756);
-        match packet.additional[0].data {
+        assert_eq!(packet.additional()[0].cls, C::IN);
+        assert_eq!(packet.additional()[0].ttl, 41756);
+        match packet.additional()[0].data {
             RData::A(addr) => {
                 assert_eq!(addr.0, Ipv4Addr::new(192, 5, 6, 30));
             }
@@ -342,7 +613,7 @@ mod test {
             \x00\x04@\xe9\xa4\x8a";
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 40425,
                 query: false,
@@ -353,6 +624,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 6,
@@ -360,12 +632,12 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "google.com");
-        assert_eq!(packet.answers.len(), 6);
-        let ips = vec![
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(&packet.questions()[0].qname.to_string()[..], "google.com");
+        assert_eq!(packet.answers().len(), 6);
+        let ips = [
             Ipv4Addr::new(64, 233, 164, 100),
             Ipv4Addr::new(64, 233, 164, 139),
             Ipv4Addr::new(64, 233, 164, 113),
@@ -373,13 +645,13 @@ mod test {
             Ipv4Addr::new(64, 233, 164, 101),
             Ipv4Addr::new(64, 233, 164, 138),
         ];
-        for i in 0..6 {
-            assert_eq!(&packet.answers[i].name.to_string()[..], "google.com");
-            assert_eq!(packet.answers[i].cls, C::IN);
-            assert_eq!(packet.answers[i].ttl, 239);
-            match packet.answers[i].data {
+        for (i, ip) in ips.iter().enumerate() {
+            assert_eq!(&packet.answers()[i].name.to_string()[..], "google.com");
+            assert_eq!(packet.answers()[i].cls, C::IN);
+            assert_eq!(packet.answers()[i].ttl, 239);
+            match packet.answers()[i].data {
                 RData::A(addr) => {
-                    assert_eq!(addr.0, ips[i]);
+                    assert_eq!(addr.0, *ip);
                 }
                 ref x => panic!("Wrong rdata {:?}", x),
             }
@@ -392,7 +664,7 @@ mod test {
             \x0c_xmpp-server\x04_tcp\x05gmail\x03com\x00\x00!\x00\x01";
         let packet = Packet::parse(query).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 23513,
                 query: true,
@@ -403,6 +675,7 @@ mod test {
                 recursion_available: false,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 0,
@@ -410,15 +683,15 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::SRV);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(packet.questions[0].prefer_unicast, false);
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::SRV);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert!(!packet.questions()[0].prefer_unicast);
         assert_eq!(
-            &packet.questions[0].qname.to_string()[..],
+            &packet.questions()[0].qname.to_string()[..],
             "_xmpp-server._tcp.gmail.com"
         );
-        assert_eq!(packet.answers.len(), 0);
+        assert_eq!(packet.answers().len(), 0);
     }
 
     #[test]
@@ -427,10 +700,10 @@ mod test {
                       \x07example\x03com\x00\x00\x01\x80\x01";
         let packet = Packet::parse(query).unwrap();
 
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(packet.questions[0].prefer_unicast, true);
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert!(packet.questions()[0].prefer_unicast);
     }
 
     #[test]
@@ -440,7 +713,7 @@ mod test {
             \x01\x00\x00\x29\x10\x00\x00\x00\x00\x00\x00\x00";
         let packet = Packet::parse(query).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 38350,
                 query: true,
@@ -451,6 +724,7 @@ mod test {
                 recursion_available: false,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 0,
@@ -458,12 +732,12 @@ mod test {
                 additional: 1,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "google.com");
-        assert_eq!(packet.answers.len(), 0);
-        match packet.opt {
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(&packet.questions()[0].qname.to_string()[..], "google.com");
+        assert_eq!(packet.answers().len(), 0);
+        match packet.opt() {
             Some(opt) => {
                 assert_eq!(opt.udp, 4096);
                 assert_eq!(opt.extrcode, 0);
@@ -473,4 +747,198 @@ mod test {
             None => panic!("Missing OPT RR"),
         }
     }
+
+    #[test]
+    fn parse_raw_packet() {
+        // an A response with an unknown type (65280) in the additional
+        // section, which has both a huge TTL and the cache-flush bit set
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x01\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04]\xb8\xd8\"\
+                         \xc0\x0c\xff\x00\x80\x01\xff\xff\xff\xff\
+                         \x00\x03abc";
+        let parsed = Packet::parse(response).unwrap();
+        let unknown = &parsed.additional()[0];
+        assert_eq!(unknown.data.typ(), Type::Unknown(0xff00));
+        assert_eq!(unknown.data, RData::Unknown(0xff00, Cow::Borrowed(b"abc")));
+        assert!(unknown.multicast_unique);
+        assert_eq!(unknown.ttl, 0);
+        let packet = RawPacket::parse(response).unwrap();
+        assert_eq!(packet.header().additional, 1);
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qname.to_string(), "example.com");
+        assert_eq!(packet.questions()[0].qtype, 1);
+        assert_eq!(packet.questions()[0].qclass, 1);
+        assert_eq!(packet.answers().len(), 1);
+        assert_eq!(packet.answers()[0].name.to_string(), "example.com");
+        assert_eq!(packet.answers()[0].typ, 1);
+        assert_eq!(packet.answers()[0].ttl, 1272);
+        assert_eq!(packet.answers()[0].data, b"]\xb8\xd8\"");
+        match packet.answers()[0].parse_data().unwrap() {
+            RData::A(addr) => assert_eq!(addr.0, Ipv4Addr::new(93, 184, 216, 34)),
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        assert!(packet.nameservers().is_empty());
+        let unknown = &packet.additional()[0];
+        assert_eq!(unknown.name.to_string(), "example.com");
+        assert_eq!(unknown.typ, 0xff00);
+        assert_eq!(unknown.cls, 0x8001);
+        assert_eq!(unknown.ttl, 0xffff_ffff);
+        assert_eq!(unknown.data, b"abc");
+        assert_eq!(unknown.parse_data().unwrap(), parsed.additional()[0].data);
+    }
+
+    #[test]
+    fn parse_raw_truncated() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04]\xb8";
+        assert!(matches!(
+            RawPacket::parse(response),
+            Err(crate::Error::UnexpectedEOF)
+        ));
+    }
+
+    #[test]
+    fn parse_unknown_classes() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x0a\x00\x00\x04\xf8\
+                         \x00\x04]\xb8\xd8\"";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(packet.answers()[0].cls, C::Unknown(10));
+        assert_eq!(packet.answers()[0].cls.code(), 10);
+        let strict = ParseOptions::new().strict_classes(true);
+        let error = Packet::parse_with_options(response, &strict).unwrap_err();
+        assert!(matches!(error.root(), crate::Error::InvalidClass(10)));
+
+        let query = b"\x06%\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\
+                      \x07example\x03com\x00\x00\x01\x00\xfe";
+        let packet = Packet::parse(query).unwrap();
+        assert_eq!(packet.questions()[0].qclass, QC::Unknown(254));
+        let error = Packet::parse_with_options(query, &strict).unwrap_err();
+        assert!(matches!(error.root(), crate::Error::InvalidQueryClass(254)));
+    }
+
+    #[test]
+    fn error_context() {
+        use crate::{ErrorKind, Section};
+        use std::error::Error as _;
+
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x0a\x00\x00\x04\xf8\
+                         \x00\x04]\xb8\xd8\"";
+        let strict = ParseOptions::new().strict_classes(true);
+        let error = Packet::parse_with_options(response, &strict).unwrap_err();
+        assert!(matches!(
+            error,
+            crate::Error::Record(Section::Answer, 0, 29, _)
+        ));
+        assert_eq!(error.offset(), Some(29));
+        assert_eq!(error.kind(), ErrorKind::Malformed);
+        assert_eq!(
+            error.to_string(),
+            "answer record 0 at offset 29: class 10 is invalid"
+        );
+        assert_eq!(error.source().unwrap().to_string(), "class 10 is invalid");
+
+        let error = Packet::parse(&response[..31]).unwrap_err();
+        assert!(matches!(error.root(), crate::Error::UnexpectedEOF));
+        assert_eq!(error.kind(), ErrorKind::Truncated);
+        assert_eq!(error.kind().as_str(), "truncated");
+        let error = Packet::parse(&response[..20]).unwrap_err();
+        assert!(matches!(error, crate::Error::Question(0, 12, _)));
+        assert!(Packet::parse(&response[..8])
+            .unwrap_err()
+            .offset()
+            .is_none());
+    }
+
+    #[test]
+    fn parse_limits() {
+        // six answers pointing to the name of the question
+        let response = b"\x9d\xe9\x81\x80\x00\x01\x00\x06\x00\x00\x00\x00\
+            \x06google\x03com\x00\x00\x01\x00\x01\xc0\x0c\
+            \x00\x01\x00\x01\x00\x00\x00\xef\x00\x04@\xe9\
+            \xa4d\xc0\x0c\x00\x01\x00\x01\x00\x00\x00\xef\
+            \x00\x04@\xe9\xa4\x8b\xc0\x0c\x00\x01\x00\x01\
+            \x00\x00\x00\xef\x00\x04@\xe9\xa4q\xc0\x0c\x00\
+            \x01\x00\x01\x00\x00\x00\xef\x00\x04@\xe9\xa4f\
+            \xc0\x0c\x00\x01\x00\x01\x00\x00\x00\xef\x00\x04@\
+            \xe9\xa4e\xc0\x0c\x00\x01\x00\x01\x00\x00\x00\xef\
+            \x00\x04@\xe9\xa4\x8a";
+        let parse = |options: ParseOptions| Packet::parse_with_options(response, &options);
+        // the labels of the question are read again only for the first
+        // answer, later ones stop at the known target
+        assert!(parse(ParseOptions::new().max_labels(4)).is_ok());
+        assert!(matches!(
+            parse(ParseOptions::new().max_labels(3)).unwrap_err().root(),
+            crate::Error::LimitExceeded(crate::Limit::Labels)
+        ));
+        assert!(parse(ParseOptions::new().max_pointers(6)).is_ok());
+        assert!(matches!(
+            parse(ParseOptions::new().max_pointers(5))
+                .unwrap_err()
+                .root(),
+            crate::Error::LimitExceeded(crate::Limit::Pointers)
+        ));
+        // 11 bytes of labels twice, 6 pointers and 6 addresses
+        assert!(parse(ParseOptions::new().max_bytes(58)).is_ok());
+        assert!(matches!(
+            parse(ParseOptions::new().max_bytes(57)).unwrap_err().root(),
+            crate::Error::LimitExceeded(crate::Limit::Bytes)
+        ));
+    }
+
+    #[test]
+    fn limits_everywhere() {
+        use crate::visitor::{parse_with_visitor_with_options, MessageVisitor};
+        use crate::{Error, Limit};
+
+        struct Ignore;
+        impl MessageVisitor<'_> for Ignore {}
+
+        // a CNAME to live.skype.com, whose address records point to the
+        // name in the record data
+        let response = b"\xda\x4a\x81\x80\x00\x01\x00\x03\x00\x00\x00\x00\
+            \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+            \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\x00\x07\x04live\xc0\x10\
+            \xc0\x2b\x00\x01\x00\x01\x00\x00\x00\x04\x00\x04\x6f\xdd\x4a\x1c\
+            \xc0\x2b\x00\x01\x00\x01\x00\x00\x00\x04\x00\x04\x6f\xdd\x4a\x1d";
+        let labels = |limit| ParseOptions::new().max_labels(limit);
+        let is_limit = |error: Error| matches!(error.root(), Error::LimitExceeded(Limit::Labels));
+        // the name in the CNAME record is charged while parsing its data
+        assert!(Packet::parse_with_options(response, &labels(10)).is_ok());
+        assert!(is_limit(
+            Packet::parse_with_options(response, &labels(9)).unwrap_err()
+        ));
+        // record data is not parsed, but the names of the records still
+        // lead into it
+        assert!(RawPacket::parse_with_options(response, &labels(7)).is_ok());
+        assert!(is_limit(
+            RawPacket::parse_with_options(response, &labels(6)).unwrap_err()
+        ));
+        assert!(is_limit(
+            parse_with_visitor_with_options(response, &labels(6), &mut Ignore).unwrap_err()
+        ));
+    }
+
+    #[test]
+    fn capacity_from_length() {
+        use super::{capacity, MIN_QUESTION_SIZE, MIN_RECORD_SIZE};
+
+        assert_eq!(capacity(u16::MAX, 12, MIN_RECORD_SIZE), 0);
+        assert_eq!(capacity(u16::MAX, 12 + 22, MIN_RECORD_SIZE), 2);
+        assert_eq!(capacity(3, 512, MIN_QUESTION_SIZE), 3);
+        // a header claiming the largest counts doesn't reserve lists for
+        // them
+        let header = b"\x00\x00\x81\x80\xff\xff\xff\xff\xff\xff\xff\xff";
+        let mut storage = crate::PacketStorage::new();
+        assert!(Packet::parse_into(header, &mut storage).is_err());
+        assert_eq!(storage.questions.capacity(), 0);
+        assert_eq!(storage.answers.capacity(), 0);
+    }
 }
@@ -0,0 +1,751 @@
+//! Reading DNS messages from packet captures
+//!
+//! [`Reader`] reads a pcap or pcapng file and yields the DNS messages
+//! found in it along with the capture time and the addresses. Traffic is
+//! recognized by port: 53 (DNS), 5353 (mDNS) and 5355 (LLMNR). DNS over
+//! TCP streams are reassembled, so messages split into several segments
+//! or several messages in one segment are handled.
+//!
+//! When the frames come from elsewhere (e.g. a live capture), feed them
+//! or just the UDP and TCP payloads to an [`Extractor`].
+//!
+//! Fragmented IP packets are skipped. Ethernet (with VLAN tags), raw IP,
+//! Linux cooked and BSD loopback link types are supported, frames of
+//! other link types are skipped.
+//!
+//! This module is enabled by the `pcap` feature.
+//!
+//! [`Reader`]: struct.Reader.html
+//! [`Extractor`]: struct.Extractor.html
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryInto;
+use std::io::{self, Read};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use quick_error::quick_error;
+
+use crate::Packet;
+
+/// Link type of Ethernet frames
+pub const LINKTYPE_ETHERNET: u32 = 1;
+/// Link type of BSD loopback frames
+pub const LINKTYPE_NULL: u32 = 0;
+/// Link type of frames starting with the IP header
+pub const LINKTYPE_RAW: u32 = 101;
+/// Link type of Linux cooked capture frames
+pub const LINKTYPE_LINUX_SLL: u32 = 113;
+
+const DNS_PORTS: [u16; 3] = [53, 5353, 5355];
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+
+quick_error! {
+    /// Error reading a capture file
+    #[derive(Debug)]
+    pub enum PcapError {
+        /// I/O error reading the file
+        Io(err: io::Error) {
+            from()
+            display("I/O error: {}", err)
+            source(err)
+        }
+        /// The file is not a valid pcap or pcapng file
+        InvalidFormat(reason: &'static str) {
+            display("invalid capture file: {}", reason)
+        }
+    }
+}
+
+/// Transport protocol a message was carried over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Transport {
+    /// UDP datagram
+    Udp,
+    /// Reassembled TCP stream
+    Tcp,
+}
+
+/// A DNS message found in the captured traffic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Capture time of the frame, for TCP of the one completing the message
+    pub timestamp: SystemTime,
+    /// Address and port of the sender
+    pub source: SocketAddr,
+    /// Address and port of the receiver
+    pub destination: SocketAddr,
+    /// The transport protocol
+    pub transport: Transport,
+    /// The message in the wire format, without the TCP length prefix
+    pub data: Vec<u8>,
+}
+
+impl Message {
+    /// Parses the message
+    pub fn packet(&self) -> Result<Packet<'_>, crate::Error> {
+        Packet::parse(&self.data)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Stream {
+    next_seq: Option<u32>,
+    buf: Vec<u8>,
+}
+
+/// Extracts DNS messages from captured frames or transport payloads
+#[derive(Debug, Default)]
+pub struct Extractor {
+    streams: HashMap<(SocketAddr, SocketAddr), Stream>,
+}
+
+impl Extractor {
+    /// Creates an extractor
+    pub fn new() -> Extractor {
+        Extractor::default()
+    }
+    /// Processes a captured frame of the link type
+    pub fn frame(&mut self, timestamp: SystemTime, link_type: u32, data: &[u8]) -> Vec<Message> {
+        let ip = match link_type {
+            LINKTYPE_NULL => data.get(4..),
+            LINKTYPE_ETHERNET => ethernet_payload(data),
+            LINKTYPE_RAW => Some(data),
+            LINKTYPE_LINUX_SLL => data.get(16..),
+            _ => None,
+        };
+        match ip {
+            Some(ip) => self.ip_packet(timestamp, ip),
+            None => Vec::new(),
+        }
+    }
+    /// Processes an IPv4 or IPv6 packet
+    pub fn ip_packet(&mut self, timestamp: SystemTime, data: &[u8]) -> Vec<Message> {
+        let (src, dst, protocol, payload) = match ip_payload(data) {
+            Some(parts) => parts,
+            None => return Vec::new(),
+        };
+        match protocol {
+            17 if payload.len() >= 8 => {
+                let (src, dst, len) = (
+                    SocketAddr::new(src, read_u16(payload, 0)),
+                    SocketAddr::new(dst, read_u16(payload, 2)),
+                    read_u16(payload, 4) as usize,
+                );
+                let end = len.clamp(8, payload.len());
+                self.udp(timestamp, src, dst, &payload[8..end])
+                    .into_iter()
+                    .collect()
+            }
+            6 if payload.len() >= 20 => {
+                let offset = (payload[12] >> 4) as usize * 4;
+                if offset < 20 || offset > payload.len() {
+                    return Vec::new();
+                }
+                self.tcp(
+                    timestamp,
+                    SocketAddr::new(src, read_u16(payload, 0)),
+                    SocketAddr::new(dst, read_u16(payload, 2)),
+                    u32::from_be_bytes(payload[4..8].try_into().unwrap()),
+                    payload[13],
+                    &payload[offset..],
+                )
+            }
+            _ => Vec::new(),
+        }
+    }
+    /// Processes a UDP payload
+    ///
+    /// Returns a message if either port is a DNS port.
+    pub fn udp(
+        &mut self,
+        timestamp: SystemTime,
+        source: SocketAddr,
+        destination: SocketAddr,
+        payload: &[u8],
+    ) -> Option<Message> {
+        if !is_dns(source, destination) || payload.is_empty() {
+            return None;
+        }
+        Some(Message {
+            timestamp,
+            source,
+            destination,
+            transport: Transport::Udp,
+            data: payload.to_vec(),
+        })
+    }
+    /// Processes a TCP segment
+    ///
+    /// `flags` is the flags byte of the TCP header. Returns the messages
+    /// completed by this segment if either port is a DNS port. Segments
+    /// must arrive in order, a gap in the sequence numbers discards the
+    /// data buffered for the stream.
+    pub fn tcp(
+        &mut self,
+        timestamp: SystemTime,
+        source: SocketAddr,
+        destination: SocketAddr,
+        seq: u32,
+        flags: u8,
+        payload: &[u8],
+    ) -> Vec<Message> {
+        if !is_dns(source, destination) {
+            return Vec::new();
+        }
+        let key = (source, destination);
+        if flags & TCP_RST != 0 {
+            self.streams.remove(&key);
+            return Vec::new();
+        }
+        let stream = self.streams.entry(key).or_default();
+        if flags & TCP_SYN != 0 {
+            stream.next_seq = Some(seq.wrapping_add(1));
+            stream.buf.clear();
+        }
+        let data_seq = if flags & TCP_SYN != 0 {
+            seq.wrapping_add(1)
+        } else {
+            seq
+        };
+        let next = stream.next_seq.unwrap_or(data_seq);
+        // distance from the expected sequence number, negative for
+        // retransmitted data
+        let diff = data_seq.wrapping_sub(next) as i32;
+        if diff > 0 {
+            stream.buf.clear();
+            stream.next_seq = Some(data_seq.wrapping_add(payload.len() as u32));
+        } else if diff.unsigned_abs() as usize <= payload.len() {
+            let new_data = &payload[diff.unsigned_abs() as usize..];
+            stream.buf.extend_from_slice(new_data);
+            stream.next_seq = Some(next.wrapping_add(new_data.len() as u32));
+        }
+
+        let mut messages = Vec::new();
+        while stream.buf.len() >= 2 {
+            let len = read_u16(&stream.buf, 0) as usize;
+            if stream.buf.len() < 2 + len {
+                break;
+            }
+            let data = stream.buf[2..2 + len].to_vec();
+            stream.buf.drain(..2 + len);
+            messages.push(Message {
+                timestamp,
+                source,
+                destination,
+                transport: Transport::Tcp,
+                data,
+            });
+        }
+        if flags & TCP_FIN != 0 {
+            self.streams.remove(&key);
+        }
+        messages
+    }
+}
+
+fn is_dns(source: SocketAddr, destination: SocketAddr) -> bool {
+    DNS_PORTS.contains(&source.port()) || DNS_PORTS.contains(&destination.port())
+}
+
+fn read_u16(data: &[u8], pos: usize) -> u16 {
+    u16::from_be_bytes([data[pos], data[pos + 1]])
+}
+
+fn ethernet_payload(data: &[u8]) -> Option<&[u8]> {
+    let mut pos = 12;
+    // skip 802.1Q and 802.1ad tags
+    while matches!(read_u16(data.get(..pos + 2)?, pos), 0x8100 | 0x88a8) {
+        pos += 4;
+    }
+    match read_u16(data, pos) {
+        0x0800 | 0x86dd => data.get(pos + 2..),
+        _ => None,
+    }
+}
+
+// Returns the addresses, the transport protocol and its data
+fn ip_payload(data: &[u8]) -> Option<(IpAddr, IpAddr, u8, &[u8])> {
+    match data.first()? >> 4 {
+        4 => {
+            let header_len = (data[0] & 0xF) as usize * 4;
+            let total_len = read_u16(data.get(..20)?, 2) as usize;
+            let fragment = read_u16(data, 6);
+            // more fragments flag or a fragment offset
+            if fragment & 0x3FFF != 0 || header_len < 20 {
+                return None;
+            }
+            let data = data.get(..total_len.min(data.len()))?;
+            let src: [u8; 4] = data[12..16].try_into().unwrap();
+            let dst: [u8; 4] = data[16..20].try_into().unwrap();
+            Some((
+                Ipv4Addr::from(src).into(),
+                Ipv4Addr::from(dst).into(),
+                data[9],
+                data.get(header_len..)?,
+            ))
+        }
+        6 => {
+            let payload_len = read_u16(data.get(..40)?, 4) as usize;
+            let src: [u8; 16] = data[8..24].try_into().unwrap();
+            let dst: [u8; 16] = data[24..40].try_into().unwrap();
+            let mut next = data[6];
+            let mut payload = &data[40..(40 + payload_len).min(data.len())];
+            // skip hop-by-hop, routing and destination options headers
+            while matches!(next, 0 | 43 | 60) {
+                let len = (*payload.get(1)? as usize + 1) * 8;
+                next = payload[0];
+                payload = payload.get(len..)?;
+            }
+            Some((
+                Ipv6Addr::from(src).into(),
+                Ipv6Addr::from(dst).into(),
+                next,
+                payload,
+            ))
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+enum Format {
+    Pcap {
+        big_endian: bool,
+        nanos: bool,
+        link_type: u32,
+    },
+    Pcapng {
+        big_endian: bool,
+        // link type and timestamp units per second of every interface
+        interfaces: Vec<(u32, u64)>,
+    },
+}
+
+/// Reads DNS messages from a pcap or pcapng file
+///
+/// The format is detected from the file header. Iterating yields the
+/// messages in capture order, an error ends the iteration.
+#[derive(Debug)]
+pub struct Reader<R: Read> {
+    reader: R,
+    format: Format,
+    extractor: Extractor,
+    pending: VecDeque<Message>,
+    failed: bool,
+}
+
+impl<R: Read> Reader<R> {
+    /// Reads the file header
+    pub fn new(mut reader: R) -> Result<Reader<R>, PcapError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        let format = match magic {
+            [0xa1, 0xb2, 0xc3, 0xd4] | [0xa1, 0xb2, 0x3c, 0x4d] => {
+                read_pcap_header(&mut reader, true, magic[2] == 0x3c)?
+            }
+            [0xd4, 0xc3, 0xb2, 0xa1] | [0x4d, 0x3c, 0xb2, 0xa1] => {
+                read_pcap_header(&mut reader, false, magic[1] == 0x3c)?
+            }
+            [0x0a, 0x0d, 0x0d, 0x0a] => Format::Pcapng {
+                big_endian: read_section_header(&mut reader)?,
+                interfaces: Vec::new(),
+            },
+            _ => return Err(PcapError::InvalidFormat("unknown magic number")),
+        };
+        Ok(Reader {
+            reader,
+            format,
+            extractor: Extractor::new(),
+            pending: VecDeque::new(),
+            failed: false,
+        })
+    }
+
+    // Returns the next frame, `None` at the end of the file
+    fn next_frame(&mut self) -> Result<Option<(SystemTime, u32, Vec<u8>)>, PcapError> {
+        match self.format {
+            Format::Pcap {
+                big_endian,
+                nanos,
+                link_type,
+            } => {
+                let mut header = [0u8; 16];
+                if !read_or_eof(&mut self.reader, &mut header)? {
+                    return Ok(None);
+                }
+                let field = |i: usize| read_u32(&header[i * 4..], big_endian);
+                let fraction = if nanos {
+                    Duration::from_nanos(field(1) as u64)
+                } else {
+                    Duration::from_micros(field(1) as u64)
+                };
+                let timestamp = UNIX_EPOCH + Duration::from_secs(field(0) as u64) + fraction;
+                let mut data = vec![0; field(2) as usize];
+                self.reader.read_exact(&mut data)?;
+                Ok(Some((timestamp, link_type, data)))
+            }
+            Format::Pcapng { .. } => loop {
+                let mut header = [0u8; 8];
+                if !read_or_eof(&mut self.reader, &mut header)? {
+                    return Ok(None);
+                }
+                if header[..4] == [0x0a, 0x0d, 0x0d, 0x0a] {
+                    // a new section, possibly of a different byte order
+                    let big_endian = read_section_header_rest(&mut self.reader, &header[4..])?;
+                    self.format = Format::Pcapng {
+                        big_endian,
+                        interfaces: Vec::new(),
+                    };
+                    continue;
+                }
+                let (big_endian, interfaces) = match self.format {
+                    Format::Pcapng {
+                        big_endian,
+                        ref mut interfaces,
+                    } => (big_endian, interfaces),
+                    Format::Pcap { .. } => unreachable!(),
+                };
+                let block_type = read_u32(&header, big_endian);
+                let len = read_u32(&header[4..], big_endian) as usize;
+                if len < 12 || !len.is_multiple_of(4) {
+                    return Err(PcapError::InvalidFormat("bad block length"));
+                }
+                let mut body = vec![0; len - 8];
+                self.reader.read_exact(&mut body)?;
+                body.truncate(len - 12);
+                match block_type {
+                    // interface description block
+                    1 => {
+                        if body.len() < 8 {
+                            return Err(PcapError::InvalidFormat("short interface block"));
+                        }
+                        let link_type = read_u16_endian(&body, big_endian) as u32;
+                        let resolution = interface_resolution(&body[8..], big_endian);
+                        interfaces.push((link_type, resolution));
+                    }
+                    // enhanced packet block
+                    6 => {
+                        if body.len() < 20 {
+                            return Err(PcapError::InvalidFormat("short packet block"));
+                        }
+                        let field = |i: usize| read_u32(&body[i * 4..], big_endian);
+                        let &(link_type, resolution) = interfaces
+                            .get(field(0) as usize)
+                            .ok_or(PcapError::InvalidFormat("unknown interface"))?;
+                        let ticks = (field(1) as u64) << 32 | field(2) as u64;
+                        let timestamp = UNIX_EPOCH
+                            + Duration::from_secs(ticks / resolution)
+                            + Duration::from_nanos(
+                                (ticks % resolution) * 1_000_000_000 / resolution,
+                            );
+                        let captured = field(3) as usize;
+                        let data = body
+                            .get(20..20 + captured)
+                            .ok_or(PcapError::InvalidFormat("short packet block"))?;
+                        return Ok(Some((timestamp, link_type, data.to_vec())));
+                    }
+                    // simple packet block, carries no timestamp
+                    3 => {
+                        let &(link_type, _) = interfaces
+                            .first()
+                            .ok_or(PcapError::InvalidFormat("unknown interface"))?;
+                        let data = body.get(4..).unwrap_or(&[]);
+                        return Ok(Some((UNIX_EPOCH, link_type, data.to_vec())));
+                    }
+                    _ => {}
+                }
+            },
+        }
+    }
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Message, PcapError>;
+
+    fn next(&mut self) -> Option<Result<Message, PcapError>> {
+        while self.pending.is_empty() {
+            if self.failed {
+                return None;
+            }
+            match self.next_frame() {
+                Ok(Some((timestamp, link_type, data))) => {
+                    let messages = self.extractor.frame(timestamp, link_type, &data);
+                    self.pending.extend(messages);
+                }
+                Ok(None) => return None,
+                Err(e) => {
+                    self.failed = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+fn read_u32(data: &[u8], big_endian: bool) -> u32 {
+    let bytes = data[..4].try_into().unwrap();
+    if big_endian {
+        u32::from_be_bytes(bytes)
+    } else {
+        u32::from_le_bytes(bytes)
+    }
+}
+
+fn read_u16_endian(data: &[u8], big_endian: bool) -> u16 {
+    let bytes = data[..2].try_into().unwrap();
+    if big_endian {
+        u16::from_be_bytes(bytes)
+    } else {
+        u16::from_le_bytes(bytes)
+    }
+}
+
+// Fills the buffer, returns false on a clean end of file
+fn read_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, PcapError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(PcapError::InvalidFormat("truncated file")),
+            Ok(n) => filled += n,
+            Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+fn read_pcap_header<R: Read>(
+    reader: &mut R,
+    big_endian: bool,
+    nanos: bool,
+) -> Result<Format, PcapError> {
+    let mut header = [0u8; 20];
+    reader.read_exact(&mut header)?;
+    Ok(Format::Pcap {
+        big_endian,
+        nanos,
+        link_type: read_u32(&header[16..], big_endian) & 0x0FFF_FFFF,
+    })
+}
+
+// Reads a section header block after its type, returns the byte order
+fn read_section_header<R: Read>(reader: &mut R) -> Result<bool, PcapError> {
+    let mut len = [0u8; 4];
+    reader.read_exact(&mut len)?;
+    read_section_header_rest(reader, &len)
+}
+
+fn read_section_header_rest<R: Read>(reader: &mut R, len: &[u8]) -> Result<bool, PcapError> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    let big_endian = match magic {
+        [0x1a, 0x2b, 0x3c, 0x4d] => true,
+        [0x4d, 0x3c, 0x2b, 0x1a] => false,
+        _ => return Err(PcapError::InvalidFormat("bad byte order magic")),
+    };
+    let len = read_u32(len, big_endian) as usize;
+    if len < 28 || !len.is_multiple_of(4) {
+        return Err(PcapError::InvalidFormat("bad block length"));
+    }
+    // skip the version, section length, options and trailing length
+    io::copy(&mut reader.take(len as u64 - 12), &mut io::sink())?;
+    Ok(big_endian)
+}
+
+// Returns timestamp units per second from the interface options
+fn interface_resolution(mut options: &[u8], big_endian: bool) -> u64 {
+    while options.len() >= 4 {
+        let code = read_u16_endian(options, big_endian);
+        let len = read_u16_endian(&options[2..], big_endian) as usize;
+        let value = match options.get(4..4 + len) {
+            Some(value) => value,
+            None => break,
+        };
+        // if_tsresol
+        if code == 9 && len == 1 {
+            let exp = (value[0] & 0x7F) as u32;
+            let base: u64 = if value[0] & 0x80 != 0 { 2 } else { 10 };
+            return base.checked_pow(exp).unwrap_or(1_000_000);
+        }
+        if code == 0 {
+            break;
+        }
+        options = options.get(4 + len.div_ceil(4) * 4..).unwrap_or(&[]);
+    }
+    1_000_000
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::{Extractor, Reader, Transport, LINKTYPE_ETHERNET, LINKTYPE_RAW};
+    use crate::{Builder, QueryClass as QC, QueryType as QT};
+
+    fn query() -> Vec<u8> {
+        let mut bld = Builder::new_query(1573, true);
+        bld.add_question("example.com", false, QT::A, QC::IN);
+        bld.build().unwrap()
+    }
+
+    fn ipv4_udp(payload: &[u8]) -> Vec<u8> {
+        let mut packet = Vec::new();
+        let total = 20 + 8 + payload.len();
+        packet.extend_from_slice(&[0x45, 0, (total >> 8) as u8, total as u8]);
+        packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 17, 0, 0]);
+        packet.extend_from_slice(&[192, 0, 2, 1, 192, 0, 2, 53]);
+        let udp_len = 8 + payload.len();
+        packet.extend_from_slice(&[0xc3, 0x50, 0, 53, (udp_len >> 8) as u8, udp_len as u8, 0, 0]);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    fn ethernet(ip: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&[0x81, 0x00, 0x00, 0x01]); // VLAN tag
+        frame.extend_from_slice(&[0x08, 0x00]);
+        frame.extend_from_slice(ip);
+        frame
+    }
+
+    #[test]
+    fn read_pcap() {
+        let frame = ethernet(&ipv4_udp(&query()));
+        let mut file = b"\xd4\xc3\xb2\xa1\x02\x00\x04\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+                         \xff\xff\x00\x00\x01\x00\x00\x00"
+            .to_vec();
+        file.extend_from_slice(&1_600_000_000u32.to_le_bytes());
+        file.extend_from_slice(&250_000u32.to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&frame);
+
+        let messages = Reader::new(&file[..])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(messages.len(), 1);
+        let message = &messages[0];
+        assert_eq!(
+            message.timestamp,
+            UNIX_EPOCH + Duration::new(1_600_000_000, 250_000_000)
+        );
+        assert_eq!(
+            message.source,
+            "192.0.2.1:50000".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(
+            message.destination,
+            "192.0.2.53:53".parse::<SocketAddr>().unwrap()
+        );
+        assert_eq!(message.transport, Transport::Udp);
+        let packet = message.packet().unwrap();
+        assert_eq!(packet.questions()[0].qname.to_string(), "example.com");
+
+        // truncated record
+        let mut reader = Reader::new(&file[..file.len() - 1]).unwrap();
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn read_pcapng() {
+        let ip = ipv4_udp(&query());
+        let mut file = Vec::new();
+        // section header block
+        file.extend_from_slice(b"\x0a\x0d\x0d\x0a\x1c\x00\x00\x00\x4d\x3c\x2b\x1a");
+        file.extend_from_slice(b"\x01\x00\x00\x00\xff\xff\xff\xff\xff\xff\xff\xff\x1c\x00\x00\x00");
+        // interface description block with nanosecond resolution
+        file.extend_from_slice(b"\x01\x00\x00\x00\x20\x00\x00\x00\x65\x00\x00\x00\x00\x00\x00\x00");
+        file.extend_from_slice(b"\x09\x00\x01\x00\x09\x00\x00\x00\x00\x00\x00\x00\x20\x00\x00\x00");
+        // enhanced packet block
+        let padded = ip.len().div_ceil(4) * 4;
+        let len = 32 + padded as u32;
+        let ticks = 1_600_000_000_000_000_123u64;
+        file.extend_from_slice(b"\x06\x00\x00\x00");
+        file.extend_from_slice(&len.to_le_bytes());
+        file.extend_from_slice(&0u32.to_le_bytes());
+        file.extend_from_slice(&((ticks >> 32) as u32).to_le_bytes());
+        file.extend_from_slice(&(ticks as u32).to_le_bytes());
+        file.extend_from_slice(&(ip.len() as u32).to_le_bytes());
+        file.extend_from_slice(&(ip.len() as u32).to_le_bytes());
+        file.extend_from_slice(&ip);
+        file.resize(file.len() + padded - ip.len(), 0);
+        file.extend_from_slice(&len.to_le_bytes());
+
+        let messages = Reader::new(&file[..])
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].timestamp,
+            UNIX_EPOCH + Duration::new(1_600_000_000, 123)
+        );
+        assert_eq!(messages[0].data, query());
+    }
+
+    #[test]
+    fn tcp_reassembly() {
+        let client: SocketAddr = "[2001:db8::1]:40000".parse().unwrap();
+        let server: SocketAddr = "[2001:db8::53]:53".parse().unwrap();
+        let mut stream = Vec::new();
+        for _ in 0..2 {
+            stream.extend_from_slice(&(query().len() as u16).to_be_bytes());
+            stream.extend_from_slice(&query());
+        }
+        let mut extractor = Extractor::new();
+        let time = UNIX_EPOCH;
+        assert!(extractor
+            .tcp(time, client, server, 1000, 0x02, &[])
+            .is_empty());
+        // the first message split, the second one follows in the segment
+        assert!(extractor
+            .tcp(time, client, server, 1001, 0x18, &stream[..10])
+            .is_empty());
+        // retransmission of the first segment
+        assert!(extractor
+            .tcp(time, client, server, 1001, 0x18, &stream[..10])
+            .is_empty());
+        let messages = extractor.tcp(time, client, server, 1011, 0x18, &stream[10..]);
+        assert_eq!(messages.len(), 2);
+        for message in &messages {
+            assert_eq!(message.transport, Transport::Tcp);
+            assert_eq!(message.data, query());
+        }
+        // not a DNS port
+        let other: SocketAddr = "[2001:db8::80]:80".parse().unwrap();
+        assert!(extractor
+            .tcp(time, client, other, 1, 0x18, &stream)
+            .is_empty());
+    }
+
+    #[test]
+    fn unsupported_frames() {
+        let mut extractor = Extractor::new();
+        let ip = ipv4_udp(&query());
+        assert_eq!(extractor.frame(UNIX_EPOCH, LINKTYPE_RAW, &ip).len(), 1);
+        // fragmented packet
+        let mut fragment = ip.clone();
+        fragment[6] = 0x20;
+        assert!(extractor
+            .frame(UNIX_EPOCH, LINKTYPE_RAW, &fragment)
+            .is_empty());
+        // ARP
+        let mut arp = ethernet(&ip);
+        arp[16..18].copy_from_slice(&[0x08, 0x06]);
+        assert!(extractor
+            .frame(UNIX_EPOCH, LINKTYPE_ETHERNET, &arp)
+            .is_empty());
+        assert!(extractor
+            .frame(UNIX_EPOCH, LINKTYPE_ETHERNET, &[0; 5])
+            .is_empty());
+        assert!(extractor.frame(UNIX_EPOCH, 12345, &ip).is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! QNAME minimisation (RFC 9156)
+//!
+//! An iterative resolver sending the full query name to every server on
+//! the way down leaks it to the root and TLD servers. With minimisation it
+//! asks each server only for the name one label below the zone cut it
+//! knows, and sends the original query type only for the target itself.
+//!
+//! ```
+//! # use dns_parser::{Name, QueryType};
+//! # use dns_parser::qmin::next_query;
+//! # fn resolve(target: &Name, cut: &Name) {
+//! // `cut` is the closest zone with known name servers, e.g. `com`
+//! if let Some(query) = next_query(target, QueryType::AAAA, cut) {
+//!     println!("ask the {} servers for {} {:?}", cut, query.name, query.qtype);
+//! }
+//! # }
+//! ```
+//!
+//! A referral moves the zone cut down, the next query is computed again
+//! from the new cut. A response without a referral means there is no cut
+//! at the name asked for, the following query is the next one of
+//! `queries` computed for the same cut.
+use crate::{Name, QueryType};
+
+/// Queries after which labels are no longer added one at a time
+pub const MINIMISE_ONE_LAB: usize = 4;
+
+/// Largest number of queries sent to reach the target from a zone cut
+pub const MAX_MINIMISE_COUNT: usize = 10;
+
+/// A query to send, see `queries`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// The name to ask for
+    pub name: Name<'static>,
+    /// The type to ask for
+    pub qtype: QueryType,
+}
+
+/// Returns the queries to send to get from `zone_cut` to `target`
+///
+/// Each query asks for a name with more labels of `target` than the one
+/// before, starting with one label below the cut. Queries for names
+/// other than the target use the type A (RFC 9156 section 2.1), the last
+/// one asks for `target` with the original `qtype`.
+///
+/// The first `MINIMISE_ONE_LAB` queries add one label each. Names with
+/// many labels get several labels added at a time after that, so that
+/// no more than `MAX_MINIMISE_COUNT` queries are sent (section 2.3).
+///
+/// Returns an empty list if `target` is not at or below `zone_cut`.
+/// If they are equal, the only query is the original one. Names are
+/// compared ignoring case.
+pub fn queries(target: &Name<'_>, qtype: QueryType, zone_cut: &Name<'_>) -> Vec<Query> {
+    if !target.is_subdomain_of(zone_cut) {
+        return Vec::new();
+    }
+    let labels = target.labels().collect::<Vec<_>>();
+    let mut remaining = labels.len() - zone_cut.labels().count();
+    let mut result = Vec::new();
+    while remaining > 0 {
+        let count = result.len();
+        let add = if count < MINIMISE_ONE_LAB {
+            1
+        } else {
+            let queries_left = MAX_MINIMISE_COUNT.saturating_sub(count).max(1);
+            remaining.div_ceil(queries_left)
+        };
+        remaining -= add;
+        let qtype = if remaining == 0 { qtype } else { QueryType::A };
+        result.push(Query {
+            name: name_from_labels(&labels[remaining..]),
+            qtype,
+        });
+    }
+    if result.is_empty() {
+        result.push(Query {
+            name: target.clone().into_owned(),
+            qtype,
+        });
+    }
+    result
+}
+
+/// Returns the first query of `queries`, the one to send next
+pub fn next_query(target: &Name<'_>, qtype: QueryType, zone_cut: &Name<'_>) -> Option<Query> {
+    queries(target, qtype, zone_cut).into_iter().next()
+}
+
+fn name_from_labels(labels: &[&[u8]]) -> Name<'static> {
+    let mut wire = Vec::new();
+    for label in labels {
+        wire.push(label.len() as u8);
+        wire.extend_from_slice(label);
+    }
+    wire.push(0);
+    Name::from_uncompressed(wire)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{next_query, queries};
+    use crate::{Name, QueryType};
+
+    fn name(text: &str) -> Name<'static> {
+        let mut wire = Vec::new();
+        for label in text.split('.').filter(|l| !l.is_empty()) {
+            wire.push(label.len() as u8);
+            wire.extend_from_slice(label.as_bytes());
+        }
+        wire.push(0);
+        Name::from_uncompressed(wire)
+    }
+
+    fn steps(target: &str, qtype: QueryType, zone_cut: &str) -> Vec<(String, QueryType)> {
+        queries(&name(target), qtype, &name(zone_cut))
+            .into_iter()
+            .map(|query| (query.name.to_string(), query.qtype))
+            .collect()
+    }
+
+    #[test]
+    fn one_label_at_a_time() {
+        assert_eq!(
+            steps("www.example.com", QueryType::AAAA, ""),
+            [
+                ("com".to_string(), QueryType::A),
+                ("example.com".to_string(), QueryType::A),
+                ("www.example.com".to_string(), QueryType::AAAA),
+            ]
+        );
+        let query = next_query(
+            &name("www.example.com"),
+            QueryType::MX,
+            &name("Example.COM"),
+        );
+        assert_eq!(query.unwrap().qtype, QueryType::MX);
+        assert_eq!(
+            steps("example.com", QueryType::DS, "example.com"),
+            [("example.com".to_string(), QueryType::DS)]
+        );
+        assert!(steps("example.com", QueryType::A, "example.net").is_empty());
+    }
+
+    #[test]
+    fn many_labels() {
+        let steps = steps("a.b.c.d.e.f.g.h.i.j.k.l.example", QueryType::TXT, "example");
+        let names = steps.iter().map(|s| s.0.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "l.example",
+                "k.l.example",
+                "j.k.l.example",
+                "i.j.k.l.example",
+                "g.h.i.j.k.l.example",
+                "e.f.g.h.i.j.k.l.example",
+                "d.e.f.g.h.i.j.k.l.example",
+                "c.d.e.f.g.h.i.j.k.l.example",
+                "b.c.d.e.f.g.h.i.j.k.l.example",
+                "a.b.c.d.e.f.g.h.i.j.k.l.example",
+            ]
+        );
+        assert_eq!(steps[8].1, QueryType::A);
+        assert_eq!(steps[9].1, QueryType::TXT);
+    }
+}
@@ -1,6 +1,6 @@
 use std::{convert::TryInto, net::Ipv4Addr};
 
-use crate::Error;
+use crate::{Error, Type};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Record(pub Ipv4Addr);
@@ -9,7 +9,11 @@ impl<'a> super::Record<'a> for Record {
     const TYPE: isize = 1;
 
     fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        let rdata: [u8; 4] = rdata.try_into().map_err(|_| Error::WrongRdataLength)?;
+        let rdata: [u8; 4] = rdata.try_into().map_err(|_| Error::InvalidRdataLength {
+            typ: Type::A,
+            expected: 4,
+            got: rdata.len(),
+        })?;
         let address = Ipv4Addr::from(rdata);
         let record = Record(address);
         Ok(super::RData::A(record))
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Record;
 
@@ -5,7 +7,8 @@ impl<'a> super::Record<'a> for Record {
 
     const TYPE: isize = 38;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+        // no parser yet, the data is kept as is
+        Ok(super::RData::Unknown(Self::TYPE as u16, Cow::Borrowed(rdata)))
     }
 }
@@ -1,6 +1,6 @@
 use std::{convert::TryInto, net::Ipv6Addr};
 
-use crate::Error;
+use crate::{Error, Type};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct Record(pub Ipv6Addr);
@@ -9,7 +9,11 @@ impl<'a> super::Record<'a> for Record {
     const TYPE: isize = 28;
 
     fn parse(rdata: &'a [u8], _record: &'a [u8]) -> super::RDataResult<'a> {
-        let rdata: [u8; 16] = rdata.try_into().map_err(|_| Error::WrongRdataLength)?;
+        let rdata: [u8; 16] = rdata.try_into().map_err(|_| Error::InvalidRdataLength {
+            typ: Type::AAAA,
+            expected: 16,
+            got: rdata.len(),
+        })?;
         let address = Ipv6Addr::from(rdata);
         let record = Record(address);
         Ok(super::RData::AAAA(record))
@@ -36,7 +40,7 @@ mod test {
 
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 43481,
                 query: false,
@@ -47,6 +51,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 1,
@@ -55,15 +60,15 @@ mod test {
             }
         );
 
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::AAAA);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "google.com");
-        assert_eq!(packet.answers.len(), 1);
-        assert_eq!(&packet.answers[0].name.to_string()[..], "google.com");
-        assert_eq!(packet.answers[0].cls, C::IN);
-        assert_eq!(packet.answers[0].ttl, 139);
-        match packet.answers[0].data {
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::AAAA);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(&packet.questions()[0].qname.to_string()[..], "google.com");
+        assert_eq!(packet.answers().len(), 1);
+        assert_eq!(&packet.answers()[0].name.to_string()[..], "google.com");
+        assert_eq!(packet.answers()[0].cls, C::IN);
+        assert_eq!(packet.answers()[0].ttl, 139);
+        match packet.answers()[0].data {
             RData::AAAA(addr) => {
                 assert_eq!(
                     addr.0,
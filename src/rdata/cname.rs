@@ -1,7 +1,9 @@
 use crate::name::NameBytes;
 use crate::Name;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Record<'a>(pub Name<'a>);
 
 impl<'a> Record<'a> {
@@ -9,12 +11,22 @@ impl<'a> Record<'a> {
     pub fn bytes(&self) -> NameBytes<'_> {
         self.0.bytes()
     }
+
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record(self.0.into_owned())
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        self.0.write_to(buf);
+    }
 }
 
-impl<'a> ToString for Record<'a> {
+impl<'a> fmt::Display for Record<'a> {
     #[inline]
-    fn to_string(&self) -> String {
-        self.0.to_string()
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
     }
 }
 
@@ -56,7 +68,7 @@ mod test {
 
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 64669,
                 query: false,
@@ -67,6 +79,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 6,
@@ -75,25 +88,25 @@ mod test {
             }
         );
 
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
         assert_eq!(
-            &packet.questions[0].qname.to_string()[..],
+            &packet.questions()[0].qname.to_string()[..],
             "cdn.sstatic.net"
         );
-        assert_eq!(packet.answers.len(), 6);
-        assert_eq!(&packet.answers[0].name.to_string()[..], "cdn.sstatic.net");
-        assert_eq!(packet.answers[0].cls, C::IN);
-        assert_eq!(packet.answers[0].ttl, 102);
-        match packet.answers[0].data {
-            RData::CNAME(cname) => {
+        assert_eq!(packet.answers().len(), 6);
+        assert_eq!(&packet.answers()[0].name.to_string()[..], "cdn.sstatic.net");
+        assert_eq!(packet.answers()[0].cls, C::IN);
+        assert_eq!(packet.answers()[0].ttl, 102);
+        match packet.answers()[0].data {
+            RData::CNAME(ref cname) => {
                 assert_eq!(&cname.0.to_string(), "sstatic.net");
             }
             ref x => panic!("Wrong rdata {:?}", x),
         }
 
-        let ips = vec![
+        let ips = [
             Ipv4Addr::new(104, 16, 103, 204),
             Ipv4Addr::new(104, 16, 107, 204),
             Ipv4Addr::new(104, 16, 104, 204),
@@ -101,10 +114,10 @@ mod test {
             Ipv4Addr::new(104, 16, 105, 204),
         ];
         for i in 1..6 {
-            assert_eq!(&packet.answers[i].name.to_string()[..], "sstatic.net");
-            assert_eq!(packet.answers[i].cls, C::IN);
-            assert_eq!(packet.answers[i].ttl, 102);
-            match packet.answers[i].data {
+            assert_eq!(&packet.answers()[i].name.to_string()[..], "sstatic.net");
+            assert_eq!(packet.answers()[i].cls, C::IN);
+            assert_eq!(packet.answers()[i].ttl, 102);
+            match packet.answers()[i].data {
                 RData::A(addr) => {
                     assert_eq!(addr.0, ips[i - 1]);
                 }
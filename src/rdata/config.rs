@@ -1,7 +1,10 @@
+use std::borrow::Cow;
+
 use crate::rdata::*;
+use crate::Name;
 
 /// The enumeration that represents implemented types of DNS resource records data
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RData<'a> {
     A(A),
     AAAA(Aaaa),
@@ -12,5 +15,157 @@ pub enum RData<'a> {
     SOA(Soa<'a>),
     SRV(Srv<'a>),
     TXT(Txt<'a>),
-    OPT(&'a [u8]),
+    OPT(Cow<'a, [u8]>),
+    DNSKEY(DnsKey<'a>),
+    NSEC3PARAM(Nsec3Param<'a>),
+    RRSIG(Rrsig<'a>),
+    NSEC(Nsec<'a>),
+    NSEC3(Nsec3<'a>),
+    CSYNC(Csync<'a>),
+    DS(Ds<'a>),
+    ZONEMD(Zonemd<'a>),
+    DNAME(Dname<'a>),
+    /// Data of a type without a parser, kept as is
+    ///
+    /// Holds the type code and the data (RFC 3597). Types this library
+    /// has a parser for are never stored here.
+    Unknown(u16, Cow<'a, [u8]>),
+}
+
+impl<'a> RData<'a> {
+    /// Converts the data into one that does not borrow the packet
+    pub fn into_owned(self) -> RData<'static> {
+        match self {
+            RData::A(a) => RData::A(a),
+            RData::AAAA(aaaa) => RData::AAAA(aaaa),
+            RData::CNAME(cname) => RData::CNAME(cname.into_owned()),
+            RData::MX(mx) => RData::MX(mx.into_owned()),
+            RData::NS(ns) => RData::NS(ns.into_owned()),
+            RData::PTR(ptr) => RData::PTR(ptr.into_owned()),
+            RData::SOA(soa) => RData::SOA(soa.into_owned()),
+            RData::SRV(srv) => RData::SRV(srv.into_owned()),
+            RData::TXT(txt) => RData::TXT(txt.into_owned()),
+            RData::OPT(opt) => RData::OPT(Cow::Owned(opt.into_owned())),
+            RData::DNSKEY(key) => RData::DNSKEY(key.into_owned()),
+            RData::NSEC3PARAM(param) => RData::NSEC3PARAM(param.into_owned()),
+            RData::RRSIG(sig) => RData::RRSIG(sig.into_owned()),
+            RData::NSEC(nsec) => RData::NSEC(nsec.into_owned()),
+            RData::NSEC3(nsec3) => RData::NSEC3(nsec3.into_owned()),
+            RData::CSYNC(csync) => RData::CSYNC(csync.into_owned()),
+            RData::DS(ds) => RData::DS(ds.into_owned()),
+            RData::ZONEMD(zonemd) => RData::ZONEMD(zonemd.into_owned()),
+            RData::DNAME(dname) => RData::DNAME(dname.into_owned()),
+            RData::Unknown(code, data) => RData::Unknown(code, Cow::Owned(data.into_owned())),
+        }
+    }
+
+    /// Returns the type of the record data
+    pub fn typ(&self) -> Type {
+        match *self {
+            RData::A(..) => Type::A,
+            RData::AAAA(..) => Type::AAAA,
+            RData::CNAME(..) => Type::CNAME,
+            RData::MX(..) => Type::MX,
+            RData::NS(..) => Type::NS,
+            RData::PTR(..) => Type::PTR,
+            RData::SOA(..) => Type::SOA,
+            RData::SRV(..) => Type::SRV,
+            RData::TXT(..) => Type::TXT,
+            RData::OPT(..) => Type::OPT,
+            RData::DNSKEY(..) => Type::DNSKEY,
+            RData::NSEC3PARAM(..) => Type::NSEC3PARAM,
+            RData::RRSIG(..) => Type::RRSIG,
+            RData::NSEC(..) => Type::NSEC,
+            RData::NSEC3(..) => Type::NSEC3,
+            RData::CSYNC(..) => Type::CSYNC,
+            RData::DS(..) => Type::DS,
+            RData::ZONEMD(..) => Type::ZONEMD,
+            RData::DNAME(..) => Type::DNAME,
+            RData::Unknown(code, _) => Type::parse(code),
+        }
+    }
+
+    /// Returns the domain names embedded in the record data
+    ///
+    /// E.g. the target of a CNAME record or both names of an SOA record.
+    pub fn names<'s>(&'s self) -> impl Iterator<Item = &'s Name<'a>> + 's {
+        let names = match *self {
+            RData::CNAME(ref cname) => [Some(&cname.0), None],
+            RData::NS(ref ns) => [Some(&ns.0), None],
+            RData::PTR(ref ptr) => [Some(&ptr.0), None],
+            RData::MX(ref mx) => [Some(&mx.exchange), None],
+            RData::SRV(ref srv) => [Some(&srv.target), None],
+            RData::SOA(ref soa) => [Some(&soa.primary_ns), Some(&soa.mailbox)],
+            RData::RRSIG(ref sig) => [Some(&sig.signer_name), None],
+            RData::NSEC(ref nsec) => [Some(&nsec.next_name), None],
+            RData::DNAME(ref dname) => [Some(&dname.0), None],
+            _ => [None, None],
+        };
+        IntoIterator::into_iter(names).flatten()
+    }
+
+    /// Writes the record data in the canonical form (RFC 4034 section 6.2)
+    ///
+    /// Same as `write_to`, but names are lowercased.
+    pub fn write_canonical_to(&self, buf: &mut Vec<u8>) {
+        match *self {
+            RData::CNAME(ref cname) => cname.0.write_canonical_to(buf),
+            RData::NS(ref ns) => ns.0.write_canonical_to(buf),
+            RData::PTR(ref ptr) => ptr.0.write_canonical_to(buf),
+            RData::DNAME(ref dname) => dname.0.write_canonical_to(buf),
+            RData::MX(ref mx) => {
+                buf.extend_from_slice(&mx.preference.to_be_bytes());
+                mx.exchange.write_canonical_to(buf);
+            }
+            RData::SOA(ref soa) => {
+                soa.primary_ns.write_canonical_to(buf);
+                soa.mailbox.write_canonical_to(buf);
+                for value in &[
+                    soa.serial,
+                    soa.refresh,
+                    soa.retry,
+                    soa.expire,
+                    soa.minimum_ttl,
+                ] {
+                    buf.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+            RData::SRV(ref srv) => {
+                for value in &[srv.priority, srv.weight, srv.port] {
+                    buf.extend_from_slice(&value.to_be_bytes());
+                }
+                srv.target.write_canonical_to(buf);
+            }
+            RData::RRSIG(ref sig) => sig.write_canonical_to(buf),
+            _ => self.write_to(buf),
+        }
+    }
+
+    /// Writes the record data in the wire format
+    ///
+    /// Names are written uncompressed.
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        match *self {
+            RData::A(ref a) => buf.extend_from_slice(&a.0.octets()),
+            RData::AAAA(ref aaaa) => buf.extend_from_slice(&aaaa.0.octets()),
+            RData::CNAME(ref cname) => cname.write_to(buf),
+            RData::MX(ref mx) => mx.write_to(buf),
+            RData::NS(ref ns) => ns.write_to(buf),
+            RData::PTR(ref ptr) => ptr.write_to(buf),
+            RData::SOA(ref soa) => soa.write_to(buf),
+            RData::SRV(ref srv) => srv.write_to(buf),
+            RData::TXT(ref txt) => txt.write_to(buf),
+            RData::OPT(ref opt) => buf.extend_from_slice(opt),
+            RData::DNSKEY(ref key) => key.write_to(buf),
+            RData::NSEC3PARAM(ref param) => param.write_to(buf),
+            RData::RRSIG(ref sig) => sig.write_to(buf),
+            RData::NSEC(ref nsec) => nsec.write_to(buf),
+            RData::NSEC3(ref nsec3) => nsec3.write_to(buf),
+            RData::CSYNC(ref csync) => csync.write_to(buf),
+            RData::DS(ref ds) => ds.write_to(buf),
+            RData::ZONEMD(ref zonemd) => zonemd.write_to(buf),
+            RData::DNAME(ref dname) => dname.write_to(buf),
+            RData::Unknown(_, ref data) => buf.extend_from_slice(data),
+        }
+    }
 }
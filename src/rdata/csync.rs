@@ -0,0 +1,81 @@
+use std::convert::TryInto;
+
+use crate::{Error, Type, TypeBitmap};
+
+/// The CSYNC resource record (RFC 7477)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub serial: u32,
+    pub flags: u16,
+    /// Types to be synchronized from the child zone
+    pub types: TypeBitmap<'a>,
+}
+
+impl<'a> Record<'a> {
+    /// Flag requiring the SOA serial to be at least `serial`
+    pub const IMMEDIATE: u16 = 0x0001;
+    /// Flag requiring the serial to be checked against the SOA
+    pub const SOA_MINIMUM: u16 = 0x0002;
+
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            serial: self.serial,
+            flags: self.flags,
+            types: self.types.into_owned(),
+        }
+    }
+
+    /// Writes the record data in the wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.serial.to_be_bytes());
+        buf.extend_from_slice(&self.flags.to_be_bytes());
+        self.types.write_to(buf);
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+    const TYPE: isize = 62;
+
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+        if rdata.len() < 6 {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::CSYNC,
+                expected: 6,
+                got: rdata.len(),
+            });
+        }
+        Ok(super::RData::CSYNC(Record {
+            serial: u32::from_be_bytes(rdata[..4].try_into().unwrap()),
+            flags: u16::from_be_bytes(rdata[4..6].try_into().unwrap()),
+            types: TypeBitmap::parse(&rdata[6..])?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::{Packet, RData, Type};
+
+    #[test]
+    fn parse_response() {
+        // example from RFC 7477 section 2.1.1
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x3e\x00\x01\x00\x00\x0e\x10\x00\x0c\
+                         \x42\x00\x00\x00\x00\x03\x00\x04\x60\x00\x00\x08";
+        let packet = Packet::parse(response).unwrap();
+        match packet.answers()[0].data {
+            RData::CSYNC(ref csync) => {
+                assert_eq!(csync.serial, 0x4200_0000);
+                assert_eq!(csync.flags, Record::IMMEDIATE | Record::SOA_MINIMUM);
+                assert_eq!(
+                    csync.types.types().collect::<Vec<_>>(),
+                    [Type::A, Type::NS, Type::AAAA]
+                );
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+}
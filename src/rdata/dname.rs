@@ -1,11 +1,53 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Record;
+use crate::Name;
 
-impl<'a> super::Record<'a> for Record {
+use std::fmt;
 
+/// The DNAME record (RFC 6672), redirecting the names below the owner
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record<'a>(pub Name<'a>);
+
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record(self.0.into_owned())
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        self.0.write_to(buf);
+    }
+}
+
+impl<'a> fmt::Display for Record<'a> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 39;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
+        let name = Name::scan(rdata, original)?;
+        Ok(super::RData::DNAME(Record(name)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{Packet, RData};
+
+    #[test]
+    fn parse_response() {
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x27\x00\x01\x00\x00\x0e\x10\x00\x0d\
+                         \x07example\x03net\x00";
+        let packet = Packet::parse(response).unwrap();
+        match packet.answers()[0].data {
+            RData::DNAME(ref dname) => assert_eq!(dname.to_string(), "example.net"),
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
     }
 }
@@ -1,11 +1,134 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Record;
+use std::borrow::Cow;
+use std::convert::TryInto;
 
-impl<'a> super::Record<'a> for Record {
+use crate::{Error, Type};
 
+/// The DNSKEY resource record (RFC 4034)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub flags: u16,
+    pub protocol: u8,
+    pub algorithm: u8,
+    pub public_key: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Flag of keys which may sign zone data
+    pub const ZONE_KEY: u16 = 0x0100;
+    /// Flag of key signing keys
+    pub const SECURE_ENTRY_POINT: u16 = 0x0001;
+    /// Flag of revoked keys (RFC 5011)
+    pub const REVOKED: u16 = 0x0080;
+
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            flags: self.flags,
+            protocol: self.protocol,
+            algorithm: self.algorithm,
+            public_key: Cow::Owned(self.public_key.into_owned()),
+        }
+    }
+
+    /// Writes the record data in the wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.flags.to_be_bytes());
+        buf.push(self.protocol);
+        buf.push(self.algorithm);
+        buf.extend_from_slice(&self.public_key);
+    }
+
+    /// Computes the key tag (RFC 4034 appendix B)
+    ///
+    /// The tag identifies the key in RRSIG and DS records. Note that tags
+    /// are not unique, several keys may share the same tag.
+    pub fn key_tag(&self) -> u16 {
+        // RSA/MD5 keys take the tag from the modulus
+        if self.algorithm == 1 {
+            let key = &self.public_key;
+            if key.len() < 3 {
+                return 0;
+            }
+            return u16::from_be_bytes([key[key.len() - 3], key[key.len() - 2]]);
+        }
+        let mut rdata = Vec::with_capacity(4 + self.public_key.len());
+        self.write_to(&mut rdata);
+        let mut acc: u32 = 0;
+        for (i, &byte) in rdata.iter().enumerate() {
+            acc += if i % 2 == 0 {
+                (byte as u32) << 8
+            } else {
+                byte as u32
+            };
+        }
+        acc += (acc >> 16) & 0xFFFF;
+        acc as u16
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 48;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+        if rdata.len() < 4 {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::DNSKEY,
+                expected: 4,
+                got: rdata.len(),
+            });
+        }
+        Ok(super::RData::DNSKEY(Record {
+            flags: u16::from_be_bytes(rdata[..2].try_into().unwrap()),
+            protocol: rdata[2],
+            algorithm: rdata[3],
+            public_key: Cow::Borrowed(&rdata[4..]),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::{Packet, RData};
+
+    // example.com key from RFC 4034 section 2.3
+    const KEY: &[u8] = b"\x01\x03\xd2\x2a\x6c\xa7\x7f\x35\xb8\x93\x20\x6f\xd3\x5e\x4c\x50\
+        \x6d\x83\x78\x84\x37\x09\xb9\x7e\x04\x16\x47\xe1\xbf\xf4\x3d\x8d\x64\xc6\x49\xaf\
+        \x1e\x37\x19\x73\xc9\xe8\x91\xfc\xe3\xdf\x51\x9a\x8c\x84\x0a\x63\xee\x42\xa6\xd2\
+        \xeb\xdd\xbb\x97\x03\x5d\x21\x5a\xa4\xe4\x17\xb1\xfa\x45\xfa\x11\xa9\x74\x1e\xa2\
+        \x09\x8c\x1d\xfa\x5f\xb5\xfe\xb3\x32\xfd\x4b\xc8\x15\x20\x89\xae\xf3\x6b\xa6\x44\
+        \xcc\xe2\x41\x3b\x3b\x72\xbe\x18\xcb\xef\x8d\xa2\x53\xf4\xe9\x3d\x21\x03\x86\x6d\
+        \x92\x34\xa2\xe2\x8d\xf5\x29\xa6\x7d\x54\x68\xdb\xef\xe3";
+
+    #[test]
+    fn parse_response() {
+        let mut response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                             \x07example\x03com\x00\x00\x30\x00\x01\x00\x01\x51\x80\x00\x86\
+                             \x01\x00\x03\x05"
+            .to_vec();
+        response.extend_from_slice(KEY);
+        let packet = Packet::parse(&response).unwrap();
+        match packet.answers()[0].data {
+            RData::DNSKEY(ref key) => {
+                assert_eq!(key.flags, Record::ZONE_KEY);
+                assert_eq!(key.protocol, 3);
+                assert_eq!(key.algorithm, 5);
+                assert_eq!(&key.public_key[..], KEY);
+                assert_eq!(key.key_tag(), 2642);
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn rsa_md5_key_tag() {
+        let key = Record {
+            flags: 256,
+            protocol: 3,
+            algorithm: 1,
+            public_key: Cow::Borrowed(b"\x01\x03\xab\xcd\x12\x34\x56"),
+        };
+        assert_eq!(key.key_tag(), 0x1234);
     }
 }
@@ -1,11 +1,77 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Record;
+use std::borrow::Cow;
+use std::convert::TryInto;
 
-impl<'a> super::Record<'a> for Record {
+use crate::{Error, Type};
 
+/// The DS resource record (RFC 4034)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub key_tag: u16,
+    pub algorithm: u8,
+    pub digest_type: u8,
+    pub digest: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            key_tag: self.key_tag,
+            algorithm: self.algorithm,
+            digest_type: self.digest_type,
+            digest: Cow::Owned(self.digest.into_owned()),
+        }
+    }
+
+    /// Writes the record data in the wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.key_tag.to_be_bytes());
+        buf.push(self.algorithm);
+        buf.push(self.digest_type);
+        buf.extend_from_slice(&self.digest);
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 43;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+        if rdata.len() < 4 {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::DS,
+                expected: 4,
+                got: rdata.len(),
+            });
+        }
+        Ok(super::RData::DS(Record {
+            key_tag: u16::from_be_bytes(rdata[..2].try_into().unwrap()),
+            algorithm: rdata[2],
+            digest_type: rdata[3],
+            digest: Cow::Borrowed(&rdata[4..]),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{Packet, RData};
+
+    #[test]
+    fn parse_response() {
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x05dskey\x07example\x03com\x00\x00\x2b\x00\x01\x00\x01\x51\x80\x00\x18\
+                         \xec\x45\x05\x01\x2b\xb1\x83\xaf\x5f\x22\x58\x81\x79\xa5\x3b\x0a\
+                         \x98\x63\x1f\xad\x1a\x29\x21\x18";
+        let packet = Packet::parse(response).unwrap();
+        match packet.answers()[0].data {
+            RData::DS(ref ds) => {
+                assert_eq!(ds.key_tag, 60485);
+                assert_eq!(ds.algorithm, 5);
+                assert_eq!(ds.digest_type, 1);
+                assert_eq!(ds.digest.len(), 20);
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
     }
 }
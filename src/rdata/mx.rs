@@ -1,19 +1,39 @@
-use crate::{Error, Name};
+use crate::{Error, Name, Type};
 
 use std::convert::TryInto;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Record<'a> {
     pub preference: u16,
     pub exchange: Name<'a>,
 }
 
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            preference: self.preference,
+            exchange: self.exchange.into_owned(),
+        }
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.preference.to_be_bytes());
+        self.exchange.write_to(buf);
+    }
+}
+
 impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 15;
 
     fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
         if rdata.len() < 3 {
-            return Err(Error::WrongRdataLength);
+            return Err(Error::InvalidRdataLength {
+                typ: Type::MX,
+                expected: 3,
+                got: rdata.len(),
+            });
         }
         let record = Record {
             preference: u16::from_be_bytes(rdata[..2].try_into().unwrap()),
@@ -47,7 +67,7 @@ mod test {
             \x00\x01\x00\x00\x04|\x00\t\x00\x1e\x04alt3\xc0)";
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 58344,
                 query: false,
@@ -58,6 +78,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 5,
@@ -65,29 +86,29 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::MX);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "gmail.com");
-        assert_eq!(packet.answers.len(), 5);
-        let items = vec![
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::MX);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(&packet.questions()[0].qname.to_string()[..], "gmail.com");
+        assert_eq!(packet.answers().len(), 5);
+        let items = [
             (5, "gmail-smtp-in.l.google.com"),
             (10, "alt1.gmail-smtp-in.l.google.com"),
             (40, "alt4.gmail-smtp-in.l.google.com"),
             (20, "alt2.gmail-smtp-in.l.google.com"),
             (30, "alt3.gmail-smtp-in.l.google.com"),
         ];
-        for i in 0..5 {
-            assert_eq!(&packet.answers[i].name.to_string()[..], "gmail.com");
-            assert_eq!(packet.answers[i].cls, C::IN);
-            assert_eq!(packet.answers[i].ttl, 1148);
-            match *&packet.answers[i].data {
+        for (i, item) in items.iter().enumerate() {
+            assert_eq!(&packet.answers()[i].name.to_string()[..], "gmail.com");
+            assert_eq!(packet.answers()[i].cls, C::IN);
+            assert_eq!(packet.answers()[i].ttl, 1148);
+            match packet.answers()[i].data {
                 RData::MX(Record {
                     preference,
-                    exchange,
+                    ref exchange,
                 }) => {
-                    assert_eq!(preference, items[i].0);
-                    assert_eq!(exchange.to_string(), (items[i].1).to_string());
+                    assert_eq!(preference, item.0);
+                    assert_eq!(exchange.to_string(), (item.1).to_string());
                 }
                 ref x => panic!("Wrong rdata {:?}", x),
             }
@@ -1,12 +1,26 @@
 use crate::Name;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Record<'a>(pub Name<'a>);
 
-impl<'a> ToString for Record<'a> {
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record(self.0.into_owned())
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        self.0.write_to(buf);
+    }
+}
+
+impl<'a> fmt::Display for Record<'a> {
     #[inline]
-    fn to_string(&self) -> String {
-        self.0.to_string()
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
     }
 }
 
@@ -44,7 +58,7 @@ mod test {
                          \xc0\x42";
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 19184,
                 query: false,
@@ -55,6 +69,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 1,
@@ -62,26 +77,26 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "www.skype.com");
-        assert_eq!(packet.answers.len(), 1);
-        assert_eq!(&packet.answers[0].name.to_string()[..], "www.skype.com");
-        assert_eq!(packet.answers[0].cls, C::IN);
-        assert_eq!(packet.answers[0].ttl, 3600);
-        match packet.answers[0].data {
-            RData::CNAME(cname) => {
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(&packet.questions()[0].qname.to_string()[..], "www.skype.com");
+        assert_eq!(packet.answers().len(), 1);
+        assert_eq!(&packet.answers()[0].name.to_string()[..], "www.skype.com");
+        assert_eq!(packet.answers()[0].cls, C::IN);
+        assert_eq!(packet.answers()[0].ttl, 3600);
+        match packet.answers()[0].data {
+            RData::CNAME(ref cname) => {
                 assert_eq!(&cname.0.to_string()[..], "livecms.trafficmanager.net");
             }
             ref x => panic!("Wrong rdata {:?}", x),
         }
-        assert_eq!(packet.nameservers.len(), 1);
-        assert_eq!(&packet.nameservers[0].name.to_string()[..], "net");
-        assert_eq!(packet.nameservers[0].cls, C::IN);
-        assert_eq!(packet.nameservers[0].ttl, 120275);
-        match packet.nameservers[0].data {
-            RData::NS(ns) => {
+        assert_eq!(packet.nameservers().len(), 1);
+        assert_eq!(&packet.nameservers()[0].name.to_string()[..], "net");
+        assert_eq!(packet.nameservers()[0].cls, C::IN);
+        assert_eq!(packet.nameservers()[0].ttl, 120275);
+        match packet.nameservers()[0].data {
+            RData::NS(ref ns) => {
                 assert_eq!(&ns.0.to_string()[..], "g.gtld-servers.net");
             }
             ref x => panic!("Wrong rdata {:?}", x),
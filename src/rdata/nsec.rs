@@ -1,11 +1,55 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Record;
+use crate::{Name, TypeBitmap};
 
-impl<'a> super::Record<'a> for Record {
+/// The NSEC resource record (RFC 4034)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record<'a> {
+    pub next_name: Name<'a>,
+    pub types: TypeBitmap<'a>,
+}
+
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            next_name: self.next_name.into_owned(),
+            types: self.types.into_owned(),
+        }
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        self.next_name.write_to(buf);
+        self.types.write_to(buf);
+    }
+}
 
+impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 47;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
+        let next_name = Name::scan(rdata, original)?;
+        let types = TypeBitmap::parse(&rdata[next_name.byte_len()..])?;
+        Ok(super::RData::NSEC(Record { next_name, types }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{Packet, RData};
+
+    #[test]
+    fn parse_response() {
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x04alfa\x07example\x03com\x00\x00\x2f\x00\x01\x00\x00\x0e\x10\x00\x0f\
+                         \x04host\xc0\x11\x00\x06\x40\x01\x00\x00\x00\x03";
+        let packet = Packet::parse(response).unwrap();
+        match packet.answers()[0].data {
+            RData::NSEC(ref nsec) => {
+                assert_eq!(nsec.next_name.to_string(), "host.example.com");
+                assert_eq!(nsec.types.codes().collect::<Vec<_>>(), [1, 15, 46, 47]);
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
     }
 }
@@ -1,11 +1,131 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Record;
+use std::borrow::Cow;
+use std::convert::TryInto;
 
-impl<'a> super::Record<'a> for Record {
+use crate::{Error, Type, TypeBitmap};
 
+/// The NSEC3 resource record (RFC 5155)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub hash_algorithm: u8,
+    pub flags: u8,
+    pub iterations: u16,
+    pub salt: Cow<'a, [u8]>,
+    /// The next hashed owner name, as a raw hash
+    pub next_hashed_owner: Cow<'a, [u8]>,
+    pub types: TypeBitmap<'a>,
+}
+
+impl<'a> Record<'a> {
+    /// Flag of records which may cover unsigned delegations
+    pub const OPT_OUT: u8 = 0x01;
+
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            hash_algorithm: self.hash_algorithm,
+            flags: self.flags,
+            iterations: self.iterations,
+            salt: Cow::Owned(self.salt.into_owned()),
+            next_hashed_owner: Cow::Owned(self.next_hashed_owner.into_owned()),
+            types: self.types.into_owned(),
+        }
+    }
+
+    /// Writes the record data in the wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.push(self.hash_algorithm);
+        buf.push(self.flags);
+        buf.extend_from_slice(&self.iterations.to_be_bytes());
+        buf.push(self.salt.len() as u8);
+        buf.extend_from_slice(&self.salt);
+        buf.push(self.next_hashed_owner.len() as u8);
+        buf.extend_from_slice(&self.next_hashed_owner);
+        self.types.write_to(buf);
+    }
+
+    /// Returns true if the opt-out flag is set
+    pub fn opt_out(&self) -> bool {
+        self.flags & Record::OPT_OUT != 0
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 50;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+        if rdata.len() < 5 {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::NSEC3,
+                expected: 5,
+                got: rdata.len(),
+            });
+        }
+        let salt_end = 5 + rdata[4] as usize;
+        if rdata.len() < salt_end + 1 {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::NSEC3,
+                expected: salt_end + 1,
+                got: rdata.len(),
+            });
+        }
+        let hash_end = salt_end + 1 + rdata[salt_end] as usize;
+        if rdata.len() < hash_end {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::NSEC3,
+                expected: hash_end,
+                got: rdata.len(),
+            });
+        }
+        Ok(super::RData::NSEC3(Record {
+            hash_algorithm: rdata[0],
+            flags: rdata[1],
+            iterations: u16::from_be_bytes(rdata[2..4].try_into().unwrap()),
+            salt: Cow::Borrowed(&rdata[5..salt_end]),
+            next_hashed_owner: Cow::Borrowed(&rdata[salt_end + 1..hash_end]),
+            types: TypeBitmap::parse(&rdata[hash_end..])?,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::dnssec::encode_base32hex;
+    use crate::{Packet, RData, Type};
+
+    #[test]
+    fn parse_response() {
+        // 0p9mhaveqvm6t7vbl5lop2u3t2rp3tom.example from RFC 5155 appendix A
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x200p9mhaveqvm6t7vbl5lop2u3t2rp3tom\x07example\x00\
+                         \x00\x32\x00\x01\x00\x00\x0e\x10\x00\x27\
+                         \x01\x01\x00\x0c\x04\xaa\xbb\xcc\xdd\x14\
+                         \x17\x4e\xb2\x40\x9f\xe2\x8b\xcb\x48\x87\xa1\x83\x6f\x95\x7f\x0a\x84\x25\xe2\x7b\
+                         \x00\x07\x22\x01\x00\x00\x00\x02\x90";
+        let packet = Packet::parse(response).unwrap();
+        match packet.answers()[0].data {
+            RData::NSEC3(ref nsec3) => {
+                assert_eq!(nsec3.hash_algorithm, 1);
+                assert!(nsec3.opt_out());
+                assert_eq!(nsec3.iterations, 12);
+                assert_eq!(&nsec3.salt[..], b"\xaa\xbb\xcc\xdd");
+                assert_eq!(
+                    encode_base32hex(&nsec3.next_hashed_owner),
+                    "2t7b4g4vsa5smi47k61mv5bv1a22bojr"
+                );
+                assert_eq!(
+                    nsec3.types.types().collect::<Vec<_>>(),
+                    [
+                        Type::NS,
+                        Type::SOA,
+                        Type::MX,
+                        Type::RRSIG,
+                        Type::DNSKEY,
+                        Type::NSEC3PARAM
+                    ]
+                );
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
     }
 }
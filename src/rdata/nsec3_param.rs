@@ -1,11 +1,101 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Record;
+use std::borrow::Cow;
+use std::convert::TryInto;
 
-impl<'a> super::Record<'a> for Record {
+use crate::dnssec::{encode_base32hex, nsec3_hash};
+use crate::{Error, Name, Type};
 
+/// The NSEC3PARAM resource record (RFC 5155)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    pub hash_algorithm: u8,
+    pub flags: u8,
+    pub iterations: u16,
+    pub salt: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            hash_algorithm: self.hash_algorithm,
+            flags: self.flags,
+            iterations: self.iterations,
+            salt: Cow::Owned(self.salt.into_owned()),
+        }
+    }
+
+    /// Writes the record data in the wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.push(self.hash_algorithm);
+        buf.push(self.flags);
+        buf.extend_from_slice(&self.iterations.to_be_bytes());
+        buf.push(self.salt.len() as u8);
+        buf.extend_from_slice(&self.salt);
+    }
+
+    /// Returns the hashed owner name of `name` in base32hex
+    ///
+    /// This is the first label of the NSEC3 record matching the name.
+    pub fn hash_name(&self, name: &Name<'_>) -> Result<String, Error> {
+        let digest = nsec3_hash(name, self.hash_algorithm, self.iterations, &self.salt)?;
+        Ok(encode_base32hex(&digest))
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 51;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+        let expected = rdata.get(4).map_or(5, |&salt_len| 5 + salt_len as usize);
+        if rdata.len() != expected {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::NSEC3PARAM,
+                expected,
+                got: rdata.len(),
+            });
+        }
+        Ok(super::RData::NSEC3PARAM(Record {
+            hash_algorithm: rdata[0],
+            flags: rdata[1],
+            iterations: u16::from_be_bytes(rdata[2..4].try_into().unwrap()),
+            salt: Cow::Borrowed(&rdata[5..]),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{Error, Packet, RData, Type};
+
+    #[test]
+    fn parse_response() {
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x07example\x00\x00\x33\x00\x01\x00\x00\x0e\x10\x00\x09\
+                         \x01\x00\x00\x0c\x04\xaa\xbb\xcc\xdd";
+        let packet = Packet::parse(response).unwrap();
+        let answer = &packet.answers()[0];
+        match answer.data {
+            RData::NSEC3PARAM(ref param) => {
+                assert_eq!(param.hash_algorithm, 1);
+                assert_eq!(param.iterations, 12);
+                assert_eq!(&param.salt[..], b"\xaa\xbb\xcc\xdd");
+                assert_eq!(
+                    param.hash_name(&answer.name).unwrap(),
+                    "0p9mhaveqvm6t7vbl5lop2u3t2rp3tom"
+                );
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        let mut truncated = response.to_vec();
+        truncated[35] = 8;
+        assert!(matches!(
+            Packet::parse(&truncated).unwrap_err().root(),
+            Error::InvalidRdataLength {
+                typ: Type::NSEC3PARAM,
+                expected: 13,
+                got: 9,
+            }
+        ));
     }
 }
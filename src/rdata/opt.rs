@@ -1,5 +1,7 @@
+use std::borrow::Cow;
+
 /// RFC 6891 OPT RR
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Record<'a> {
     pub udp: u16,
     pub extrcode: u8,
@@ -8,10 +10,23 @@ pub struct Record<'a> {
     pub data: super::RData<'a>,
 }
 
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            udp: self.udp,
+            extrcode: self.extrcode,
+            version: self.version,
+            flags: self.flags,
+            data: self.data.into_owned(),
+        }
+    }
+}
+
 impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 41;
 
     fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        Ok(super::RData::OPT(rdata))
+        Ok(super::RData::OPT(Cow::Borrowed(rdata)))
     }
 }
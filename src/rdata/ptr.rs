@@ -1,12 +1,26 @@
 use crate::Name;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Record<'a>(pub Name<'a>);
 
-impl<'a> ToString for Record<'a> {
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record(self.0.into_owned())
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        self.0.write_to(buf);
+    }
+}
+
+impl<'a> fmt::Display for Record<'a> {
     #[inline]
-    fn to_string(&self) -> String {
-        self.0.to_string()
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
     }
 }
 
@@ -40,7 +54,7 @@ mod test {
                            \x10pool-72-75-93-69\x07verizon\x03net\x00";
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 21462,
                 query: false,
@@ -51,6 +65,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 1,
@@ -58,22 +73,22 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::PTR);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::PTR);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
         assert_eq!(
-            &packet.questions[0].qname.to_string()[..],
+            &packet.questions()[0].qname.to_string()[..],
             "69.93.75.72.in-addr.arpa"
         );
-        assert_eq!(packet.answers.len(), 1);
+        assert_eq!(packet.answers().len(), 1);
         assert_eq!(
-            &packet.answers[0].name.to_string()[..],
+            &packet.answers()[0].name.to_string()[..],
             "69.93.75.72.in-addr.arpa"
         );
-        assert_eq!(packet.answers[0].cls, C::IN);
-        assert_eq!(packet.answers[0].ttl, 86400);
-        match packet.answers[0].data {
-            RData::PTR(name) => {
+        assert_eq!(packet.answers()[0].cls, C::IN);
+        assert_eq!(packet.answers()[0].ttl, 86400);
+        match packet.answers()[0].data {
+            RData::PTR(ref name) => {
                 assert_eq!(&name.0.to_string()[..], "pool-72-75-93-69.verizon.net");
             }
             ref x => panic!("Wrong rdata {:?}", x),
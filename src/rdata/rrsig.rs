@@ -1,11 +1,123 @@
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Record;
+use std::borrow::Cow;
+use std::convert::TryInto;
 
-impl<'a> super::Record<'a> for Record {
+use crate::{Error, Name, Type};
 
+/// The RRSIG resource record (RFC 4034)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record<'a> {
+    /// Type code of the RRset covered by the signature
+    pub type_covered: u16,
+    pub algorithm: u8,
+    /// Number of labels of the original owner name, wildcards excluded
+    pub labels: u8,
+    pub original_ttl: u32,
+    /// Signature expiration, seconds since the epoch modulo 2^32
+    pub expiration: u32,
+    /// Signature inception, seconds since the epoch modulo 2^32
+    pub inception: u32,
+    pub key_tag: u16,
+    pub signer_name: Name<'a>,
+    pub signature: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            type_covered: self.type_covered,
+            algorithm: self.algorithm,
+            labels: self.labels,
+            original_ttl: self.original_ttl,
+            expiration: self.expiration,
+            inception: self.inception,
+            key_tag: self.key_tag,
+            signer_name: self.signer_name.into_owned(),
+            signature: Cow::Owned(self.signature.into_owned()),
+        }
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        self.write_header_to(buf, false);
+        buf.extend_from_slice(&self.signature);
+    }
+
+    /// Writes the record data in the canonical form
+    pub fn write_canonical_to(&self, buf: &mut Vec<u8>) {
+        self.write_header_to(buf, true);
+        buf.extend_from_slice(&self.signature);
+    }
+
+    // Writes all fields except the signature
+    pub(crate) fn write_header_to(&self, buf: &mut Vec<u8>, canonical: bool) {
+        buf.extend_from_slice(&self.type_covered.to_be_bytes());
+        buf.push(self.algorithm);
+        buf.push(self.labels);
+        buf.extend_from_slice(&self.original_ttl.to_be_bytes());
+        buf.extend_from_slice(&self.expiration.to_be_bytes());
+        buf.extend_from_slice(&self.inception.to_be_bytes());
+        buf.extend_from_slice(&self.key_tag.to_be_bytes());
+        if canonical {
+            self.signer_name.write_canonical_to(buf);
+        } else {
+            self.signer_name.write_to(buf);
+        }
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 46;
 
-    fn parse(_rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
-        unimplemented!();
+    fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
+        if rdata.len() < 19 {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::RRSIG,
+                expected: 19,
+                got: rdata.len(),
+            });
+        }
+        let signer_name = Name::scan(&rdata[18..], original)?;
+        let signature = &rdata[18 + signer_name.byte_len()..];
+        Ok(super::RData::RRSIG(Record {
+            type_covered: u16::from_be_bytes(rdata[..2].try_into().unwrap()),
+            algorithm: rdata[2],
+            labels: rdata[3],
+            original_ttl: u32::from_be_bytes(rdata[4..8].try_into().unwrap()),
+            expiration: u32::from_be_bytes(rdata[8..12].try_into().unwrap()),
+            inception: u32::from_be_bytes(rdata[12..16].try_into().unwrap()),
+            key_tag: u16::from_be_bytes(rdata[16..18].try_into().unwrap()),
+            signer_name,
+            signature: Cow::Borrowed(signature),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{Packet, RData};
+
+    #[test]
+    fn parse_response() {
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x04host\x07example\x03com\x00\x00\x2e\x00\x01\x00\x00\x0e\x10\x00\x1a\
+                         \x00\x01\x08\x03\x00\x00\x0e\x10\x65\x00\x00\x00\x64\x00\x00\x00\x0a\x52\
+                         \xc0\x11\xde\xad\xbe\xef\xca\xfe";
+        let packet = Packet::parse(response).unwrap();
+        match packet.answers()[0].data {
+            RData::RRSIG(ref sig) => {
+                assert_eq!(sig.type_covered, 1);
+                assert_eq!(sig.algorithm, 8);
+                assert_eq!(sig.labels, 3);
+                assert_eq!(sig.original_ttl, 3600);
+                assert_eq!(sig.expiration, 0x6500_0000);
+                assert_eq!(sig.inception, 0x6400_0000);
+                assert_eq!(sig.key_tag, 2642);
+                assert_eq!(sig.signer_name.to_string(), "example.com");
+                assert_eq!(&sig.signature[..], b"\xde\xad\xbe\xef\xca\xfe");
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
     }
 }
@@ -1,9 +1,10 @@
-use crate::{Error, Name};
+use crate::{Error, Name, SerialNumber, Type};
 
+use std::cmp::Ordering;
 use std::convert::TryInto;
 
 /// The SOA (Start of Authority) record
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Record<'a> {
     pub primary_ns: Name<'a>,
     pub mailbox: Name<'a>,
@@ -14,6 +15,57 @@ pub struct Record<'a> {
     pub minimum_ttl: u32,
 }
 
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            primary_ns: self.primary_ns.into_owned(),
+            mailbox: self.mailbox.into_owned(),
+            serial: self.serial,
+            refresh: self.refresh,
+            retry: self.retry,
+            expire: self.expire,
+            minimum_ttl: self.minimum_ttl,
+        }
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        self.primary_ns.write_to(buf);
+        self.mailbox.write_to(buf);
+        for value in &[
+            self.serial,
+            self.refresh,
+            self.retry,
+            self.expire,
+            self.minimum_ttl,
+        ] {
+            buf.extend_from_slice(&value.to_be_bytes());
+        }
+    }
+
+    /// Returns the serial with the RFC 1982 comparison semantics
+    pub fn serial_number(&self) -> SerialNumber {
+        SerialNumber(self.serial)
+    }
+
+    /// Compares the serials of two versions of a zone
+    ///
+    /// Returns `None` if the serials are exactly 2^31 apart, in which
+    /// case it is undefined which one is newer.
+    pub fn cmp_serial(&self, other: &Record<'_>) -> Option<Ordering> {
+        self.serial_number().partial_cmp(&other.serial_number())
+    }
+
+    /// Returns true if this version of the zone has a greater serial
+    ///
+    /// A secondary server transfers the zone when the SOA of the primary
+    /// is newer than its own.
+    pub fn is_newer_than(&self, other: &Record<'_>) -> bool {
+        self.serial_number() > other.serial_number()
+    }
+}
+
 impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 6;
 
@@ -24,7 +76,11 @@ impl<'a> super::Record<'a> for Record<'a> {
         let mailbox = Name::scan(&rdata[pos..], original)?;
         pos += mailbox.byte_len();
         if rdata[pos..].len() < 20 {
-            return Err(Error::WrongRdataLength);
+            return Err(Error::InvalidRdataLength {
+                typ: Type::SOA,
+                expected: pos + 20,
+                got: rdata.len(),
+            });
         }
         let record = Record {
             primary_ns: primary_name_server,
@@ -41,14 +97,16 @@ impl<'a> super::Record<'a> for Record<'a> {
 
 #[cfg(test)]
 mod test {
+    use std::cmp::Ordering;
 
+    use super::Record;
     use crate::Class as C;
     use crate::Opcode::*;
     use crate::QueryClass as QC;
     use crate::QueryType as QT;
     use crate::RData;
     use crate::ResponseCode::NameError;
-    use crate::{Header, Packet};
+    use crate::{Header, Name, Packet, SerialNumber};
 
     #[test]
     fn parse_response() {
@@ -59,7 +117,7 @@ mod test {
                           \x0e\x10\x00\x00\x3a\x80\x00\x00\x2a\x30";
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 40901,
                 query: false,
@@ -70,6 +128,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NameError,
                 questions: 1,
                 answers: 0,
@@ -77,21 +136,21 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::A);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::A);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
         assert_eq!(
-            &packet.questions[0].qname.to_string()[..],
+            &packet.questions()[0].qname.to_string()[..],
             "dlkfjkdjdslfkj.youtube.com"
         );
-        assert_eq!(packet.answers.len(), 0);
+        assert_eq!(packet.answers().len(), 0);
 
-        assert_eq!(packet.nameservers.len(), 1);
-        assert_eq!(&packet.nameservers[0].name.to_string()[..], "youtube.com");
-        assert_eq!(packet.nameservers[0].cls, C::IN);
-        assert_eq!(packet.nameservers[0].multicast_unique, false);
-        assert_eq!(packet.nameservers[0].ttl, 10800);
-        match packet.nameservers[0].data {
+        assert_eq!(packet.nameservers().len(), 1);
+        assert_eq!(&packet.nameservers()[0].name.to_string()[..], "youtube.com");
+        assert_eq!(packet.nameservers()[0].cls, C::IN);
+        assert!(!packet.nameservers()[0].multicast_unique);
+        assert_eq!(packet.nameservers()[0].ttl, 10800);
+        match packet.nameservers()[0].data {
             RData::SOA(ref soa_rec) => {
                 assert_eq!(&soa_rec.primary_ns.to_string()[..], "youtube.com");
                 assert_eq!(&soa_rec.mailbox.to_string()[..], "admin.youtube.com");
@@ -104,4 +163,24 @@ mod test {
             ref x => panic!("Wrong rdata {:?}", x),
         }
     }
+
+    #[test]
+    fn serial_compare() {
+        let name = Name::scan(b"\x00", b"\x00").unwrap();
+        let soa = |serial| Record {
+            primary_ns: name.clone(),
+            mailbox: name.clone(),
+            serial,
+            refresh: 0,
+            retry: 0,
+            expire: 0,
+            minimum_ttl: 0,
+        };
+        assert!(soa(1).is_newer_than(&soa(0xFFFF_FFFF)));
+        assert!(!soa(0xFFFF_FFFF).is_newer_than(&soa(1)));
+        assert!(!soa(7).is_newer_than(&soa(7)));
+        assert_eq!(soa(2).cmp_serial(&soa(3)), Some(Ordering::Less));
+        assert_eq!(soa(0).cmp_serial(&soa(0x8000_0000)), None);
+        assert_eq!(soa(2012031603).serial_number(), SerialNumber(2012031603));
+    }
 }
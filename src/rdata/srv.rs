@@ -1,8 +1,8 @@
-use crate::{Error, Name};
+use crate::{Error, Name, Type};
 
 use std::convert::TryInto;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Record<'a> {
     pub priority: u16,
     pub weight: u16,
@@ -10,12 +10,84 @@ pub struct Record<'a> {
     pub target: Name<'a>,
 }
 
+impl<'a> Record<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            target: self.target.into_owned(),
+            ..self
+        }
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.priority.to_be_bytes());
+        buf.extend_from_slice(&self.weight.to_be_bytes());
+        buf.extend_from_slice(&self.port.to_be_bytes());
+        self.target.write_to(buf);
+    }
+}
+
+/// Orders SRV records in which their targets should be tried (RFC 2782)
+///
+/// Records are tried by priority, lowest first. Records of the same
+/// priority are picked one at a time at random, with a chance
+/// proportional to their weight. Zero weight records are only picked
+/// first when the random number is zero, so they are tried last in
+/// practice.
+///
+/// `random(max)` must return a uniformly distributed number from 0 to
+/// `max` inclusive, e.g. `rng.gen_range(0..=max)` with the `rand` crate.
+///
+/// Records with the target "." are left out, such a record means the
+/// service is not available at this domain.
+pub fn select<'r, 'a, I, F>(records: I, mut random: F) -> Vec<&'r Record<'a>>
+where
+    I: IntoIterator<Item = &'r Record<'a>>,
+    F: FnMut(u32) -> u32,
+    'a: 'r,
+{
+    let mut records = records
+        .into_iter()
+        .filter(|record| record.target.labels().next().is_some())
+        .collect::<Vec<_>>();
+    // zero weight records go first within a priority
+    records.sort_by_key(|record| (record.priority, record.weight != 0));
+    let mut result = Vec::with_capacity(records.len());
+    while !records.is_empty() {
+        let priority = records[0].priority;
+        let end = records
+            .iter()
+            .position(|record| record.priority != priority)
+            .unwrap_or(records.len());
+        let mut group = records.drain(..end).collect::<Vec<_>>();
+        while !group.is_empty() {
+            let total = group.iter().map(|record| record.weight as u32).sum();
+            let value = random(total).min(total);
+            let mut sum = 0;
+            let index = group
+                .iter()
+                .position(|record| {
+                    sum += record.weight as u32;
+                    sum >= value
+                })
+                .unwrap();
+            result.push(group.remove(index));
+        }
+    }
+    result
+}
+
 impl<'a> super::Record<'a> for Record<'a> {
     const TYPE: isize = 33;
 
     fn parse(rdata: &'a [u8], original: &'a [u8]) -> super::RDataResult<'a> {
         if rdata.len() < 7 {
-            return Err(Error::WrongRdataLength);
+            return Err(Error::InvalidRdataLength {
+                typ: Type::SRV,
+                expected: 7,
+                got: rdata.len(),
+            });
         }
         let record = Record {
             priority: u16::from_be_bytes(rdata[..2].try_into().unwrap()),
@@ -55,7 +127,7 @@ mod test {
             \x14\x95\x04alt4\x0bxmpp-server\x01l\x06google\x03com\x00";
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 23513,
                 query: false,
@@ -66,6 +138,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 5,
@@ -73,42 +146,75 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::SRV);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::SRV);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
         assert_eq!(
-            &packet.questions[0].qname.to_string()[..],
+            &packet.questions()[0].qname.to_string()[..],
             "_xmpp-server._tcp.gmail.com"
         );
-        assert_eq!(packet.answers.len(), 5);
-        let items = vec![
+        assert_eq!(packet.answers().len(), 5);
+        let items = [
             (5, 0, 5269, "xmpp-server.l.google.com"),
             (20, 0, 5269, "alt3.xmpp-server.l.google.com"),
             (20, 0, 5269, "alt1.xmpp-server.l.google.com"),
             (20, 0, 5269, "alt2.xmpp-server.l.google.com"),
             (20, 0, 5269, "alt4.xmpp-server.l.google.com"),
         ];
-        for i in 0..5 {
+        for (i, item) in items.iter().enumerate() {
             assert_eq!(
-                &packet.answers[i].name.to_string()[..],
+                &packet.answers()[i].name.to_string()[..],
                 "_xmpp-server._tcp.gmail.com"
             );
-            assert_eq!(packet.answers[i].cls, C::IN);
-            assert_eq!(packet.answers[i].ttl, 900);
-            match *&packet.answers[i].data {
+            assert_eq!(packet.answers()[i].cls, C::IN);
+            assert_eq!(packet.answers()[i].ttl, 900);
+            match packet.answers()[i].data {
                 RData::SRV(Record {
                     priority,
                     weight,
                     port,
-                    target,
+                    ref target,
                 }) => {
-                    assert_eq!(priority, items[i].0);
-                    assert_eq!(weight, items[i].1);
-                    assert_eq!(port, items[i].2);
-                    assert_eq!(target.to_string(), (items[i].3).to_string());
+                    assert_eq!(priority, item.0);
+                    assert_eq!(weight, item.1);
+                    assert_eq!(port, item.2);
+                    assert_eq!(target.to_string(), (item.3).to_string());
                 }
                 ref x => panic!("Wrong rdata {:?}", x),
             }
         }
     }
+
+    #[test]
+    fn select_targets() {
+        let name = |text: &[u8]| Name::scan(text, text).unwrap().into_owned();
+        let srv = |priority, weight, target: &[u8]| Record {
+            priority,
+            weight,
+            port: 5269,
+            target: name(target),
+        };
+        let records = [
+            srv(20, 0, b"\x04zero\x00"),
+            srv(10, 60, b"\x05sixty\x00"),
+            srv(10, 40, b"\x05forty\x00"),
+            srv(20, 10, b"\x03ten\x00"),
+        ];
+        let targets = |values: &[u32]| {
+            let mut values = values.iter();
+            select(&records, |max| *values.next().unwrap().min(&max))
+                .into_iter()
+                .map(|record| record.target.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            targets(&[100, 40, 10, 10]),
+            ["forty", "sixty", "ten", "zero"]
+        );
+        assert_eq!(targets(&[60, 40, 0, 0]), ["sixty", "forty", "zero", "ten"]);
+        assert_eq!(targets(&[61, 0, 1, 0]), ["forty", "sixty", "ten", "zero"]);
+
+        let unavailable = [srv(0, 0, b"\x00")];
+        assert!(select(&unavailable, |_| 0).is_empty());
+    }
 }
@@ -1,11 +1,14 @@
-use crate::Error;
+use std::borrow::Cow;
+
+use crate::{Error, Type};
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Record<'a> {
-    bytes: &'a [u8],
+    bytes: Cow<'a, [u8]>,
 }
 
-#[derive(Debug)]
+/// Iterator over the character-strings of a TXT record, see `Record::iter`
+#[derive(Debug, Clone)]
 pub struct RecordIter<'a> {
     bytes: &'a [u8],
 }
@@ -25,10 +28,130 @@ impl<'a> Iterator for RecordIter<'a> {
     }
 }
 
+/// Iterator over `key=value` attributes, see `Record::attributes`
+#[derive(Debug, Clone)]
+pub struct Attributes<'a> {
+    strings: RecordIter<'a>,
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = (&'a [u8], Option<&'a [u8]>);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let string = self.strings.next()?;
+            match find_equals(string) {
+                // no key, to be silently ignored
+                Some(0) => continue,
+                Some(pos) => return Some((&string[..pos], Some(&string[pos + 1..]))),
+                None if string.is_empty() => continue,
+                None => return Some((string, None)),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "memchr")]
+fn find_equals(string: &[u8]) -> Option<usize> {
+    memchr::memchr(b'=', string)
+}
+
+#[cfg(not(feature = "memchr"))]
+fn find_equals(string: &[u8]) -> Option<usize> {
+    string.iter().position(|&b| b == b'=')
+}
+
+/// Maximum length of a character-string
+const MAX_STRING_LEN: usize = 255;
+
+impl Record<'static> {
+    /// Creates a record holding `text`, split into strings of 255 bytes
+    ///
+    /// Use this for values that are joined by the reader, like SPF
+    /// policies or DKIM keys. An empty text gives a single empty string,
+    /// as a TXT record has at least one string.
+    pub fn from_bytes(text: &[u8]) -> Record<'static> {
+        Record::from_strings(Some(text))
+    }
+
+    /// Creates a record holding several strings
+    ///
+    /// Strings longer than 255 bytes are split, so each of them may
+    /// become several strings of the record. Empty strings are kept. No
+    /// strings at all give a single empty string.
+    pub fn from_strings<I, S>(strings: I) -> Record<'static>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<[u8]>,
+    {
+        let mut bytes = Vec::new();
+        for string in strings {
+            let string = string.as_ref();
+            if string.is_empty() {
+                bytes.push(0);
+            }
+            for chunk in string.chunks(MAX_STRING_LEN) {
+                bytes.push(chunk.len() as u8);
+                bytes.extend_from_slice(chunk);
+            }
+        }
+        if bytes.is_empty() {
+            bytes.push(0);
+        }
+        Record {
+            bytes: Cow::Owned(bytes),
+        }
+    }
+}
+
 impl<'a> Record<'a> {
-    // Returns iterator over text chunks
-    pub fn iter(&self) -> RecordIter<'a> {
-        RecordIter { bytes: self.bytes }
+    /// Returns an iterator over the character-strings of the record
+    ///
+    /// The strings are not copied. Boundaries between them are kept,
+    /// see `joined` to get the text as a whole.
+    pub fn iter(&self) -> RecordIter<'_> {
+        RecordIter { bytes: &self.bytes }
+    }
+
+    /// Returns the character-strings concatenated without separators
+    ///
+    /// This is how long texts like SPF policies or DKIM keys are split
+    /// into strings of at most 255 bytes. No copy is made if the record
+    /// has a single string.
+    pub fn joined(&self) -> Cow<'_, [u8]> {
+        let mut strings = self.iter();
+        match (strings.next(), strings.next()) {
+            (None, _) => Cow::Borrowed(&[]),
+            (Some(first), None) => Cow::Borrowed(first),
+            _ => Cow::Owned(self.iter().flatten().cloned().collect()),
+        }
+    }
+
+    /// Returns an iterator over `key=value` attributes (RFC 6763 section 6)
+    ///
+    /// Every character-string is one attribute. The key is the part
+    /// before the first `=` and the value is the rest, which may be empty
+    /// and may contain `=`. A string without `=` yields a boolean
+    /// attribute with no value. Empty strings and strings starting with
+    /// `=` are skipped. Keys should be compared ignoring ASCII case.
+    ///
+    /// For SPF and similar policies, which put several space separated
+    /// terms in one text, split `joined` on spaces instead.
+    pub fn attributes(&self) -> Attributes<'_> {
+        Attributes {
+            strings: self.iter(),
+        }
+    }
+
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            bytes: Cow::Owned(self.bytes.into_owned()),
+        }
+    }
+
+    /// Writes the record data in the uncompressed wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.bytes);
     }
 }
 
@@ -44,26 +167,36 @@ impl<'a> super::Record<'a> for Record<'a> {
             let rdlen = rdata[pos] as usize;
             pos += 1;
             if len < rdlen + pos {
-                return Err(Error::WrongRdataLength);
+                return Err(Error::InvalidRdataLength {
+                    typ: Type::TXT,
+                    expected: rdlen + pos,
+                    got: len,
+                });
             }
             pos += rdlen;
         }
-        Ok(super::RData::TXT(Record { bytes: rdata }))
+        Ok(super::RData::TXT(Record {
+            bytes: Cow::Borrowed(rdata),
+        }))
     }
 }
 
 #[cfg(test)]
 mod test {
 
+    use std::borrow::Cow;
     use std::str::from_utf8;
 
+    use super::Record;
+    use crate::rdata::Record as _;
+
     use crate::Class as C;
     use crate::Opcode::*;
     use crate::QueryClass as QC;
     use crate::QueryType as QT;
     use crate::RData;
     use crate::ResponseCode::NoError;
-    use crate::{Header, Packet};
+    use crate::{Builder, Header, Name, Packet, ResourceRecord, Ttl};
 
     #[test]
     fn parse_response_multiple_strings() {
@@ -76,7 +209,7 @@ mod test {
 
         let packet = Packet::parse(response).unwrap();
         assert_eq!(
-            packet.header,
+            *packet.header(),
             Header {
                 id: 1573,
                 query: false,
@@ -87,6 +220,7 @@ mod test {
                 recursion_available: true,
                 authenticated_data: false,
                 checking_disabled: false,
+                reserved: false,
                 response_code: NoError,
                 questions: 1,
                 answers: 1,
@@ -94,16 +228,16 @@ mod test {
                 additional: 0,
             }
         );
-        assert_eq!(packet.questions.len(), 1);
-        assert_eq!(packet.questions[0].qtype, QT::TXT);
-        assert_eq!(packet.questions[0].qclass, QC::IN);
-        assert_eq!(&packet.questions[0].qname.to_string()[..], "facebook.com");
-        assert_eq!(packet.answers.len(), 1);
-        assert_eq!(&packet.answers[0].name.to_string()[..], "facebook.com");
-        assert_eq!(packet.answers[0].multicast_unique, false);
-        assert_eq!(packet.answers[0].cls, C::IN);
-        assert_eq!(packet.answers[0].ttl, 86333);
-        match packet.answers[0].data {
+        assert_eq!(packet.questions().len(), 1);
+        assert_eq!(packet.questions()[0].qtype, QT::TXT);
+        assert_eq!(packet.questions()[0].qclass, QC::IN);
+        assert_eq!(&packet.questions()[0].qname.to_string()[..], "facebook.com");
+        assert_eq!(packet.answers().len(), 1);
+        assert_eq!(&packet.answers()[0].name.to_string()[..], "facebook.com");
+        assert!(!packet.answers()[0].multicast_unique);
+        assert_eq!(packet.answers()[0].cls, C::IN);
+        assert_eq!(packet.answers()[0].ttl, 86333);
+        match packet.answers()[0].data {
             RData::TXT(ref text) => {
                 assert_eq!(
                     text.iter()
@@ -125,4 +259,64 @@ mod test {
             ref x => panic!("Wrong rdata {:?}", x),
         }
     }
+
+    #[test]
+    fn build_long_text() {
+        let key = (0..600).map(|i| b'a' + (i % 26) as u8).collect::<Vec<_>>();
+        let txt = Record::from_bytes(&key);
+        assert_eq!(
+            txt.iter().map(|s| s.len()).collect::<Vec<_>>(),
+            [255, 255, 90]
+        );
+        assert_eq!(&txt.joined()[..], &key[..]);
+
+        let txt = Record::from_strings(vec![&b"v=DKIM1"[..], b"", &key[..255]]);
+        assert_eq!(
+            txt.iter().collect::<Vec<_>>(),
+            [&b"v=DKIM1"[..], b"", &key[..255]]
+        );
+        assert_eq!(
+            Record::from_strings(Vec::<&str>::new()),
+            Record::from_bytes(b"")
+        );
+        assert_eq!(Record::from_bytes(b"").iter().collect::<Vec<_>>(), [b""]);
+
+        let mut builder = Builder::new_query(1, false);
+        builder.add_answer(&ResourceRecord {
+            name: Name::scan(b"\x00", b"\x00").unwrap(),
+            multicast_unique: false,
+            cls: C::IN,
+            ttl: Ttl(60),
+            data: RData::TXT(Record::from_bytes(&key[..300])),
+        });
+        let data = builder.build().unwrap();
+        match Packet::parse(&data).unwrap().answers()[0].data {
+            RData::TXT(ref parsed) => assert_eq!(&parsed.joined()[..], &key[..300]),
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn joined_and_attributes() {
+        let record = |rdata: &'static [u8]| match Record::parse(rdata, rdata).unwrap() {
+            RData::TXT(txt) => txt,
+            ref x => panic!("Wrong rdata {:?}", x),
+        };
+        let spf = record(b"\x06v=spf1\x08 -all ok");
+        assert_eq!(&spf.joined()[..], b"v=spf1 -all ok");
+        assert!(matches!(spf.joined(), Cow::Owned(_)));
+        let single = record(b"\x06v=spf1");
+        assert!(matches!(single.joined(), Cow::Borrowed(b"v=spf1")));
+        assert!(record(b"").joined().is_empty());
+
+        let sd = record(b"\x07txtvers\x0btxtvers=1=x\x00\x04=bad\x06paper=");
+        assert_eq!(
+            sd.attributes().collect::<Vec<_>>(),
+            [
+                (&b"txtvers"[..], None),
+                (&b"txtvers"[..], Some(&b"1=x"[..])),
+                (&b"paper"[..], Some(&b""[..])),
+            ]
+        );
+    }
 }
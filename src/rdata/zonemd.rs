@@ -0,0 +1,96 @@
+use std::borrow::Cow;
+use std::convert::TryInto;
+
+use crate::{Error, Type};
+
+/// The ZONEMD resource record (RFC 8976)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    /// Serial of the zone version the digest was computed for
+    pub serial: u32,
+    pub scheme: u8,
+    pub hash_algorithm: u8,
+    pub digest: Cow<'a, [u8]>,
+}
+
+impl<'a> Record<'a> {
+    /// The only scheme defined, a digest over all records of the zone
+    pub const SCHEME_SIMPLE: u8 = 1;
+    /// Hash algorithm SHA-384
+    pub const SHA384: u8 = 1;
+    /// Hash algorithm SHA-512
+    pub const SHA512: u8 = 2;
+
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> Record<'static> {
+        Record {
+            serial: self.serial,
+            scheme: self.scheme,
+            hash_algorithm: self.hash_algorithm,
+            digest: Cow::Owned(self.digest.into_owned()),
+        }
+    }
+
+    /// Writes the record data in the wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.serial.to_be_bytes());
+        buf.push(self.scheme);
+        buf.push(self.hash_algorithm);
+        buf.extend_from_slice(&self.digest);
+    }
+}
+
+impl<'a> super::Record<'a> for Record<'a> {
+    const TYPE: isize = 63;
+
+    fn parse(rdata: &'a [u8], _original: &'a [u8]) -> super::RDataResult<'a> {
+        if rdata.len() < 6 {
+            return Err(Error::InvalidRdataLength {
+                typ: Type::ZONEMD,
+                expected: 6,
+                got: rdata.len(),
+            });
+        }
+        Ok(super::RData::ZONEMD(Record {
+            serial: u32::from_be_bytes(rdata[..4].try_into().unwrap()),
+            scheme: rdata[4],
+            hash_algorithm: rdata[5],
+            digest: Cow::Borrowed(&rdata[6..]),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use crate::{Error, Packet, RData, Type};
+
+    #[test]
+    fn parse_response() {
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x01\x00\x00\x00\x00\
+                         \x07example\x00\x00\x3f\x00\x01\x00\x00\x0e\x10\x00\x12\
+                         \x78\x48\xb9\x1c\x01\x01\x00\x01\x02\x03\x04\x05\x06\x07\
+                         \x08\x09\x0a\x0b";
+        let packet = Packet::parse(response).unwrap();
+        match packet.answers()[0].data {
+            RData::ZONEMD(ref zonemd) => {
+                assert_eq!(zonemd.serial, 2018031900);
+                assert_eq!(zonemd.scheme, 1);
+                assert_eq!(zonemd.hash_algorithm, 1);
+                assert_eq!(zonemd.digest.len(), 12);
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        let mut truncated = response.to_vec();
+        truncated[30] = 5;
+        truncated.truncate(36);
+        assert!(matches!(
+            Packet::parse(&truncated).unwrap_err().root(),
+            Error::InvalidRdataLength {
+                typ: Type::ZONEMD,
+                expected: 6,
+                got: 5,
+            }
+        ));
+    }
+}
@@ -0,0 +1,456 @@
+//! Grouping of resource records into RRsets
+//!
+//! An RRset is the set of records sharing owner name, type and class
+//! (RFC 2181 section 5). DNSSEC signs RRsets and caches store them, so
+//! most processing beyond parsing works on RRsets rather than on single
+//! records. Use `group` on a list of records or `Packet::rrsets` on a
+//! whole packet.
+//!
+//! `dedup` removes duplicate records, which buggy or malicious servers
+//! send, before records are grouped or cached.
+//!
+//! `rotate_addresses` and `shuffle_addresses` reorder the address records
+//! of a response for round-robin load balancing, before the response is
+//! built again with `Builder`.
+//!
+//! RRSIG records are grouped by the type they cover as well, so the
+//! signatures of every RRset end up in an RRset of their own.
+use crate::structs::Records;
+use crate::{Class, Name, Packet, RData, ResourceRecord, Section, Ttl, Type};
+
+/// Records sharing owner name, type and class, see `group`
+///
+/// The records are borrowed from the list they were grouped from and
+/// kept in their original order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RRset<'r, 'a> {
+    // the first of `records`, kept apart so it's there without indexing
+    first: &'r ResourceRecord<'a>,
+    records: Vec<&'r ResourceRecord<'a>>,
+}
+
+impl<'r, 'a> RRset<'r, 'a> {
+    /// Returns the owner name of the first record
+    ///
+    /// Names of the other records are equal ignoring case.
+    pub fn name(&self) -> &'r Name<'a> {
+        &self.first.name
+    }
+    /// Returns the type of the records
+    pub fn typ(&self) -> Type {
+        self.first.data.typ()
+    }
+    /// Returns the class of the records
+    pub fn cls(&self) -> Class {
+        self.first.cls
+    }
+    /// Returns the type covered if this is a set of RRSIG records
+    pub fn type_covered(&self) -> Option<u16> {
+        type_covered(self.first)
+    }
+    /// Returns the smallest TTL of the records
+    ///
+    /// The records of an RRset should all have the same TTL, if they
+    /// don't the smallest one should be used (RFC 2181 section 5.2).
+    pub fn ttl(&self) -> Ttl {
+        self.records
+            .iter()
+            .map(|r| r.ttl)
+            .fold(self.first.ttl, Ord::min)
+    }
+    /// Returns the records, never empty
+    pub fn records(&self) -> &[&'r ResourceRecord<'a>] {
+        &self.records
+    }
+    /// Returns the number of records
+    pub fn len(&self) -> usize {
+        self.records.len()
+    }
+    /// Always false, RRsets have at least one record
+    pub fn is_empty(&self) -> bool {
+        self.records.is_empty()
+    }
+    /// Sorts the records by their data in the canonical order
+    ///
+    /// See `sort_canonical`.
+    pub fn sort_canonical(&mut self) {
+        self.records
+            .sort_by_cached_key(|record| canonical_key(record));
+        if let Some(&first) = self.records.first() {
+            self.first = first;
+        }
+    }
+    /// Copies the records into a list, e.g. for `dnssec::signature_input`
+    pub fn to_records(&self) -> Vec<ResourceRecord<'a>> {
+        self.records.iter().map(|&r| r.clone()).collect()
+    }
+}
+
+/// Groups records by owner name (ignoring case), type and class
+///
+/// RRsets are returned in the order their first record appears in.
+pub fn group<'r, 'a>(records: &'r [ResourceRecord<'a>]) -> Vec<RRset<'r, 'a>> {
+    let mut result: Vec<RRset<'r, 'a>> = Vec::new();
+    for record in records {
+        let found = result.iter_mut().find(|set| {
+            let first = set.first;
+            first.data.typ() == record.data.typ()
+                && first.cls == record.cls
+                && type_covered(first) == type_covered(record)
+                && first.name.eq_ignore_ascii_case(&record.name)
+        });
+        match found {
+            Some(set) => set.records.push(record),
+            None => result.push(RRset {
+                first: record,
+                records: vec![record],
+            }),
+        }
+    }
+    result
+}
+
+/// Returns the indices of records equal to an earlier record
+///
+/// Records are equal if they have the same owner name (ignoring case),
+/// type, class and data in the canonical form. With `ignore_ttl` unset
+/// the TTL must be equal too.
+pub fn duplicates(records: &[ResourceRecord<'_>], ignore_ttl: bool) -> Vec<usize> {
+    let rdatas = records
+        .iter()
+        .map(|record| {
+            let mut rdata = Vec::new();
+            record.data.write_canonical_to(&mut rdata);
+            rdata
+        })
+        .collect::<Vec<_>>();
+    (0..records.len())
+        .filter(|&i| {
+            let record = &records[i];
+            (0..i).any(|j| {
+                let other = &records[j];
+                rdatas[i] == rdatas[j]
+                    && record.data.typ() == other.data.typ()
+                    && record.cls == other.cls
+                    && (ignore_ttl || record.ttl == other.ttl)
+                    && record.name.eq_ignore_ascii_case(&other.name)
+            })
+        })
+        .collect()
+}
+
+/// Removes records equal to an earlier record, see `duplicates`
+///
+/// The first of the equal records is kept. Returns the number of records
+/// removed.
+pub fn dedup(records: &mut Vec<ResourceRecord<'_>>, ignore_ttl: bool) -> usize {
+    let duplicates = duplicates(records, ignore_ttl);
+    let mut index = 0;
+    records.retain(|_| {
+        index += 1;
+        duplicates.binary_search(&(index - 1)).is_err()
+    });
+    duplicates.len()
+}
+
+// `dedup` for a section of a packet
+fn dedup_section(records: &mut Records<'_>, ignore_ttl: bool) -> usize {
+    let duplicates = duplicates(records, ignore_ttl);
+    let mut index = 0;
+    records.retain(|_| {
+        index += 1;
+        duplicates.binary_search(&(index - 1)).is_err()
+    });
+    duplicates.len()
+}
+
+/// Sorts records in the canonical order (RFC 4034 section 6.3)
+///
+/// Records of an RRset are ordered by their record data in the canonical
+/// form, compared as unsigned byte strings. This is the order in which
+/// they are signed and hashed. Records of different RRsets are ordered by
+/// owner name in the canonical order (RFC 4034 section 6.1), then by type
+/// and class, so a whole zone or section can be sorted at once.
+pub fn sort_canonical(records: &mut [ResourceRecord<'_>]) {
+    records.sort_by_cached_key(canonical_key);
+}
+
+// Lowercased labels from the rightmost one, type, class and record data
+fn canonical_key(record: &ResourceRecord<'_>) -> (Vec<Vec<u8>>, u16, u16, Vec<u8>) {
+    let mut labels = record
+        .name
+        .labels()
+        .map(|label| label.to_ascii_lowercase())
+        .collect::<Vec<_>>();
+    labels.reverse();
+    let mut rdata = Vec::new();
+    record.data.write_canonical_to(&mut rdata);
+    (labels, record.data.typ().code(), record.cls.code(), rdata)
+}
+
+/// Rotates the records of every A and AAAA RRset by `n` positions
+///
+/// The first `n` records of an RRset (modulo its length) move to its end.
+/// Records only change places with records of the same RRset, all other
+/// records stay where they are. Incrementing `n` with every response
+/// gives the classic round-robin order.
+pub fn rotate_addresses(records: &mut [ResourceRecord<'_>], n: usize) {
+    for positions in address_sets(records) {
+        let mut set = positions
+            .iter()
+            .map(|&i| records[i].clone())
+            .collect::<Vec<_>>();
+        set.rotate_left(n % positions.len());
+        for (&i, record) in positions.iter().zip(set) {
+            records[i] = record;
+        }
+    }
+}
+
+/// Shuffles the records of every A and AAAA RRset
+///
+/// Records only change places with records of the same RRset, all other
+/// records stay where they are. `random(max)` must return a uniformly
+/// distributed number from 0 to `max` inclusive, like for
+/// `srv::select`.
+pub fn shuffle_addresses<F>(records: &mut [ResourceRecord<'_>], mut random: F)
+where
+    F: FnMut(u32) -> u32,
+{
+    for positions in address_sets(records) {
+        for i in (1..positions.len()).rev() {
+            let j = (random(i as u32) as usize).min(i);
+            records.swap(positions[i], positions[j]);
+        }
+    }
+}
+
+// Positions of the records of every A and AAAA RRset
+fn address_sets(records: &[ResourceRecord<'_>]) -> Vec<Vec<usize>> {
+    let mut sets: Vec<Vec<usize>> = Vec::new();
+    for (i, record) in records.iter().enumerate() {
+        if !matches!(record.data, RData::A(..) | RData::AAAA(..)) {
+            continue;
+        }
+        let set = sets.iter_mut().find(|set| {
+            let first = &records[set[0]];
+            first.data.typ() == record.data.typ()
+                && first.cls == record.cls
+                && first.name.eq_ignore_ascii_case(&record.name)
+        });
+        match set {
+            Some(set) => set.push(i),
+            None => sets.push(vec![i]),
+        }
+    }
+    sets
+}
+
+fn type_covered(record: &ResourceRecord<'_>) -> Option<u16> {
+    match record.data {
+        RData::RRSIG(ref rrsig) => Some(rrsig.type_covered),
+        _ => None,
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Groups the records of every section into RRsets
+    ///
+    /// Records of different sections are never grouped together. The OPT
+    /// pseudo-record is not included.
+    pub fn rrsets(&self) -> Vec<(Section, RRset<'_, 'a>)> {
+        let sections = [
+            (Section::Answer, self.answers()),
+            (Section::Authority, self.nameservers()),
+            (Section::Additional, self.additional()),
+        ];
+        sections
+            .iter()
+            .flat_map(|&(section, records)| {
+                group(records).into_iter().map(move |set| (section, set))
+            })
+            .collect()
+    }
+    /// Removes duplicate records from every section, see `rrset::dedup`
+    ///
+    /// The record counts of the header are updated. Returns the number
+    /// of records removed.
+    pub fn dedup(&mut self, ignore_ttl: bool) -> usize {
+        let removed = dedup_section(&mut self.answers, ignore_ttl)
+            + dedup_section(&mut self.nameservers, ignore_ttl)
+            + dedup_section(&mut self.additional, ignore_ttl);
+        self.header.answers = self.answers.len() as u16;
+        self.header.nameservers = self.nameservers.len() as u16;
+        self.header.additional = (self.additional.len() + self.opt.is_some() as usize) as u16;
+        removed
+    }
+    /// Rotates the address records of the answer section
+    ///
+    /// See `rrset::rotate_addresses`.
+    pub fn rotate_addresses(&mut self, n: usize) {
+        rotate_addresses(&mut self.answers, n)
+    }
+    /// Shuffles the address records of the answer section
+    ///
+    /// See `rrset::shuffle_addresses`.
+    pub fn shuffle_addresses<F: FnMut(u32) -> u32>(&mut self, random: F) {
+        shuffle_addresses(&mut self.answers, random)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::{dedup, duplicates, group, sort_canonical};
+    use crate::rdata::Rrsig;
+    use crate::{Class, Name, Packet, RData, ResourceRecord, Section, Ttl, Type};
+
+    #[test]
+    fn packet_rrsets() {
+        // two A records of example.com around an AAAA record, one of them
+        // in upper case, then an A record in the authority section
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x03\x00\x01\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\x00\x00\x00\x3c\
+                         \x00\x04\xc0\x00\x02\x01\
+                         \xc0\x0c\x00\x1c\x00\x01\x00\x00\x00\x3c\x00\x10\
+                         \x20\x01\x0d\xb8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\
+                         \x07EXAMPLE\x03com\x00\x00\x01\x00\x01\x00\x00\x00\x1e\
+                         \x00\x04\xc0\x00\x02\x02\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x03";
+        let packet = Packet::parse(response).unwrap();
+        let rrsets = packet.rrsets();
+        assert_eq!(rrsets.len(), 3);
+        let (section, ref a) = rrsets[0];
+        assert_eq!(section, Section::Answer);
+        assert_eq!(a.name().to_string(), "example.com");
+        assert_eq!(a.typ(), Type::A);
+        assert_eq!(a.cls(), Class::IN);
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.ttl(), 30);
+        assert_eq!(a.type_covered(), None);
+        assert_eq!(a.records()[1], &packet.answers()[2]);
+        assert_eq!(rrsets[1].1.typ(), Type::AAAA);
+        assert_eq!(rrsets[2].0, Section::Authority);
+        assert_eq!(rrsets[2].1.len(), 1);
+
+        assert_eq!(group(&packet.answers()[2..]).len(), 1);
+        assert!(group(&[]).is_empty());
+    }
+
+    #[test]
+    fn duplicate_records() {
+        // A records of example.com: 192.0.2.1 with TTL 60, 192.0.2.2,
+        // 192.0.2.1 in upper case with TTL 30 and 192.0.2.1 with TTL 60
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x04\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\x00\x00\x00\x3c\
+                         \x00\x04\xc0\x00\x02\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x02\
+                         \x07EXAMPLE\x03com\x00\x00\x01\x00\x01\x00\x00\x00\x1e\
+                         \x00\x04\xc0\x00\x02\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x01";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(duplicates(packet.answers(), false), [3]);
+        assert_eq!(duplicates(packet.answers(), true), [2, 3]);
+
+        let mut deduped = packet.clone();
+        assert_eq!(deduped.dedup(true), 2);
+        assert_eq!(deduped.answers(), &packet.answers()[..2]);
+        assert_eq!(deduped.header().answers, 2);
+        let mut records = packet.answers().to_vec();
+        assert_eq!(dedup(&mut records, false), 1);
+        assert_eq!(records, &packet.answers()[..3]);
+    }
+
+    #[test]
+    fn canonical_order() {
+        // A records of example.com: 192.0.2.10, 192.0.2.9, an AAAA
+        // record, then an A record of a.example.com
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x04\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\x00\x00\x00\x3c\
+                         \x00\x04\xc0\x00\x02\x0a\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x09\
+                         \xc0\x0c\x00\x1c\x00\x01\x00\x00\x00\x3c\x00\x10\
+                         \x20\x01\x0d\xb8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\
+                         \x01A\xc0\x0c\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x01";
+        let packet = Packet::parse(response).unwrap();
+        let answers = packet.answers();
+        let mut sorted = answers.to_vec();
+        sort_canonical(&mut sorted);
+        assert_eq!(
+            sorted,
+            [
+                answers[1].clone(),
+                answers[0].clone(),
+                answers[2].clone(),
+                answers[3].clone()
+            ]
+        );
+
+        let mut rrset = group(answers).remove(0);
+        rrset.sort_canonical();
+        assert_eq!(rrset.records(), [&answers[1], &answers[0]]);
+    }
+
+    #[test]
+    fn address_rotation() {
+        // www.example.com CNAME example.com, then A records 192.0.2.1,
+        // 192.0.2.2 and 192.0.2.3 of example.com
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x04\x00\x00\x00\x00\
+                         \x03www\x07example\x03com\x00\x00\x05\x00\x01\x00\x00\x00\x3c\
+                         \x00\x02\xc0\x10\
+                         \xc0\x10\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x01\
+                         \xc0\x10\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x02\
+                         \xc0\x10\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x03";
+        fn order(packet: &Packet<'_>) -> Vec<u8> {
+            packet
+                .answers()
+                .iter()
+                .map(|record| match record.data {
+                    RData::A(ref a) => a.0.octets()[3],
+                    _ => 0,
+                })
+                .collect()
+        }
+        let mut packet = Packet::parse(response).unwrap();
+        packet.rotate_addresses(1);
+        assert_eq!(order(&packet), [0, 2, 3, 1]);
+        packet.rotate_addresses(5);
+        assert_eq!(order(&packet), [0, 1, 2, 3]);
+
+        // always swapping with the first record
+        packet.shuffle_addresses(|_| 0);
+        assert_eq!(order(&packet), [0, 2, 3, 1]);
+        packet.shuffle_addresses(|max| max);
+        assert_eq!(order(&packet), [0, 2, 3, 1]);
+    }
+
+    #[test]
+    fn signatures() {
+        let name = Name::scan(b"\x07example\x00", b"\x07example\x00").unwrap();
+        let rrsig = |type_covered| ResourceRecord {
+            name: name.clone(),
+            multicast_unique: false,
+            cls: Class::IN,
+            ttl: Ttl(60),
+            data: RData::RRSIG(Rrsig {
+                type_covered,
+                algorithm: 8,
+                labels: 1,
+                original_ttl: 60,
+                expiration: 0,
+                inception: 0,
+                key_tag: 0,
+                signer_name: name.clone(),
+                signature: Cow::Borrowed(b""),
+            }),
+        };
+        let records = [rrsig(1), rrsig(28), rrsig(1)];
+        let rrsets = group(&records);
+        assert_eq!(rrsets.len(), 2);
+        assert_eq!(rrsets[0].typ(), Type::RRSIG);
+        assert_eq!(rrsets[0].type_covered(), Some(1));
+        assert_eq!(rrsets[0].len(), 2);
+        assert_eq!(rrsets[1].type_covered(), Some(28));
+    }
+}
@@ -0,0 +1,406 @@
+use crate::name::{check_labels, scan_name, Budget, Message};
+use crate::parser::{capacity, parse_class_code, parse_qclass_code};
+use crate::parser::{MIN_QUESTION_SIZE, MIN_RECORD_SIZE};
+use crate::rdata::opt::Record as Opt;
+use crate::structs::{Questions, Records};
+use crate::{Error, Header, Name, Packet, PacketBuf, ParseOptions, QueryType, Question};
+use crate::{RData, ResourceRecord, Section, Ttl, Type};
+
+impl<'a> Packet<'a> {
+    /// Parse a packet split across several buffers
+    ///
+    /// This is for segmented receive buffers, e.g. a ring buffer where the
+    /// message wraps around the end. The segments are read in place, only
+    /// names and record data are copied into the resulting packet, just
+    /// like `Packet::to_packet_buf` does. Errors are the same as of
+    /// `Packet::parse` on the concatenated data.
+    pub fn parse_segments(segments: &[&[u8]]) -> Result<PacketBuf, Error> {
+        Packet::parse_segments_with_options(segments, &ParseOptions::default())
+    }
+    /// Parse a packet split across several buffers, with custom options
+    ///
+    /// Compressed names are followed to their end every time, so the
+    /// limits of `options` may be reached earlier than by
+    /// `Packet::parse_with_options` on the concatenated data.
+    pub fn parse_segments_with_options(
+        segments: &[&[u8]],
+        options: &ParseOptions,
+    ) -> Result<PacketBuf, Error> {
+        if let [data] = segments {
+            return Packet::parse_with_options(data, options).map(Packet::into_owned);
+        }
+        Segments::new(segments).parse(options)
+    }
+    /// Parse a packet from the chunks of a `bytes::Buf`
+    ///
+    /// The buffer is not advanced. All chunks must be exposed by
+    /// `Buf::chunks_vectored`, which is the case for `Chain` and the
+    /// standard buffers, but not for buffers relying on its default
+    /// implementation.
+    #[cfg(feature = "bytes")]
+    pub fn parse_buf<B: bytes::Buf>(buf: &B) -> Result<PacketBuf, Error> {
+        Packet::parse_buf_with_options(buf, &ParseOptions::default())
+    }
+    /// Parse a packet from the chunks of a `bytes::Buf`, with custom
+    /// options
+    #[cfg(feature = "bytes")]
+    pub fn parse_buf_with_options<B: bytes::Buf>(
+        buf: &B,
+        options: &ParseOptions,
+    ) -> Result<PacketBuf, Error> {
+        use std::io::IoSlice;
+
+        let mut slices = vec![IoSlice::new(&[]); 4];
+        loop {
+            let count = buf.chunks_vectored(&mut slices);
+            if count < slices.len() {
+                slices.truncate(count);
+                break;
+            }
+            let new_len = slices.len() * 2;
+            slices.resize(new_len, IoSlice::new(&[]));
+        }
+        let segments = slices.iter().map(|slice| &**slice).collect::<Vec<_>>();
+        Packet::parse_segments_with_options(&segments, options)
+    }
+}
+
+// Random access over the segments of a message
+struct Segments<'s> {
+    parts: &'s [&'s [u8]],
+    len: usize,
+}
+
+impl<'s> Segments<'s> {
+    fn new(parts: &'s [&'s [u8]]) -> Segments<'s> {
+        Segments {
+            parts,
+            len: parts.iter().map(|part| part.len()).sum(),
+        }
+    }
+
+    // Calls `f` on the pieces of the range in order
+    fn chunks<F: FnMut(&[u8])>(
+        &self,
+        mut pos: usize,
+        mut len: usize,
+        mut f: F,
+    ) -> Result<(), Error> {
+        if pos + len > self.len {
+            return Err(Error::UnexpectedEOF);
+        }
+        for part in self.parts {
+            if len == 0 {
+                break;
+            }
+            if pos >= part.len() {
+                pos -= part.len();
+                continue;
+            }
+            let count = len.min(part.len() - pos);
+            f(&part[pos..pos + count]);
+            len -= count;
+            pos = 0;
+        }
+        Ok(())
+    }
+
+    fn read<const N: usize>(&self, pos: usize) -> Result<[u8; N], Error> {
+        let mut result = [0; N];
+        let mut filled = 0;
+        self.chunks(pos, N, |chunk| {
+            result[filled..filled + chunk.len()].copy_from_slice(chunk);
+            filled += chunk.len();
+        })?;
+        Ok(result)
+    }
+
+    fn copy(&self, pos: usize, len: usize, buf: &mut Vec<u8>) -> Result<(), Error> {
+        self.chunks(pos, len, |chunk| buf.extend_from_slice(chunk))
+    }
+
+    // Appends the uncompressed name starting at `start` to `buf`, returns
+    // the length of the name in the message. `end` is the end of the data
+    // holding the name, like the `data` of `Name::scan`.
+    fn read_name(
+        &self,
+        start: usize,
+        end: usize,
+        budget: &mut Budget,
+        buf: &mut Vec<u8>,
+    ) -> Result<usize, Error> {
+        let len = scan_name(self, start, end, self, None, budget, |src, from, to| {
+            let labels = buf.len();
+            src.copy(from, to - from, buf)?;
+            check_labels(&buf[labels..])
+        })?;
+        buf.push(0);
+        Ok(len)
+    }
+
+    // Copies the record data, expanding the compressed names which
+    // `RData::parse` reads
+    fn read_rdata(
+        &self,
+        typ: Type,
+        start: usize,
+        end: usize,
+        budget: &mut Budget,
+    ) -> Result<Vec<u8>, Error> {
+        let mut buf = Vec::with_capacity(end - start);
+        let names = typ.rdata_names().filter(|names| names.parsed);
+        let first = names.and_then(|names| Some((names.count, names.start(self, start, end)?)));
+        // too short for a name, leave reporting the error to the parser
+        let (count, mut pos) = match first {
+            Some(first) => first,
+            None => {
+                self.copy(start, end - start, &mut buf)?;
+                return Ok(buf);
+            }
+        };
+        self.copy(start, pos - start, &mut buf)?;
+        for _ in 0..count {
+            pos += self.read_name(pos, end, budget, &mut buf)?;
+        }
+        self.copy(pos, end - pos, &mut buf)?;
+        Ok(buf)
+    }
+
+    fn parse(&self, options: &ParseOptions) -> Result<PacketBuf, Error> {
+        let mut budget = Budget::new(options);
+        let mut head = Vec::with_capacity(Header::size());
+        self.copy(0, self.len.min(Header::size()), &mut head)?;
+        let header = Header::parse(&head)?;
+        let mut offset = Header::size();
+        let mut questions =
+            Questions::with_capacity(capacity(header.questions, self.len, MIN_QUESTION_SIZE));
+        for index in 0..header.questions as usize {
+            let start = offset;
+            let question = self
+                .question(&mut offset, &mut budget, options)
+                .map_err(|error| Error::in_question(index, start, error))?;
+            questions.push(question);
+        }
+        let mut answers =
+            Records::with_capacity(capacity(header.answers, self.len, MIN_RECORD_SIZE));
+        for index in 0..header.answers as usize {
+            let start = offset;
+            let record = self
+                .record(&mut offset, &mut budget, options)
+                .map_err(|error| Error::in_record(Section::Answer, index, start, error))?;
+            answers.push(record);
+        }
+        let mut nameservers =
+            Records::with_capacity(capacity(header.nameservers, self.len, MIN_RECORD_SIZE));
+        for index in 0..header.nameservers as usize {
+            let start = offset;
+            let record = self
+                .record(&mut offset, &mut budget, options)
+                .map_err(|error| Error::in_record(Section::Authority, index, start, error))?;
+            nameservers.push(record);
+        }
+        let mut additional =
+            Records::with_capacity(capacity(header.additional, self.len, MIN_RECORD_SIZE));
+        let mut opt = None;
+        for index in 0..header.additional as usize {
+            let start = offset;
+            let in_record = |error| Error::in_record(Section::Additional, index, start, error);
+            if self.read::<3>(offset).ok() == Some([0, 0, 41]) {
+                if opt.is_none() {
+                    opt = Some(self.opt(&mut offset).map_err(in_record)?);
+                } else {
+                    return Err(in_record(Error::AdditionalOPT));
+                }
+            } else {
+                additional.push(
+                    self.record(&mut offset, &mut budget, options)
+                        .map_err(in_record)?,
+                );
+            }
+        }
+        Ok(Packet {
+            header,
+            questions,
+            answers,
+            nameservers,
+            additional,
+            opt,
+        })
+    }
+
+    fn question(
+        &self,
+        offset: &mut usize,
+        budget: &mut Budget,
+        options: &ParseOptions,
+    ) -> Result<Question<'static>, Error> {
+        let qname = self.name(offset, budget)?;
+        let [t1, t2, c1, c2] = self.read(*offset)?;
+        *offset += 4;
+        let (prefer_unicast, qclass) = parse_qclass_code(u16::from_be_bytes([c1, c2]), options)?;
+        Ok(Question {
+            qname,
+            qtype: QueryType::parse(u16::from_be_bytes([t1, t2])),
+            prefer_unicast,
+            qclass,
+        })
+    }
+
+    fn name(&self, offset: &mut usize, budget: &mut Budget) -> Result<Name<'static>, Error> {
+        let mut labels = Vec::new();
+        *offset += self.read_name(*offset, self.len, budget, &mut labels)?;
+        Ok(Name::from_uncompressed(labels))
+    }
+
+    fn record(
+        &self,
+        offset: &mut usize,
+        budget: &mut Budget,
+        options: &ParseOptions,
+    ) -> Result<ResourceRecord<'static>, Error> {
+        let name = self.name(offset, budget)?;
+        let fields: [u8; 10] = self.read(*offset)?;
+        *offset += 10;
+        let rdlen = u16::from_be_bytes([fields[8], fields[9]]) as usize;
+        if *offset + rdlen > self.len {
+            return Err(Error::UnexpectedEOF);
+        }
+        let start = *offset;
+        *offset += rdlen;
+        let typ = Type::parse(u16::from_be_bytes([fields[0], fields[1]]));
+        let (multicast_unique, cls) =
+            parse_class_code(u16::from_be_bytes([fields[2], fields[3]]), options)?;
+        let ttl = Ttl::new(u32::from_be_bytes([
+            fields[4], fields[5], fields[6], fields[7],
+        ]));
+        budget.bytes(rdlen)?;
+        let rdata = self.read_rdata(typ, start, *offset, budget)?;
+        let data = RData::parse(typ, &rdata, &rdata)?.into_owned();
+        Ok(ResourceRecord {
+            name,
+            multicast_unique,
+            cls,
+            ttl,
+            data,
+        })
+    }
+
+    fn opt(&self, offset: &mut usize) -> Result<Opt<'static>, Error> {
+        let fields: [u8; 11] = self.read(*offset)?;
+        *offset += 11;
+        let typ = Type::parse(u16::from_be_bytes([fields[1], fields[2]]));
+        if typ != Type::OPT {
+            return Err(Error::InvalidType(typ.code()));
+        }
+        let rdlen = u16::from_be_bytes([fields[9], fields[10]]) as usize;
+        let mut rdata = Vec::with_capacity(rdlen);
+        self.copy(*offset, rdlen, &mut rdata)?;
+        *offset += rdlen;
+        Ok(Opt {
+            udp: u16::from_be_bytes([fields[3], fields[4]]),
+            extrcode: fields[5],
+            version: fields[6],
+            flags: u16::from_be_bytes([fields[7], fields[8]]),
+            data: RData::parse(typ, &rdata, &rdata)?.into_owned(),
+        })
+    }
+}
+
+impl<'s> Message for Segments<'s> {
+    fn len(&self) -> usize {
+        self.len
+    }
+    fn byte(&self, mut pos: usize) -> u8 {
+        for part in self.parts {
+            match part.get(pos) {
+                Some(&byte) => return byte,
+                None => pos -= part.len(),
+            }
+        }
+        0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Packet;
+
+    const MX_RESPONSE: &[u8] = b"\xe3\xe8\x81\x80\x00\x01\x00\x03\x00\x00\x00\x00\
+        \x05gmail\x03com\x00\x00\x0f\x00\x01\xc0\x0c\x00\x0f\x00\x01\
+        \x00\x00\x04|\x00\x1b\x00\x05\rgmail-smtp-in\x01l\x06google\xc0\
+        \x12\xc0\x0c\x00\x0f\x00\x01\x00\x00\x04|\x00\t\x00\
+        \n\x04alt1\xc0)\xc0\x0c\x00\x0f\x00\x01\x00\x00\x04|\
+        \x00\t\x00(\x04alt4\xc0)";
+
+    const SOA_RESPONSE: &[u8] = b"\x9f\xc5\x85\x83\x00\x01\x00\x00\x00\x01\x00\x01\
+        \x0edlkfjkdjdslfkj\x07youtube\x03com\x00\x00\x01\x00\x01\
+        \xc0\x1b\x00\x06\x00\x01\x00\x00\x2a\x30\x00\x1e\xc0\x1b\
+        \x05admin\xc0\x1b\x77\xed\x2a\x73\x00\x00\x51\x80\x00\x00\
+        \x0e\x10\x00\x00\x3a\x80\x00\x00\x2a\x30\
+        \x00\x00\x29\x10\x00\x00\x00\x80\x00\x00\x04\x00\x0a\x00\x00";
+
+    #[test]
+    fn split_anywhere() {
+        for &response in &[MX_RESPONSE, SOA_RESPONSE] {
+            let expected = Packet::parse(response).unwrap().to_packet_buf();
+            for split in 0..=response.len() {
+                let (a, b) = response.split_at(split);
+                assert_eq!(Packet::parse_segments(&[a, b]).unwrap(), expected);
+            }
+            let bytes = response.chunks(1).collect::<Vec<_>>();
+            assert_eq!(Packet::parse_segments(&bytes).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn same_errors() {
+        for len in 0..SOA_RESPONSE.len() {
+            let data = &SOA_RESPONSE[..len];
+            let expected = Packet::parse(data).unwrap_err().to_string();
+            let (a, b) = data.split_at(len / 2);
+            let error = Packet::parse_segments(&[a, b]).unwrap_err();
+            assert_eq!(error.to_string(), expected);
+        }
+        // pointer loop
+        let looped = b"\x00\x00\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\
+                       \xc0\x0e\xc0\x0c\x00\x01\x00\x01";
+        let (a, b) = looped.split_at(13);
+        assert_eq!(
+            Packet::parse_segments(&[a, b]).unwrap_err().to_string(),
+            Packet::parse(looped).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    fn options() {
+        use crate::{Error, ParseOptions};
+
+        let (a, b) = MX_RESPONSE.split_at(40);
+        let few_labels = ParseOptions::new().max_labels(4);
+        let expected = Packet::parse_with_options(MX_RESPONSE, &few_labels).unwrap_err();
+        let error = Packet::parse_segments_with_options(&[a, b], &few_labels).unwrap_err();
+        assert_eq!(error.to_string(), expected.to_string());
+
+        let chaos = b"\x00\x00\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\
+                      \x00\x00\x01\x00\x0a";
+        let (a, b) = chaos.split_at(14);
+        let strict = ParseOptions::new().strict_classes(true);
+        assert!(Packet::parse_segments(&[a, b]).is_ok());
+        let error = Packet::parse_segments_with_options(&[a, b], &strict).unwrap_err();
+        assert!(matches!(error.root(), Error::InvalidQueryClass(10)));
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn parse_chain() {
+        use bytes::{Buf, Bytes};
+
+        let (a, b) = MX_RESPONSE.split_at(40);
+        let buf = Bytes::from_static(a).chain(Bytes::from_static(b));
+        assert_eq!(
+            Packet::parse_buf(&buf).unwrap(),
+            Packet::parse(MX_RESPONSE).unwrap().to_packet_buf()
+        );
+        assert_eq!(buf.remaining(), MX_RESPONSE.len());
+    }
+}
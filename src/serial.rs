@@ -0,0 +1,94 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Add;
+
+/// A sequence number with wrap-around arithmetic (RFC 1982)
+///
+/// Used for SOA serials and the RRSIG validity period. Numbers are
+/// compared by distance modulo 2^32: `a < b` if `b` is less than 2^31
+/// ahead of `a`. Two numbers exactly 2^31 apart are not comparable, so
+/// only `PartialOrd` is implemented.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SerialNumber(pub u32);
+
+impl SerialNumber {
+    /// The largest value that may be added to a serial number
+    pub const MAX_INCREMENT: u32 = (1 << 31) - 1;
+
+    /// Adds `n` to the serial number, wrapping around
+    ///
+    /// Returns `None` if `n` is larger than `MAX_INCREMENT`, such
+    /// additions are undefined.
+    pub fn checked_add(self, n: u32) -> Option<SerialNumber> {
+        if n > SerialNumber::MAX_INCREMENT {
+            return None;
+        }
+        Some(SerialNumber(self.0.wrapping_add(n)))
+    }
+}
+
+impl Add<u32> for SerialNumber {
+    type Output = SerialNumber;
+    /// Adds `n` to the serial number, wrapping around
+    ///
+    /// # Panics
+    ///
+    /// When `n` is larger than `MAX_INCREMENT`.
+    fn add(self, n: u32) -> SerialNumber {
+        self.checked_add(n)
+            .expect("serial number increment out of range")
+    }
+}
+
+impl PartialOrd for SerialNumber {
+    fn partial_cmp(&self, other: &SerialNumber) -> Option<Ordering> {
+        match other.0.wrapping_sub(self.0) {
+            0 => Some(Ordering::Equal),
+            0x8000_0000 => None,
+            distance if distance < 0x8000_0000 => Some(Ordering::Less),
+            _ => Some(Ordering::Greater),
+        }
+    }
+}
+
+impl From<u32> for SerialNumber {
+    fn from(value: u32) -> SerialNumber {
+        SerialNumber(value)
+    }
+}
+
+impl fmt::Display for SerialNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SerialNumber as S;
+
+    #[test]
+    fn compare() {
+        assert!(S(1) < S(2));
+        assert!(S(0xFFFF_FFFF) < S(0));
+        assert!(S(0) > S(0xFFFF_FFFF));
+        assert!(S(0) < S(0x7FFF_FFFF));
+        assert!(S(0) > S(0x8000_0001));
+        assert!(S(5) <= S(5));
+        assert_eq!(S(0).partial_cmp(&S(0x8000_0000)), None);
+    }
+
+    #[test]
+    fn add() {
+        assert_eq!(S(0xFFFF_FFFF) + 2, S(1));
+        assert_eq!(S(10).checked_add(S::MAX_INCREMENT), Some(S(0x8000_0009)));
+        assert_eq!(S(10).checked_add(S::MAX_INCREMENT + 1), None);
+        assert!(S(10) + S::MAX_INCREMENT > S(10));
+    }
+
+    #[test]
+    #[should_panic]
+    fn add_out_of_range() {
+        let _ = S(0) + 0x8000_0000;
+    }
+}
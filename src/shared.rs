@@ -0,0 +1,323 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Range;
+
+#[cfg(feature = "bytes")]
+use bytes::Bytes;
+
+use crate::rdata::opt;
+use crate::{Class, Error, Header, Name, Packet, ParseOptions, QueryClass, QueryType};
+use crate::{Question, RData, ResourceRecord, Ttl, Type};
+
+/// A parsed packet that keeps the buffer it was parsed from
+///
+/// The buffer may be anything holding the bytes, e.g. `Vec<u8>`,
+/// `Arc<[u8]>`, `Rc<[u8]>` or a memory-mapped file. Nothing is copied out
+/// of it: the packet keeps where the names and record data are, and
+/// `packet` returns a `Packet` borrowing them from the buffer. So the
+/// parsed packet may be stored in long-lived collections, or sent to
+/// another thread if the buffer may be, without copying the names.
+///
+/// Names are not scanned again by `packet`, record data is decoded again
+/// on every call, which doesn't copy it either. The buffer must return the
+/// same bytes from every `as_ref` call, as all the types above do.
+#[derive(Clone)]
+pub struct SharedPacket<B: AsRef<[u8]>> {
+    buf: B,
+    header: Header,
+    questions: Vec<QuestionAt>,
+    answers: Vec<RecordAt>,
+    nameservers: Vec<RecordAt>,
+    additional: Vec<RecordAt>,
+    opt: Option<OptAt>,
+}
+
+/// A parsed packet that keeps its `Bytes` buffer alive
+///
+/// Names and record data point into the memory of the `Bytes`, e.g. into
+/// the frame received by `codec::DnsCodec`.
+#[cfg(feature = "bytes")]
+pub type BytesPacket = SharedPacket<Bytes>;
+
+// Where the parts of a question are in the buffer
+#[derive(Debug, Clone)]
+struct QuestionAt {
+    qname: Range<usize>,
+    prefer_unicast: bool,
+    qtype: QueryType,
+    qclass: QueryClass,
+}
+
+#[derive(Debug, Clone)]
+struct RecordAt {
+    name: Range<usize>,
+    multicast_unique: bool,
+    cls: Class,
+    ttl: Ttl,
+    typ: Type,
+    data: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct OptAt {
+    udp: u16,
+    extrcode: u8,
+    version: u8,
+    flags: u16,
+    data: Range<usize>,
+}
+
+// Range of `part` in `buf`, which `part` is borrowed from
+fn range_of(buf: &[u8], part: &[u8]) -> Range<usize> {
+    let start = (part.as_ptr() as usize).wrapping_sub(buf.as_ptr() as usize);
+    start..start + part.len()
+}
+
+fn name_at<'a>(buf: &'a [u8], range: &Range<usize>) -> Name<'a> {
+    // the root name if the buffer changed, see the docs of `SharedPacket`
+    let labels = buf.get(range.clone()).unwrap_or(b"\0");
+    Name::from_scanned(labels, buf)
+}
+
+fn data_at<'a>(buf: &'a [u8], range: &Range<usize>) -> &'a [u8] {
+    buf.get(range.clone()).unwrap_or(&[])
+}
+
+impl RecordAt {
+    fn new(buf: &[u8], record: &ResourceRecord<'_>) -> RecordAt {
+        let name = range_of(buf, record.name.encoded());
+        // the type, class, TTL and data length follow the name
+        let start = name.end + 10;
+        let len = match buf.get(name.end + 8..start) {
+            Some(&[high, low]) => u16::from_be_bytes([high, low]) as usize,
+            _ => 0,
+        };
+        RecordAt {
+            name,
+            multicast_unique: record.multicast_unique,
+            cls: record.cls,
+            ttl: record.ttl,
+            typ: record.data.typ(),
+            data: start..start + len,
+        }
+    }
+    fn record<'a>(&self, buf: &'a [u8]) -> ResourceRecord<'a> {
+        let rdata = data_at(buf, &self.data);
+        // the data was parsed before, so this only fails if the buffer
+        // changed
+        let data = RData::parse(self.typ, rdata, buf)
+            .unwrap_or_else(|_| RData::Unknown(self.typ.code(), Cow::Borrowed(rdata)));
+        ResourceRecord {
+            name: name_at(buf, &self.name),
+            multicast_unique: self.multicast_unique,
+            cls: self.cls,
+            ttl: self.ttl,
+            data,
+        }
+    }
+}
+
+impl<B: AsRef<[u8]>> SharedPacket<B> {
+    /// Parse a full DNS packet from the buffer
+    pub fn parse(buf: B) -> Result<SharedPacket<B>, Error> {
+        SharedPacket::parse_with_options(buf, &ParseOptions::default())
+    }
+    /// Parse a full DNS packet from the buffer, with custom options
+    pub fn parse_with_options(buf: B, options: &ParseOptions) -> Result<SharedPacket<B>, Error> {
+        let data = buf.as_ref();
+        let packet = Packet::parse_with_options(data, options)?;
+        let records = |records: &[ResourceRecord<'_>]| {
+            records
+                .iter()
+                .map(|record| RecordAt::new(data, record))
+                .collect()
+        };
+        let questions = packet
+            .questions()
+            .iter()
+            .map(|question| QuestionAt {
+                qname: range_of(data, question.qname.encoded()),
+                prefer_unicast: question.prefer_unicast,
+                qtype: question.qtype,
+                qclass: question.qclass,
+            })
+            .collect();
+        let answers = records(packet.answers());
+        let nameservers = records(packet.nameservers());
+        let additional = records(packet.additional());
+        let opt = packet.opt().map(|opt| OptAt {
+            udp: opt.udp,
+            extrcode: opt.extrcode,
+            version: opt.version,
+            flags: opt.flags,
+            data: match opt.data {
+                RData::OPT(Cow::Borrowed(rdata)) => range_of(data, rdata),
+                _ => 0..0,
+            },
+        });
+        let header = *packet.header();
+        Ok(SharedPacket {
+            buf,
+            header,
+            questions,
+            answers,
+            nameservers,
+            additional,
+            opt,
+        })
+    }
+    /// Returns the parsed packet, borrowing the buffer
+    pub fn packet(&self) -> Packet<'_> {
+        let buf = self.buf.as_ref();
+        let records = |records: &[RecordAt]| records.iter().map(|at| at.record(buf)).collect();
+        Packet {
+            header: self.header,
+            questions: self
+                .questions
+                .iter()
+                .map(|at| Question {
+                    qname: name_at(buf, &at.qname),
+                    prefer_unicast: at.prefer_unicast,
+                    qtype: at.qtype,
+                    qclass: at.qclass,
+                })
+                .collect(),
+            answers: records(&self.answers),
+            nameservers: records(&self.nameservers),
+            additional: records(&self.additional),
+            opt: self.opt.as_ref().map(|at| opt::Record {
+                udp: at.udp,
+                extrcode: at.extrcode,
+                version: at.version,
+                flags: at.flags,
+                data: RData::OPT(Cow::Borrowed(data_at(buf, &at.data))),
+            }),
+        }
+    }
+    /// Returns the header of the packet
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+    /// Returns the buffer the packet was parsed from
+    pub fn buffer(&self) -> &B {
+        &self.buf
+    }
+    /// Drops the parsed data and returns the buffer
+    pub fn into_buffer(self) -> B {
+        self.buf
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl SharedPacket<Bytes> {
+    /// Returns the buffer the packet was parsed from
+    pub fn bytes(&self) -> &Bytes {
+        &self.buf
+    }
+    /// Drops the parsed data and returns the buffer
+    pub fn into_bytes(self) -> Bytes {
+        self.buf
+    }
+}
+
+impl<B: AsRef<[u8]>> fmt::Debug for SharedPacket<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedPacket")
+            .field("packet", &self.packet())
+            .field("len", &self.buf.as_ref().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+    use std::sync::Arc;
+
+    use super::SharedPacket;
+
+    const RESPONSE: &[u8] = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+        \x07example\x03com\x00\x00\x01\x00\x01\
+        \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+        \x00\x04]\xb8\xd8\"";
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn parse_bytes() {
+        use super::BytesPacket;
+        use crate::RData;
+        use bytes::Bytes;
+        use std::net::Ipv4Addr;
+
+        let response = Bytes::from(RESPONSE);
+        let packet = BytesPacket::parse(response.clone()).unwrap();
+        drop(response);
+        let packet = std::thread::spawn(move || packet).join().unwrap();
+        assert_eq!(packet.packet().answers().len(), 1);
+        assert_eq!(packet.packet().answers()[0].name.to_string(), "example.com");
+        match packet.packet().answers()[0].data {
+            RData::A(addr) => assert_eq!(addr.0, Ipv4Addr::new(93, 184, 216, 34)),
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        assert_eq!(packet.into_bytes().len(), 45);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[test]
+    fn zero_copy() {
+        use super::BytesPacket;
+        use crate::RData;
+        use bytes::Bytes;
+
+        // a CNAME and an OPT record
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x01\
+            \x03www\x07example\x03com\x00\x00\x05\x00\x01\
+            \xc0\x0c\x00\x05\x00\x01\x00\x00\x04\xf8\x00\x07\x04host\xc0\x10\
+            \x00\x00\x29\x10\x00\x00\x00\x00\x00\x00\x04\x00\x0a\x00\x00";
+        let frame = Bytes::copy_from_slice(response);
+        let memory = frame.as_ptr_range();
+        let packet = BytesPacket::parse(frame.clone()).unwrap();
+        let inside = |part: &[u8]| {
+            memory.contains(&part.as_ptr())
+                && part.len() <= memory.end as usize - part.as_ptr() as usize
+        };
+        let view = packet.packet();
+        assert!(inside(view.questions()[0].qname.encoded()));
+        let answer = &view.answers()[0];
+        assert!(inside(answer.name.encoded()));
+        match answer.data {
+            RData::CNAME(ref cname) => {
+                assert!(inside(cname.0.encoded()));
+                assert_eq!(cname.0.to_string(), "host.example.com");
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        match view.opt().unwrap().data {
+            RData::OPT(ref data) => assert!(inside(data)),
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+        assert_eq!(view, crate::Packet::parse(response).unwrap());
+    }
+
+    #[test]
+    fn other_buffers() {
+        let packets = (0..3)
+            .map(|_| SharedPacket::parse(Arc::<[u8]>::from(RESPONSE)).unwrap())
+            .collect::<Vec<_>>();
+        let packet = std::thread::spawn(move || packets[2].clone())
+            .join()
+            .unwrap();
+        assert_eq!(packet.packet().answers()[0].name.to_string(), "example.com");
+
+        let owned = SharedPacket::parse(RESPONSE.to_vec()).unwrap();
+        let copy = owned.clone();
+        drop(owned);
+        assert_eq!(copy.header().id, 0x0625);
+        assert_eq!(copy.packet(), crate::Packet::parse(RESPONSE).unwrap());
+        assert_eq!(copy.into_buffer(), RESPONSE);
+
+        let shared = SharedPacket::parse(Rc::<[u8]>::from(RESPONSE)).unwrap();
+        assert_eq!(shared.clone().packet(), shared.packet());
+        assert!(SharedPacket::parse(RESPONSE[..20].to_vec()).is_err());
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::slice;
+
+// A vector keeping up to two items inline, moved to the heap when it
+// grows larger
+//
+// Two items cover the typical response with one question and one or two
+// answers without allocating. The price is size: every section of a
+// `Packet` takes the space of two records, so a `Packet` is about 1.2 KiB
+// on 64-bit targets (see the `size` test). Moving that much is still
+// cheaper than the two heap allocations it saves per parse, a single
+// slot would make two-answer responses allocate again.
+//
+// There's no unsafe code and no `Drop` implementation, so just like
+// `Vec` it is covariant and a packet may be dropped after the buffer it
+// borrows, which isn't the case with the `SmallVec` of the smallvec
+// crate.
+#[derive(Default)]
+pub(crate) enum SmallVec<T> {
+    #[default]
+    Empty,
+    One([T; 1]),
+    Two([T; 2]),
+    Heap(Vec<T>),
+}
+
+impl<T> SmallVec<T> {
+    pub(crate) fn with_capacity(capacity: usize) -> SmallVec<T> {
+        if capacity <= 2 {
+            SmallVec::Empty
+        } else {
+            SmallVec::Heap(Vec::with_capacity(capacity))
+        }
+    }
+    // Like `with_capacity`, the items that don't fit inline are stored in
+    // the buffer returned by `spare` if any
+    pub(crate) fn with_spare<F>(capacity: usize, spare: F) -> SmallVec<T>
+    where
+        F: FnOnce() -> Option<Vec<T>>,
+    {
+        if capacity <= 2 {
+            return SmallVec::Empty;
+        }
+        match spare() {
+            Some(mut items) => {
+                items.clear();
+                items.reserve(capacity);
+                SmallVec::Heap(items)
+            }
+            None => SmallVec::Heap(Vec::with_capacity(capacity)),
+        }
+    }
+    // Returns the heap buffer, if the items were moved there
+    pub(crate) fn into_vec(self) -> Option<Vec<T>> {
+        match self {
+            SmallVec::Heap(items) => Some(items),
+            _ => None,
+        }
+    }
+    pub(crate) fn push(&mut self, item: T) {
+        *self = match mem::take(self) {
+            SmallVec::Empty => SmallVec::One([item]),
+            SmallVec::One([first]) => SmallVec::Two([first, item]),
+            SmallVec::Two([first, second]) => {
+                let mut items = Vec::with_capacity(4);
+                items.extend([first, second, item]);
+                SmallVec::Heap(items)
+            }
+            SmallVec::Heap(mut items) => {
+                items.push(item);
+                SmallVec::Heap(items)
+            }
+        };
+    }
+    pub(crate) fn retain<F: FnMut(&T) -> bool>(&mut self, mut keep: F) {
+        match self {
+            SmallVec::Heap(items) => items.retain(keep),
+            _ => {
+                *self = mem::take(self)
+                    .into_iter()
+                    .filter(|item| keep(item))
+                    .collect()
+            }
+        }
+    }
+}
+
+impl<T> Deref for SmallVec<T> {
+    type Target = [T];
+    fn deref(&self) -> &[T] {
+        match self {
+            SmallVec::Empty => &[],
+            SmallVec::One(items) => items,
+            SmallVec::Two(items) => items,
+            SmallVec::Heap(items) => items,
+        }
+    }
+}
+
+impl<T> DerefMut for SmallVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        match self {
+            SmallVec::Empty => &mut [],
+            SmallVec::One(items) => items,
+            SmallVec::Two(items) => items,
+            SmallVec::Heap(items) => items,
+        }
+    }
+}
+
+impl<T: Clone> Clone for SmallVec<T> {
+    fn clone(&self) -> SmallVec<T> {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SmallVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SmallVec<T> {
+    fn eq(&self, other: &SmallVec<T>) -> bool {
+        **self == **other
+    }
+}
+
+impl<T> Extend<T> for SmallVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for SmallVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> SmallVec<T> {
+        let iter = iter.into_iter();
+        let mut result = SmallVec::with_capacity(iter.size_hint().0);
+        result.extend(iter);
+        result
+    }
+}
+
+impl<T> IntoIterator for SmallVec<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+    fn into_iter(self) -> IntoIter<T> {
+        match self {
+            SmallVec::Empty => IntoIter::Inline(None, None),
+            SmallVec::One([first]) => IntoIter::Inline(Some(first), None),
+            SmallVec::Two([first, second]) => IntoIter::Inline(Some(first), Some(second)),
+            SmallVec::Heap(items) => IntoIter::Heap(items.into_iter()),
+        }
+    }
+}
+
+impl<'s, T> IntoIterator for &'s SmallVec<T> {
+    type Item = &'s T;
+    type IntoIter = slice::Iter<'s, T>;
+    fn into_iter(self) -> slice::Iter<'s, T> {
+        self.iter()
+    }
+}
+
+pub(crate) enum IntoIter<T> {
+    Inline(Option<T>, Option<T>),
+    Heap(std::vec::IntoIter<T>),
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+    fn next(&mut self) -> Option<T> {
+        match self {
+            IntoIter::Inline(first, second) => first.take().or_else(|| second.take()),
+            IntoIter::Heap(items) => items.next(),
+        }
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            IntoIter::Inline(first, second) => {
+                let len = first.is_some() as usize + second.is_some() as usize;
+                (len, Some(len))
+            }
+            IntoIter::Heap(items) => items.size_hint(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::SmallVec;
+
+    #[test]
+    fn spill() {
+        let mut items = SmallVec::with_capacity(1);
+        items.push(1);
+        items.push(2);
+        assert!(matches!(items, SmallVec::Two(_)));
+        items.push(3);
+        assert!(matches!(items, SmallVec::Heap(_)));
+        assert_eq!(*items, [1, 2, 3]);
+        items.retain(|&item| item != 2);
+        assert_eq!(items.into_iter().collect::<Vec<_>>(), [1, 3]);
+
+        let mut items = (0..2).collect::<SmallVec<u32>>();
+        items.retain(|&item| item != 0);
+        assert!(matches!(items, SmallVec::One([1])));
+        assert_eq!(items.clone(), items);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn size() {
+        use std::mem::size_of;
+
+        use crate::{Packet, Question, ResourceRecord};
+
+        // the variant tag fits into a niche of the items
+        assert_eq!(
+            size_of::<SmallVec<Question<'_>>>(),
+            2 * size_of::<Question<'_>>()
+        );
+        assert_eq!(
+            size_of::<SmallVec<ResourceRecord<'_>>>(),
+            2 * size_of::<ResourceRecord<'_>>()
+        );
+        // keep an eye on the whole packet, it is moved around by value
+        assert!(size_of::<Packet<'_>>() <= 1280);
+    }
+}
@@ -0,0 +1,274 @@
+//! Aggregated statistics over DNS traffic
+//!
+//! Feed packets to a [`Collector`] and read the tallies at any time.
+//! Collectors of several threads may be combined with `merge`.
+//!
+//! [`Collector`]: struct.Collector.html
+use std::collections::HashMap;
+
+use crate::{Header, Packet, RawPacket, ResourceRecord, ResponseCode, Type};
+
+/// Combination of the header flags, used as a key in `Collector::flags`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(missing_docs)] // named after the fields of `Header`
+pub struct Flags {
+    pub query: bool,
+    pub authoritative: bool,
+    pub truncated: bool,
+    pub recursion_desired: bool,
+    pub recursion_available: bool,
+    pub authenticated_data: bool,
+    pub checking_disabled: bool,
+}
+
+impl<'a> From<&'a Header> for Flags {
+    fn from(header: &'a Header) -> Flags {
+        Flags {
+            query: header.query,
+            authoritative: header.authoritative,
+            truncated: header.truncated,
+            recursion_desired: header.recursion_desired,
+            recursion_available: header.recursion_available,
+            authenticated_data: header.authenticated_data,
+            checking_disabled: header.checking_disabled,
+        }
+    }
+}
+
+/// Histogram with power of two buckets
+///
+/// Bucket zero counts zeros, bucket `n` counts values from `2^(n-1)` to
+/// `2^n - 1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: [u64; 33],
+}
+
+impl Default for Histogram {
+    fn default() -> Histogram {
+        Histogram { buckets: [0; 33] }
+    }
+}
+
+impl Histogram {
+    /// Counts a value
+    pub fn add(&mut self, value: u32) {
+        let bucket = 32 - value.leading_zeros() as usize;
+        self.buckets[bucket] += 1;
+    }
+    /// Returns the counts of all buckets
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+    /// Returns the number of values counted
+    pub fn total(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+    /// Adds the counts of another histogram
+    pub fn merge(&mut self, other: &Histogram) {
+        for (a, b) in self.buckets.iter_mut().zip(other.buckets.iter()) {
+            *a += b;
+        }
+    }
+}
+
+/// Accumulates counts over the packets fed to it
+#[derive(Debug, Clone, Default)]
+pub struct Collector {
+    queries: u64,
+    responses: u64,
+    parse_errors: u64,
+    qtypes: HashMap<u16, u64>,
+    rcodes: HashMap<ResponseCode, u64>,
+    flags: HashMap<Flags, u64>,
+    response_sizes: Histogram,
+    ttls: Histogram,
+}
+
+impl Collector {
+    /// Creates an empty collector
+    pub fn new() -> Collector {
+        Collector::default()
+    }
+    /// Counts a parsed packet
+    ///
+    /// The size of the packet is not known, so it's not counted in
+    /// `response_sizes`, use `add_raw` for that.
+    pub fn add_packet(&mut self, packet: &Packet<'_>) {
+        self.add_header(packet.header());
+        for question in packet.questions() {
+            self.add_qtype(question.qtype.code());
+        }
+        let records = packet
+            .answers()
+            .iter()
+            .chain(packet.nameservers())
+            .chain(packet.additional());
+        for record in records {
+            self.add_record(record);
+        }
+    }
+    /// Parses and counts a packet
+    ///
+    /// The packet is parsed with `RawPacket::parse`, so records of unknown
+    /// types are fine. Packets which fail to parse are only counted in
+    /// `parse_errors`.
+    pub fn add_raw(&mut self, data: &[u8]) {
+        let packet = match RawPacket::parse(data) {
+            Ok(packet) => packet,
+            Err(_) => {
+                self.parse_errors += 1;
+                return;
+            }
+        };
+        self.add_header(packet.header());
+        if !packet.header().query {
+            self.response_sizes.add(data.len() as u32);
+        }
+        for question in packet.questions() {
+            self.add_qtype(question.qtype);
+        }
+        let records = packet
+            .answers()
+            .iter()
+            .chain(packet.nameservers())
+            .chain(packet.additional());
+        for record in records {
+            // the TTL field of OPT holds flags
+            if record.typ != Type::OPT.code() {
+                self.ttls.add(record.ttl);
+            }
+        }
+    }
+    /// Adds the counts of another collector
+    pub fn merge(&mut self, other: &Collector) {
+        self.queries += other.queries;
+        self.responses += other.responses;
+        self.parse_errors += other.parse_errors;
+        for (&qtype, &count) in &other.qtypes {
+            *self.qtypes.entry(qtype).or_insert(0) += count;
+        }
+        for (&rcode, &count) in &other.rcodes {
+            *self.rcodes.entry(rcode).or_insert(0) += count;
+        }
+        for (&flags, &count) in &other.flags {
+            *self.flags.entry(flags).or_insert(0) += count;
+        }
+        self.response_sizes.merge(&other.response_sizes);
+        self.ttls.merge(&other.ttls);
+    }
+    /// Number of queries counted
+    pub fn queries(&self) -> u64 {
+        self.queries
+    }
+    /// Number of responses counted
+    pub fn responses(&self) -> u64 {
+        self.responses
+    }
+    /// Number of packets passed to `add_raw` which failed to parse
+    pub fn parse_errors(&self) -> u64 {
+        self.parse_errors
+    }
+    /// Number of questions by the type code
+    ///
+    /// Questions of both queries and responses are counted.
+    pub fn qtypes(&self) -> &HashMap<u16, u64> {
+        &self.qtypes
+    }
+    /// Number of responses by the response code
+    pub fn rcodes(&self) -> &HashMap<ResponseCode, u64> {
+        &self.rcodes
+    }
+    /// Number of packets by the combination of header flags
+    pub fn flags(&self) -> &HashMap<Flags, u64> {
+        &self.flags
+    }
+    /// Sizes of responses passed to `add_raw` in bytes
+    pub fn response_sizes(&self) -> &Histogram {
+        &self.response_sizes
+    }
+    /// TTLs of all records except OPT
+    pub fn ttls(&self) -> &Histogram {
+        &self.ttls
+    }
+
+    fn add_header(&mut self, header: &Header) {
+        if header.query {
+            self.queries += 1;
+        } else {
+            self.responses += 1;
+            *self.rcodes.entry(header.response_code).or_insert(0) += 1;
+        }
+        *self.flags.entry(Flags::from(header)).or_insert(0) += 1;
+    }
+    fn add_qtype(&mut self, qtype: u16) {
+        *self.qtypes.entry(qtype).or_insert(0) += 1;
+    }
+    fn add_record(&mut self, record: &ResourceRecord<'_>) {
+        self.ttls.add(record.ttl.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Collector, Flags, Histogram};
+    use crate::{Builder, Packet, ResponseCode};
+    use crate::{QueryClass as QC, QueryType as QT};
+
+    const RESPONSE: &[u8] = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                              \x07example\x03com\x00\x00\x01\x00\x01\
+                              \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                              \x00\x04\x5d\xb8\xd8\x22";
+
+    #[test]
+    fn histogram() {
+        let mut hist = Histogram::default();
+        for &value in &[0, 1, 2, 3, 4, 1272, u32::MAX] {
+            hist.add(value);
+        }
+        assert_eq!(&hist.buckets()[..4], [1, 1, 2, 1]);
+        assert_eq!(hist.buckets()[11], 1);
+        assert_eq!(hist.buckets()[32], 1);
+        assert_eq!(hist.total(), 7);
+    }
+
+    #[test]
+    fn collect() {
+        let mut bld = Builder::new_query(1573, true);
+        bld.add_question("example.com", false, QT::AAAA, QC::IN);
+        let query = bld.build().unwrap();
+
+        let mut stats = Collector::new();
+        stats.add_raw(&query);
+        stats.add_raw(RESPONSE);
+        stats.add_raw(&RESPONSE[..20]);
+        stats.add_packet(&Packet::parse(RESPONSE).unwrap());
+
+        assert_eq!(stats.queries(), 1);
+        assert_eq!(stats.responses(), 2);
+        assert_eq!(stats.parse_errors(), 1);
+        assert_eq!(stats.qtypes()[&QT::A.code()], 2);
+        assert_eq!(stats.qtypes()[&QT::AAAA.code()], 1);
+        assert_eq!(stats.rcodes()[&ResponseCode::NoError], 2);
+        let response_flags = Flags {
+            query: false,
+            authoritative: false,
+            truncated: false,
+            recursion_desired: true,
+            recursion_available: true,
+            authenticated_data: false,
+            checking_disabled: false,
+        };
+        assert_eq!(stats.flags()[&response_flags], 2);
+        assert_eq!(stats.response_sizes().total(), 1);
+        assert_eq!(stats.response_sizes().buckets()[6], 1);
+        assert_eq!(stats.ttls().buckets()[11], 2);
+
+        let mut total = Collector::new();
+        total.merge(&stats);
+        total.merge(&stats);
+        assert_eq!(total.responses(), 4);
+        assert_eq!(total.qtypes()[&QT::A.code()], 4);
+        assert_eq!(total.ttls().total(), 4);
+    }
+}
@@ -1,25 +1,40 @@
-use crate::rdata::opt;
-use crate::{Class, Header, Name, QueryClass, QueryType, RData};
+use std::cmp::Ordering;
+use std::hash::{Hash, Hasher};
+use std::iter;
+
+use crate::rdata::{opt, RDataResult};
+use crate::small_vec::SmallVec;
+use crate::{Class, Header, Name, QueryClass, QueryType, RData, Ttl, Type};
 
 /// Parsed DNS packet
-#[derive(Debug, PartialEq)]
-#[allow(missing_docs)] // should be covered by spec
+///
+/// Sections are accessed with methods, so that more data (e.g. parsed
+/// EDNS options) may be added in the future without breaking changes.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub struct Packet<'a> {
-    pub header: Header,
-    pub questions: Vec<Question<'a>>,
-    pub answers: Vec<ResourceRecord<'a>>,
-    pub nameservers: Vec<ResourceRecord<'a>>,
-    pub additional: Vec<ResourceRecord<'a>>,
-    /// Optional Pseudo-RR
-    /// When present it is sent as an RR in the additional section. In this RR
-    /// the `class` and `ttl` fields store max udp packet size and flags
-    /// respectively. To keep `ResourceRecord` clean we store the OPT record
-    /// here.
-    pub opt: Option<opt::Record<'a>>,
+    pub(crate) header: Header,
+    pub(crate) questions: Questions<'a>,
+    pub(crate) answers: Records<'a>,
+    pub(crate) nameservers: Records<'a>,
+    pub(crate) additional: Records<'a>,
+    pub(crate) opt: Option<opt::Record<'a>>,
 }
 
+// Sections of a packet, up to two entries are stored inline, so parsing a
+// typical query or response doesn't allocate for the sections
+pub(crate) type Questions<'a> = SmallVec<Question<'a>>;
+pub(crate) type Records<'a> = SmallVec<ResourceRecord<'a>>;
+
+/// A packet that does not borrow the buffer it was parsed from
+///
+/// Created with `Packet::to_packet_buf` or `Packet::into_owned`. Such packets
+/// may be queued, cached or sent to another thread after the receive
+/// buffer has been reused.
+pub type PacketBuf = Packet<'static>;
+
 /// A parsed chunk of data in the Query section of the packet
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(missing_docs)] // should be covered by spec
 pub struct Question<'a> {
     pub qname: Name<'a>,
@@ -30,12 +45,25 @@ pub struct Question<'a> {
     pub qclass: QueryClass,
 }
 
+/// A question as the key of a cache
+///
+/// The name is lowercased and owned, so questions differing only in the
+/// case of their names, e.g. because of 0x20 randomization, give equal
+/// keys. Keys are ordered by name in the canonical order (RFC 4034
+/// section 6.1), then by type and class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuestionKey {
+    name: Name<'static>,
+    qtype: QueryType,
+    qclass: QueryClass,
+}
+
 /// A single DNS record
 ///
 /// We aim to provide whole range of DNS records available. But as time is
 /// limited we have some types of packets which are parsed and other provided
 /// as unparsed slice of bytes.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(missing_docs)] // should be covered by spec
 pub struct ResourceRecord<'a> {
     pub name: Name<'a>,
@@ -44,6 +72,493 @@ pub struct ResourceRecord<'a> {
     /// packets. Only used for multicast DNS.
     pub multicast_unique: bool,
     pub cls: Class,
-    pub ttl: u32,
+    pub ttl: Ttl,
     pub data: RData<'a>,
 }
+
+/// DNS packet with record data left unparsed
+///
+/// See `RawPacket::parse`. Type and class codes are kept as numbers, so
+/// packets with types unknown to this library may still be inspected.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct RawPacket<'a> {
+    pub(crate) header: Header,
+    pub(crate) questions: Vec<RawQuestion<'a>>,
+    pub(crate) answers: Vec<RawRecord<'a>>,
+    pub(crate) nameservers: Vec<RawRecord<'a>>,
+    pub(crate) additional: Vec<RawRecord<'a>>,
+    // the packet and the offset of the bytes following the last record
+    pub(crate) original: &'a [u8],
+    pub(crate) end: usize,
+}
+
+impl<'a> RawPacket<'a> {
+    /// Returns the header of the packet
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+    /// Returns the question section
+    pub fn questions(&self) -> &[RawQuestion<'a>] {
+        &self.questions
+    }
+    /// Returns the answer section
+    pub fn answers(&self) -> &[RawRecord<'a>] {
+        &self.answers
+    }
+    /// Returns the authority section
+    pub fn nameservers(&self) -> &[RawRecord<'a>] {
+        &self.nameservers
+    }
+    /// Returns the additional section
+    ///
+    /// Unlike with `Packet`, this includes the OPT pseudo-record if present.
+    pub fn additional(&self) -> &[RawRecord<'a>] {
+        &self.additional
+    }
+    /// Returns the bytes following the last record
+    ///
+    /// They are ignored by the parser, a well-formed packet has none.
+    pub fn trailing(&self) -> &'a [u8] {
+        &self.original[self.end..]
+    }
+}
+
+/// A question with the type and class left as numbers
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawQuestion<'a> {
+    /// The name being queried
+    pub qname: Name<'a>,
+    /// The type code
+    pub qtype: u16,
+    /// The class code, including the unicast-response bit used in
+    /// multicast DNS
+    pub qclass: u16,
+}
+
+/// A resource record with the data left unparsed
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawRecord<'a> {
+    /// The owner name
+    pub name: Name<'a>,
+    /// The type code
+    pub typ: u16,
+    /// The class code, including the cache-flush bit used in multicast DNS
+    pub cls: u16,
+    /// Time to live as received, not capped in any way
+    pub ttl: u32,
+    /// The record data, compressed names in it point into the packet
+    pub data: &'a [u8],
+    pub(crate) original: &'a [u8],
+}
+
+impl<'a> RawRecord<'a> {
+    /// Parses the record data
+    ///
+    /// Fails if the data is invalid. Data of types unknown to this
+    /// library is returned as `RData::Unknown`.
+    pub fn parse_data(&self) -> RDataResult<'a> {
+        RData::parse(Type::parse(self.typ), self.data, self.original)
+    }
+}
+
+impl<'a> Packet<'a> {
+    /// Returns the header of the packet
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+    /// Returns the question section
+    pub fn questions(&self) -> &[Question<'a>] {
+        &self.questions
+    }
+    /// Returns the answer section
+    pub fn answers(&self) -> &[ResourceRecord<'a>] {
+        &self.answers
+    }
+    /// Returns the authority section
+    pub fn nameservers(&self) -> &[ResourceRecord<'a>] {
+        &self.nameservers
+    }
+    /// Returns the additional section
+    ///
+    /// The OPT pseudo-record is not included, see `opt`.
+    pub fn additional(&self) -> &[ResourceRecord<'a>] {
+        &self.additional
+    }
+    /// Returns the OPT pseudo-record (RFC 6891)
+    ///
+    /// When present it is sent as an RR in the additional section. In this
+    /// RR the `class` and `ttl` fields store max udp packet size and flags
+    /// respectively. To keep `ResourceRecord` clean we store the OPT record
+    /// separately.
+    pub fn opt(&self) -> Option<&opt::Record<'a>> {
+        self.opt.as_ref()
+    }
+    /// Returns every name occurring in the packet
+    ///
+    /// Yields the names of the questions, then for every record of the
+    /// answer, authority and additional sections its owner name followed
+    /// by the names in its data (see `RData::names`).
+    pub fn names<'s>(&'s self) -> impl Iterator<Item = &'s Name<'a>> + 's {
+        let questions = self.questions.iter().map(|q| &q.qname);
+        let records = self
+            .answers
+            .iter()
+            .chain(&self.nameservers)
+            .chain(&self.additional)
+            .flat_map(|r| iter::once(&r.name).chain(r.data.names()));
+        questions.chain(records)
+    }
+    /// Returns the smallest TTL of the answer section
+    ///
+    /// This is how long the answer as a whole may be cached. Returns
+    /// `None` if there are no answers.
+    pub fn min_answer_ttl(&self) -> Option<Ttl> {
+        self.answers.iter().map(|r| r.ttl).min()
+    }
+    /// Returns how long a negative answer may be cached (RFC 2308)
+    ///
+    /// That is the smaller of the TTL of the SOA record in the authority
+    /// section and its minimum field. Returns `None` if there is no SOA
+    /// record, such negative answers should not be cached. Checking that
+    /// the response is NXDOMAIN or NODATA is up to the caller.
+    pub fn negative_ttl(&self) -> Option<Ttl> {
+        self.nameservers.iter().find_map(|r| match r.data {
+            RData::SOA(ref soa) => Some(r.ttl.min(Ttl::new(soa.minimum_ttl))),
+            _ => None,
+        })
+    }
+    /// Returns the A and AAAA records of the additional section at `name`
+    ///
+    /// These are the glue records for a name server, mail exchanger or
+    /// service target named in another record. Names are compared
+    /// ignoring case.
+    pub fn find_additional_addresses<'s>(
+        &'s self,
+        name: &'s Name<'_>,
+    ) -> impl Iterator<Item = &'s ResourceRecord<'a>> + 's {
+        self.additional.iter().filter(move |r| {
+            matches!(r.data, RData::A(..) | RData::AAAA(..)) && r.name.eq_ignore_ascii_case(name)
+        })
+    }
+    /// Makes a deep copy of the packet that does not borrow the buffer
+    ///
+    /// All names are decompressed and copied along with the record data.
+    pub fn to_packet_buf(&self) -> PacketBuf {
+        self.clone().into_owned()
+    }
+    /// Converts the packet into one that does not borrow the buffer
+    pub fn into_owned(self) -> PacketBuf {
+        Packet {
+            header: self.header,
+            questions: self
+                .questions
+                .into_iter()
+                .map(Question::into_owned)
+                .collect(),
+            answers: self
+                .answers
+                .into_iter()
+                .map(ResourceRecord::into_owned)
+                .collect(),
+            nameservers: self
+                .nameservers
+                .into_iter()
+                .map(ResourceRecord::into_owned)
+                .collect(),
+            additional: self
+                .additional
+                .into_iter()
+                .map(ResourceRecord::into_owned)
+                .collect(),
+            opt: self.opt.map(opt::Record::into_owned),
+        }
+    }
+    /// Checks whether this packet is a plausible response to `query`
+    ///
+    /// The IDs must be equal, `query` must be a query and `self` must be a
+    /// response, and both packets must carry the same questions in the
+    /// same order. Names are compared case-insensitively so that responses
+    /// from servers which don't preserve case still match. Resolvers that
+    /// use 0x20 encoding (randomized case) should use
+    /// `is_response_to_0x20` instead.
+    pub fn is_response_to(&self, query: &Packet<'_>) -> bool {
+        self.matches_query(query, Name::eq_ignore_ascii_case)
+    }
+    /// Like `is_response_to` but also requires the response to echo the
+    /// exact case of every question name
+    ///
+    /// This is the check needed by resolvers randomizing the case of query
+    /// names (draft-vixie-dnsext-dns0x20) as an extra defense against
+    /// spoofed responses.
+    pub fn is_response_to_0x20(&self, query: &Packet<'_>) -> bool {
+        self.matches_query(query, Name::eq_case_sensitive)
+    }
+    fn matches_query<F>(&self, query: &Packet<'_>, names_equal: F) -> bool
+    where
+        F: Fn(&Name<'a>, &Name<'_>) -> bool,
+    {
+        self.header.id == query.header().id
+            && query.header().query
+            && !self.header.query
+            && self.questions.len() == query.questions().len()
+            && self.questions.iter().zip(query.questions()).all(|(r, q)| {
+                r.qtype == q.qtype && r.qclass == q.qclass && names_equal(&r.qname, &q.qname)
+            })
+    }
+}
+
+impl<'a> Question<'a> {
+    /// Converts the question into one that does not borrow the packet
+    pub fn into_owned(self) -> Question<'static> {
+        Question {
+            qname: self.qname.into_owned(),
+            prefer_unicast: self.prefer_unicast,
+            qtype: self.qtype,
+            qclass: self.qclass,
+        }
+    }
+}
+
+impl<'a> ResourceRecord<'a> {
+    /// Converts the record into one that does not borrow the packet
+    pub fn into_owned(self) -> ResourceRecord<'static> {
+        ResourceRecord {
+            name: self.name.into_owned(),
+            multicast_unique: self.multicast_unique,
+            cls: self.cls,
+            ttl: self.ttl,
+            data: self.data.into_owned(),
+        }
+    }
+}
+
+impl QuestionKey {
+    /// Creates a key, lowercasing the name
+    pub fn new(name: &Name<'_>, qtype: QueryType, qclass: QueryClass) -> QuestionKey {
+        let mut labels = Vec::with_capacity(name.byte_len());
+        name.write_to(&mut labels);
+        labels.make_ascii_lowercase();
+        QuestionKey {
+            name: Name::from_uncompressed(labels),
+            qtype,
+            qclass,
+        }
+    }
+    /// The lowercased name
+    pub fn name(&self) -> &Name<'static> {
+        &self.name
+    }
+    /// The query type
+    pub fn qtype(&self) -> QueryType {
+        self.qtype
+    }
+    /// The query class
+    pub fn qclass(&self) -> QueryClass {
+        self.qclass
+    }
+}
+
+impl<'a, 'b> From<&'b Question<'a>> for QuestionKey {
+    fn from(question: &'b Question<'a>) -> QuestionKey {
+        QuestionKey::new(&question.qname, question.qtype, question.qclass)
+    }
+}
+
+impl Hash for QuestionKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for label in self.name.labels() {
+            label.hash(state);
+        }
+        self.qtype.code().hash(state);
+        self.qclass.code().hash(state);
+    }
+}
+
+impl PartialOrd for QuestionKey {
+    fn partial_cmp(&self, other: &QuestionKey) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for QuestionKey {
+    fn cmp(&self, other: &QuestionKey) -> Ordering {
+        self.name
+            .canonical_cmp(&other.name)
+            .then(self.qtype.code().cmp(&other.qtype.code()))
+            .then(self.qclass.code().cmp(&other.qclass.code()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{Packet, PacketBuf, QueryClass, QueryType, QuestionKey, RData, Ttl};
+
+    const QUERY: &[u8] = b"\x06%\x01\x00\x00\x01\x00\x00\x00\x00\x00\x00\
+                           \x07eXaMpLe\x03com\x00\x00\x01\x00\x01";
+
+    #[test]
+    fn response_matches_query() {
+        let query = Packet::parse(QUERY).unwrap();
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07eXaMpLe\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04]\xb8\xd8\"";
+        let response = Packet::parse(response).unwrap();
+        assert!(response.is_response_to(&query));
+        assert!(response.is_response_to_0x20(&query));
+        // A query is never a response to itself
+        assert!(!query.is_response_to(&query));
+    }
+
+    #[test]
+    fn response_case_folded() {
+        let query = Packet::parse(QUERY).unwrap();
+        let response = b"\x06%\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01";
+        let response = Packet::parse(response).unwrap();
+        assert!(response.is_response_to(&query));
+        assert!(!response.is_response_to_0x20(&query));
+    }
+
+    #[test]
+    fn response_mismatch() {
+        let query = Packet::parse(QUERY).unwrap();
+        // wrong id
+        let response = b"\x06&\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01";
+        assert!(!Packet::parse(response).unwrap().is_response_to(&query));
+        // wrong qtype
+        let response = b"\x06%\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x1c\x00\x01";
+        assert!(!Packet::parse(response).unwrap().is_response_to(&query));
+        // wrong name
+        let response = b"\x06%\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\
+                         \x07example\x03org\x00\x00\x01\x00\x01";
+        assert!(!Packet::parse(response).unwrap().is_response_to(&query));
+        // no question
+        let response = b"\x06%\x81\x80\x00\x00\x00\x00\x00\x00\x00\x00";
+        assert!(!Packet::parse(response).unwrap().is_response_to(&query));
+    }
+
+    #[test]
+    fn owned_packet() {
+        fn assert_send<T: Send + 'static>(_: &T) {}
+
+        let mut buf = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                        \x07example\x03com\x00\x00\x0f\x00\x01\
+                        \xc0\x0c\x00\x0f\x00\x01\x00\x00\x04\xf8\
+                        \x00\x07\x00\x0a\x02mx\xc0\x0c"
+            .to_vec();
+        let packet = Packet::parse(&buf).unwrap();
+        let owned: PacketBuf = packet.to_packet_buf();
+        assert_eq!(owned, packet);
+        buf.iter_mut().for_each(|b| *b = 0);
+        assert_send(&owned);
+        assert_eq!(owned.questions()[0].qname.to_string(), "example.com");
+        assert_eq!(owned.answers()[0].name.to_string(), "example.com");
+        match owned.answers()[0].data {
+            RData::MX(ref mx) => {
+                assert_eq!(mx.preference, 10);
+                assert_eq!(mx.exchange.to_string(), "mx.example.com");
+                assert_eq!(mx.exchange.byte_len(), 16);
+            }
+            ref x => panic!("Wrong rdata {:?}", x),
+        }
+    }
+
+    #[test]
+    fn ttls() {
+        // CNAME with TTL 3600 and A with TTL 60
+        let response = b"\x00\x01\x81\x80\x00\x00\x00\x02\x00\x00\x00\x00\
+                         \x03www\x07example\x03com\x00\x00\x05\x00\x01\x00\x00\x0e\x10\
+                         \x00\x02\xc0\x10\
+                         \xc0\x10\x00\x01\x00\x01\x00\x00\x00\x3c\x00\x04\xc0\x00\x02\x01";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(packet.min_answer_ttl(), Some(Ttl(60)));
+        assert_eq!(packet.negative_ttl(), None);
+
+        // NXDOMAIN with SOA TTL 900 and minimum 300
+        let response = b"\x00\x01\x81\x83\x00\x00\x00\x00\x00\x01\x00\x00\
+                         \x07example\x03com\x00\x00\x06\x00\x01\x00\x00\x03\x84\x00\x16\
+                         \x00\x00\x00\x00\x00\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+                         \x00\x00\x01\x2c";
+        let packet = Packet::parse(response).unwrap();
+        assert_eq!(packet.min_answer_ttl(), None);
+        assert_eq!(packet.negative_ttl(), Some(Ttl(300)));
+    }
+
+    #[test]
+    fn glue() {
+        // referral to example.com with name servers ns1.example.net and
+        // ns2.example.com, an A and an AAAA record for the first and an A
+        // record for the second
+        let response = b"\x00\x01\x81\x00\x00\x01\x00\x00\x00\x02\x00\x03\
+                         \x03www\x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x10\x00\x02\x00\x01\x00\x00\x0e\x10\x00\x11\
+                         \x03ns1\x07example\x03net\x00\
+                         \xc0\x10\x00\x02\x00\x01\x00\x00\x0e\x10\x00\x06\x03ns2\xc0\x10\
+                         \x03NS1\x07example\x03net\x00\
+                         \x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xc0\x00\x02\x01\
+                         \xc0\x2d\x00\x1c\x00\x01\x00\x00\x0e\x10\x00\x10\
+                         \x20\x01\x0d\xb8\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x01\
+                         \xc0\x4a\x00\x01\x00\x01\x00\x00\x0e\x10\x00\x04\xc0\x00\x02\x02";
+        let packet = Packet::parse(response).unwrap();
+        let glue = packet
+            .nameservers()
+            .iter()
+            .map(|r| match r.data {
+                RData::NS(ref ns) => packet.find_additional_addresses(&ns.0).count(),
+                ref x => panic!("Wrong rdata {:?}", x),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(glue, [2, 1]);
+        let question = &packet.questions()[0];
+        assert_eq!(packet.find_additional_addresses(&question.qname).count(), 0);
+    }
+
+    #[test]
+    fn question_keys() {
+        let mut cache = HashMap::new();
+        let query = Packet::parse(QUERY).unwrap();
+        cache.insert(QuestionKey::from(&query.questions()[0]), 1);
+
+        let response = b"\x06%\x81\x80\x00\x01\x00\x00\x00\x00\x00\x00\
+                         \x07example\x03COM\x00\x00\x01\x00\x01";
+        let response = Packet::parse(response).unwrap();
+        let key = QuestionKey::from(&response.questions()[0]);
+        assert_eq!(key.name().to_string(), "example.com");
+        assert_eq!(cache.get(&key), Some(&1));
+
+        let aaaa = QuestionKey::new(key.name(), QueryType::AAAA, QueryClass::IN);
+        assert!(!cache.contains_key(&aaaa));
+        assert!(key < aaaa);
+    }
+
+    #[test]
+    fn all_names() {
+        let response = b"\x4a\xf0\x81\x80\x00\x01\x00\x02\x00\x01\x00\x00\
+                         \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\
+                         \x00\x1c\x07livecms\x0etrafficmanager\x03net\x00\
+                         \xc0\x2b\x00\x01\x00\x01\x00\x00\x0e\x10\
+                         \x00\x04\xc0\x00\x02\x21\
+                         \xc0\x16\x00\x02\x00\x01\x00\x00\x0e\x10\
+                         \x00\x06\x03ns1\xc0\x16";
+        let packet = Packet::parse(response).unwrap();
+        let names = packet.names().map(|n| n.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "www.skype.com",
+                "www.skype.com",
+                "livecms.trafficmanager.net",
+                "livecms.trafficmanager.net",
+                "com",
+                "ns1.com",
+            ]
+        );
+    }
+}
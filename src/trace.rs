@@ -0,0 +1,103 @@
+// Instrumentation through the `tracing` crate
+//
+// With the `tracing` feature disabled the macros expand to nothing and
+// their arguments are not evaluated. They are statements, not
+// expressions.
+
+// Emits an event at a level given as the name of a `tracing` macro,
+// e.g. `trace_event!(debug, count, "message")`
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+// Enters a span until the end of the enclosing block, the level is given
+// as the name of a span macro, e.g. `trace_span!(debug_span, "parse")`
+macro_rules! trace_span {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::$level!($($arg)+).entered();
+    };
+}
+
+#[cfg(all(test, feature = "tracing"))]
+mod test {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Mutex;
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::Packet;
+
+    // Keeps the messages of the events and the names of the spans
+    #[derive(Default)]
+    struct Collect {
+        next_id: AtomicU64,
+        lines: Mutex<Vec<String>>,
+    }
+
+    struct Message<'s>(&'s mut String);
+
+    impl<'s> Visit for Message<'s> {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() == "message" {
+                *self.0 = format!("{:?}", value);
+            }
+        }
+    }
+
+    impl Subscriber for &'static Collect {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let line = format!("span {}", span.metadata().name());
+            self.lines.lock().unwrap().push(line);
+            Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut message = String::new();
+            event.record(&mut Message(&mut message));
+            self.lines.lock().unwrap().push(message);
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[test]
+    fn parse_events() {
+        let response = b"\x06%\x81\x80\x00\x01\x00\x01\x00\x00\x00\x00\
+                         \x07example\x03com\x00\x00\x01\x00\x01\
+                         \xc0\x0c\x00\x01\x00\x01\x00\x00\x04\xf8\
+                         \x00\x04]\xb8\xd8\"";
+        let collect: &'static Collect = Box::leak(Box::default());
+        tracing::subscriber::with_default(collect, || {
+            Packet::parse(response).unwrap();
+            Packet::parse(&response[..40]).unwrap_err();
+        });
+        let lines = collect.lines.lock().unwrap();
+        assert_eq!(
+            *lines,
+            [
+                "span parse",
+                "parsed header",
+                "span section",
+                "followed compression pointer",
+                "parsed record",
+                "span section",
+                "span section",
+                "span parse",
+                "parsed header",
+                "span section",
+                "followed compression pointer",
+                "invalid record",
+            ]
+        );
+    }
+}
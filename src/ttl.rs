@@ -0,0 +1,110 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Time to live of a record, in seconds
+///
+/// TTLs are unsigned 32-bit numbers on the wire, but RFC 2181 section 8
+/// limits them to 2^31 - 1 and says larger values are to be treated as
+/// zero, which is what `Ttl::new` and the parser do. The raw number is
+/// still accessible as the public field.
+///
+/// Caches store the time a record was received along with its TTL, then
+/// use `decay` to get the TTL left when answering from the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Ttl(pub u32);
+
+impl Ttl {
+    /// The largest valid TTL, 2^31 - 1 seconds (about 68 years)
+    pub const MAX: Ttl = Ttl(0x7FFF_FFFF);
+
+    /// Creates a TTL, values larger than `MAX` become zero (RFC 2181)
+    pub fn new(secs: u32) -> Ttl {
+        if secs > Ttl::MAX.0 {
+            Ttl(0)
+        } else {
+            Ttl(secs)
+        }
+    }
+    /// Converts a duration, rounding down to whole seconds
+    ///
+    /// Durations longer than `MAX` are capped to it.
+    pub fn from_duration(duration: Duration) -> Ttl {
+        Ttl(duration.as_secs().min(u64::from(Ttl::MAX.0)) as u32)
+    }
+    /// Returns the number of seconds
+    pub fn as_secs(self) -> u32 {
+        self.0
+    }
+    /// Returns the TTL as a duration
+    pub fn as_duration(self) -> Duration {
+        Duration::from_secs(u64::from(self.0))
+    }
+    /// Returns the TTL left after `elapsed` time, zero if it has expired
+    ///
+    /// Fractions of a second count as a whole second, so a record is
+    /// never kept longer than allowed.
+    pub fn decay(self, elapsed: Duration) -> Ttl {
+        Ttl::from_duration(self.as_duration().saturating_sub(elapsed))
+    }
+}
+
+impl From<u32> for Ttl {
+    fn from(secs: u32) -> Ttl {
+        Ttl(secs)
+    }
+}
+
+impl From<Ttl> for u32 {
+    fn from(ttl: Ttl) -> u32 {
+        ttl.0
+    }
+}
+
+impl From<Ttl> for Duration {
+    fn from(ttl: Ttl) -> Duration {
+        ttl.as_duration()
+    }
+}
+
+impl PartialEq<u32> for Ttl {
+    fn eq(&self, secs: &u32) -> bool {
+        self.0 == *secs
+    }
+}
+
+impl fmt::Display for Ttl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::Ttl;
+
+    #[test]
+    fn rfc2181() {
+        assert_eq!(Ttl::new(3600), 3600);
+        assert_eq!(Ttl::new(0x7FFF_FFFF), Ttl::MAX);
+        assert_eq!(Ttl::new(0x8000_0000), Ttl(0));
+        assert_eq!(Ttl::from(0x8000_0000), 0x8000_0000);
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(Ttl(300).as_duration(), Duration::from_secs(300));
+        assert_eq!(Ttl::from_duration(Duration::from_millis(1999)), Ttl(1));
+        assert_eq!(Ttl::from_duration(Duration::from_secs(1 << 40)), Ttl::MAX);
+    }
+
+    #[test]
+    fn decay() {
+        let ttl = Ttl(300);
+        assert_eq!(ttl.decay(Duration::from_secs(100)), Ttl(200));
+        assert_eq!(ttl.decay(Duration::from_millis(100_500)), Ttl(199));
+        assert_eq!(ttl.decay(Duration::from_secs(300)), Ttl(0));
+        assert_eq!(ttl.decay(Duration::from_secs(1000)), Ttl(0));
+    }
+}
@@ -0,0 +1,196 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::{Error, Type};
+
+/// Set of record types in the window block format (RFC 4034 section 4.1.2)
+///
+/// This encoding is used by NSEC, NSEC3 and CSYNC records. The set is
+/// kept in the wire format, so parsing does not allocate.
+#[derive(Clone, PartialEq, Eq)]
+pub struct TypeBitmap<'a> {
+    data: Cow<'a, [u8]>,
+}
+
+impl<'a> TypeBitmap<'a> {
+    /// Validates the wire format of a type bitmap
+    ///
+    /// Windows must come in increasing order and hold 1 to 32 bytes.
+    pub fn parse(data: &'a [u8]) -> Result<TypeBitmap<'a>, Error> {
+        let mut pos = 0;
+        let mut last_window = None;
+        while pos < data.len() {
+            if pos + 2 > data.len() {
+                return Err(Error::InvalidTypeBitmap);
+            }
+            let window = data[pos];
+            let len = data[pos + 1] as usize;
+            if last_window.is_some_and(|last| window <= last) || len == 0 || len > 32 {
+                return Err(Error::InvalidTypeBitmap);
+            }
+            if pos + 2 + len > data.len() {
+                return Err(Error::InvalidTypeBitmap);
+            }
+            last_window = Some(window);
+            pos += 2 + len;
+        }
+        Ok(TypeBitmap {
+            data: Cow::Borrowed(data),
+        })
+    }
+    /// Encodes a set of type codes, duplicates are allowed
+    pub fn from_codes<I: IntoIterator<Item = u16>>(codes: I) -> TypeBitmap<'static> {
+        let mut codes = codes.into_iter().collect::<Vec<_>>();
+        codes.sort_unstable();
+        codes.dedup();
+        let mut data = Vec::new();
+        let mut window_start = 0;
+        for code in codes {
+            let window = (code >> 8) as u8;
+            let byte = (code & 0xFF) as usize / 8;
+            if data.is_empty() || data[window_start] != window {
+                window_start = data.len();
+                data.extend_from_slice(&[window, 0]);
+            }
+            let len = data[window_start + 1] as usize;
+            if byte >= len {
+                data.resize(window_start + 2 + byte + 1, 0);
+                data[window_start + 1] = byte as u8 + 1;
+            }
+            data[window_start + 2 + byte] |= 0x80 >> (code & 7);
+        }
+        TypeBitmap {
+            data: Cow::Owned(data),
+        }
+    }
+    /// Encodes a set of types, duplicates are allowed
+    pub fn from_types<I: IntoIterator<Item = Type>>(types: I) -> TypeBitmap<'static> {
+        TypeBitmap::from_codes(types.into_iter().map(Type::code))
+    }
+    /// Returns true if the type code is in the set
+    pub fn contains(&self, code: u16) -> bool {
+        self.codes().any(|c| c == code)
+    }
+    /// Returns the type codes in increasing order
+    pub fn codes(&self) -> Codes<'_> {
+        Codes {
+            data: &self.data,
+            pos: 0,
+            bit: 0,
+        }
+    }
+    /// Returns the types in increasing code order
+    ///
+    /// Codes unknown to this library are returned as `Type::Unknown`.
+    pub fn types(&self) -> impl Iterator<Item = Type> + '_ {
+        self.codes().map(Type::parse)
+    }
+    /// Returns true if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    /// Returns the set in the wire format
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data
+    }
+    /// Writes the set in the wire format
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.data);
+    }
+    /// Converts the set into one that does not borrow the packet
+    pub fn into_owned(self) -> TypeBitmap<'static> {
+        TypeBitmap {
+            data: Cow::Owned(self.data.into_owned()),
+        }
+    }
+}
+
+impl<'a> fmt::Debug for TypeBitmap<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.codes()).finish()
+    }
+}
+
+/// Iterator over the codes of a `TypeBitmap`, see `TypeBitmap::codes`
+#[derive(Debug, Clone)]
+pub struct Codes<'a> {
+    data: &'a [u8],
+    // start of the current window
+    pos: usize,
+    // next bit to check in the current window
+    bit: usize,
+}
+
+impl<'a> Iterator for Codes<'a> {
+    type Item = u16;
+    fn next(&mut self) -> Option<u16> {
+        while self.pos + 2 <= self.data.len() {
+            let window = self.data[self.pos] as u16;
+            let len = self.data[self.pos + 1] as usize;
+            let bitmap = &self.data[self.pos + 2..self.pos + 2 + len];
+            while self.bit < len * 8 {
+                let bit = self.bit;
+                self.bit += 1;
+                if bitmap[bit / 8] & (0x80 >> (bit % 8)) != 0 {
+                    return Some(window << 8 | bit as u16);
+                }
+            }
+            self.pos += 2 + len;
+            self.bit = 0;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TypeBitmap;
+    use crate::{Error, Type};
+
+    #[test]
+    fn rfc4034_example() {
+        // types of the NSEC record in RFC 4034 section 4.3
+        let data = b"\x00\x06\x40\x01\x00\x00\x00\x03\x04\x1b\x00\x00\x00\x00\x00\x00\
+                     \x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\
+                     \x00\x00\x00\x00\x20";
+        let bitmap = TypeBitmap::parse(data).unwrap();
+        assert_eq!(bitmap.codes().collect::<Vec<_>>(), [1, 15, 46, 47, 1234]);
+        assert_eq!(
+            bitmap.types().collect::<Vec<_>>(),
+            [
+                Type::A,
+                Type::MX,
+                Type::RRSIG,
+                Type::NSEC,
+                Type::Unknown(1234)
+            ]
+        );
+        assert!(bitmap.contains(1234));
+        assert!(!bitmap.contains(2));
+        assert_eq!(TypeBitmap::from_codes(vec![1234, 47, 1, 46, 15, 1]), bitmap);
+        assert_eq!(format!("{:?}", bitmap), "{1, 15, 46, 47, 1234}");
+    }
+
+    #[test]
+    fn encode_types() {
+        let bitmap = TypeBitmap::from_types(vec![Type::NS, Type::SOA, Type::DNSKEY]);
+        assert_eq!(bitmap.as_bytes(), b"\x00\x07\x22\x00\x00\x00\x00\x00\x80");
+        assert!(TypeBitmap::from_codes(vec![]).is_empty());
+    }
+
+    #[test]
+    fn invalid() {
+        for data in &[
+            &b"\x00"[..],
+            b"\x00\x00",
+            b"\x00\x21",
+            b"\x00\x02\x40",
+            b"\x01\x01\x40\x00\x01\x40",
+        ] {
+            assert!(matches!(
+                TypeBitmap::parse(data),
+                Err(Error::InvalidTypeBitmap)
+            ));
+        }
+    }
+}
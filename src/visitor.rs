@@ -0,0 +1,194 @@
+//! Push-based parsing of DNS packets
+//!
+//! [`parse_with_visitor`] scans a packet and passes every part of it to
+//! a [`MessageVisitor`] as soon as it's found. Nothing is allocated while
+//! scanning, and the visitor may stop early, e.g. a filter which only
+//! looks at the question doesn't pay for scanning the records.
+//!
+//! [`parse_with_visitor`]: fn.parse_with_visitor.html
+//! [`MessageVisitor`]: trait.MessageVisitor.html
+use std::fmt;
+use std::ops::ControlFlow;
+
+use crate::name::Budget;
+use crate::parser::{parse_raw_question, parse_raw_record};
+use crate::{Error, Header, ParseOptions, RawQuestion, RawRecord};
+
+/// A section of the packet containing resource records
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    /// The answer section
+    Answer,
+    /// The authority section (`nameservers` in `Packet`)
+    Authority,
+    /// The additional section, including the OPT pseudo-record
+    Additional,
+}
+
+/// Writes the name of the section in lower case, e.g. `answer`
+impl fmt::Display for Section {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(match self {
+            Section::Answer => "answer",
+            Section::Authority => "authority",
+            Section::Additional => "additional",
+        })
+    }
+}
+
+/// Callbacks invoked by `parse_with_visitor`
+///
+/// All methods continue scanning by default. Return
+/// `ControlFlow::Break(())` to stop, the rest of the packet is not
+/// checked in that case.
+pub trait MessageVisitor<'a> {
+    /// Called with the header before anything else
+    fn header(&mut self, header: &Header) -> ControlFlow<()> {
+        let _ = header;
+        ControlFlow::Continue(())
+    }
+    /// Called for every question
+    fn question(&mut self, question: &RawQuestion<'a>) -> ControlFlow<()> {
+        let _ = question;
+        ControlFlow::Continue(())
+    }
+    /// Called for every record of the answer, authority and additional
+    /// sections
+    fn record(&mut self, section: Section, record: &RawRecord<'a>) -> ControlFlow<()> {
+        let _ = (section, record);
+        ControlFlow::Continue(())
+    }
+}
+
+/// Scans a packet passing its parts to the visitor
+///
+/// Questions and records are passed in the same form as in `RawPacket`,
+/// their names borrow `data`. Returns an error if the packet is malformed
+/// before the visitor stopped the scan.
+pub fn parse_with_visitor<'a, V>(data: &'a [u8], visitor: &mut V) -> Result<(), Error>
+where
+    V: MessageVisitor<'a> + ?Sized,
+{
+    parse_with_visitor_with_options(data, &ParseOptions::default(), visitor)
+}
+
+/// Scans a packet like `parse_with_visitor` does, with custom options
+///
+/// Only the limits of `options` apply, classes are not interpreted.
+pub fn parse_with_visitor_with_options<'a, V>(
+    data: &'a [u8],
+    options: &ParseOptions,
+    visitor: &mut V,
+) -> Result<(), Error>
+where
+    V: MessageVisitor<'a> + ?Sized,
+{
+    let header = Header::parse(data)?;
+    if visitor.header(&header).is_break() {
+        return Ok(());
+    }
+    let mut offset = Header::size();
+    let mut budget = Budget::new(options);
+    // names are scanned without a `NameCache`, its table is allocated
+    for _ in 0..header.questions {
+        let question = parse_raw_question(data, &mut offset, None, &mut budget)?;
+        if visitor.question(&question).is_break() {
+            return Ok(());
+        }
+    }
+    let sections = [
+        (Section::Answer, header.answers),
+        (Section::Authority, header.nameservers),
+        (Section::Additional, header.additional),
+    ];
+    for &(section, count) in sections.iter() {
+        for _ in 0..count {
+            let record = parse_raw_record(data, &mut offset, None, &mut budget)?;
+            if visitor.record(section, &record).is_break() {
+                return Ok(());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::ControlFlow;
+
+    use super::{parse_with_visitor, MessageVisitor, Section};
+    use crate::{Header, RawQuestion, RawRecord};
+
+    const RESPONSE: &[u8] = b"\xda\x4a\x81\x80\x00\x01\x00\x03\x00\x00\x00\x00\
+        \x03www\x05skype\x03com\x00\x00\x01\x00\x01\
+        \xc0\x0c\x00\x05\x00\x01\x00\x00\x0e\x10\x00\x06\x04live\x00\
+        \xc0\x2b\x00\x01\x00\x01\x00\x00\x00\x04\x00\x04\x6f\xdd\x4a\x1c\
+        \xc0\x2b\x00\x01\x00\x01\x00\x00\x00\x04\x00\x04\x6f\xdd\x4a\x1d";
+
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        stop_after_question: bool,
+    }
+
+    impl<'a> MessageVisitor<'a> for Recorder {
+        fn header(&mut self, header: &Header) -> ControlFlow<()> {
+            self.events.push(format!("header {}", header.id));
+            ControlFlow::Continue(())
+        }
+        fn question(&mut self, question: &RawQuestion<'a>) -> ControlFlow<()> {
+            self.events.push(format!("question {}", question.qname));
+            if self.stop_after_question {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        }
+        fn record(&mut self, section: Section, record: &RawRecord<'a>) -> ControlFlow<()> {
+            self.events
+                .push(format!("{:?} {} {}", section, record.name, record.typ));
+            ControlFlow::Continue(())
+        }
+    }
+
+    #[test]
+    fn visit_all() {
+        let mut visitor = Recorder::default();
+        parse_with_visitor(RESPONSE, &mut visitor).unwrap();
+        assert_eq!(
+            visitor.events,
+            [
+                "header 55882",
+                "question www.skype.com",
+                "Answer www.skype.com 5",
+                "Answer live 1",
+                "Answer live 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn stop_early() {
+        let mut visitor = Recorder {
+            stop_after_question: true,
+            ..Recorder::default()
+        };
+        // the records are garbage, but they are never looked at
+        let mut data = RESPONSE[..31].to_vec();
+        data.extend_from_slice(b"\xff\xff\xff");
+        parse_with_visitor(&data, &mut visitor).unwrap();
+        assert_eq!(visitor.events, ["header 55882", "question www.skype.com"]);
+
+        let mut visitor = Recorder::default();
+        assert!(parse_with_visitor(&data, &mut visitor).is_err());
+        assert_eq!(visitor.events.len(), 2);
+    }
+
+    #[test]
+    fn default_methods() {
+        struct Nothing;
+        impl MessageVisitor<'_> for Nothing {}
+        parse_with_visitor(RESPONSE, &mut Nothing).unwrap();
+        assert!(parse_with_visitor(&RESPONSE[..50], &mut Nothing).is_err());
+    }
+}